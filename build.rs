@@ -26,12 +26,14 @@ fn main() {
                           "WGL_ARB_extensions_string",
                           "WGL_ARB_framebuffer_sRGB",
                           "WGL_ARB_multisample",
+                          "WGL_ARB_pbuffer",
                           "WGL_ARB_pixel_format",
                           "WGL_ARB_pixel_format_float",
                           "WGL_EXT_create_context_es2_profile",
                           "WGL_EXT_extensions_string",
                           "WGL_EXT_framebuffer_sRGB",
                           "WGL_EXT_swap_control",
+                          "WGL_NV_multisample_coverage",
                       ])
             .write_bindings(gl_generator::StructGenerator, &mut file).unwrap();
 
@@ -69,6 +71,8 @@ fn main() {
                           "GLX_EXT_framebuffer_sRGB",
                           "GLX_ARB_multisample",
                           "GLX_EXT_swap_control",
+                          "GLX_NV_multisample_coverage",
+                          "GLX_OML_swap_method",
                           "GLX_SGI_swap_control"
                       ])
             .write_bindings(gl_generator::StructGenerator, &mut file).unwrap();
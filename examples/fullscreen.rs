@@ -50,7 +50,7 @@ fn main() {
 
         match event {
             glutin::Event::Closed => break,
-            glutin::Event::KeyboardInput(_, _, Some(glutin::VirtualKeyCode::Escape)) => break,
+            glutin::Event::KeyboardInput(_, _, Some(glutin::VirtualKeyCode::Escape), _) => break,
             _ => ()
         }
     }
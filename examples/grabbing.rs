@@ -21,7 +21,7 @@ fn main() {
 
     for event in window.wait_events() {
         match event {
-            Event::KeyboardInput(ElementState::Pressed, _, _) => {
+            Event::KeyboardInput(ElementState::Pressed, _, _, _) => {
                 if grabbed {
                     grabbed = false;
                     window.set_cursor_state(glutin::CursorState::Normal)
@@ -0,0 +1,184 @@
+//! Keyboard shortcuts of menus, shown and matched the way each platform does.
+//!
+//! OS X shows the modifiers as symbols in a fixed order before the key, for example `⌥⌘S`,
+//! while the other platforms spell them out, for example `Ctrl+Alt+S`. The shortcuts that use
+//! the Command key on OS X usually use the Control key elsewhere, which `Accelerator::primary`
+//! takes care of.
+
+use std::fmt;
+
+use ElementState;
+use Event;
+use ModifiersState;
+use VirtualKeyCode;
+
+/// A key combined with modifiers, which triggers an action of the application.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct Accelerator {
+    /// The key that must be pressed.
+    pub key: VirtualKeyCode,
+    /// The modifiers that must be held down, and only them.
+    pub modifiers: ModifiersState,
+}
+
+impl Accelerator {
+    #[inline]
+    pub fn new(key: VirtualKeyCode, modifiers: ModifiersState) -> Accelerator {
+        Accelerator {
+            key: key,
+            modifiers: modifiers,
+        }
+    }
+
+    /// Builds an accelerator with the primary modifier of the platform, which is the Command key
+    /// on OS X and the Control key on the other platforms. For example `primary(S)` is `⌘S` on
+    /// OS X and `Ctrl+S` elsewhere.
+    #[inline]
+    pub fn primary(key: VirtualKeyCode) -> Accelerator {
+        let mut modifiers = ModifiersState::default();
+        if cfg!(target_os = "macos") {
+            modifiers.logo = true;
+        } else {
+            modifiers.ctrl = true;
+        }
+        Accelerator::new(key, modifiers)
+    }
+
+    /// Returns true if the event is the press of this accelerator.
+    ///
+    /// The modifiers must be exactly the same, so that `Ctrl+S` doesn't trigger the action of
+    /// `S` or of `Ctrl+Shift+S`. Repeated presses match as well.
+    pub fn matches(&self, event: &Event) -> bool {
+        match *event {
+            Event::KeyboardInput(ElementState::Pressed, _, Some(key), modifiers) => {
+                key == self.key && modifiers == self.modifiers
+            },
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Accelerator {
+    /// Shows the accelerator the way the menus of the platform do.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let modifiers = &self.modifiers;
+
+        if cfg!(target_os = "macos") {
+            // the order of the Human Interface Guidelines
+            if modifiers.ctrl { try!(formatter.write_str("⌃")); }
+            if modifiers.alt { try!(formatter.write_str("⌥")); }
+            if modifiers.shift { try!(formatter.write_str("⇧")); }
+            if modifiers.logo { try!(formatter.write_str("⌘")); }
+            formatter.write_str(&key_name(self.key))
+        } else {
+            let logo = if cfg!(target_os = "windows") { "Win+" } else { "Super+" };
+            if modifiers.ctrl { try!(formatter.write_str("Ctrl+")); }
+            if modifiers.alt { try!(formatter.write_str("Alt+")); }
+            if modifiers.shift { try!(formatter.write_str("Shift+")); }
+            if modifiers.logo { try!(formatter.write_str(logo)); }
+            formatter.write_str(&key_name(self.key))
+        }
+    }
+}
+
+/// The accelerators of an application, each associated with the action that it triggers.
+#[derive(Debug, Clone)]
+pub struct AcceleratorTable<T> {
+    entries: Vec<(Accelerator, T)>,
+}
+
+impl<T> AcceleratorTable<T> {
+    #[inline]
+    pub fn new() -> AcceleratorTable<T> {
+        AcceleratorTable { entries: Vec::new() }
+    }
+
+    /// Registers an accelerator. Returns the action that it previously triggered, if any.
+    pub fn insert(&mut self, accelerator: Accelerator, action: T) -> Option<T> {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.0 == accelerator) {
+            return Some(::std::mem::replace(&mut entry.1, action));
+        }
+
+        self.entries.push((accelerator, action));
+        None
+    }
+
+    /// Unregisters an accelerator. Returns the action that it triggered, if any.
+    pub fn remove(&mut self, accelerator: &Accelerator) -> Option<T> {
+        match self.entries.iter().position(|e| e.0 == *accelerator) {
+            Some(index) => Some(self.entries.remove(index).1),
+            None => None,
+        }
+    }
+
+    /// Returns the accelerator that triggers an action, to show it next to the item of a menu.
+    pub fn accelerator_of(&self, action: &T) -> Option<Accelerator> where T: PartialEq {
+        self.entries.iter().find(|e| e.1 == *action).map(|e| e.0)
+    }
+
+    /// Returns the action that the event triggers, if any.
+    pub fn find(&self, event: &Event) -> Option<&T> {
+        self.entries.iter().find(|e| e.0.matches(event)).map(|e| &e.1)
+    }
+}
+
+impl<T> Default for AcceleratorTable<T> {
+    #[inline]
+    fn default() -> AcceleratorTable<T> {
+        AcceleratorTable::new()
+    }
+}
+
+/// Returns the label of a key on the platform.
+fn key_name(key: VirtualKeyCode) -> String {
+    use VirtualKeyCode::*;
+
+    let mac = cfg!(target_os = "macos");
+    let name = match key {
+        Key0 | Numpad0 => "0",
+        Key1 | Numpad1 => "1",
+        Key2 | Numpad2 => "2",
+        Key3 | Numpad3 => "3",
+        Key4 | Numpad4 => "4",
+        Key5 | Numpad5 => "5",
+        Key6 | Numpad6 => "6",
+        Key7 | Numpad7 => "7",
+        Key8 | Numpad8 => "8",
+        Key9 | Numpad9 => "9",
+        Escape => if mac { "⎋" } else { "Esc" },
+        Insert => "Ins",
+        Home => if mac { "↖" } else { "Home" },
+        Delete => if mac { "⌦" } else { "Del" },
+        End => if mac { "↘" } else { "End" },
+        PageDown => if mac { "⇟" } else { "PgDn" },
+        PageUp => if mac { "⇞" } else { "PgUp" },
+        Left => if mac { "←" } else { "Left" },
+        Up => if mac { "↑" } else { "Up" },
+        Right => if mac { "→" } else { "Right" },
+        Down => if mac { "↓" } else { "Down" },
+        Back => if mac { "⌫" } else { "Backspace" },
+        Return | NumpadEnter => if mac { "↩" } else { "Enter" },
+        Space => "Space",
+        Tab => if mac { "⇥" } else { "Tab" },
+        Add => "+",
+        Subtract | Minus => "-",
+        Multiply => "*",
+        Divide | Slash => "/",
+        Decimal | Period => ".",
+        Comma | NumpadComma => ",",
+        Equals | NumpadEquals => "=",
+        Apostrophe => "'",
+        Backslash => "\\",
+        Grave => "`",
+        LBracket => "[",
+        RBracket => "]",
+        Semicolon => ";",
+        Colon => ":",
+        At => "@",
+        Snapshot => "PrtSc",
+        Pause => "Pause",
+        // the letters and the function keys are named like the variants
+        _ => return format!("{:?}", key),
+    };
+    name.to_owned()
+}
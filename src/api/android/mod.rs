@@ -10,16 +10,22 @@ use CreationError::OsError;
 use events::ElementState::{Pressed, Released};
 use events::{Touch, TouchPhase};
 
+use std::cmp;
 use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
 
 use Api;
 use ContextError;
 use CursorState;
 use GlAttributes;
 use GlContext;
+use LockKeys;
+use PointerAcceleration;
 use GlRequest;
 use PixelFormat;
 use PixelFormatRequirements;
+use SnapPosition;
 use WindowAttributes;
 use native_monitor::NativeMonitorId;
 
@@ -63,6 +69,26 @@ impl MonitorId {
     pub fn get_dimensions(&self) -> (u32, u32) {
         unimplemented!()
     }
+
+    #[inline]
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        None
+    }
 }
 
 #[derive(Clone, Default)]
@@ -78,21 +104,25 @@ impl<'a> Iterator for PollEventsIterator<'a> {
     type Item = Event;
 
     fn next(&mut self) -> Option<Event> {
-        match self.window.event_rx.try_recv() {
-            Ok(android_glue::Event::EventMotion(motion)) => {
-                Some(Event::Touch(Touch {
-                    phase: match motion.action {
-                        android_glue::MotionAction::Down => TouchPhase::Started,
-                        android_glue::MotionAction::Move => TouchPhase::Moved,
-                        android_glue::MotionAction::Up => TouchPhase::Ended,
-                        android_glue::MotionAction::Cancel => TouchPhase::Cancelled,
-                    },
-                    location: (motion.x as f64, motion.y as f64),
-                    id: motion.pointer_id as u64,
-                }))
-            }
-            _ => {
-                None
+        loop {
+            match self.window.event_rx.try_recv() {
+                Ok(android_glue::Event::EventMotion(motion)) => {
+                    return Some(Event::Touch(Touch {
+                        phase: match motion.action {
+                            android_glue::MotionAction::Down => TouchPhase::Started,
+                            android_glue::MotionAction::Move => TouchPhase::Moved,
+                            android_glue::MotionAction::Up => TouchPhase::Ended,
+                            android_glue::MotionAction::Cancel => TouchPhase::Cancelled,
+                        },
+                        location: (motion.x as f64, motion.y as f64),
+                        id: motion.pointer_id as u64,
+                    }));
+                },
+                Ok(android_glue::Event::GainedFocus) => return Some(Event::Focused(true)),
+                Ok(android_glue::Event::LostFocus) => return Some(Event::Focused(false)),
+                // skipping the events that glutin doesn't handle
+                Ok(_) => (),
+                Err(_) => return None,
             }
         }
     }
@@ -120,6 +150,16 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
 }
 
 impl Window {
+    /// Windows created by other libraries are not supported on this platform.
+    #[inline]
+    pub unsafe fn from_existing(_: *mut libc::c_void, _: *mut libc::c_void, _: &WindowAttributes,
+                                _: &PixelFormatRequirements, _: &GlAttributes<&Window>,
+                                _: &PlatformSpecificWindowBuilderAttributes)
+                                -> Result<Window, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     pub fn new(win_attribs: &WindowAttributes, pf_reqs: &PixelFormatRequirements,
                opengl: &GlAttributes<&Window>, _: &PlatformSpecificWindowBuilderAttributes)
                -> Result<Window, CreationError>
@@ -160,6 +200,11 @@ impl Window {
     pub fn set_title(&self, _: &str) {
     }
 
+    #[inline]
+    pub fn get_title(&self) -> Option<String> {
+        None
+    }
+
     #[inline]
     pub fn show(&self) {
     }
@@ -168,6 +213,18 @@ impl Window {
     pub fn hide(&self) {
     }
 
+    #[inline]
+    pub fn focus(&self) {
+    }
+
+    #[inline]
+    pub fn raise(&self) {
+    }
+
+    #[inline]
+    pub fn lower(&self) {
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<(i32, i32)> {
         None
@@ -177,6 +234,56 @@ impl Window {
     pub fn set_position(&self, _x: i32, _y: i32) {
     }
 
+    #[inline]
+    pub fn get_lock_keys(&self) -> LockKeys {
+        LockKeys::default()
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        self.context.make_not_current()
+    }
+
+    #[inline]
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        None
+    }
+
+    #[inline]
+    pub fn set_lock_keys(&self, _lock_keys: LockKeys) -> Result<(), String> {
+        Err("the lock keys can't be changed on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, _monitor: Option<MonitorId>) {
+    }
+
+    #[inline]
+    pub fn set_parent(&self, _parent: Option<::WindowID>) -> Result<(), String> {
+        Err("windows can't be embedded on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_menu_bar(&self, _menus: &[::Menu]) {
+    }
+
+    #[inline]
+    pub fn get_menu_bar_height(&self) -> u32 {
+        0
+    }
+
+    #[inline]
+    pub fn set_maximized(&self, _maximized: bool) {
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) {
+    }
+
+    #[inline]
+    pub fn snap_to(&self, _position: SnapPosition, _monitor: &MonitorId) {
+    }
+
     #[inline]
     pub fn get_inner_size(&self) -> Option<(u32, u32)> {
         let native_window = unsafe { android_glue::get_native_window() };
@@ -219,6 +326,13 @@ impl Window {
         }
     }
 
+    /// There is no way to block on the events of this platform, so this sleeps for a frame
+    /// at most.
+    pub fn wait_for_events(_: &[&Window], timeout: Option<Duration>) {
+        let frame = Duration::from_millis(16);
+        thread::sleep(timeout.map_or(frame, |timeout| cmp::min(timeout, frame)));
+    }
+
     #[inline]
     pub fn platform_display(&self) -> *mut libc::c_void {
         unimplemented!();
@@ -247,11 +361,51 @@ impl Window {
         Ok(())
     }
 
+    #[inline]
+    pub fn set_variable_refresh(&self, _enabled: bool) {
+    }
+
+    #[inline]
+    pub fn set_cursor_size(&self, _size: u32) {
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, _size: Option<(u32, u32)>) {
+    }
+
+    #[inline]
+    pub fn set_clipboard_string(&self, _text: &str) {
+    }
+
+    #[inline]
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        None
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, _size: Option<(u32, u32)>) {
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
         1.0
     }
 
+    #[inline]
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        self.context.set_swap_interval(interval)
+    }
+
     #[inline]
     pub fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), ()> {
         unimplemented!();
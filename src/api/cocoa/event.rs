@@ -1,5 +1,16 @@
+use cocoa::appkit::{self, NSEventModifierFlags};
 use events;
 
+/// Reads the modifiers from the flags of an `NSEvent`.
+pub fn modifiers_from_flags(flags: NSEventModifierFlags) -> events::ModifiersState {
+    events::ModifiersState {
+        shift: flags.contains(appkit::NSShiftKeyMask),
+        ctrl: flags.contains(appkit::NSControlKeyMask),
+        alt: flags.contains(appkit::NSAlternateKeyMask),
+        logo: flags.contains(appkit::NSCommandKeyMask),
+    }
+}
+
 pub fn vkeycode_to_element(code: u16) -> Option<events::VirtualKeyCode> {
     Some(match code {
         0x00 => events::VirtualKeyCode::A,
@@ -1,4 +1,5 @@
 
+use ColorSpace;
 use CreationError;
 use GlAttributes;
 use GlProfile;
@@ -62,6 +63,12 @@ pub fn build_nsattributes<T>(pf_reqs: &PixelFormatRequirements, opengl: &GlAttri
         return Err(CreationError::NoAvailablePixelFormat);
     }
 
+    // the framebuffer is always sRGB-capable
+    match pf_reqs.color_space {
+        ColorSpace::Default | ColorSpace::Srgb => (),
+        _ => return Err(CreationError::NoAvailablePixelFormat),
+    }
+
     if pf_reqs.stereoscopy {
         unimplemented!();   // TODO:
     }
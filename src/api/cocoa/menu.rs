@@ -0,0 +1,166 @@
+//! Menus of the global menu bar.
+//!
+//! The menus of the application are inserted between the application menu and the "View" menu
+//! that glutin creates. Their items send `glutinMenuItemSelected:` to the delegate of the window,
+//! with their ID as tag, and AppKit triggers their key equivalents by itself.
+//!
+//! The main menu belongs to the whole application, so the menus of the last window that called
+//! `set_menu_bar` replace the ones of the other windows. They are removed when that window is
+//! destroyed, since their items would otherwise target a freed delegate.
+
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+use objc::runtime::{YES, NO};
+
+use cocoa::base::{id, nil};
+use cocoa::foundation::{NSInteger, NSString};
+use cocoa::appkit::*;
+
+use Accelerator;
+use Menu;
+use MenuItem;
+
+/// The number of menus inserted in the main menu.
+static MENU_COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+/// The delegate that the inserted items target, as an address.
+static MENU_TARGET: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Replaces the menus that were inserted in the main menu by any window.
+///
+/// Must be called on the main thread, like all the functions of AppKit.
+pub unsafe fn set_menu_bar(target: id, menus: &[Menu]) {
+    let main_menu: id = msg_send![NSApp(), mainMenu];
+    if main_menu == nil {
+        return;
+    }
+
+    remove_inserted_menus(main_menu);
+
+    for (index, menu) in menus.iter().enumerate() {
+        let item = submenu_item(target, menu);
+        // the application menu is always the first one
+        let _: () = msg_send![main_menu, insertItem:item atIndex:(index + 1) as NSInteger];
+    }
+
+    MENU_COUNT.store(menus.len(), Ordering::SeqCst);
+    MENU_TARGET.store(target as usize, Ordering::SeqCst);
+}
+
+/// Removes the inserted menus if their items target the delegate that is being destroyed.
+pub unsafe fn remove_menus_of(target: id) {
+    if MENU_TARGET.load(Ordering::SeqCst) != target as usize {
+        return;
+    }
+
+    let main_menu: id = msg_send![NSApp(), mainMenu];
+    if main_menu != nil {
+        remove_inserted_menus(main_menu);
+    }
+    MENU_TARGET.store(0, Ordering::SeqCst);
+}
+
+unsafe fn remove_inserted_menus(main_menu: id) {
+    for _ in 0..MENU_COUNT.swap(0, Ordering::SeqCst) {
+        let _: () = msg_send![main_menu, removeItemAtIndex:1 as NSInteger];
+    }
+}
+
+/// Builds the item that opens a menu, in the menu bar or in a parent menu.
+unsafe fn submenu_item(target: id, menu: &Menu) -> id {
+    let title = NSString::alloc(nil).init_str(&menu.title);
+    let submenu = NSMenu::alloc(nil).initWithTitle_(title);
+    // the items are enabled according to the model, not to the responder chain
+    let _: () = msg_send![submenu, setAutoenablesItems:NO];
+
+    for item in menu.items.iter() {
+        match *item {
+            MenuItem::Action { id: tag, ref label, accelerator, enabled } => {
+                let (key, modifiers) = match accelerator.and_then(key_equivalent) {
+                    Some(equivalent) => equivalent,
+                    None => ("".to_owned(), NSEventModifierFlags::empty()),
+                };
+                let item = submenu.addItemWithTitle_action_keyEquivalent(
+                    NSString::alloc(nil).init_str(label),
+                    sel!(glutinMenuItemSelected:),
+                    NSString::alloc(nil).init_str(&key));
+                item.setKeyEquivalentModifierMask_(modifiers);
+                let _: () = msg_send![item, setTarget:target];
+                let _: () = msg_send![item, setTag:tag as NSInteger];
+                let _: () = msg_send![item, setEnabled:if enabled { YES } else { NO }];
+            },
+            MenuItem::Separator => submenu.addItem_(NSMenuItem::separatorItem(nil)),
+            MenuItem::Submenu(ref menu) => submenu.addItem_(submenu_item(target, menu)),
+        }
+    }
+
+    let item = NSMenuItem::alloc(nil).init();
+    item.setTitle_(title);
+    item.setSubmenu_(submenu);
+    item
+}
+
+/// Returns the key equivalent of an accelerator and its modifier mask, if AppKit can show it.
+fn key_equivalent(accelerator: Accelerator) -> Option<(String, NSEventModifierFlags)> {
+    use VirtualKeyCode::*;
+
+    let key = match accelerator.key {
+        A => 'a', B => 'b', C => 'c', D => 'd', E => 'e', F => 'f', G => 'g', H => 'h',
+        I => 'i', J => 'j', K => 'k', L => 'l', M => 'm', N => 'n', O => 'o', P => 'p',
+        Q => 'q', R => 'r', S => 's', T => 't', U => 'u', V => 'v', W => 'w', X => 'x',
+        Y => 'y', Z => 'z',
+        Key0 | Numpad0 => '0',
+        Key1 | Numpad1 => '1',
+        Key2 | Numpad2 => '2',
+        Key3 | Numpad3 => '3',
+        Key4 | Numpad4 => '4',
+        Key5 | Numpad5 => '5',
+        Key6 | Numpad6 => '6',
+        Key7 | Numpad7 => '7',
+        Key8 | Numpad8 => '8',
+        Key9 | Numpad9 => '9',
+        Add => '+',
+        Subtract | Minus => '-',
+        Multiply => '*',
+        Divide | Slash => '/',
+        Decimal | Period => '.',
+        Comma | NumpadComma => ',',
+        Equals | NumpadEquals => '=',
+        Apostrophe => '\'',
+        Backslash => '\\',
+        Grave => '`',
+        LBracket => '[',
+        RBracket => ']',
+        Semicolon => ';',
+        Colon => ':',
+        At => '@',
+        Space => ' ',
+        Tab => '\t',
+        Return | NumpadEnter => '\r',
+        Escape => '\u{1b}',
+        Back => '\u{8}',
+        // the private use characters of `NSEvent` for the function keys
+        Up => '\u{F700}',
+        Down => '\u{F701}',
+        Left => '\u{F702}',
+        Right => '\u{F703}',
+        F1 => '\u{F704}', F2 => '\u{F705}', F3 => '\u{F706}', F4 => '\u{F707}',
+        F5 => '\u{F708}', F6 => '\u{F709}', F7 => '\u{F70A}', F8 => '\u{F70B}',
+        F9 => '\u{F70C}', F10 => '\u{F70D}', F11 => '\u{F70E}', F12 => '\u{F70F}',
+        F13 => '\u{F710}', F14 => '\u{F711}', F15 => '\u{F712}',
+        Delete => '\u{F728}',
+        Home => '\u{F729}',
+        End => '\u{F72B}',
+        PageUp => '\u{F72C}',
+        PageDown => '\u{F72D}',
+        _ => return None,
+    };
+
+    let modifiers = accelerator.modifiers;
+    let mut mask = NSEventModifierFlags::empty();
+    if modifiers.ctrl { mask = mask | NSControlKeyMask; }
+    if modifiers.alt { mask = mask | NSAlternateKeyMask; }
+    if modifiers.shift { mask = mask | NSShiftKeyMask; }
+    if modifiers.logo { mask = mask | NSCommandKeyMask; }
+
+    Some((key.to_string(), mask))
+}
@@ -7,9 +7,14 @@ use libc;
 use ContextError;
 use GlAttributes;
 use GlContext;
+use LockKeys;
+use PointerAcceleration;
+use NativePixelFormatId;
 use PixelFormat;
 use PixelFormatRequirements;
+use ColorSpace;
 use Robustness;
+use SnapPosition;
 use WindowAttributes;
 use native_monitor::NativeMonitorId;
 use os::macos::ActivationPolicy;
@@ -21,7 +26,7 @@ use cgl::{CGLEnable, kCGLCECrashOnRemovedFunctions, CGLSetParameter, kCGLCPSurfa
 
 use cocoa::base::{id, nil};
 use cocoa::foundation::{NSAutoreleasePool, NSArray, NSDate, NSDefaultRunLoopMode, NSPoint, NSRect};
-use cocoa::foundation::{NSRunLoop, NSSize, NSString, NSUInteger};
+use cocoa::foundation::{NSInteger, NSRunLoop, NSSize, NSString, NSUInteger};
 use cocoa::appkit;
 use cocoa::appkit::*;
 use cocoa::appkit::NSEventSubtype::*;
@@ -43,6 +48,8 @@ use std::sync::Mutex;
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::env;
+use std::f64;
+use std::time::Duration;
 
 use events::ElementState;
 use events::{self, MouseButton, TouchPhase};
@@ -55,6 +62,7 @@ mod monitor;
 mod event;
 mod headless;
 mod helpers;
+mod menu;
 
 /// The height of the titlebar (draggable area for resizing) when decorations are off.
 ///
@@ -64,6 +72,9 @@ const TITLEBAR_HEIGHT: f64 = 32.0;
 /// The corner radius for the window.
 const CORNER_RADIUS: CGFloat = 6.0;
 
+/// The value of `NSPasteboardTypeString`.
+const PASTEBOARD_TYPE_STRING: &'static str = "public.utf8-plain-text";
+
 static mut shift_pressed: bool = false;
 static mut ctrl_pressed: bool = false;
 static mut win_pressed: bool = false;
@@ -79,6 +90,25 @@ struct DelegateState {
 
     /// Events that have been retreived with XLib but not dispatched with iterators yet
     pending_events: Mutex<VecDeque<Event>>,
+
+    /// The state of the cursor, and whether we have hidden it
+    cursor: Mutex<(CursorState, bool)>,
+}
+
+/// Hides or shows the cursor. `NSCursor` counts the calls to `hide` and `unhide`, so they are
+/// only made when the visibility changes.
+fn set_cursor_hidden(hidden: &mut bool, hide: bool) {
+    if *hidden == hide {
+        return;
+    }
+
+    let cls = Class::get("NSCursor").unwrap();
+    if hide {
+        let _: () = unsafe { msg_send![cls, hide] };
+    } else {
+        let _: () = unsafe { msg_send![cls, unhide] };
+    }
+    *hidden = hide;
 }
 
 struct WindowDelegate {
@@ -93,16 +123,17 @@ impl WindowDelegate {
         use std::sync::{Once, ONCE_INIT};
 
         extern fn window_should_close(this: &Object, _: Sel, _: id) -> BOOL {
-            unsafe {
+            ::api::panic::abort_on_panic("windowShouldClose:", || unsafe {
                 let state: *mut c_void = *this.get_ivar("glutinState");
                 let state = state as *mut DelegateState;
                 (*state).pending_events.lock().unwrap().push_back(Event::Closed);
-            }
-            YES
+                YES
+            })
         }
 
         extern fn window_did_resize(this: &Object, _: Sel, _: id) {
-            unsafe {
+            // the resize handler is user code, which must not unwind into Objective-C
+            ::api::panic::abort_on_panic("windowDidResize:", || unsafe {
                 let state: *mut c_void = *this.get_ivar("glutinState");
                 let state = &mut *(state as *mut DelegateState);
 
@@ -114,30 +145,59 @@ impl WindowDelegate {
                     (handler)((scale_factor * rect.size.width as f32) as u32,
                               (scale_factor * rect.size.height as f32) as u32);
                 }
-            }
+            })
         }
 
         extern fn window_did_become_key(this: &Object, _: Sel, _: id) {
-            unsafe {
+            ::api::panic::abort_on_panic("windowDidBecomeKey:", || unsafe {
                 // TODO: center the cursor if the window had mouse grab when it
                 // lost focus
 
                 let state: *mut c_void = *this.get_ivar("glutinState");
                 let state = state as *mut DelegateState;
+
+                // the soft cursor mode only hides the cursor while the window is key
+                let mut cursor = (*state).cursor.lock().unwrap();
+                if cursor.0 == CursorState::Soft {
+                    set_cursor_hidden(&mut cursor.1, true);
+                }
+
                 (*state).pending_events.lock().unwrap().push_back(Event::Focused(true));
-            }
+            })
         }
 
         extern fn window_did_resign_key(this: &Object, _: Sel, _: id) {
-            unsafe {
+            ::api::panic::abort_on_panic("windowDidResignKey:", || unsafe {
                 let state: *mut c_void = *this.get_ivar("glutinState");
                 let state = state as *mut DelegateState;
+
+                let mut cursor = (*state).cursor.lock().unwrap();
+                if cursor.0 == CursorState::Soft {
+                    set_cursor_hidden(&mut cursor.1, false);
+                }
+
                 (*state).pending_events.lock().unwrap().push_back(Event::Focused(false));
-            }
+            })
+        }
+
+        extern fn window_did_miniaturize(this: &Object, _: Sel, _: id) {
+            ::api::panic::abort_on_panic("windowDidMiniaturize:", || unsafe {
+                let state: *mut c_void = *this.get_ivar("glutinState");
+                let state = state as *mut DelegateState;
+                (*state).pending_events.lock().unwrap().push_back(Event::Minimized(true));
+            })
+        }
+
+        extern fn window_did_deminiaturize(this: &Object, _: Sel, _: id) {
+            ::api::panic::abort_on_panic("windowDidDeminiaturize:", || unsafe {
+                let state: *mut c_void = *this.get_ivar("glutinState");
+                let state = state as *mut DelegateState;
+                (*state).pending_events.lock().unwrap().push_back(Event::Minimized(false));
+            })
         }
 
         extern fn activate_with_view(this: &Object, _: Sel, view: id) {
-            unsafe {
+            ::api::panic::abort_on_panic("activateWithView:", || unsafe {
                 let this: *mut Object = this as *const Object as *mut Object;
                 let state: *mut c_void = *(*this).get_ivar("glutinState");
                 let state = state as *mut DelegateState;
@@ -153,7 +213,16 @@ impl WindowDelegate {
                 if !(*state).decorations {
                     update_surface_and_window_shape(view)
                 }
-            }
+            })
+        }
+
+        extern fn menu_item_selected(this: &Object, _: Sel, sender: id) {
+            ::api::panic::abort_on_panic("glutinMenuItemSelected:", || unsafe {
+                let state: *mut c_void = *this.get_ivar("glutinState");
+                let state = state as *mut DelegateState;
+                let tag: NSInteger = msg_send![sender, tag];
+                (*state).pending_events.lock().unwrap().push_back(Event::MenuItem(tag as u32));
+            })
         }
 
         static mut delegate_class: *const Class = 0 as *const Class;
@@ -174,9 +243,15 @@ impl WindowDelegate {
                 window_did_become_key as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowDidResignKey:),
                 window_did_resign_key as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidMiniaturize:),
+                window_did_miniaturize as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidDeminiaturize:),
+                window_did_deminiaturize as extern fn(&Object, Sel, id));
 
             decl.add_method(sel!(activateWithView:),
                 activate_with_view as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(glutinMenuItemSelected:),
+                menu_item_selected as extern fn(&Object, Sel, id));
 
             // Store internal state as user data
             decl.add_ivar::<*mut c_void>("glutinState");
@@ -209,7 +284,11 @@ impl Drop for WindowDelegate {
         unsafe {
             // Nil the window's delegate so it doesn't still reference us
             let _: () = msg_send![*self.state.window, setDelegate:nil];
+            // the items of the menu bar may target us as well
+            menu::remove_menus_of(*self.this);
         }
+        // the cursor is hidden for the whole application
+        set_cursor_hidden(&mut self.state.cursor.lock().unwrap().1, false);
     }
 }
 
@@ -307,6 +386,16 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
 }
 
 impl Window {
+    /// Windows created by other libraries are not supported on this platform.
+    #[inline]
+    pub unsafe fn from_existing(_: *mut libc::c_void, _: *mut libc::c_void, _: &WindowAttributes,
+                                _: &PixelFormatRequirements, _: &GlAttributes<&Window>,
+                                _: &PlatformSpecificWindowBuilderAttributes)
+                                -> Result<Window, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     pub fn new(win_attribs: &WindowAttributes, pf_reqs: &PixelFormatRequirements,
                opengl: &GlAttributes<&Window>,
                pl_attribs: &PlatformSpecificWindowBuilderAttributes)
@@ -316,10 +405,6 @@ impl Window {
             unimplemented!()
         }
 
-        // not implemented
-        assert!(win_attribs.min_dimensions.is_none());
-        assert!(win_attribs.max_dimensions.is_none());
-
         match opengl.robustness {
             Robustness::RobustNoResetNotification | Robustness::RobustLoseContextOnReset => {
                 return Err(CreationError::RobustnessNotSupported);
@@ -361,6 +446,7 @@ impl Window {
             visible: win_attribs.visible,
             decorations: win_attribs.decorations,
             pending_events: Mutex::new(VecDeque::new()),
+            cursor: Mutex::new((CursorState::Normal, false)),
         };
 
         let window = Window {
@@ -371,6 +457,13 @@ impl Window {
             delegate: WindowDelegate::new(ds),
         };
 
+        if win_attribs.min_dimensions.is_some() {
+            window.set_min_size(win_attribs.min_dimensions);
+        }
+        if win_attribs.max_dimensions.is_some() {
+            window.set_max_size(win_attribs.max_dimensions);
+        }
+
         unsafe {
             let run_loop: id = NSRunLoop::currentRunLoop();
             let modes: id = NSArray::arrayWithObject(nil, NSDefaultRunLoopMode);
@@ -474,6 +567,15 @@ impl Window {
                 NSTitledWindowMask as NSUInteger
             };
 
+            // the frame is the one of the content view, which is smaller than the window if the
+            // dimensions include the title bar
+            let frame = if screen.is_none() && attrs.outer_dimensions {
+                msg_send![Class::get("NSWindow").unwrap(), contentRectForFrameRect:frame
+                                                           styleMask:masks]
+            } else {
+                frame
+            };
+
             let window_class = match Class::get("GlutinWindow") {
                 Some(window_class) => window_class,
                 None => {
@@ -620,7 +722,11 @@ impl Window {
                             } else {
                                 None
                             },
+                            coverage_samples: None,
                             srgb: true,
+                            color_space: ColorSpace::Srgb,
+                            swap_method: None,
+                            native_id: NativePixelFormatId::Unavailable,
                         }
                     };
 
@@ -628,6 +734,17 @@ impl Window {
                     let value = if opengl.vsync { 1 } else { 0 };
                     cxt.setValues_forParameter_(&value, appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval);
 
+                    if opengl.strict_vsync {
+                        let mut swap: i32 = 0;
+                        let _: () = msg_send![*cxt, getValues:&mut swap
+                                              forParameter:appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval];
+                        if swap != value {
+                            return Err(CreationError::OsError(format!("Couldn't setup vsync: expected \
+                                                                       interval `{}` but got `{}`",
+                                                                      value, swap)));
+                        }
+                    }
+
                     CGLEnable(cxt.CGLContextObj() as *mut _, kCGLCECrashOnRemovedFunctions);
 
                     Ok((cxt, pf))
@@ -738,6 +855,17 @@ impl Window {
         }
     }
 
+    pub fn get_title(&self) -> Option<String> {
+        unsafe {
+            let title: id = msg_send![*self.window, title];
+            if title == nil {
+                return None;
+            }
+
+            Some(CStr::from_ptr(title.UTF8String()).to_string_lossy().into_owned())
+        }
+    }
+
     #[inline]
     pub fn show(&self) {
         unsafe { NSWindow::makeKeyAndOrderFront_(*self.window, nil); }
@@ -748,6 +876,23 @@ impl Window {
         unsafe { NSWindow::orderOut_(*self.window, nil); }
     }
 
+    pub fn focus(&self) {
+        unsafe {
+            NSApp().activateIgnoringOtherApps_(YES);
+            NSWindow::makeKeyAndOrderFront_(*self.window, nil);
+        }
+    }
+
+    #[inline]
+    pub fn raise(&self) {
+        unsafe { let _: () = msg_send![*self.window, orderFront:nil]; }
+    }
+
+    #[inline]
+    pub fn lower(&self) {
+        unsafe { let _: () = msg_send![*self.window, orderBack:nil]; }
+    }
+
     pub fn get_position(&self) -> Option<(i32, i32)> {
         unsafe {
             let content_rect = NSWindow::contentRectForFrameRect_(*self.window, NSWindow::frame(*self.window));
@@ -778,6 +923,81 @@ impl Window {
         }
     }
 
+    /// Only Caps Lock exists on OS X.
+    pub fn get_lock_keys(&self) -> LockKeys {
+        let flags: NSUInteger = unsafe { msg_send![Class::get("NSEvent").unwrap(), modifierFlags] };
+        LockKeys {
+            caps_lock: flags & NSAlphaShiftKeyMask as NSUInteger != 0,
+            .. LockKeys::default()
+        }
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        let _: () = msg_send![Class::get("NSOpenGLContext").unwrap(), clearCurrentContext];
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        None
+    }
+
+    #[inline]
+    pub fn set_lock_keys(&self, _lock_keys: LockKeys) -> Result<(), String> {
+        Err("the lock keys can't be changed on OS X".to_string())
+    }
+
+    /// Native fullscreen always happens on the screen of the window, so the monitor is ignored.
+    pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
+        unsafe {
+            let style_mask: NSUInteger = msg_send![*self.window, styleMask];
+            let is_fullscreen = style_mask & NSFullScreenWindowMask as NSUInteger != 0;
+            if is_fullscreen != monitor.is_some() {
+                let _: () = msg_send![*self.window, toggleFullScreen:nil];
+            }
+        }
+    }
+
+    pub fn set_parent(&self, _parent: Option<::WindowID>) -> Result<(), String> {
+        Err("windows can't be embedded on OS X".to_string())
+    }
+
+    #[inline]
+    pub fn set_menu_bar(&self, menus: &[::Menu]) {
+        unsafe { menu::set_menu_bar(*self.delegate.this, menus) }
+    }
+
+    /// The menus are in the global menu bar.
+    #[inline]
+    pub fn get_menu_bar_height(&self) -> u32 {
+        0
+    }
+
+    pub fn set_maximized(&self, maximized: bool) {
+        unsafe {
+            let is_zoomed: BOOL = msg_send![*self.window, isZoomed];
+            if (is_zoomed == YES) != maximized {
+                let _: () = msg_send![*self.window, zoom:nil];
+            }
+        }
+    }
+
+    pub fn set_minimized(&self, minimized: bool) {
+        unsafe {
+            if minimized {
+                let _: () = msg_send![*self.window, miniaturize:nil];
+            } else {
+                let _: () = msg_send![*self.window, deminiaturize:nil];
+            }
+        }
+    }
+
+    #[inline]
+    pub fn snap_to(&self, _position: SnapPosition, _monitor: &MonitorId) {
+        // TODO: compute the frame from the `visibleFrame` of the screen
+    }
+
     #[inline]
     pub fn get_inner_size(&self) -> Option<(u32, u32)> {
         unsafe {
@@ -820,11 +1040,41 @@ impl Window {
         }
     }
 
+    /// Blocks until one of the windows may have events, or until the timeout expires.
+    ///
+    /// The events of all the windows go through the queue of the application, which is only
+    /// peeked at: the event is left for the next poll.
+    pub fn wait_for_events(windows: &[&Window], timeout: Option<Duration>) {
+        if windows.iter().any(|w| !w.delegate.state.pending_events.lock().unwrap().is_empty()) {
+            return;
+        }
+
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+
+            let until = match timeout {
+                Some(timeout) => {
+                    let seconds = timeout.as_secs() as f64 + timeout.subsec_nanos() as f64 / 1e9;
+                    msg_send![Class::get("NSDate").unwrap(), dateWithTimeIntervalSinceNow:seconds]
+                },
+                None => NSDate::distantFuture(nil)
+            };
+            let _: id = appkit::NSApp().nextEventMatchingMask_untilDate_inMode_dequeue_(
+                appkit::NSAnyEventMask.bits() | appkit::NSEventMaskPressure.bits(),
+                until,
+                NSDefaultRunLoopMode,
+                NO);
+
+            let _: () = msg_send![pool, release];
+        }
+    }
+
     unsafe fn modifier_event(event: id, keymask: appkit::NSEventModifierFlags, key: events::VirtualKeyCode, key_pressed: bool) -> Option<Event> {
+        let modifiers = event::modifiers_from_flags(NSEvent::modifierFlags(event));
         if !key_pressed && NSEvent::modifierFlags(event).contains(keymask) {
-            return Some(Event::KeyboardInput(ElementState::Pressed, NSEvent::keyCode(event) as u8, Some(key)));
+            return Some(Event::KeyboardInput(ElementState::Pressed, NSEvent::keyCode(event) as u8, Some(key), modifiers));
         } else if key_pressed && !NSEvent::modifierFlags(event).contains(keymask) {
-            return Some(Event::KeyboardInput(ElementState::Released, NSEvent::keyCode(event) as u8, Some(key)));
+            return Some(Event::KeyboardInput(ElementState::Released, NSEvent::keyCode(event) as u8, Some(key), modifiers));
         }
 
         return None;
@@ -884,26 +1134,89 @@ impl Window {
     }
 
     pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
-        let cls = Class::get("NSCursor").unwrap();
+        let mut cursor = self.delegate.state.cursor.lock().unwrap();
+        cursor.0 = state;
 
         // TODO: Check for errors.
         match state {
             CursorState::Normal => {
-                let _: () = unsafe { msg_send![cls, unhide] };
+                set_cursor_hidden(&mut cursor.1, false);
                 let _: i32 = unsafe { CGAssociateMouseAndMouseCursorPosition(true) };
                 Ok(())
             },
             CursorState::Hide => {
-                let _: () = unsafe { msg_send![cls, hide] };
+                set_cursor_hidden(&mut cursor.1, true);
+                Ok(())
+            },
+            // shown again by `windowDidResignKey:`, and hidden by `windowDidBecomeKey:`
+            CursorState::Soft => {
+                let key: BOOL = unsafe { msg_send![*self.window, isKeyWindow] };
+                set_cursor_hidden(&mut cursor.1, key == YES);
                 Ok(())
             },
-            CursorState::Grab => {
+            // the cursor doesn't move while it is dissociated from the mouse, so it never
+            // reaches the edges of the screen
+            CursorState::Grab | CursorState::GrabRelative => {
                 let _: i32 = unsafe { CGAssociateMouseAndMouseCursorPosition(false) };
                 Ok(())
             }
         }
     }
 
+    #[inline]
+    pub fn set_variable_refresh(&self, _enabled: bool) {
+    }
+
+    #[inline]
+    pub fn set_cursor_size(&self, _size: u32) {
+        // the size of the cursor is a system-wide setting that applications can't override
+    }
+
+    pub fn set_clipboard_string(&self, text: &str) {
+        unsafe {
+            let pasteboard: id = msg_send![Class::get("NSPasteboard").unwrap(), generalPasteboard];
+            let _: NSInteger = msg_send![pasteboard, clearContents];
+
+            let text = IdRef::new(NSString::alloc(nil).init_str(text));
+            let ty = IdRef::new(NSString::alloc(nil).init_str(PASTEBOARD_TYPE_STRING));
+            let _: BOOL = msg_send![pasteboard, setString:*text forType:*ty];
+        }
+    }
+
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        unsafe {
+            let pasteboard: id = msg_send![Class::get("NSPasteboard").unwrap(), generalPasteboard];
+
+            let ty = IdRef::new(NSString::alloc(nil).init_str(PASTEBOARD_TYPE_STRING));
+            let text: id = msg_send![pasteboard, stringForType:*ty];
+            if text == nil {
+                return None;
+            }
+
+            Some(CStr::from_ptr(text.UTF8String()).to_string_lossy().into_owned())
+        }
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, size: Option<(u32, u32)>) {
+        let (width, height) = size.unwrap_or((0, 0));
+        unsafe {
+            let size = NSSize::new(width as f64, height as f64);
+            let _: () = msg_send![*self.window, setContentMinSize:size];
+        }
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, size: Option<(u32, u32)>) {
+        let size = match size {
+            Some((width, height)) => NSSize::new(width as f64, height as f64),
+            None => NSSize::new(f64::MAX, f64::MAX),
+        };
+        unsafe {
+            let _: () = msg_send![*self.window, setContentMaxSize:size];
+        }
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
         unsafe {
@@ -911,6 +1224,31 @@ impl Window {
         }
     }
 
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        let (window_x, window_y) = match self.get_position() {
+            Some(position) => position,
+            None => return None
+        };
+        let (x, y) = cursor_location();
+        let scale_factor = self.hidpi_factor() as f64;
+        Some(((scale_factor * (x - window_x as f64)) as i32,
+              (scale_factor * (y - window_y as f64)) as i32))
+    }
+
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        let (x, y) = cursor_location();
+        let scale_factor = self.hidpi_factor() as f64;
+        Some(((scale_factor * x) as i32, (scale_factor * y) as i32))
+    }
+
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        let value = interval as i32;
+        unsafe {
+            self.context.setValues_forParameter_(&value, appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval);
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), ()> {
         let (window_x, window_y) = self.get_position().unwrap_or((0, 0));
@@ -1027,6 +1365,17 @@ impl Clone for IdRef {
     }
 }
 
+/// Returns the position of the cursor on the screen in points, with the origin at the top-left
+/// corner of the main display.
+fn cursor_location() -> (f64, f64) {
+    unsafe {
+        let location: NSPoint = msg_send![Class::get("NSEvent").unwrap(), mouseLocation];
+        // Cocoa puts the origin at the bottom-left corner of the main display
+        let main_height = CGDisplayPixelsHigh(CGMainDisplayID()) as f64;
+        (location.x, main_height - location.y)
+    }
+}
+
 #[allow(non_snake_case, non_upper_case_globals)]
 unsafe fn NSEventToEvent(window: &Window, nsevent: id) -> Option<Event> {
     unsafe fn get_mouse_position(window: &Window, nsevent: id) -> (i32, i32) {
@@ -1055,19 +1404,23 @@ unsafe fn NSEventToEvent(window: &Window, nsevent: id) -> Option<Event> {
     match event_type {
         NSLeftMouseDown         => {
             Some(Event::MouseInput(ElementState::Pressed, MouseButton::Left,
-                                   Some(get_mouse_position(window, nsevent))))
+                                   Some(get_mouse_position(window, nsevent)),
+                                   event::modifiers_from_flags(NSEvent::modifierFlags(nsevent))))
         },
         NSLeftMouseUp           => {
             Some(Event::MouseInput(ElementState::Released, MouseButton::Left,
-                                   Some(get_mouse_position(window, nsevent))))
+                                   Some(get_mouse_position(window, nsevent)),
+                                   event::modifiers_from_flags(NSEvent::modifierFlags(nsevent))))
         },
         NSRightMouseDown        => {
             Some(Event::MouseInput(ElementState::Pressed, MouseButton::Right,
-                                   Some(get_mouse_position(window, nsevent))))
+                                   Some(get_mouse_position(window, nsevent)),
+                                   event::modifiers_from_flags(NSEvent::modifierFlags(nsevent))))
         },
         NSRightMouseUp          => {
             Some(Event::MouseInput(ElementState::Released, MouseButton::Right,
-                                   Some(get_mouse_position(window, nsevent))))
+                                   Some(get_mouse_position(window, nsevent)),
+                                   event::modifiers_from_flags(NSEvent::modifierFlags(nsevent))))
         },
         NSMouseMoved            |
         NSLeftMouseDragged      |
@@ -1080,12 +1433,17 @@ unsafe fn NSEventToEvent(window: &Window, nsevent: id) -> Option<Event> {
             let mut events = VecDeque::new();
             let received_c_str = nsevent.characters().UTF8String();
             let received_str = CStr::from_ptr(received_c_str);
-            for received_char in from_utf8(received_str.to_bytes()).unwrap().chars() {
+            let received_str = from_utf8(received_str.to_bytes()).unwrap();
+            if !received_str.is_empty() {
+                events.push_back(Event::ReceivedText(received_str.to_owned()));
+            }
+            for received_char in received_str.chars() {
                 events.push_back(Event::ReceivedCharacter(received_char));
             }
 
             let vkey =  event::vkeycode_to_element(NSEvent::keyCode(nsevent));
-            events.push_back(Event::KeyboardInput(ElementState::Pressed, NSEvent::keyCode(nsevent) as u8, vkey));
+            events.push_back(Event::KeyboardInput(ElementState::Pressed, NSEvent::keyCode(nsevent) as u8, vkey,
+                                              event::modifiers_from_flags(NSEvent::modifierFlags(nsevent))));
             let event = events.pop_front();
             window.delegate.state.pending_events.lock().unwrap().extend(events.into_iter());
             event
@@ -1093,7 +1451,8 @@ unsafe fn NSEventToEvent(window: &Window, nsevent: id) -> Option<Event> {
         appkit::NSKeyUp => {
             let vkey =  event::vkeycode_to_element(NSEvent::keyCode(nsevent));
 
-            Some(Event::KeyboardInput(ElementState::Released, NSEvent::keyCode(nsevent) as u8, vkey))
+            Some(Event::KeyboardInput(ElementState::Released, NSEvent::keyCode(nsevent) as u8, vkey,
+                                              event::modifiers_from_flags(NSEvent::modifierFlags(nsevent))))
         },
         appkit::NSFlagsChanged => {
             let mut events = VecDeque::new();
@@ -1131,16 +1490,28 @@ unsafe fn NSEventToEvent(window: &Window, nsevent: id) -> Option<Event> {
                 LineDelta(scale_factor * nsevent.scrollingDeltaX() as f32,
                           scale_factor * nsevent.scrollingDeltaY() as f32)
             };
-            let phase = match nsevent.phase() {
+            // once the fingers are lifted, the inertial scrolling has its own phases
+            let momentum = nsevent.momentumPhase() != appkit::NSEventPhaseNone;
+            let phase = if momentum { nsevent.momentumPhase() } else { nsevent.phase() };
+            let phase = match phase {
                 appkit::NSEventPhaseMayBegin | appkit::NSEventPhaseBegan => TouchPhase::Started,
                 appkit::NSEventPhaseEnded => TouchPhase::Ended,
+                appkit::NSEventPhaseCancelled => TouchPhase::Cancelled,
                 _ => TouchPhase::Moved,
             };
-            Some(Event::MouseWheel(delta, phase))
+            Some(Event::MouseWheel(delta, phase, momentum))
         },
         appkit::NSEventTypePressure => {
             Some(Event::TouchpadPressure(nsevent.pressure(), nsevent.stage()))
         },
+        appkit::NSEventTypeMagnify => {
+            let magnification: CGFloat = msg_send![nsevent, magnification];
+            Some(Event::PinchGesture(magnification as f32))
+        },
+        appkit::NSEventTypeRotate => {
+            let rotation: f32 = msg_send![nsevent, rotation];
+            Some(Event::RotateGesture(rotation))
+        },
         _  => { None },
     }
 }
@@ -1203,11 +1574,13 @@ fn update_surface_and_window_shape(view: id) {
 
 // Called whenever
 extern fn surface_geometry_changed(this: &Object, _: Sel, _: id) {
-    update_surface_and_window_shape(this as *const Object as *mut Object)
+    ::api::panic::abort_on_panic("_surfaceResized:", || {
+        update_surface_and_window_shape(this as *const Object as *mut Object)
+    })
 }
 
 extern fn draw_rect_in_glutin_content_view(this: &Object, _: Sel, _: NSRect) {
-    unsafe {
+    ::api::panic::abort_on_panic("drawRect:", || unsafe {
         let this: *mut Object = this as *const Object as *mut Object;
         if *(*this).get_ivar("drawnOnce") {
             // Draw this only once. This is expensive since it paints on CPU, and it seems only
@@ -1222,7 +1595,7 @@ extern fn draw_rect_in_glutin_content_view(this: &Object, _: Sel, _: NSRect) {
         NSRectFill(bounds);
 
         (*this).set_ivar("drawnOnce", true);
-    }
+    })
 }
 
 /// Creates a `CGSRegion` describing a rounded rect with the given dimensions and radius.
@@ -1,7 +1,41 @@
+use cocoa::appkit::NSScreen;
+use cocoa::base::{id, nil};
+use cocoa::foundation::{NSString, NSUInteger};
+use core_foundation::base::TCFType;
+use core_foundation::data::{CFData, CFDataRef};
+use core_foundation::string::CFString;
 use core_graphics::display;
+use libc::c_void;
 use std::collections::VecDeque;
+use std::str::FromStr;
 use native_monitor::NativeMonitorId;
 
+type CGColorSpaceRef = *const c_void;
+type CFDictionaryRef = *const c_void;
+type IOServiceT = u32;
+
+/// Only reads the name of the display in the preferred language, which skips the other names.
+const IO_DISPLAY_ONLY_PREFERRED_NAME: u32 = 0x00000200;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn CGDisplayCopyColorSpace(display: u32) -> CGColorSpaceRef;
+    fn CGColorSpaceCopyICCProfile(space: CGColorSpaceRef) -> CFDataRef;
+    fn CGColorSpaceRelease(space: CGColorSpaceRef);
+    fn CGDisplayIOServicePort(display: u32) -> IOServiceT;
+}
+
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn IODisplayCreateInfoDictionary(framebuffer: IOServiceT, options: u32) -> CFDictionaryRef;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFDictionaryGetValue(dictionary: CFDictionaryRef, key: *const c_void) -> *const c_void;
+    fn CFRelease(object: *const c_void);
+}
+
 #[derive(Clone)]
 pub struct MonitorId(u32);
 
@@ -53,4 +87,94 @@ impl MonitorId {
         };
         dimension
     }
+
+    /// Returns the part of the display that is not covered by the menu bar and the Dock, as
+    /// `(x, y, width, height)` with the origin at the top-left corner of the main display.
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        let MonitorId(display_id) = *self;
+        unsafe {
+            let screens = NSScreen::screens(nil);
+            let count: NSUInteger = msg_send![screens, count];
+            let key = NSString::alloc(nil).init_str("NSScreenNumber");
+            let mut visible_frame = None;
+            for i in 0..count {
+                let screen: id = msg_send![screens, objectAtIndex:i];
+                let value: id = msg_send![NSScreen::deviceDescription(screen), objectForKey:key];
+                if value != nil {
+                    let screen_number: NSUInteger = msg_send![value, unsignedIntegerValue];
+                    if screen_number as u32 == display_id {
+                        visible_frame = Some(NSScreen::visibleFrame(screen));
+                        break;
+                    }
+                }
+            }
+            let _: () = msg_send![key, release];
+
+            match visible_frame {
+                Some(frame) => {
+                    // Cocoa puts the origin at the bottom-left corner
+                    let main_height = display::CGDisplayPixelsHigh(display::CGMainDisplayID()) as f64;
+                    Some((frame.origin.x as i32, (main_height - frame.origin.y - frame.size.height) as i32,
+                          frame.size.width as u32, frame.size.height as u32))
+                },
+                None => {
+                    let (width, height) = self.get_dimensions();
+                    Some((0, 0, width, height))
+                },
+            }
+        }
+    }
+
+    /// Reads the `IODisplayEDID` property of the display in the IOKit registry.
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        let MonitorId(display_id) = *self;
+        unsafe {
+            let service = CGDisplayIOServicePort(display_id);
+            if service == 0 {
+                return None;
+            }
+
+            let info = IODisplayCreateInfoDictionary(service, IO_DISPLAY_ONLY_PREFERRED_NAME);
+            if info.is_null() {
+                return None;
+            }
+
+            let key: CFString = FromStr::from_str("IODisplayEDID").unwrap();
+            let edid = CFDictionaryGetValue(info, key.as_concrete_TypeRef() as *const c_void);
+            // the value is owned by the dictionary
+            let result = if edid.is_null() {
+                None
+            } else {
+                Some(CFData::wrap_under_get_rule(edid as CFDataRef).bytes().to_vec())
+            };
+
+            CFRelease(info);
+            result
+        }
+    }
+
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        false
+    }
+
+    /// Returns the ICC profile that ColorSync assigns to the display.
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        let MonitorId(display_id) = *self;
+        unsafe {
+            let space = CGDisplayCopyColorSpace(display_id);
+            if space.is_null() {
+                return None;
+            }
+
+            let data = CGColorSpaceCopyICCProfile(space);
+            CGColorSpaceRelease(space);
+            if data.is_null() {
+                return None;
+            }
+
+            let data = CFData::wrap_under_create_rule(data);
+            Some(data.bytes().to_vec())
+        }
+    }
 }
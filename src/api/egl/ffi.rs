@@ -35,3 +35,10 @@ pub type EGLNativeWindowType = *const libc::c_void;
 pub type EGLNativeWindowType = *const libc::c_void;
 #[cfg(any(target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
 pub type EGLNativeWindowType = *const libc::c_void;
+
+// constants of extensions that are too recent for the registry used by gl_generator
+pub const COLOR_COMPONENT_TYPE_EXT: EGLint = 0x3339;
+pub const COLOR_COMPONENT_TYPE_FLOAT_EXT: EGLint = 0x333B;
+pub const GL_COLORSPACE_BT2020_PQ_EXT: EGLint = 0x3340;
+pub const GL_COLORSPACE_DISPLAY_P3_EXT: EGLint = 0x3363;
+pub const GL_COLORSPACE_SCRGB_LINEAR_EXT: EGLint = 0x3350;
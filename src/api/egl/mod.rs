@@ -6,16 +6,22 @@ use ContextError;
 use CreationError;
 use GlAttributes;
 use GlContext;
+use GlProfile;
 use GlRequest;
+use NativePixelFormatId;
 use PixelFormat;
 use PixelFormatRequirements;
 use ReleaseBehavior;
 use Robustness;
 use Api;
+use ColorSpace;
+use SwapMethod;
 
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_void, c_int};
-use std::{mem, ptr};
+use std::{cmp, io, mem, ptr};
+
+use api::pixel_format;
 
 pub mod ffi;
 
@@ -33,8 +39,14 @@ pub enum NativeDisplay {
     Device(ffi::EGLNativeDisplayType),
     /// Don't specify any display type. Useful on windows. `None` means `EGL_DEFAULT_DISPLAY`.
     Other(Option<ffi::EGLNativeDisplayType>),
+    /// Mesa's surfaceless platform, which doesn't need any display server. Only pbuffers can be
+    /// created on it.
+    Surfaceless,
 }
 
+/// The platform of `EGL_MESA_platform_surfaceless`.
+const PLATFORM_SURFACELESS_MESA: ffi::egl::types::EGLenum = 0x31DD;
+
 pub struct Context {
     egl: ffi::egl::Egl,
     display: ffi::egl::types::EGLDisplay,
@@ -142,6 +154,23 @@ fn get_native_display(egl: &ffi::egl::Egl,
                                             ptr::null()) }
         },
 
+        NativeDisplay::Surfaceless if has_dp_extension("EGL_MESA_platform_surfaceless") &&
+                                      egl.GetPlatformDisplay.is_loaded() =>
+        {
+            unsafe { egl.GetPlatformDisplay(PLATFORM_SURFACELESS_MESA,
+                                            ffi::egl::DEFAULT_DISPLAY as *mut _, ptr::null()) }
+        },
+
+        NativeDisplay::Surfaceless if has_dp_extension("EGL_MESA_platform_surfaceless") &&
+                                      egl.GetPlatformDisplayEXT.is_loaded() =>
+        {
+            unsafe { egl.GetPlatformDisplayEXT(PLATFORM_SURFACELESS_MESA,
+                                               ffi::egl::DEFAULT_DISPLAY as *mut _, ptr::null()) }
+        },
+
+        // the default display would try to connect to a display server
+        NativeDisplay::Surfaceless => ptr::null(),
+
         NativeDisplay::X11(Some(display)) | NativeDisplay::Gbm(Some(display)) |
         NativeDisplay::Wayland(Some(display)) | NativeDisplay::Device(display) |
         NativeDisplay::Other(Some(display)) => {
@@ -169,6 +198,12 @@ impl Context {
             unimplemented!()
         }
 
+        // the surfaceless platform has no window, so the configs must support pbuffers instead
+        let pbuffer_only = match native_display {
+            NativeDisplay::Surfaceless => true,
+            _ => false,
+        };
+
         // calling `eglGetDisplay` or equivalent
         let display = get_native_display(&egl, native_display);
 
@@ -177,8 +212,8 @@ impl Context {
         }
 
         let egl_version = unsafe {
-            let mut major: ffi::egl::types::EGLint = mem::uninitialized();
-            let mut minor: ffi::egl::types::EGLint = mem::uninitialized();
+            let mut major: ffi::egl::types::EGLint = mem::zeroed();
+            let mut minor: ffi::egl::types::EGLint = mem::zeroed();
 
             if egl.Initialize(display, &mut major, &mut minor) == 0 {
                 return Err(CreationError::OsError(format!("eglInitialize failed")))
@@ -249,7 +284,8 @@ impl Context {
         };
 
         let (config_id, pixel_format) = unsafe {
-            try!(choose_fbconfig(&egl, display, &egl_version, api, version, pf_reqs))
+            try!(choose_fbconfig(&egl, display, &egl_version, &extensions, api, version, pf_reqs,
+                                 pbuffer_only))
         };
 
         Ok(ContextPrototype {
@@ -262,18 +298,54 @@ impl Context {
             version: version,
             config_id: config_id,
             pixel_format: pixel_format,
+            swap_method: pf_reqs.swap_method,
         })
     }
 }
 
+impl Context {
+    /// Changes the number of vertical blanks that `swap_buffers` waits for. The context must be
+    /// current.
+    ///
+    /// The interval is clamped to the range supported by the config. It belongs to the surface
+    /// of the window, so every window keeps its own.
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        // `eglSwapInterval` changes the interval of the current surface, which may be the one
+        // of another window that shares the context
+        if unsafe { self.egl.GetCurrentSurface(ffi::egl::DRAW as i32) } != self.surface {
+            return Err("the window must be current to change its swap interval".to_string());
+        }
+
+        match unsafe { self.egl.SwapInterval(self.display, interval as c_int) } {
+            0 => Err("eglSwapInterval failed".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Unbinds the current context of the calling thread, whichever it is.
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        let ret = self.egl.MakeCurrent(self.display, ffi::egl::NO_SURFACE, ffi::egl::NO_SURFACE,
+                                       ffi::egl::NO_CONTEXT);
+
+        if ret == 0 {
+            Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                format!("eglMakeCurrent failed (eglGetError returned 0x{:x})",
+                        self.egl.GetError()))))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl GlContext for Context {
     unsafe fn make_current(&self) -> Result<(), ContextError> {
         let ret = self.egl.MakeCurrent(self.display, self.surface, self.surface, self.context);
 
         if ret == 0 {
             match self.egl.GetError() as u32 {
-                ffi::egl::CONTEXT_LOST => return Err(ContextError::ContextLost),
-                err => panic!("eglMakeCurrent failed (eglGetError returned 0x{:x})", err)
+                ffi::egl::CONTEXT_LOST => Err(ContextError::ContextLost),
+                err => Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                    format!("eglMakeCurrent failed (eglGetError returned 0x{:x})", err)))),
             }
 
         } else {
@@ -302,8 +374,9 @@ impl GlContext for Context {
 
         if ret == 0 {
             match unsafe { self.egl.GetError() } as u32 {
-                ffi::egl::CONTEXT_LOST => return Err(ContextError::ContextLost),
-                err => panic!("eglSwapBuffers failed (eglGetError returned 0x{:x})", err)
+                ffi::egl::CONTEXT_LOST => Err(ContextError::ContextLost),
+                err => Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                    format!("eglSwapBuffers failed (eglGetError returned 0x{:x})", err)))),
             }
 
         } else {
@@ -347,11 +420,12 @@ pub struct ContextPrototype<'a> {
     version: Option<(u8, u8)>,
     config_id: ffi::egl::types::EGLConfig,
     pixel_format: PixelFormat,
+    swap_method: Option<SwapMethod>,
 }
 
 impl<'a> ContextPrototype<'a> {
     pub fn get_native_visual_id(&self) -> ffi::egl::types::EGLint {
-        let mut value = unsafe { mem::uninitialized() };
+        let mut value = unsafe { mem::zeroed() };
         let ret = unsafe { self.egl.GetConfigAttrib(self.display, self.config_id,
                                                     ffi::egl::NATIVE_VISUAL_ID
                                                     as ffi::egl::types::EGLint, &mut value) };
@@ -359,19 +433,86 @@ impl<'a> ContextPrototype<'a> {
         value
     }
 
-    pub fn finish(self, native_window: ffi::EGLNativeWindowType)
+    pub fn finish(mut self, native_window: ffi::EGLNativeWindowType)
                   -> Result<Context, CreationError>
     {
+        let attrs = match color_space_attribute(self.pixel_format.color_space) {
+            Some(value) => vec![ffi::egl::GL_COLORSPACE as c_int, value as c_int],
+            None => vec![],
+        };
+        let attrs = attrs.into_iter().chain(Some(ffi::egl::NONE as c_int)).collect::<Vec<_>>();
+
         let surface = unsafe {
             let surface = self.egl.CreateWindowSurface(self.display, self.config_id, native_window,
-                                                       ptr::null());
+                                                       attrs.as_ptr());
             if surface.is_null() {
                 return Err(CreationError::OsError(format!("eglCreateWindowSurface failed")))
             }
             surface
         };
 
-        self.finish_impl(surface)
+        // the content of the back buffer is destroyed by default, even if the config
+        // supports preserving it
+        if self.swap_method == Some(SwapMethod::Copy) {
+            unsafe {
+                self.egl.SurfaceAttrib(self.display, surface, ffi::egl::SWAP_BEHAVIOR as c_int,
+                                       ffi::egl::BUFFER_PRESERVED as c_int);
+            }
+        }
+
+        self.pixel_format.swap_method = unsafe {
+            let mut value = mem::zeroed();
+            if self.egl.QuerySurface(self.display, surface, ffi::egl::SWAP_BEHAVIOR as c_int,
+                                     &mut value) == 0
+            {
+                None
+            } else if value == ffi::egl::BUFFER_PRESERVED as c_int {
+                Some(SwapMethod::Copy)
+            } else {
+                Some(SwapMethod::Undefined)
+            }
+        };
+
+        // the swap interval of EGL is `1` by default, and silently clamped to the range of the
+        // config
+        let swap_interval = if self.opengl.strict_vsync {
+            let interval = if self.opengl.vsync { 1 } else { 0 };
+            let (min, max) = unsafe {
+                let mut min = 0;
+                let mut max = 0;
+                self.egl.GetConfigAttrib(self.display, self.config_id,
+                                         ffi::egl::MIN_SWAP_INTERVAL as c_int, &mut min);
+                self.egl.GetConfigAttrib(self.display, self.config_id,
+                                         ffi::egl::MAX_SWAP_INTERVAL as c_int, &mut max);
+                (min, max)
+            };
+            if interval < min || interval > max {
+                unsafe { self.egl.DestroySurface(self.display, surface) };
+                return Err(CreationError::OsError(format!("Couldn't setup vsync: the swap \
+                                                           interval must be between `{}` and \
+                                                           `{}`", min, max)));
+            }
+            Some(interval)
+        } else {
+            None
+        };
+
+        let context = try!(self.finish_impl(surface));
+
+        if let Some(interval) = swap_interval {
+            unsafe {
+                let ok = context.egl.MakeCurrent(context.display, context.surface,
+                                                 context.surface, context.context) != 0 &&
+                         context.egl.SwapInterval(context.display, interval) != 0;
+                context.egl.MakeCurrent(context.display, ffi::egl::NO_SURFACE,
+                                        ffi::egl::NO_SURFACE, ffi::egl::NO_CONTEXT);
+                if !ok {
+                    return Err(CreationError::OsError(format!("eglSwapInterval failed")));
+                }
+            }
+        }
+
+        Ok(context)
     }
 
     pub fn finish_pbuffer(self, dimensions: (u32, u32)) -> Result<Context, CreationError> {
@@ -400,18 +541,23 @@ impl<'a> ContextPrototype<'a> {
             if let Some(version) = self.version {
                 try!(create_context(&self.egl, self.display, &self.egl_version,
                                     &self.extensions, self.api, version, self.config_id,
-                                    self.opengl.debug, self.opengl.robustness))
+                                    self.opengl.debug,
+                                    self.opengl.forward_compatible,
+                                    self.opengl.robustness, self.opengl.profile))
 
             } else if self.api == Api::OpenGlEs {
                 if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
                                                  &self.extensions, self.api, (2, 0), self.config_id,
-                                                 self.opengl.debug, self.opengl.robustness)
+                                                 self.opengl.debug,
+                                                 self.opengl.forward_compatible,
+                                                 self.opengl.robustness, self.opengl.profile)
                 {
                     ctxt
                 } else if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
                                                         &self.extensions, self.api, (1, 0),
                                                         self.config_id, self.opengl.debug,
-                                                        self.opengl.robustness)
+                                                        self.opengl.forward_compatible,
+                                                        self.opengl.robustness, self.opengl.profile)
                 {
                     ctxt
                 } else {
@@ -421,19 +567,23 @@ impl<'a> ContextPrototype<'a> {
             } else {
                 if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
                                                  &self.extensions, self.api, (3, 2), self.config_id,
-                                                 self.opengl.debug, self.opengl.robustness)
+                                                 self.opengl.debug,
+                                                 self.opengl.forward_compatible,
+                                                 self.opengl.robustness, self.opengl.profile)
                 {
                     ctxt
                 } else if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
                                                         &self.extensions, self.api, (3, 1),
                                                         self.config_id, self.opengl.debug,
-                                                        self.opengl.robustness)
+                                                        self.opengl.forward_compatible,
+                                                        self.opengl.robustness, self.opengl.profile)
                 {
                     ctxt
                 } else if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
                                                         &self.extensions, self.api, (1, 0),
                                                         self.config_id, self.opengl.debug,
-                                                        self.opengl.robustness)
+                                                        self.opengl.forward_compatible,
+                                                        self.opengl.robustness, self.opengl.profile)
                 {
                     ctxt
                 } else {
@@ -455,7 +605,8 @@ impl<'a> ContextPrototype<'a> {
 
 unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
                           egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
-                          api: Api, version: Option<(u8, u8)>, reqs: &PixelFormatRequirements)
+                          extensions: &[String], api: Api, version: Option<(u8, u8)>,
+                          reqs: &PixelFormatRequirements, pbuffer_only: bool)
                           -> Result<(ffi::egl::types::EGLConfig, PixelFormat), CreationError>
 {
     let descriptor = {
@@ -469,7 +620,17 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
         out.push(ffi::egl::SURFACE_TYPE as c_int);
         // TODO: Some versions of Mesa report a BAD_ATTRIBUTE error
         // if we ask for PBUFFER_BIT as well as WINDOW_BIT
-        out.push((ffi::egl::WINDOW_BIT) as c_int);
+        let surface_type = if pbuffer_only { ffi::egl::PBUFFER_BIT } else { ffi::egl::WINDOW_BIT };
+        match reqs.swap_method {
+            Some(SwapMethod::Copy) => {
+                // EGL_SWAP_BEHAVIOR_PRESERVED_BIT was added in EGL 1.4
+                if egl_version < &(1, 4) { return Err(CreationError::NoAvailablePixelFormat); }
+                out.push((surface_type | ffi::egl::SWAP_BEHAVIOR_PRESERVED_BIT) as c_int);
+            },
+            // EGL never guarantees that the buffers are exchanged
+            Some(SwapMethod::Exchange) => return Err(CreationError::NoAvailablePixelFormat),
+            Some(SwapMethod::Undefined) | None => out.push(surface_type as c_int),
+        }
 
         match (api, version) {
             (Api::OpenGlEs, Some((3, _))) => {
@@ -547,11 +708,37 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
             out.push(multisampling as c_int);
         }
 
+        if reqs.coverage_samples.is_some() {
+            return Err(CreationError::NoAvailablePixelFormat);
+        }
+
         if reqs.stereoscopy {
             return Err(CreationError::NoAvailablePixelFormat);
         }
 
-        // FIXME: srgb is not taken into account
+        if reqs.float_color_buffer {
+            if extensions.iter().find(|s| s == &"EGL_EXT_pixel_format_float").is_none() {
+                return Err(CreationError::NoAvailablePixelFormat);
+            }
+            out.push(ffi::COLOR_COMPONENT_TYPE_EXT as c_int);
+            out.push(ffi::COLOR_COMPONENT_TYPE_FLOAT_EXT as c_int);
+        }
+
+        // the color space is passed when creating the surface, but we check here that it
+        // is supported
+        let color_space_extension = match reqs.color_space {
+            ColorSpace::Default => None,
+            ColorSpace::Linear | ColorSpace::Srgb if egl_version >= &(1, 5) => None,
+            ColorSpace::Linear | ColorSpace::Srgb => Some("EGL_KHR_gl_colorspace"),
+            ColorSpace::DisplayP3 => Some("EGL_EXT_gl_colorspace_display_p3"),
+            ColorSpace::ScRgbLinear => Some("EGL_EXT_gl_colorspace_scrgb_linear"),
+            ColorSpace::Bt2020Pq => Some("EGL_EXT_gl_colorspace_bt2020_pq"),
+        };
+        if let Some(color_space_extension) = color_space_extension {
+            if extensions.iter().find(|s| s == &color_space_extension).is_none() {
+                return Err(CreationError::NoAvailablePixelFormat);
+            }
+        }
 
         match reqs.release_behavior {
             ReleaseBehavior::Flush => (),
@@ -566,20 +753,36 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
     };
 
     // calling `eglChooseConfig`
-    let mut config_id = mem::uninitialized();
-    let mut num_configs = mem::uninitialized();
-    if egl.ChooseConfig(display, descriptor.as_ptr(), &mut config_id, 1, &mut num_configs) == 0 {
+    let mut configs: [ffi::egl::types::EGLConfig; 256] = mem::zeroed();
+    let mut num_configs = mem::zeroed();
+    if egl.ChooseConfig(display, descriptor.as_ptr(), configs.as_mut_ptr(),
+                        configs.len() as ffi::egl::types::EGLint, &mut num_configs) == 0
+    {
         return Err(CreationError::OsError(format!("eglChooseConfig failed")));
     }
-    if num_configs == 0 {
-        return Err(CreationError::NoAvailablePixelFormat);
+
+    // analyzing each config, EGL sorts them by decreasing sizes and we prefer the closest to
+    // the requirements
+    let configs = &configs[.. cmp::min(num_configs as usize, configs.len())];
+    let mut formats = Vec::with_capacity(configs.len());
+    for &config_id in configs.iter() {
+        formats.push(try!(describe_config(egl, display, config_id, reqs)));
     }
+    match pixel_format::choose_best(&formats, reqs) {
+        Some(index) => Ok((configs[index], formats[index].clone())),
+        None => Err(CreationError::NoAvailablePixelFormat),
+    }
+}
 
-    // analyzing each config
+/// Queries the attributes of a config.
+unsafe fn describe_config(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
+                          config_id: ffi::egl::types::EGLConfig, reqs: &PixelFormatRequirements)
+                          -> Result<PixelFormat, CreationError>
+{
     macro_rules! attrib {
         ($egl:expr, $display:expr, $config:expr, $attr:expr) => (
             {
-                let mut value = mem::uninitialized();
+                let mut value = mem::zeroed();
                 let res = $egl.GetConfigAttrib($display, $config,
                                                $attr as ffi::egl::types::EGLint, &mut value);
                 if res == 0 {
@@ -590,7 +793,7 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
         )
     };
 
-    let desc = PixelFormat {
+    Ok(PixelFormat {
         hardware_accelerated: attrib!(egl, display, config_id, ffi::egl::CONFIG_CAVEAT)
                                       != ffi::egl::SLOW_CONFIG as i32,
         color_bits: attrib!(egl, display, config_id, ffi::egl::RED_SIZE) as u8 +
@@ -605,17 +808,36 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
             0 | 1 => None,
             a => Some(a as u16),
         },
-        srgb: false,        // TODO: use EGL_KHR_gl_colorspace to know that
-    };
+        coverage_samples: None,
+        srgb: reqs.color_space == ColorSpace::Srgb || reqs.color_space == ColorSpace::DisplayP3,
+        color_space: reqs.color_space,
+        // the actual behavior is queried once the surface is created
+        swap_method: None,
+        native_id: NativePixelFormatId::Egl {
+            config_id: attrib!(egl, display, config_id, ffi::egl::CONFIG_ID),
+            native_visual_id: attrib!(egl, display, config_id, ffi::egl::NATIVE_VISUAL_ID),
+        },
+    })
+}
 
-    Ok((config_id, desc))
+/// Returns the value of the `EGL_GL_COLORSPACE` surface attribute that corresponds to a color
+/// space, or `None` if the attribute should be left to its default value.
+fn color_space_attribute(color_space: ColorSpace) -> Option<ffi::egl::types::EGLint> {
+    match color_space {
+        ColorSpace::Default => None,
+        ColorSpace::Linear => Some(ffi::egl::GL_COLORSPACE_LINEAR as ffi::egl::types::EGLint),
+        ColorSpace::Srgb => Some(ffi::egl::GL_COLORSPACE_SRGB as ffi::egl::types::EGLint),
+        ColorSpace::DisplayP3 => Some(ffi::GL_COLORSPACE_DISPLAY_P3_EXT),
+        ColorSpace::ScRgbLinear => Some(ffi::GL_COLORSPACE_SCRGB_LINEAR_EXT),
+        ColorSpace::Bt2020Pq => Some(ffi::GL_COLORSPACE_BT2020_PQ_EXT),
+    }
 }
 
 unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
                          egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
                          extensions: &[String], api: Api, version: (u8, u8),
                          config_id: ffi::egl::types::EGLConfig, gl_debug: bool,
-                         gl_robustness: Robustness)
+                         gl_forward_compatible: bool, gl_robustness: Robustness, gl_profile: Option<GlProfile>)
                          -> Result<ffi::egl::types::EGLContext, CreationError>
 {
     let mut context_attributes = Vec::with_capacity(10);
@@ -629,6 +851,17 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
         context_attributes.push(ffi::egl::CONTEXT_MINOR_VERSION as i32);
         context_attributes.push(version.1 as i32);
 
+        // profiles only exist for desktop OpenGL 3.2 and above
+        if let Some(profile) = gl_profile {
+            if api == Api::OpenGl && version >= (3, 2) {
+                context_attributes.push(ffi::egl::CONTEXT_OPENGL_PROFILE_MASK as i32);
+                context_attributes.push(match profile {
+                    GlProfile::Compatibility => ffi::egl::CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT,
+                    GlProfile::Core => ffi::egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                } as i32);
+            }
+        }
+
         // handling robustness
         let supports_robustness = egl_version >= &(1, 5) ||
                                   extensions.iter()
@@ -699,9 +932,26 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
             //flags = flags | ffi::egl::CONTEXT_OPENGL_DEBUG_BIT_KHR as i32;
         }
 
+        // the flag only exists for desktop OpenGL 3.0 and above
+        if gl_forward_compatible && api == Api::OpenGl {
+            if version < (3, 0) {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
+
+            if egl_version >= &(1, 5) {
+                context_attributes.push(ffi::egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE as i32);
+                context_attributes.push(ffi::egl::TRUE as i32);
+            } else {
+                flags = flags | ffi::egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE_BIT_KHR as i32;
+            }
+        }
+
         context_attributes.push(ffi::egl::CONTEXT_FLAGS_KHR as i32);
         context_attributes.push(flags);
 
+    } else if gl_forward_compatible && api == Api::OpenGl {
+        return Err(CreationError::OpenGlVersionNotSupported);
+
     } else if egl_version >= &(1, 3) && api == Api::OpenGlEs {
         // robustness is not supported
         match gl_robustness {
@@ -1,6 +1,7 @@
 #![cfg(target_os = "emscripten")]
 
 use std::ffi::CString;
+use std::io;
 use libc;
 use Api;
 use Event;
@@ -9,12 +10,18 @@ use ContextError;
 use CursorState;
 use GlAttributes;
 use GlContext;
+use LockKeys;
+use PointerAcceleration;
 use MouseCursor;
 use PixelFormat;
 use PixelFormatRequirements;
+use SnapPosition;
 use WindowAttributes;
 
+use std::cmp;
 use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
 
 mod ffi;
 
@@ -88,16 +95,46 @@ impl MonitorId {
     pub fn get_dimensions(&self) -> (u32, u32) {
         unimplemented!()
     }
+
+    #[inline]
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        None
+    }
 }
 
 impl Window {
+    /// Windows created by other libraries are not supported on this platform.
+    #[inline]
+    pub unsafe fn from_existing(_: *mut libc::c_void, _: *mut libc::c_void, _: &WindowAttributes,
+                                _: &PixelFormatRequirements, _: &GlAttributes<&Window>,
+                                _: &::platform::PlatformSpecificWindowBuilderAttributes)
+                                -> Result<Window, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     pub fn new(window: &WindowAttributes, pf_reqs: &PixelFormatRequirements,
                opengl: &GlAttributes<&Window>) -> Result<Window, CreationError>
     {
         // getting the default values of attributes
         let mut attributes = unsafe {
             use std::mem;
-            let mut attributes: ffi::EmscriptenWebGLContextAttributes = mem::uninitialized();
+            let mut attributes: ffi::EmscriptenWebGLContextAttributes = mem::zeroed();
             ffi::emscripten_webgl_init_context_attributes(&mut attributes);
             attributes
         };
@@ -134,6 +171,11 @@ impl Window {
     pub fn set_title(&self, _title: &str) {
     }
 
+    #[inline]
+    pub fn get_title(&self) -> Option<String> {
+        None
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<(i32, i32)> {
         Some((0, 0))
@@ -143,11 +185,65 @@ impl Window {
     pub fn set_position(&self, _: i32, _: i32) {
     }
 
+    #[inline]
+    pub fn get_lock_keys(&self) -> LockKeys {
+        LockKeys::default()
+    }
+
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        // `0` is the null handle, which unbinds the current context
+        match ffi::emscripten_webgl_make_context_current(0) {
+            ffi::EMSCRIPTEN_RESULT_SUCCESS => Ok(()),
+            err => Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                format!("emscripten_webgl_make_context_current failed ({})", err)))),
+        }
+    }
+
+    #[inline]
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        None
+    }
+
+    #[inline]
+    pub fn set_lock_keys(&self, _lock_keys: LockKeys) -> Result<(), String> {
+        Err("the lock keys can't be changed on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, _monitor: Option<MonitorId>) {
+    }
+
+    #[inline]
+    pub fn set_parent(&self, _parent: Option<::WindowID>) -> Result<(), String> {
+        Err("windows can't be embedded on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_menu_bar(&self, _menus: &[::Menu]) {
+    }
+
+    #[inline]
+    pub fn get_menu_bar_height(&self) -> u32 {
+        0
+    }
+
+    #[inline]
+    pub fn set_maximized(&self, _maximized: bool) {
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) {
+    }
+
+    #[inline]
+    pub fn snap_to(&self, _position: SnapPosition, _monitor: &MonitorId) {
+    }
+
     pub fn get_inner_size(&self) -> Option<(u32, u32)> {
         unsafe {
             use std::{mem, ptr};
-            let mut width = mem::uninitialized();
-            let mut height = mem::uninitialized();
+            let mut width = mem::zeroed();
+            let mut height = mem::zeroed();
 
             if ffi::emscripten_get_element_css_size(ptr::null(), &mut width, &mut height)
                 != ffi::EMSCRIPTEN_RESULT_SUCCESS
@@ -187,6 +283,13 @@ impl Window {
         }
     }
 
+    /// There is no way to block on the events of this platform, so this sleeps for a frame
+    /// at most.
+    pub fn wait_for_events(_: &[&Window], timeout: Option<Duration>) {
+        let frame = Duration::from_millis(16);
+        thread::sleep(timeout.map_or(frame, |timeout| cmp::min(timeout, frame)));
+    }
+
     #[inline]
     pub fn create_window_proxy(&self) -> WindowProxy {
         WindowProxy
@@ -196,6 +299,12 @@ impl Window {
     pub fn show(&self) {}
     #[inline]
     pub fn hide(&self) {}
+    #[inline]
+    pub fn focus(&self) {}
+    #[inline]
+    pub fn raise(&self) {}
+    #[inline]
+    pub fn lower(&self) {}
 
     #[inline]
     pub fn platform_display(&self) -> *mut libc::c_void {
@@ -220,11 +329,51 @@ impl Window {
         Ok(())
     }
 
+    #[inline]
+    pub fn set_variable_refresh(&self, _: bool) {
+    }
+
+    #[inline]
+    pub fn set_cursor_size(&self, _size: u32) {
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, _size: Option<(u32, u32)>) {
+    }
+
+    #[inline]
+    pub fn set_clipboard_string(&self, _text: &str) {
+    }
+
+    #[inline]
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        None
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, _size: Option<(u32, u32)>) {
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
         1.0
     }
 
+    #[inline]
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, _interval: u32) -> Result<(), String> {
+        Err("the swap interval can't be changed with emscripten".to_string())
+    }
+
     #[inline]
     pub fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), ()> {
         Ok(())
@@ -234,9 +383,11 @@ impl Window {
 impl GlContext for Window {
     #[inline]
     unsafe fn make_current(&self) -> Result<(), ContextError> {
-        // TOOD: check if == EMSCRIPTEN_RESULT
-        ffi::emscripten_webgl_make_context_current(self.context);
-        Ok(())
+        match ffi::emscripten_webgl_make_context_current(self.context) {
+            ffi::EMSCRIPTEN_RESULT_SUCCESS => Ok(()),
+            err => Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                format!("emscripten_webgl_make_context_current failed ({})", err)))),
+        }
     }
 
     #[inline]
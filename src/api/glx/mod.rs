@@ -7,16 +7,20 @@ use GlContext;
 use GlProfile;
 use GlRequest;
 use Api;
+use ColorSpace;
+use NativePixelFormatId;
 use PixelFormat;
 use PixelFormatRequirements;
+use SwapMethod;
 use ReleaseBehavior;
 use Robustness;
 
 use libc;
 use libc::c_int;
 use std::ffi::{CStr, CString};
-use std::{mem, ptr, slice};
+use std::{cmp, io, mem, ptr, slice};
 
+use api::pixel_format;
 use api::x11::ffi;
 
 use platform::Window as PlatformWindow;
@@ -27,6 +31,8 @@ pub struct Context {
     window: ffi::Window,
     context: ffi::GLXContext,
     pixel_format: PixelFormat,
+    /// True if `window` is a pbuffer that belongs to the context
+    pbuffer: bool,
 }
 
 // TODO: remove me
@@ -37,9 +43,37 @@ fn with_c_str<F, T>(s: &str, f: F) -> T where F: FnOnce(*const libc::c_char) ->
 }
 
 impl Context {
+    /// If `transparent` is true, a format whose X visual has an alpha channel is preferred, so
+    /// that compositing managers blend the window with what is behind it.
+    ///
+    /// If `visual_id` is given, only the formats of this X visual are considered, which is
+    /// required for a window that already exists. `NoAvailablePixelFormat` is returned if none
+    /// of them matches the requirements.
     pub fn new<'a>(glx: ffi::glx::Glx, xlib: &ffi::Xlib, pf_reqs: &PixelFormatRequirements,
                    opengl: &'a GlAttributes<&'a Context>, display: *mut ffi::Display,
-                   screen_id: libc::c_int) -> Result<ContextPrototype<'a>, CreationError>
+                   screen_id: libc::c_int, transparent: bool, visual_id: Option<ffi::VisualID>)
+                   -> Result<ContextPrototype<'a>, CreationError>
+    {
+        Context::new_impl(glx, xlib, pf_reqs, opengl, display, screen_id, transparent, visual_id,
+                          false)
+    }
+
+    /// Same as `new`, but only the configs that can also be used with pbuffers are considered,
+    /// so that the prototype can be finished with `finish_pbuffer`.
+    #[inline]
+    pub fn new_pbuffer<'a>(glx: ffi::glx::Glx, xlib: &ffi::Xlib, pf_reqs: &PixelFormatRequirements,
+                           opengl: &'a GlAttributes<&'a Context>, display: *mut ffi::Display,
+                           screen_id: libc::c_int)
+                           -> Result<ContextPrototype<'a>, CreationError>
+    {
+        Context::new_impl(glx, xlib, pf_reqs, opengl, display, screen_id, false, None, true)
+    }
+
+    fn new_impl<'a>(glx: ffi::glx::Glx, xlib: &ffi::Xlib, pf_reqs: &PixelFormatRequirements,
+                    opengl: &'a GlAttributes<&'a Context>, display: *mut ffi::Display,
+                    screen_id: libc::c_int, transparent: bool,
+                    visual_id: Option<ffi::VisualID>, pbuffer: bool)
+                    -> Result<ContextPrototype<'a>, CreationError>
     {
         // This is completely ridiculous, but VirtualBox's OpenGL driver needs some call handled by
         // *it* (i.e. not Mesa) to occur before anything else can happen. That is because
@@ -62,8 +96,35 @@ impl Context {
 
         // finding the pixel format we want
         let (fb_config, pixel_format) = unsafe {
-            try!(choose_fbconfig(&glx, &extensions, xlib, display, screen_id, pf_reqs)
-                                          .map_err(|_| CreationError::NoAvailablePixelFormat))
+            match choose_fbconfig(&glx, &extensions, xlib, display, screen_id, pf_reqs, transparent,
+                                  visual_id, pbuffer)
+            {
+                Ok(config) => config,
+                Err(()) if pf_reqs.deep_color_fallback && pf_reqs.color_bits.map_or(false, |c| c > 24) => {
+                    let mut fallback_reqs = pf_reqs.clone();
+                    fallback_reqs.color_bits = Some(24);
+                    fallback_reqs.alpha_bits = pf_reqs.alpha_bits.map(|a| cmp::min(a, 8));
+
+                    match choose_fbconfig(&glx, &extensions, xlib, display, screen_id, &fallback_reqs,
+                                         transparent, visual_id, pbuffer) {
+                        Ok(config) => config,
+                        Err(()) if visual_id.is_some() => {
+                            return Err(CreationError::NoAvailablePixelFormat);
+                        },
+                        Err(()) => return Err(diagnose_fbconfig_failure(&glx, &extensions, xlib, display,
+                                                                        screen_id, &fallback_reqs,
+                                                                        pbuffer)),
+                    }
+                },
+                // the requirements may be fine, but not for the visual of the window
+                Err(()) if visual_id.is_some() => {
+                    return Err(CreationError::NoAvailablePixelFormat);
+                },
+                Err(()) => {
+                    return Err(diagnose_fbconfig_failure(&glx, &extensions, xlib, display,
+                                                         screen_id, pf_reqs, pbuffer));
+                },
+            }
         };
 
         // getting the visual infos
@@ -89,16 +150,98 @@ impl Context {
     }
 }
 
-impl GlContext for Context {
-    unsafe fn make_current(&self) -> Result<(), ContextError> {
-        // TODO: glutin needs some internal changes for proper error recovery
-        let res = self.glx.MakeCurrent(self.display as *mut _, self.window, self.context);
+impl Context {
+    /// Makes the context current on another window. The window must have been created with
+    /// the same visual as the one of the context.
+    ///
+    /// The drawable may have been destroyed by the server in the meantime, so a failure is
+    /// returned instead of aborting.
+    pub unsafe fn make_current_on(&self, window: ffi::Window) -> Result<(), ContextError> {
+        let res = self.glx.MakeCurrent(self.display as *mut _, window, self.context);
         if res == 0 {
-            panic!("glx::MakeCurrent failed");
+            return Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                                                            "glXMakeCurrent failed")));
         }
         Ok(())
     }
 
+    /// Unbinds the current context of the calling thread, whichever it is.
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if self.glx.MakeCurrent(self.display as *mut _, 0, ptr::null()) == 0 {
+            return Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                                                            "glXMakeCurrent failed")));
+        }
+        Ok(())
+    }
+
+    /// Swaps the buffers of another window that the context has been made current on.
+    #[inline]
+    pub fn swap_buffers_on(&self, window: ffi::Window) -> Result<(), ContextError> {
+        try!(self.check_current());
+        unsafe { self.glx.SwapBuffers(self.display as *mut _, window); }
+        Ok(())
+    }
+
+    /// Returns an error if the context isn't current on this thread, since the swap wouldn't
+    /// flush its commands.
+    fn check_current(&self) -> Result<(), ContextError> {
+        if unsafe { self.glx.GetCurrentContext() } == self.context {
+            Ok(())
+        } else {
+            Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                                                     "the context is not current")))
+        }
+    }
+
+    /// Changes the number of vertical blanks that `swap_buffers` waits for. The context must be
+    /// current.
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        self.set_swap_interval_on(self.window, interval)
+    }
+
+    /// Changes the number of vertical blanks that `swap_buffers_on` waits for, on another window
+    /// that the context has been made current on.
+    ///
+    /// With `GLX_EXT_swap_control`, the interval belongs to the window, so every window keeps
+    /// its own. `GLX_SGI_swap_control` changes the interval of the current drawable instead,
+    /// which must then be the window.
+    pub fn set_swap_interval_on(&self, window: ffi::Window, interval: u32)
+                                -> Result<(), String>
+    {
+        let extra_functions = ffi::glx_extra::Glx::load_with(|addr| {
+            with_c_str(addr, |s| {
+                unsafe { self.glx.GetProcAddress(s as *const u8) as *const _ }
+            })
+        });
+
+        if extra_functions.SwapIntervalEXT.is_loaded() {
+            unsafe {
+                extra_functions.SwapIntervalEXT(self.display as *mut _, window, interval as c_int);
+            }
+            Ok(())
+        } else if extra_functions.SwapIntervalSGI.is_loaded() && interval != 0 {
+            if unsafe { self.glx.GetCurrentDrawable() } != window {
+                return Err("glXSwapIntervalSGI only applies to the current drawable".to_string());
+            }
+
+            // GLX_SGI_swap_control can't disable vsync
+            match unsafe { extra_functions.SwapIntervalSGI(interval as c_int) } {
+                0 => Ok(()),
+                _ => Err("glXSwapIntervalSGI failed".to_string()),
+            }
+        } else {
+            Err("no available extension can change the swap interval".to_string())
+        }
+    }
+}
+
+impl GlContext for Context {
+    #[inline]
+    unsafe fn make_current(&self) -> Result<(), ContextError> {
+        self.make_current_on(self.window)
+    }
+
     #[inline]
     fn is_current(&self) -> bool {
         unsafe { self.glx.GetCurrentContext() == self.context }
@@ -112,9 +255,21 @@ impl GlContext for Context {
         }
     }
 
-    #[inline]
     fn swap_buffers(&self) -> Result<(), ContextError> {
-        // TODO: glutin needs some internal changes for proper error recovery
+        try!(self.check_current());
+
+        // `glXSwapBuffers` does nothing on single-buffered drawables
+        if !self.pixel_format.double_buffer {
+            let flush = self.get_proc_address("glFlush");
+            if !flush.is_null() {
+                unsafe {
+                    let flush: extern "system" fn() = mem::transmute(flush);
+                    flush();
+                }
+            }
+            return Ok(());
+        }
+
         unsafe { self.glx.SwapBuffers(self.display as *mut _, self.window); }
         Ok(())
     }
@@ -141,6 +296,10 @@ impl Drop for Context {
             }
 
             self.glx.DestroyContext(self.display as *mut _, self.context);
+
+            if self.pbuffer {
+                self.glx.DestroyPbuffer(self.display as *mut _, self.window);
+            }
         }
     }
 }
@@ -162,115 +321,179 @@ impl<'a> ContextPrototype<'a> {
     }
 
     pub fn finish(self, window: ffi::Window) -> Result<Context, CreationError> {
-        let share = match self.opengl.sharing {
-            Some(ctxt) => ctxt.context,
-            None => ptr::null()
+        let extra_functions = self.load_extra_functions();
+        let context = try!(self.create_gl_context(&extra_functions));
+
+        // vsync
+        if self.opengl.vsync || self.opengl.strict_vsync {
+            let interval = if self.opengl.vsync { 1 } else { 0 };
+            unsafe { self.glx.MakeCurrent(self.display as *mut _, window, context) };
+
+            let result = if extra_functions.SwapIntervalEXT.is_loaded() {
+                // this should be the most common extension
+                unsafe {
+                    extra_functions.SwapIntervalEXT(self.display as *mut _, window, interval);
+                }
+
+                // checking that it worked, as some drivers force their own setting
+                if self.opengl.strict_vsync {
+                    let mut swap = unsafe { mem::zeroed() };
+                    unsafe {
+                        self.glx.QueryDrawable(self.display as *mut _, window,
+                                               ffi::glx_extra::SWAP_INTERVAL_EXT as i32,
+                                               &mut swap);
+                    }
+
+                    if swap != interval as libc::c_uint {
+                        Err(CreationError::OsError(format!("Couldn't setup vsync: expected \
+                                                            interval `{}` but got `{}`",
+                                                           interval, swap)))
+                    } else {
+                        Ok(())
+                    }
+                } else {
+                    Ok(())
+                }
+
+            // GLX_MESA_swap_control is not official
+            /*} else if extra_functions.SwapIntervalMESA.is_loaded() {
+                unsafe {
+                    extra_functions.SwapIntervalMESA(1);
+                }*/
+
+            // GLX_SGI_swap_control can't disable vsync, an interval of `0` is an error
+            } else if extra_functions.SwapIntervalSGI.is_loaded() && interval != 0 {
+                unsafe {
+                    extra_functions.SwapIntervalSGI(interval);
+                }
+                Ok(())
+
+            } else if self.opengl.strict_vsync {
+                Err(CreationError::OsError(format!("Couldn't find any available vsync extension")))
+            } else {
+                Ok(())
+            };
+
+            unsafe { self.glx.MakeCurrent(self.display as *mut _, 0, ptr::null()) };
+
+            if let Err(err) = result {
+                unsafe { self.glx.DestroyContext(self.display as *mut _, context) };
+                return Err(err);
+            }
+        }
+
+        Ok(Context {
+            glx: self.glx,
+            display: self.display,
+            window: window,
+            context: context,
+            pixel_format: self.pixel_format,
+            pbuffer: false,
+        })
+    }
+
+    /// Creates a pbuffer of the given dimensions, and the context that renders to it.
+    ///
+    /// The prototype must have been created with `Context::new_pbuffer`. A pbuffer is never
+    /// displayed, so the vsync attributes are ignored.
+    pub fn finish_pbuffer(self, dimensions: (u32, u32)) -> Result<Context, CreationError> {
+        let extra_functions = self.load_extra_functions();
+        let context = try!(self.create_gl_context(&extra_functions));
+
+        let attributes = [
+            ffi::glx::PBUFFER_WIDTH as c_int, dimensions.0 as c_int,
+            ffi::glx::PBUFFER_HEIGHT as c_int, dimensions.1 as c_int,
+            0,
+        ];
+
+        let pbuffer = unsafe {
+            self.glx.CreatePbuffer(self.display as *mut _, self.fb_config, attributes.as_ptr())
         };
 
-        // loading the extra GLX functions
-        let extra_functions = ffi::glx_extra::Glx::load_with(|addr| {
+        if pbuffer == 0 {
+            unsafe { self.glx.DestroyContext(self.display as *mut _, context) };
+            return Err(CreationError::OsError(format!("glXCreatePbuffer failed")));
+        }
+
+        Ok(Context {
+            glx: self.glx,
+            display: self.display,
+            window: pbuffer,
+            context: context,
+            pixel_format: self.pixel_format,
+            pbuffer: true,
+        })
+    }
+
+    fn load_extra_functions(&self) -> ffi::glx_extra::Glx {
+        ffi::glx_extra::Glx::load_with(|addr| {
             with_c_str(addr, |s| {
                 unsafe { self.glx.GetProcAddress(s as *const u8) as *const _ }
             })
-        });
+        })
+    }
+
+    fn create_gl_context(&self, extra_functions: &ffi::glx_extra::Glx)
+                            -> Result<ffi::GLXContext, CreationError>
+    {
+        let share = match self.opengl.sharing {
+            Some(ctxt) => ctxt.context,
+            None => ptr::null()
+        };
 
         // creating GL context
         let context = match self.opengl.version {
             GlRequest::Latest => {
-                if let Ok(ctxt) = create_context(&self.glx, &extra_functions, &self.extensions, (3, 2),
+                if let Ok(ctxt) = create_context(&self.glx, extra_functions, &self.extensions, (3, 2),
                                                  self.opengl.profile, self.opengl.debug,
+                                                 self.opengl.forward_compatible,
                                                  self.opengl.robustness, share,
                                                  self.display, self.fb_config, &self.visual_infos)
                 {
                     ctxt
-                } else if let Ok(ctxt) = create_context(&self.glx, &extra_functions, &self.extensions,
+                } else if let Ok(ctxt) = create_context(&self.glx, extra_functions, &self.extensions,
                                                         (3, 1), self.opengl.profile,
                                                         self.opengl.debug,
+                                                        self.opengl.forward_compatible,
                                                         self.opengl.robustness, share, self.display,
                                                         self.fb_config, &self.visual_infos)
                 {
                     ctxt
 
                 } else {
-                    try!(create_context(&self.glx, &extra_functions, &self.extensions, (1, 0),
+                    try!(create_context(&self.glx, extra_functions, &self.extensions, (1, 0),
                                         self.opengl.profile, self.opengl.debug,
+                                        self.opengl.forward_compatible,
                                         self.opengl.robustness,
                                         share, self.display, self.fb_config, &self.visual_infos))
                 }
             },
             GlRequest::Specific(Api::OpenGl, (major, minor)) => {
-                try!(create_context(&self.glx, &extra_functions, &self.extensions, (major, minor),
+                try!(create_context(&self.glx, extra_functions, &self.extensions, (major, minor),
                                     self.opengl.profile, self.opengl.debug,
+                                    self.opengl.forward_compatible,
                                     self.opengl.robustness, share, self.display, self.fb_config,
                                     &self.visual_infos))
             },
-            GlRequest::Specific(_, _) => panic!("Only OpenGL is supported"),
+            // `Window::new` sends OpenGL ES requests to EGL
+            GlRequest::Specific(_, _) => return Err(CreationError::OpenGlVersionNotSupported),
             GlRequest::GlThenGles { opengl_version: (major, minor), .. } => {
-                try!(create_context(&self.glx, &extra_functions, &self.extensions, (major, minor),
+                try!(create_context(&self.glx, extra_functions, &self.extensions, (major, minor),
                                     self.opengl.profile, self.opengl.debug,
+                                    self.opengl.forward_compatible,
                                     self.opengl.robustness, share, self.display, self.fb_config,
                                     &self.visual_infos))
             },
         };
 
-        // vsync
-        if self.opengl.vsync {
-            unsafe { self.glx.MakeCurrent(self.display as *mut _, window, context) };
-
-            if extra_functions.SwapIntervalEXT.is_loaded() {
-                // this should be the most common extension
-                unsafe {
-                    extra_functions.SwapIntervalEXT(self.display as *mut _, window, 1);
-                }
-
-                // checking that it worked
-                // TODO: handle this
-                /*if self.builder.strict {
-                    let mut swap = unsafe { mem::uninitialized() };
-                    unsafe {
-                        self.glx.QueryDrawable(self.display as *mut _, window,
-                                               ffi::glx_extra::SWAP_INTERVAL_EXT as i32,
-                                               &mut swap);
-                    }
-
-                    if swap != 1 {
-                        return Err(CreationError::OsError(format!("Couldn't setup vsync: expected \
-                                                    interval `1` but got `{}`", swap)));
-                    }
-                }*/
-
-            // GLX_MESA_swap_control is not official
-            /*} else if extra_functions.SwapIntervalMESA.is_loaded() {
-                unsafe {
-                    extra_functions.SwapIntervalMESA(1);
-                }*/
-
-            } else if extra_functions.SwapIntervalSGI.is_loaded() {
-                unsafe {
-                    extra_functions.SwapIntervalSGI(1);
-                }
-
-            }/* else if self.builder.strict {
-                // TODO: handle this
-                return Err(CreationError::OsError(format!("Couldn't find any available vsync extension")));
-            }*/
-
-            unsafe { self.glx.MakeCurrent(self.display as *mut _, 0, ptr::null()) };
-        }
-
-        Ok(Context {
-            glx: self.glx,
-            display: self.display,
-            window: window,
-            context: context,
-            pixel_format: self.pixel_format,
-        })
+        Ok(context)
     }
 }
 
 fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, extensions: &str,
                   version: (u8, u8), profile: Option<GlProfile>, debug: bool,
-                  robustness: Robustness, share: ffi::GLXContext, display: *mut ffi::Display,
+                  forward_compatible: bool, robustness: Robustness, share: ffi::GLXContext,
+                  display: *mut ffi::Display,
                   fb_config: ffi::glx::types::GLXFBConfig,
                   visual_infos: &ffi::XVisualInfo)
                   -> Result<ffi::GLXContext, CreationError>
@@ -285,6 +508,10 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
             attributes.push(version.1 as c_int);
 
             if let Some(profile) = profile {
+                if extensions.split(' ').find(|&i| i == "GLX_ARB_create_context_profile").is_none() {
+                    return Err(CreationError::NotSupported);
+                }
+
                 let flag = match profile {
                     GlProfile::Compatibility =>
                         ffi::glx_extra::CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB,
@@ -328,6 +555,14 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
                     flags = flags | ffi::glx_extra::CONTEXT_DEBUG_BIT_ARB as c_int;
                 }
 
+                if forward_compatible {
+                    // the flag doesn't exist before OpenGL 3.0 and the driver would reject it
+                    if version < (3, 0) {
+                        return Err(CreationError::OpenGlVersionNotSupported);
+                    }
+                    flags = flags | ffi::glx_extra::CONTEXT_FORWARD_COMPATIBLE_BIT_ARB as c_int;
+                }
+
                 flags
             };
 
@@ -340,6 +575,12 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
                                                     attributes.as_ptr())
 
         } else {
+            // without `GLX_ARB_create_context`, the driver returns whatever version it wants,
+            // which is at most 3.0 with the compatibility profile
+            if profile == Some(GlProfile::Core) || version > (3, 0) || forward_compatible {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
+
             let visual_infos: *const ffi::XVisualInfo = visual_infos;
             glx.CreateContext(display as *mut _, visual_infos as *mut _, share, 1)
         };
@@ -353,118 +594,289 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
     }
 }
 
-/// Enumerates all available FBConfigs
-unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xlib,
-                          display: *mut ffi::Display, screen_id: libc::c_int,
-                          reqs: &PixelFormatRequirements)
-                          -> Result<(ffi::glx::types::GLXFBConfig, PixelFormat), ()>
+/// Called when `choose_fbconfig` fails. Drops the requirements one by one in order to find which
+/// one prevents a config from being found.
+unsafe fn diagnose_fbconfig_failure(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xlib,
+                                    display: *mut ffi::Display, screen_id: libc::c_int,
+                                    reqs: &PixelFormatRequirements, pbuffer: bool)
+                                    -> CreationError
 {
-    let descriptor = {
-        let mut out: Vec<c_int> = Vec::with_capacity(37);
+    let requirements = ["float_color_buffer", "color_bits", "alpha_bits", "depth_bits",
+                        "stencil_bits", "multisampling", "coverage_samples", "double_buffer",
+                        "stereoscopy", "swap_method", "color_space"];
+
+    for &requirement in requirements.iter() {
+        let mut relaxed = reqs.clone();
+        // only the requirements are diagnosed, not the choice of the user
+        relaxed.selector = None;
+        match requirement {
+            "float_color_buffer" => relaxed.float_color_buffer = false,
+            "color_bits" => relaxed.color_bits = None,
+            "alpha_bits" => relaxed.alpha_bits = None,
+            "depth_bits" => relaxed.depth_bits = None,
+            "stencil_bits" => relaxed.stencil_bits = None,
+            "multisampling" => relaxed.multisampling = None,
+            "coverage_samples" => relaxed.coverage_samples = None,
+            "double_buffer" => relaxed.double_buffer = None,
+            "stereoscopy" => relaxed.stereoscopy = false,
+            "swap_method" => relaxed.swap_method = None,
+            "color_space" => relaxed.color_space = ColorSpace::Default,
+            _ => unreachable!()
+        }
 
-        out.push(ffi::glx::X_RENDERABLE as c_int);
-        out.push(1);
+        if choose_fbconfig(glx, extensions, xlib, display, screen_id, &relaxed, false,
+                           pbuffer).is_ok()
+        {
+            if requirement == "color_space" && reqs.color_space == ColorSpace::Srgb {
+                let found = list_fbconfigs(glx, extensions, xlib, display, screen_id, &relaxed,
+                                           pbuffer);
+                return CreationError::NoSrgbPixelFormat(found);
+            }
+            return CreationError::PixelFormatRequirementNotSupported(requirement);
+        }
+    }
 
-        out.push(ffi::glx::X_VISUAL_TYPE as c_int);
-        out.push(ffi::glx::TRUE_COLOR as c_int);
+    CreationError::NoAvailablePixelFormat
+}
 
-        out.push(ffi::glx::DRAWABLE_TYPE as c_int);
+/// Builds the attributes passed to `glXChooseFBConfig`. Returns `None` if the requirements
+/// need an extension that is not supported.
+///
+/// If `pbuffer` is true, the configs must support pbuffers in addition to windows.
+fn fbconfig_descriptor(extensions: &str, reqs: &PixelFormatRequirements, pbuffer: bool)
+                       -> Option<Vec<c_int>>
+{
+    let mut out: Vec<c_int> = Vec::with_capacity(37);
+
+    out.push(ffi::glx::X_RENDERABLE as c_int);
+    out.push(1);
+
+    out.push(ffi::glx::X_VISUAL_TYPE as c_int);
+    out.push(ffi::glx::TRUE_COLOR as c_int);
+
+    out.push(ffi::glx::DRAWABLE_TYPE as c_int);
+    if pbuffer {
+        out.push((ffi::glx::WINDOW_BIT | ffi::glx::PBUFFER_BIT) as c_int);
+    } else {
         out.push(ffi::glx::WINDOW_BIT as c_int);
+    }
 
-        out.push(ffi::glx::RENDER_TYPE as c_int);
-        if reqs.float_color_buffer {
-            if extensions.split(' ').find(|&i| i == "GLX_ARB_fbconfig_float").is_some() {
-                out.push(ffi::glx_extra::RGBA_FLOAT_BIT_ARB as c_int);
-            } else {
-                return Err(());
-            }
+    out.push(ffi::glx::RENDER_TYPE as c_int);
+    if reqs.float_color_buffer {
+        if extensions.split(' ').find(|&i| i == "GLX_ARB_fbconfig_float").is_some() {
+            out.push(ffi::glx_extra::RGBA_FLOAT_BIT_ARB as c_int);
         } else {
-            out.push(ffi::glx::RGBA_BIT as c_int);
-        }
-
-        if let Some(color) = reqs.color_bits {
-            out.push(ffi::glx::RED_SIZE as c_int);
-            out.push((color / 3) as c_int);
-            out.push(ffi::glx::GREEN_SIZE as c_int);
-            out.push((color / 3 + if color % 3 != 0 { 1 } else { 0 }) as c_int);
-            out.push(ffi::glx::BLUE_SIZE as c_int);
-            out.push((color / 3 + if color % 3 == 2 { 1 } else { 0 }) as c_int);
+            return None;
         }
+    } else {
+        out.push(ffi::glx::RGBA_BIT as c_int);
+    }
 
-        if let Some(alpha) = reqs.alpha_bits {
-            out.push(ffi::glx::ALPHA_SIZE as c_int);
-            out.push(alpha as c_int);
-        }
+    if let Some(color) = reqs.color_bits {
+        out.push(ffi::glx::RED_SIZE as c_int);
+        out.push((color / 3) as c_int);
+        out.push(ffi::glx::GREEN_SIZE as c_int);
+        out.push((color / 3 + if color % 3 != 0 { 1 } else { 0 }) as c_int);
+        out.push(ffi::glx::BLUE_SIZE as c_int);
+        out.push((color / 3 + if color % 3 == 2 { 1 } else { 0 }) as c_int);
+    }
 
-        if let Some(depth) = reqs.depth_bits {
-            out.push(ffi::glx::DEPTH_SIZE as c_int);
-            out.push(depth as c_int);
-        }
+    if let Some(alpha) = reqs.alpha_bits {
+        out.push(ffi::glx::ALPHA_SIZE as c_int);
+        out.push(alpha as c_int);
+    }
 
-        if let Some(stencil) = reqs.stencil_bits {
-            out.push(ffi::glx::STENCIL_SIZE as c_int);
-            out.push(stencil as c_int);
-        }
+    if let Some(depth) = reqs.depth_bits {
+        out.push(ffi::glx::DEPTH_SIZE as c_int);
+        out.push(depth as c_int);
+    }
 
-        let double_buffer = reqs.double_buffer.unwrap_or(true);
-        out.push(ffi::glx::DOUBLEBUFFER as c_int);
-        out.push(if double_buffer { 1 } else { 0 });
+    if let Some(stencil) = reqs.stencil_bits {
+        out.push(ffi::glx::STENCIL_SIZE as c_int);
+        out.push(stencil as c_int);
+    }
 
-        if let Some(multisampling) = reqs.multisampling {
-            if extensions.split(' ').find(|&i| i == "GLX_ARB_multisample").is_some() {
-                out.push(ffi::glx_extra::SAMPLE_BUFFERS_ARB as c_int);
-                out.push(if multisampling == 0 { 0 } else { 1 });
-                out.push(ffi::glx_extra::SAMPLES_ARB as c_int);
-                out.push(multisampling as c_int);
-            } else {
-                return Err(());
-            }
+    let double_buffer = reqs.double_buffer.unwrap_or(true);
+    out.push(ffi::glx::DOUBLEBUFFER as c_int);
+    out.push(if double_buffer { 1 } else { 0 });
+
+    if let Some(coverage) = reqs.coverage_samples {
+        // GLX_COVERAGE_SAMPLES_NV is an alias of GLX_SAMPLES_ARB
+        if extensions.split(' ').find(|&i| i == "GLX_NV_multisample_coverage").is_some() {
+            out.push(ffi::glx_extra::SAMPLE_BUFFERS_ARB as c_int);
+            out.push(1);
+            out.push(ffi::glx_extra::COVERAGE_SAMPLES_NV as c_int);
+            out.push(coverage as c_int);
+            out.push(ffi::glx_extra::COLOR_SAMPLES_NV as c_int);
+            out.push(reqs.multisampling.unwrap_or(coverage) as c_int);
+        } else {
+            return None;
         }
+    } else if let Some(multisampling) = reqs.multisampling {
+        if extensions.split(' ').find(|&i| i == "GLX_ARB_multisample").is_some() {
+            out.push(ffi::glx_extra::SAMPLE_BUFFERS_ARB as c_int);
+            out.push(if multisampling == 0 { 0 } else { 1 });
+            out.push(ffi::glx_extra::SAMPLES_ARB as c_int);
+            out.push(multisampling as c_int);
+        } else {
+            return None;
+        }
+    }
 
-        out.push(ffi::glx::STEREO as c_int);
-        out.push(if reqs.stereoscopy { 1 } else { 0 });
+    out.push(ffi::glx::STEREO as c_int);
+    out.push(if reqs.stereoscopy { 1 } else { 0 });
+
+    if let Some(swap_method) = reqs.swap_method {
+        if extensions.split(' ').find(|&i| i == "GLX_OML_swap_method").is_some() {
+            out.push(ffi::glx_extra::SWAP_METHOD_OML as c_int);
+            out.push(match swap_method {
+                SwapMethod::Exchange => ffi::glx_extra::SWAP_EXCHANGE_OML,
+                SwapMethod::Copy => ffi::glx_extra::SWAP_COPY_OML,
+                SwapMethod::Undefined => ffi::glx_extra::SWAP_UNDEFINED_OML,
+            } as c_int);
+        } else {
+            return None;
+        }
+    }
 
-        if reqs.srgb {
+    match reqs.color_space {
+        ColorSpace::Default => (),
+        ColorSpace::Srgb | ColorSpace::Linear => {
+            let value = if reqs.color_space == ColorSpace::Srgb { 1 } else { 0 };
             if extensions.split(' ').find(|&i| i == "GLX_ARB_framebuffer_sRGB").is_some() {
                 out.push(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int);
-                out.push(1);
+                out.push(value);
             } else if extensions.split(' ').find(|&i| i == "GLX_EXT_framebuffer_sRGB").is_some() {
                 out.push(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int);
-                out.push(1);
-            } else {
-                return Err(());
+                out.push(value);
+            } else if reqs.color_space == ColorSpace::Srgb {
+                return None;
             }
-        }
+        },
+        // GLX doesn't let us choose the primaries or the transfer function
+        _ => return None,
+    }
 
-        match reqs.release_behavior {
-            ReleaseBehavior::Flush => (),
-            ReleaseBehavior::None => {
-                if extensions.split(' ').find(|&i| i == "GLX_ARB_context_flush_control").is_some() {
-                    out.push(ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_ARB as c_int);
-                    out.push(ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_NONE_ARB as c_int);
-                }
-            },
-        }
+    match reqs.release_behavior {
+        ReleaseBehavior::Flush => (),
+        ReleaseBehavior::None => {
+            if extensions.split(' ').find(|&i| i == "GLX_ARB_context_flush_control").is_some() {
+                out.push(ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_ARB as c_int);
+                out.push(ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_NONE_ARB as c_int);
+            }
+        },
+    }
 
-        out.push(ffi::glx::CONFIG_CAVEAT as c_int);
-        out.push(ffi::glx::DONT_CARE as c_int);
+    out.push(ffi::glx::CONFIG_CAVEAT as c_int);
+    out.push(ffi::glx::DONT_CARE as c_int);
 
-        out.push(0);
-        out
+    out.push(0);
+    Some(out)
+}
+
+/// Enumerates all available FBConfigs
+unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xlib,
+                          display: *mut ffi::Display, screen_id: libc::c_int,
+                          reqs: &PixelFormatRequirements, transparent: bool,
+                          visual_id: Option<ffi::VisualID>, pbuffer: bool)
+                          -> Result<(ffi::glx::types::GLXFBConfig, PixelFormat), ()>
+{
+    let descriptor = match fbconfig_descriptor(extensions, reqs, pbuffer) {
+        Some(descriptor) => descriptor,
+        None => return Err(()),
     };
 
     // calling glXChooseFBConfig
-    let fb_config = {
-        let mut num_configs = 1;
-        let result = glx.ChooseFBConfig(display as *mut _, screen_id, descriptor.as_ptr(),
-                                        &mut num_configs);
-        if result.is_null() { return Err(()); }
-        if num_configs == 0 { return Err(()); }
-        let val = *result;
-        (xlib.XFree)(result as *mut _);
-        val
+    let mut num_configs = 0;
+    let result = glx.ChooseFBConfig(display as *mut _, screen_id, descriptor.as_ptr(),
+                                    &mut num_configs);
+    if result.is_null() { return Err(()); }
+
+    // some drivers ignore `GLX_FRAMEBUFFER_SRGB_CAPABLE` when choosing the configs, so an
+    // sRGB request is checked again here instead of silently returning a linear config
+    let candidates: Vec<_> = slice::from_raw_parts(result, num_configs as usize).iter()
+                                 .map(|&c| (c, describe_fbconfig(glx, extensions, display, c)))
+                                 .filter(|&(_, ref f)| reqs.color_space != ColorSpace::Srgb ||
+                                                       f.srgb)
+                                 .filter(|&(_, ref f)| match (visual_id, &f.native_id) {
+                                     (Some(id), &NativePixelFormatId::Glx { visual_id, .. }) => {
+                                         visual_id == id as u64
+                                     },
+                                     _ => true,
+                                 })
+                                 .collect();
+    (xlib.XFree)(result as *mut _);
+
+    // the alpha of the framebuffer is only used by the compositor if the visual of the
+    // window has an alpha channel, which means a depth of 32 bits
+    let has_alpha_visual = |config: ffi::glx::types::GLXFBConfig| {
+        let vi = glx.GetVisualFromFBConfig(display as *mut _, config);
+        if vi.is_null() {
+            return false;
+        }
+        let depth = (*(vi as *const ffi::XVisualInfo)).depth;
+        (xlib.XFree)(vi as *mut _);
+        depth == 32
+    };
+    let transparent_candidates: Vec<_> = if transparent {
+        candidates.iter().filter(|&&(c, _)| has_alpha_visual(c)).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    let candidates = if transparent_candidates.is_empty() {
+        candidates
+    } else {
+        transparent_candidates
+    };
+
+    // GLX sorts the configs by decreasing sizes, we prefer the closest to the requirements
+    let formats: Vec<_> = candidates.iter().map(|&(_, ref f)| f.clone()).collect();
+    match pixel_format::choose_best(&formats, reqs) {
+        Some(index) => Ok(candidates[index].clone()),
+        None => Err(()),
+    }
+}
+
+/// Returns the description of every FBConfig that matches the requirements, without the
+/// additional filtering of `choose_fbconfig`.
+unsafe fn list_fbconfigs(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xlib,
+                         display: *mut ffi::Display, screen_id: libc::c_int,
+                         reqs: &PixelFormatRequirements, pbuffer: bool) -> Vec<PixelFormat>
+{
+    let descriptor = match fbconfig_descriptor(extensions, reqs, pbuffer) {
+        Some(descriptor) => descriptor,
+        None => return Vec::new(),
     };
 
+    let mut num_configs = 0;
+    let result = glx.ChooseFBConfig(display as *mut _, screen_id, descriptor.as_ptr(),
+                                    &mut num_configs);
+    if result.is_null() {
+        return Vec::new();
+    }
+
+    let formats = slice::from_raw_parts(result, num_configs as usize).iter()
+                       .map(|&config| describe_fbconfig(glx, extensions, display, config))
+                       .collect();
+    (xlib.XFree)(result as *mut _);
+    formats
+}
+
+/// Returns true if the FBConfig can be used with `GL_FRAMEBUFFER_SRGB`.
+unsafe fn is_srgb_capable(glx: &ffi::glx::Glx, display: *mut ffi::Display,
+                          config: ffi::glx::types::GLXFBConfig) -> bool
+{
+    // the ARB and EXT attributes have the same value
+    let mut value = 0;
+    glx.GetFBConfigAttrib(display as *mut _, config,
+                          ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int, &mut value);
+    value != 0
+}
+
+/// Queries the attributes of an FBConfig.
+unsafe fn describe_fbconfig(glx: &ffi::glx::Glx, extensions: &str, display: *mut ffi::Display,
+                            fb_config: ffi::glx::types::GLXFBConfig) -> PixelFormat
+{
     let get_attrib = |attrib: c_int| -> i32 {
         let mut value = 0;
         glx.GetFBConfigAttrib(display as *mut _, fb_config, attrib, &mut value);
@@ -472,7 +884,30 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
         value
     };
 
-    let pf_desc = PixelFormat {
+    let srgb = is_srgb_capable(glx, display, fb_config);
+
+    let swap_method = if extensions.split(' ').find(|&i| i == "GLX_OML_swap_method").is_some() {
+        match get_attrib(ffi::glx_extra::SWAP_METHOD_OML as c_int) as u32 {
+            ffi::glx_extra::SWAP_EXCHANGE_OML => Some(SwapMethod::Exchange),
+            ffi::glx_extra::SWAP_COPY_OML => Some(SwapMethod::Copy),
+            _ => Some(SwapMethod::Undefined),
+        }
+    } else {
+        None
+    };
+
+    // with CSAA, GLX_SAMPLES is the number of coverage samples
+    let (multisampling, coverage_samples) = if get_attrib(ffi::glx::SAMPLE_BUFFERS as c_int) == 0 {
+        (None, None)
+    } else if extensions.split(' ').find(|&i| i == "GLX_NV_multisample_coverage").is_some() {
+        let coverage = get_attrib(ffi::glx_extra::COVERAGE_SAMPLES_NV as c_int) as u16;
+        let color = get_attrib(ffi::glx_extra::COLOR_SAMPLES_NV as c_int) as u16;
+        (Some(color), if coverage > color { Some(coverage) } else { None })
+    } else {
+        (Some(get_attrib(ffi::glx::SAMPLES as c_int) as u16), None)
+    };
+
+    PixelFormat {
         hardware_accelerated: get_attrib(ffi::glx::CONFIG_CAVEAT as c_int) !=
                                                             ffi::glx::SLOW_CONFIG as c_int,
         color_bits: get_attrib(ffi::glx::RED_SIZE as c_int) as u8 +
@@ -483,14 +918,14 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
         stencil_bits: get_attrib(ffi::glx::STENCIL_SIZE as c_int) as u8,
         stereoscopy: get_attrib(ffi::glx::STEREO as c_int) != 0,
         double_buffer: get_attrib(ffi::glx::DOUBLEBUFFER as c_int) != 0,
-        multisampling: if get_attrib(ffi::glx::SAMPLE_BUFFERS as c_int) != 0 {
-            Some(get_attrib(ffi::glx::SAMPLES as c_int) as u16)
-        } else {
-            None
+        multisampling: multisampling,
+        coverage_samples: coverage_samples,
+        srgb: srgb,
+        color_space: if srgb { ColorSpace::Srgb } else { ColorSpace::Linear },
+        swap_method: swap_method,
+        native_id: NativePixelFormatId::Glx {
+            fbconfig_id: get_attrib(ffi::glx::FBCONFIG_ID as c_int),
+            visual_id: get_attrib(ffi::glx::VISUAL_ID as c_int) as u64,
         },
-        srgb: get_attrib(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int) != 0 ||
-              get_attrib(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int) != 0,
-    };
-
-    Ok((fb_config, pf_desc))
+    }
 }
@@ -23,7 +23,7 @@ use super::jmpbuf;
 
 pub fn create_delegate_class() {
     extern fn did_finish_launching(this: &mut Object, _: Sel, _: id, _: id) -> BOOL {
-        unsafe {
+        ::api::panic::abort_on_panic("application:didFinishLaunchingWithOptions:", || unsafe {
             let main_screen: id = msg_send![Class::get("UIScreen").unwrap(), mainScreen];
             let bounds: CGRect = msg_send![main_screen, bounds];
             let scale: CGFloat = msg_send![main_screen, nativeScale];
@@ -56,8 +56,8 @@ pub fn create_delegate_class() {
 
 
             let _: () = msg_send![this, performSelector:sel!(postLaunch:) withObject:nil afterDelay:0.0];
-        }
-        YES
+            YES
+        })
     }
 
     extern fn post_launch(_: &Object, _: Sel, _: id) {
@@ -65,50 +65,51 @@ pub fn create_delegate_class() {
     }
 
     extern fn did_become_active(this: &Object, _: Sel, _: id) {
-        unsafe {
+        ::api::panic::abort_on_panic("applicationDidBecomeActive:", || unsafe {
             let state: *mut libc::c_void = *this.get_ivar("glutinState");
             let state = &mut *(state as *mut DelegateState);
             state.events_queue.push_back(Event::Focused(true));
-        }
+        })
     }
 
     extern fn will_resign_active(this: &Object, _: Sel, _: id) {
-        unsafe {
+        ::api::panic::abort_on_panic("applicationWillResignActive:", || unsafe {
             let state: *mut libc::c_void = *this.get_ivar("glutinState");
             let state = &mut *(state as *mut DelegateState);
             state.events_queue.push_back(Event::Focused(false));
-        }
+        })
     }
 
     extern fn will_enter_foreground(this: &Object, _: Sel, _: id) {
-        unsafe {
+        ::api::panic::abort_on_panic("applicationWillEnterForeground:", || unsafe {
             let state: *mut libc::c_void = *this.get_ivar("glutinState");
             let state = &mut *(state as *mut DelegateState);
             state.events_queue.push_back(Event::Suspended(false));
-        }
+        })
     }
 
     extern fn did_enter_background(this: &Object, _: Sel, _: id) {
-        unsafe {
+        ::api::panic::abort_on_panic("applicationDidEnterBackground:", || unsafe {
             let state: *mut libc::c_void = *this.get_ivar("glutinState");
             let state = &mut *(state as *mut DelegateState);
             state.events_queue.push_back(Event::Suspended(true));
-        }
+        })
     }
 
     extern fn will_terminate(this: &Object, _: Sel, _: id) {
-        unsafe {
+        ::api::panic::abort_on_panic("applicationWillTerminate:", || unsafe {
             let state: *mut libc::c_void = *this.get_ivar("glutinState");
             let state = &mut *(state as *mut DelegateState);
             // push event to the front to garantee that we'll process it
             // immidiatly after jump
             state.events_queue.push_front(Event::Closed);
-            longjmp(mem::transmute(&mut jmpbuf),1);
-        }
+        });
+        // jumping out of `abort_on_panic` would skip the end of `catch_unwind`
+        unsafe { longjmp(mem::transmute(&mut jmpbuf),1); }
     }
 
     extern fn handle_touches(this: &Object, _: Sel, touches: id, _:id) {
-        unsafe {
+        ::api::panic::abort_on_panic("touches*:withEvent:", || unsafe {
             let state: *mut libc::c_void = *this.get_ivar("glutinState");
             let state = &mut *(state as *mut DelegateState);
 
@@ -136,7 +137,7 @@ pub fn create_delegate_class() {
                     }
                 }));
             }
-        }
+        })
     }
 
     let superclass = Class::get("UIResponder").unwrap();
@@ -193,7 +194,7 @@ pub fn create_view_class() {
     decl.register();
 
     extern fn init_for_gl(this: &Object, _: Sel, frame: *const libc::c_void) -> id {
-        unsafe {
+        ::api::panic::abort_on_panic("initForGl:", || unsafe {
             let bounds: *const CGRect = mem::transmute(frame);
             let view: id = msg_send![this, initWithFrame:(*bounds).clone()];
 
@@ -204,7 +205,7 @@ pub fn create_view_class() {
             let _ : () = msg_send![layer, setOpaque:YES];
 
             view
-        }
+        })
     }
 
     extern fn layer_class(_: &Class, _: Sel) -> *const Class {
@@ -62,7 +62,10 @@
 #![cfg(target_os = "ios")]
 #![deny(warnings)]
 
+use std::cmp;
 use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
 use std::ptr;
 use std::io;
 use std::mem;
@@ -73,7 +76,7 @@ use objc::runtime::{Class, BOOL, YES, NO };
 
 use native_monitor::NativeMonitorId;
 use { Api, PixelFormat, CreationError, GlContext, CursorState, MouseCursor, Event };
-use { PixelFormatRequirements, GlAttributes, WindowAttributes, ContextError };
+use { LockKeys, PixelFormatRequirements, PointerAcceleration, GlAttributes, SnapPosition, WindowAttributes, ContextError };
 use CreationError::OsError;
 
 mod delegate;
@@ -175,6 +178,26 @@ impl MonitorId {
     pub fn get_dimensions(&self) -> (u32, u32) {
         unimplemented!()
     }
+
+    #[inline]
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        None
+    }
 }
 
 #[derive(Clone, Default)]
@@ -182,6 +205,16 @@ pub struct PlatformSpecificWindowBuilderAttributes;
 
 impl Window {
 
+    /// Windows created by other libraries are not supported on this platform.
+    #[inline]
+    pub unsafe fn from_existing(_: *mut libc::c_void, _: *mut libc::c_void, _: &WindowAttributes,
+                                _: &PixelFormatRequirements, _: &GlAttributes<&Window>,
+                                _: &PlatformSpecificWindowBuilderAttributes)
+                                -> Result<Window, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     pub fn new(builder: &WindowAttributes, _: &PixelFormatRequirements, _: &GlAttributes<&Window>,
                _: &PlatformSpecificWindowBuilderAttributes) -> Result<Window, CreationError>
     {
@@ -282,6 +315,11 @@ impl Window {
     pub fn set_title(&self, _: &str) {
     }
 
+    #[inline]
+    pub fn get_title(&self) -> Option<String> {
+        None
+    }
+
     #[inline]
     pub fn show(&self) {
     }
@@ -290,6 +328,18 @@ impl Window {
     pub fn hide(&self) {
     }
 
+    #[inline]
+    pub fn focus(&self) {
+    }
+
+    #[inline]
+    pub fn raise(&self) {
+    }
+
+    #[inline]
+    pub fn lower(&self) {
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<(i32, i32)> {
         None
@@ -299,6 +349,60 @@ impl Window {
     pub fn set_position(&self, _x: i32, _y: i32) {
     }
 
+    #[inline]
+    pub fn get_lock_keys(&self) -> LockKeys {
+        LockKeys::default()
+    }
+
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        let res: BOOL = msg_send![Class::get("EAGLContext").unwrap(), setCurrentContext: nil];
+        if res == YES {
+            Ok(())
+        } else {
+            Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other, "EAGLContext::setCurrentContext unsuccessful")))
+        }
+    }
+
+    #[inline]
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        None
+    }
+
+    #[inline]
+    pub fn set_lock_keys(&self, _lock_keys: LockKeys) -> Result<(), String> {
+        Err("the lock keys can't be changed on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, _monitor: Option<MonitorId>) {
+    }
+
+    #[inline]
+    pub fn set_parent(&self, _parent: Option<::WindowID>) -> Result<(), String> {
+        Err("windows can't be embedded on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_menu_bar(&self, _menus: &[::Menu]) {
+    }
+
+    #[inline]
+    pub fn get_menu_bar_height(&self) -> u32 {
+        0
+    }
+
+    #[inline]
+    pub fn set_maximized(&self, _maximized: bool) {
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) {
+    }
+
+    #[inline]
+    pub fn snap_to(&self, _position: SnapPosition, _monitor: &MonitorId) {
+    }
+
     #[inline]
     pub fn get_inner_size(&self) -> Option<(u32, u32)> {
         unsafe { Some((&*self.delegate_state).size) }
@@ -327,6 +431,13 @@ impl Window {
         }
     }
 
+    /// There is no way to block on the events of this platform, so this sleeps for a frame
+    /// at most.
+    pub fn wait_for_events(_: &[&Window], timeout: Option<Duration>) {
+        let frame = Duration::from_millis(16);
+        thread::sleep(timeout.map_or(frame, |timeout| cmp::min(timeout, frame)));
+    }
+
     #[inline]
     pub fn platform_display(&self) -> *mut libc::c_void {
         unimplemented!();
@@ -355,11 +466,51 @@ impl Window {
         Ok(())
     }
 
+    #[inline]
+    pub fn set_variable_refresh(&self, _enabled: bool) {
+    }
+
+    #[inline]
+    pub fn set_cursor_size(&self, _size: u32) {
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, _size: Option<(u32, u32)>) {
+    }
+
+    #[inline]
+    pub fn set_clipboard_string(&self, _text: &str) {
+    }
+
+    #[inline]
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        None
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, _size: Option<(u32, u32)>) {
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
         unsafe { (&*self.delegate_state) }.scale
     }
 
+    #[inline]
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, _interval: u32) -> Result<(), String> {
+        Err("the swap interval can't be changed on iOS".to_string())
+    }
+
     #[inline]
     pub fn set_cursor_position(&self, _x: i32, _y: i32) -> Result<(), ()> {
         unimplemented!();
@@ -6,6 +6,8 @@ pub mod egl;
 pub mod emscripten;
 pub mod glx;
 pub mod osmesa;
+pub mod panic;
+pub mod pixel_format;
 pub mod wayland;
 pub mod wgl;
 pub mod win32;
@@ -13,7 +13,7 @@ use PixelFormat;
 use PixelFormatRequirements;
 use Robustness;
 use libc;
-use std::{mem, ptr};
+use std::{io, mem, ptr};
 use std::ffi::CString;
 
 pub struct OsMesaContext {
@@ -95,7 +95,7 @@ impl OsMesaContext {
         Ok(OsMesaContext {
             width: dimensions.0,
             height: dimensions.1,
-            buffer: ::std::iter::repeat(unsafe { mem::uninitialized() })
+            buffer: ::std::iter::repeat(0)
                 .take((dimensions.0 * dimensions.1) as usize).collect(),
             context: unsafe {
                 let ctxt = osmesa_sys::OSMesaCreateContextAttribs(attribs.as_ptr(), ptr::null_mut());
@@ -134,7 +134,8 @@ impl GlContext for OsMesaContext {
         // an error can only happen in case of invalid parameter, which would indicate a bug
         // in glutin
         if ret == 0 {
-            panic!("OSMesaMakeCurrent failed");
+            return Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                                                            "OSMesaMakeCurrent failed")));
         }
 
         Ok(())
@@ -0,0 +1,40 @@
+//! Protection against panics crossing `extern` functions.
+//!
+//! Unwinding through a C stack frame is undefined behavior, so all the functions that are
+//! called by the system (error handlers, window procedures, Objective-C methods) must run their
+//! body inside `abort_on_panic`.
+//!
+//! This module also lets the backends clean up the global state of the system, such as pointer
+//! grabs, when the program panics.
+
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+
+/// Runs the closure and aborts the process if it panics.
+///
+/// The panic message has already been printed by the panic hook when this function aborts, so
+/// the only thing we add is the name of the callback that panicked.
+pub fn abort_on_panic<F, R>(callback_name: &str, f: F) -> R where F: FnOnce() -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(_) => {
+            let _ = writeln!(::std::io::stderr(), "[glutin] panic in `{}`, aborting because \
+                                                    unwinding into the system is not possible",
+                             callback_name);
+            process::abort()
+        }
+    }
+}
+
+/// Runs `cleanup` every time a thread panics, before the previous panic hook.
+///
+/// The hook runs while the panicking thread may hold any lock, so `cleanup` must not block.
+#[allow(dead_code)]     // only the backends that grab the pointer need it
+pub fn add_panic_cleanup<F>(cleanup: F) where F: Fn() + Send + Sync + 'static {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        cleanup();
+        previous(info);
+    }));
+}
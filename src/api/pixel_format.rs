@@ -0,0 +1,200 @@
+#![cfg(any(target_os = "windows", target_os = "linux", target_os = "android",
+           target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
+
+//! Ranking of the pixel formats returned by GLX, WGL and EGL.
+//!
+//! The platforms return every format that satisfies the minimums of the requirements, sorted by
+//! their own rules, which favor the largest buffers. The first one often has more color, depth
+//! or samples than requested, which wastes memory and bandwidth. Instead, the formats are ranked
+//! by how close they are to the requirements.
+
+use ColorSpace;
+use PixelFormat;
+use PixelFormatRequirements;
+
+/// The number of color bits that is preferred when the user doesn't care.
+const DEFAULT_COLOR_BITS: u8 = 24;
+
+/// Returns how far the format is from the requirements. Lower is better, and `0` is a perfect
+/// match.
+///
+/// The formats are supposed to satisfy the requirements already, so this only ranks the
+/// differences that the platforms allow: extra bits, extra samples, and the preferences that
+/// are expressed with `None`.
+pub fn score(format: &PixelFormat, reqs: &PixelFormatRequirements) -> u32 {
+    let distance = |value: u8, wanted: u8| (value as i32 - wanted as i32).abs() as u32;
+    let mut score = 0;
+
+    // a software renderer is so much slower that nothing else matters
+    if !format.hardware_accelerated && reqs.hardware_accelerated != Some(false) {
+        score += 1_000_000;
+    }
+
+    // sRGB is filtered by the backends when it is required, but a linear framebuffer must be
+    // preferred when it is explicitly requested
+    match reqs.color_space {
+        ColorSpace::Srgb if !format.srgb => score += 100_000,
+        ColorSpace::Linear if format.srgb => score += 100_000,
+        _ => (),
+    }
+
+    if format.double_buffer != reqs.double_buffer.unwrap_or(true) {
+        score += 10_000;
+    }
+    if format.stereoscopy != reqs.stereoscopy {
+        score += 10_000;
+    }
+
+    // multisampling costs a lot of memory and bandwidth, so it is avoided unless requested
+    let samples = format.multisampling.unwrap_or(0);
+    score += 1_000 * match reqs.multisampling {
+        Some(wanted) => (samples as i32 - wanted as i32).abs() as u32,
+        None => samples as u32,
+    };
+    score += 1_000 * match (reqs.coverage_samples, format.coverage_samples) {
+        (Some(wanted), Some(coverage)) => (coverage as i32 - wanted as i32).abs() as u32,
+        (Some(wanted), None) => wanted as u32,
+        (None, Some(coverage)) => coverage as u32,
+        (None, None) => 0,
+    };
+
+    score += 10 * distance(format.color_bits, reqs.color_bits.unwrap_or(DEFAULT_COLOR_BITS));
+    score += 10 * reqs.alpha_bits.map_or(0, |alpha| distance(format.alpha_bits, alpha));
+    score += reqs.depth_bits.map_or(0, |depth| distance(format.depth_bits, depth));
+    score += reqs.stencil_bits.map_or(0, |stencil| distance(format.stencil_bits, stencil));
+
+    // the platforms treat the sizes as minimums, but zero means that the buffer is not wanted
+    // at all, which is worth more than the exact number of color bits
+    if reqs.depth_bits == Some(0) && format.depth_bits != 0 {
+        score += 10_000;
+    }
+    if reqs.stencil_bits == Some(0) && format.stencil_bits != 0 {
+        score += 10_000;
+    }
+
+    score
+}
+
+/// Returns the index of the format that is closest to the requirements. The first format wins
+/// in case of a tie, so that the order of the platform is kept.
+///
+/// If the user has set a selector, it makes the choice instead.
+///
+/// Returns `None` if there is no format, or if the selector returned an invalid index.
+pub fn choose_best(formats: &[PixelFormat], reqs: &PixelFormatRequirements) -> Option<usize> {
+    if formats.is_empty() {
+        return None;
+    }
+
+    if let Some(ref selector) = reqs.selector {
+        let index = (selector.0)(formats);
+        return if index < formats.len() { Some(index) } else { None };
+    }
+
+    formats.iter()
+           .map(|format| score(format, reqs))
+           .enumerate()
+           .min_by_key(|&(index, score)| (score, index))
+           .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ColorSpace;
+    use NativePixelFormatId;
+    use PixelFormat;
+    use PixelFormatRequirements;
+    use PixelFormatSelector;
+
+    use super::{choose_best, score};
+
+    /// A hardware-accelerated format that matches the default requirements exactly.
+    fn format(id: i32) -> PixelFormat {
+        PixelFormat {
+            hardware_accelerated: true,
+            color_bits: 24,
+            alpha_bits: 8,
+            depth_bits: 24,
+            stencil_bits: 8,
+            stereoscopy: false,
+            double_buffer: true,
+            multisampling: None,
+            coverage_samples: None,
+            srgb: false,
+            color_space: ColorSpace::Linear,
+            swap_method: None,
+            native_id: NativePixelFormatId::Wgl(id),
+        }
+    }
+
+    #[test]
+    fn perfect_match_scores_zero() {
+        assert_eq!(score(&format(1), &PixelFormatRequirements::default()), 0);
+    }
+
+    #[test]
+    fn zero_depth_and_stencil_prefer_formats_without_them() {
+        let mut reqs = PixelFormatRequirements::default();
+        reqs.depth_bits = Some(0);
+        reqs.stencil_bits = Some(0);
+
+        let mut bare = format(2);
+        bare.depth_bits = 0;
+        bare.stencil_bits = 0;
+
+        assert_eq!(score(&bare, &reqs), 0);
+        assert_eq!(choose_best(&[format(1), bare.clone()], &reqs), Some(1));
+
+        // even if the format without them has fewer color bits
+        bare.color_bits = 16;
+        assert_eq!(choose_best(&[format(1), bare], &reqs), Some(1));
+    }
+
+    #[test]
+    fn software_formats_are_penalized() {
+        let mut software = format(1);
+        software.hardware_accelerated = false;
+        let mut multisampled = format(2);
+        multisampled.multisampling = Some(16);
+
+        let reqs = PixelFormatRequirements::default();
+        assert!(score(&software, &reqs) > score(&multisampled, &reqs));
+        assert_eq!(choose_best(&[software.clone(), multisampled], &reqs), Some(1));
+
+        // unless a software renderer has been requested
+        let mut reqs = PixelFormatRequirements::default();
+        reqs.hardware_accelerated = Some(false);
+        assert_eq!(score(&software, &reqs), 0);
+    }
+
+    #[test]
+    fn ties_keep_the_order_of_the_platform() {
+        let reqs = PixelFormatRequirements::default();
+        assert_eq!(choose_best(&[format(1), format(2), format(3)], &reqs), Some(0));
+
+        let mut worse = format(1);
+        worse.color_bits = 30;
+        assert_eq!(choose_best(&[worse, format(2), format(3)], &reqs), Some(1));
+    }
+
+    #[test]
+    fn selector_makes_the_choice() {
+        let mut reqs = PixelFormatRequirements::default();
+        reqs.selector = Some(PixelFormatSelector(Arc::new(|formats: &[PixelFormat]| formats.len() - 1)));
+        assert_eq!(choose_best(&[format(1), format(2)], &reqs), Some(1));
+    }
+
+    #[test]
+    fn out_of_range_selector_chooses_nothing() {
+        let mut reqs = PixelFormatRequirements::default();
+        reqs.selector = Some(PixelFormatSelector(Arc::new(|formats: &[PixelFormat]| formats.len())));
+        assert_eq!(choose_best(&[format(1), format(2)], &reqs), None);
+    }
+
+    #[test]
+    fn no_format_chooses_nothing() {
+        assert_eq!(choose_best(&[], &PixelFormatRequirements::default()), None);
+    }
+}
@@ -1,10 +1,19 @@
 use Event as GlutinEvent;
+use ModifiersState;
 
 use std::collections::{HashMap, VecDeque, HashSet};
-use std::sync::{Arc, Mutex};
+use std::io;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Instant;
 
+use libc;
 use libc::c_void;
 
+use api::dlopen;
+use termination;
+
 use wayland_client::{EventIterator, Proxy, ProxyId};
 use wayland_client::wayland::get_display;
 use wayland_client::wayland::compositor::{WlCompositor, WlSurface};
@@ -36,7 +45,9 @@ pub struct WaylandFocuses {
     pub pointer_on: Option<ProxyId>,
     pub pointer_at: Option<(f64, f64)>,
     pub keyboard: Option<MappedKeyboard>,
-    pub keyboard_on: Option<ProxyId>
+    pub keyboard_on: Option<ProxyId>,
+    // tracked from the key events of the modifiers
+    pub modifiers: ModifiersState
 }
 
 pub struct WaylandContext {
@@ -45,7 +56,17 @@ pub struct WaylandContext {
     monitors: Vec<(WlOutput, u32, u32, String)>,
     queues: Mutex<HashMap<ProxyId, Arc<Mutex<VecDeque<GlutinEvent>>>>>,
     known_surfaces: Mutex<HashSet<ProxyId>>,
-    focuses: Mutex<WaylandFocuses>
+    focuses: Mutex<WaylandFocuses>,
+    timer: Mutex<Timer>,
+    timer_signal: Condvar,
+    /// The socket of the connection, which is watched along with the termination pipe
+    display_fd: Option<libc::c_int>,
+}
+
+/// The state of the thread that wakes up the event loop at scheduled instants.
+struct Timer {
+    deadline: Option<Instant>,
+    running: bool
 }
 
 impl WaylandContext {
@@ -70,6 +91,8 @@ impl WaylandContext {
 
         super::monitor::init_monitors(&mut monitors, outputs_events);
 
+        let display_fd = get_display_fd(inner_env.display.ptr() as *mut c_void);
+
         Some(WaylandContext {
             inner: inner_env,
             iterator: Mutex::new(iterator),
@@ -81,8 +104,12 @@ impl WaylandContext {
                 pointer_on: None,
                 pointer_at: None,
                 keyboard: None,
-                keyboard_on: None
-            })
+                keyboard_on: None,
+                modifiers: ModifiersState::default()
+            }),
+            timer: Mutex::new(Timer { deadline: None, running: false }),
+            timer_signal: Condvar::new(),
+            display_fd: display_fd,
         })
     }
 
@@ -175,16 +202,83 @@ impl WaylandContext {
         }
     }
 
+    /// Wakes up a thread blocked in `read_events`.
+    ///
+    /// The compositor answers a `wl_display.sync` request with a `done` event, which unblocks
+    /// the reader.
+    pub fn wakeup(&self) {
+        self.inner.display.sync();
+        let _ = self.inner.display.flush();
+    }
+
+    /// Makes sure that a thread blocked in `read_events` wakes up at the given instant.
+    ///
+    /// A single thread, started on the first call, waits for the earliest scheduled instant.
+    pub fn wakeup_at(&'static self, instant: Instant) {
+        let mut timer = self.timer.lock().unwrap();
+        if timer.deadline.map_or(false, |deadline| deadline <= instant) {
+            return;
+        }
+        timer.deadline = Some(instant);
+
+        if !timer.running {
+            timer.running = true;
+            thread::spawn(move || self.run_timer());
+        }
+        self.timer_signal.notify_one();
+    }
+
+    fn run_timer(&self) {
+        let mut timer = self.timer.lock().unwrap();
+        loop {
+            timer = match timer.deadline {
+                None => self.timer_signal.wait(timer).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if deadline <= now {
+                        timer.deadline = None;
+                        self.wakeup();
+                        continue;
+                    }
+                    self.timer_signal.wait_timeout(timer, deadline - now).unwrap().0
+                }
+            };
+        }
+    }
+
     pub fn flush_events(&self) -> ::std::io::Result<i32> {
         self.inner.display.flush()
     }
 
-    pub fn read_events(&self) -> ::std::io::Result<Option<i32>> {
+    /// Reads the events sent by the compositor, blocking until there are some unless events are
+    /// already waiting to be dispatched.
+    ///
+    /// Also returns when a termination is requested, in which case `Ok(true)` is returned.
+    pub fn read_events(&self) -> io::Result<bool> {
         let guard = match self.inner.display.prepare_read() {
             Some(g) => g,
-            None => return Ok(None)
+            None => return Ok(false)
         };
-        return guard.read_events().map(|i| Some(i));
+
+        if let (Some(display_fd), Some(wakeup_fd)) = (self.display_fd, termination::wakeup_fd()) {
+            let mut fds = [
+                libc::pollfd { fd: display_fd, events: libc::POLLIN, revents: 0 },
+                libc::pollfd { fd: wakeup_fd, events: libc::POLLIN, revents: 0 },
+            ];
+
+            // interrupted by a signal if `poll` returns `-1`, in which case the handler may have
+            // written to the pipe, which is checked by the next call
+            while unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) } <= 0 {}
+
+            if fds[1].revents & libc::POLLIN != 0 {
+                termination::drain();
+                // dropping the guard cancels the read, and the events that are already
+                // readable are read by the next call
+                return Ok(true);
+            }
+        }
+
+        guard.read_events().map(|_| false)
     }
 
     pub fn monitor_ids(&self) -> Vec<ProxyId> {
@@ -209,3 +303,25 @@ impl WaylandContext {
         None
     }
 }
+
+/// Returns the file descriptor of the socket of a `wl_display`.
+///
+/// wayland-client doesn't expose it, so `wl_display_get_fd` is loaded from the library that it
+/// uses.
+fn get_display_fd(display: *mut c_void) -> Option<libc::c_int> {
+    unsafe {
+        let lib = dlopen::dlopen(b"libwayland-client.so.0\0".as_ptr() as *const _,
+                                 dlopen::RTLD_NOW);
+        if lib.is_null() {
+            return None;
+        }
+
+        let get_fd = dlopen::dlsym(lib, b"wl_display_get_fd\0".as_ptr() as *const _);
+        if get_fd.is_null() {
+            return None;
+        }
+
+        let get_fd: unsafe extern "C" fn(*mut c_void) -> libc::c_int = mem::transmute(get_fd);
+        Some(get_fd(display))
+    }
+}
@@ -84,7 +84,8 @@ pub fn translate_event(
                             // TODO figure out the translation ?
                             _ => return None
                         },
-                        None
+                        None,
+                        focuses.modifiers
                     ), surface))
                 } else {
                     None
@@ -101,7 +102,8 @@ pub fn translate_event(
                                 MouseScrollDelta::PixelDelta(0.0, amplitude as f32)
                             }
                         },
-                        TouchPhase::Moved
+                        TouchPhase::Moved,
+                        false
                     ), surface))
                 } else {
                     None
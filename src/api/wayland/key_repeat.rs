@@ -0,0 +1,127 @@
+//! Synthesis of key repeats.
+//!
+//! Contrary to X11, the Wayland protocol doesn't repeat the keys that are held down: this is
+//! left to the client. The compositor can advertise its repeat settings with the `repeat_info`
+//! event of `wl_keyboard`, but the version of the protocol that we bind doesn't have it, so the
+//! settings are read from the X server of the session through XWayland instead. The usual
+//! defaults of the desktop environments are used if there is none.
+
+use std::time::{Duration, Instant};
+
+use ElementState;
+use Event;
+use ScanCode;
+use VirtualKeyCode;
+
+/// The delay before a held key starts repeating, in milliseconds.
+const DEFAULT_DELAY_MS: u64 = 600;
+
+/// The number of repeats per second.
+const DEFAULT_RATE: u64 = 25;
+
+lazy_static! {
+    /// The delay and the interval of the repeats, read once for the whole process.
+    static ref REPEAT_RATE: (Duration, Duration) = {
+        ::api::x11::query_key_repeat().unwrap_or((Duration::from_millis(DEFAULT_DELAY_MS),
+                                                  Duration::from_millis(1000 / DEFAULT_RATE)))
+    };
+}
+
+/// Keeps track of the key that is held down and produces its repeats.
+pub struct KeyRepeat {
+    delay: Duration,
+    interval: Duration,
+    held: Option<HeldKey>,
+}
+
+struct HeldKey {
+    scancode: ScanCode,
+    /// The events that are produced every time the key repeats: the `KeyboardInput` event,
+    /// followed by its `ReceivedText` and `ReceivedCharacter` events if any.
+    events: Vec<Event>,
+    next_repeat: Instant,
+}
+
+impl KeyRepeat {
+    #[inline]
+    pub fn new() -> KeyRepeat {
+        KeyRepeat {
+            delay: REPEAT_RATE.0,
+            interval: REPEAT_RATE.1,
+            held: None,
+        }
+    }
+
+    /// Updates the held key according to an event that is about to be returned to the user.
+    pub fn process(&mut self, event: &Event) {
+        match *event {
+            Event::KeyboardInput(ElementState::Pressed, scancode, vkey, _) => {
+                if vkey.map_or(false, is_modifier) {
+                    return;
+                }
+
+                self.held = Some(HeldKey {
+                    scancode: scancode,
+                    events: vec![event.clone()],
+                    next_repeat: Instant::now() + self.delay,
+                });
+            },
+
+            Event::ReceivedText(_) | Event::ReceivedCharacter(_) => {
+                if let Some(ref mut held) = self.held {
+                    if held.events.len() < 3 {
+                        held.events.push(event.clone());
+                    }
+                }
+            },
+
+            Event::KeyboardInput(ElementState::Released, scancode, _, _) => {
+                if self.held.as_ref().map_or(false, |held| held.scancode == scancode) {
+                    self.held = None;
+                }
+            },
+
+            Event::Focused(false) | Event::Closed => {
+                self.held = None;
+            },
+
+            _ => ()
+        }
+    }
+
+    /// Returns the repeats that are due, in order.
+    ///
+    /// If the application didn't fetch its events for a long time, only one repeat is produced
+    /// instead of a burst.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let now = Instant::now();
+
+        match self.held {
+            Some(ref mut held) if held.next_repeat <= now => {
+                held.next_repeat = held.next_repeat + self.interval;
+                if held.next_repeat <= now {
+                    held.next_repeat = now + self.interval;
+                }
+                held.events.clone()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the instant of the next repeat, if a key is held down.
+    #[inline]
+    pub fn next_repeat(&self) -> Option<Instant> {
+        self.held.as_ref().map(|held| held.next_repeat)
+    }
+}
+
+/// Modifiers don't repeat.
+fn is_modifier(vkey: VirtualKeyCode) -> bool {
+    match vkey {
+        VirtualKeyCode::LShift | VirtualKeyCode::RShift | VirtualKeyCode::LControl |
+        VirtualKeyCode::RControl | VirtualKeyCode::LAlt | VirtualKeyCode::RAlt |
+        VirtualKeyCode::LWin | VirtualKeyCode::RWin | VirtualKeyCode::Capital |
+        VirtualKeyCode::Numlock | VirtualKeyCode::Scroll => true,
+        _ => false,
+    }
+}
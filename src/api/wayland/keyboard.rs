@@ -2,6 +2,7 @@ use std::collections::HashSet;
 
 use Event as GlutinEvent;
 use ElementState;
+use ModifiersState;
 use VirtualKeyCode;
 
 use wayland_client::ProxyId;
@@ -36,22 +37,35 @@ pub fn translate_kbd_events(
                             _ => kevt.as_symbol().and_then(keysym_to_vkey)
                         };
                         let text = kevt.as_utf8();
+                        let state = match kevt.keystate {
+                            WlKeyboardKeyState::Pressed => ElementState::Pressed,
+                            WlKeyboardKeyState::Released =>ElementState::Released
+                        };
                         out.push((
                             GlutinEvent::KeyboardInput(
-                                match kevt.keystate {
-                                    WlKeyboardKeyState::Pressed => ElementState::Pressed,
-                                    WlKeyboardKeyState::Released =>ElementState::Released
-                                },
+                                state,
                                 (kevt.keycode & 0xff) as u8,
-                                vkcode
+                                vkcode,
+                                focuses.modifiers
                             ),
                             surface
                         ));
-                        if let Some(c) = text.and_then(|s| s.chars().next()) {
-                            out.push((
-                                GlutinEvent::ReceivedCharacter(c),
-                                surface
-                            ));
+                        if let Some(vkcode) = vkcode {
+                            update_modifiers(&mut focuses.modifiers, vkcode,
+                                             state == ElementState::Pressed);
+                        }
+                        if let Some(text) = text {
+                            let first = text.chars().next();
+                            if let Some(c) = first {
+                                out.push((
+                                    GlutinEvent::ReceivedText(text),
+                                    surface
+                                ));
+                                out.push((
+                                    GlutinEvent::ReceivedCharacter(c),
+                                    surface
+                                ));
+                            }
                         }
                     }
                     
@@ -64,6 +78,8 @@ pub fn translate_kbd_events(
                         }
                     },
                     WlKeyboardEvent::Leave(_, surface) => {
+                        // the modifiers may be released while another surface has the focus
+                        focuses.modifiers = ModifiersState::default();
                         if known_surfaces.contains(&surface) {
                             focuses.keyboard_on = None;
                             out.push((GlutinEvent::Focused(false), surface));
@@ -77,6 +93,16 @@ pub fn translate_kbd_events(
     out
 }
 
+fn update_modifiers(modifiers: &mut ModifiersState, vkey: VirtualKeyCode, pressed: bool) {
+    match vkey {
+        VirtualKeyCode::LShift | VirtualKeyCode::RShift => modifiers.shift = pressed,
+        VirtualKeyCode::LControl | VirtualKeyCode::RControl => modifiers.ctrl = pressed,
+        VirtualKeyCode::LAlt | VirtualKeyCode::RAlt => modifiers.alt = pressed,
+        VirtualKeyCode::LWin | VirtualKeyCode::RWin => modifiers.logo = pressed,
+        _ => ()
+    }
+}
+
 pub fn keysym_to_vkey(keysym: u32) -> Option<VirtualKeyCode> {
     use super::wayland_kbd::keysyms;
     match keysym {
@@ -179,7 +205,7 @@ pub fn keysym_to_vkey(keysym: u32) -> Option<VirtualKeyCode> {
         keysyms::XKB_KEY_Control_L => Some(VirtualKeyCode::LControl),
         // => Some(VirtualKeyCode::LMenu),
         keysyms::XKB_KEY_Shift_L => Some(VirtualKeyCode::LShift),
-        // => Some(VirtualKeyCode::LWin),
+        keysyms::XKB_KEY_Super_L => Some(VirtualKeyCode::LWin),
         // => Some(VirtualKeyCode::Mail),
         // => Some(VirtualKeyCode::MediaSelect),
         // => Some(VirtualKeyCode::MediaStop),
@@ -202,7 +228,7 @@ pub fn keysym_to_vkey(keysym: u32) -> Option<VirtualKeyCode> {
         keysyms::XKB_KEY_Control_R => Some(VirtualKeyCode::RControl),
         // => Some(VirtualKeyCode::RMenu),
         keysyms::XKB_KEY_Shift_R => Some(VirtualKeyCode::RShift),
-        // => Some(VirtualKeyCode::RWin),
+        keysyms::XKB_KEY_Super_R => Some(VirtualKeyCode::RWin),
         keysyms::XKB_KEY_semicolon => Some(VirtualKeyCode::Semicolon),
         keysyms::XKB_KEY_slash => Some(VirtualKeyCode::Slash),
         // => Some(VirtualKeyCode::Sleep),
@@ -34,6 +34,30 @@ impl MonitorId {
     pub fn get_dimensions(&self) -> (u32, u32) {
         WAYLAND_CONTEXT.as_ref().and_then(|ctxt| ctxt.monitor_dimensions(self.0)).unwrap()
     }
+
+    /// `wl_output` doesn't tell which part of the output is covered by panels, so this is the
+    /// whole output.
+    #[inline]
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        let (width, height) = self.get_dimensions();
+        Some((0, 0, width, height))
+    }
+
+    #[inline]
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        // wl_output doesn't expose the EDID
+        None
+    }
+
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        None
+    }
 }
 
 pub fn proxid_from_monitorid(x: &MonitorId) -> ProxyId {
@@ -1,11 +1,13 @@
 use std::collections::VecDeque;
 use std::ffi::CString;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use libc;
 
-use {ContextError, CreationError, CursorState, Event, GlAttributes, GlContext,
-     MouseCursor, PixelFormat, PixelFormatRequirements, WindowAttributes};
+use {ContextError, CreationError, CursorState, Event, GlAttributes, GlContext, LockKeys,
+     LogicalSize, MouseCursor, PixelFormat, PixelFormatRequirements, PointerAcceleration,
+     SnapPosition, WindowAttributes};
 use api::dlopen;
 use api::egl;
 use api::egl::Context as EglContext;
@@ -16,14 +18,19 @@ use wayland_client::egl as wegl;
 use wayland_client::wayland::shell::WlShellSurface;
 use super::wayland_window::{DecoratedSurface, add_borders, substract_borders};
 use super::context::{WaylandContext, WAYLAND_CONTEXT};
+use super::key_repeat::KeyRepeat;
 
 #[derive(Clone)]
-pub struct WindowProxy;
+pub struct WindowProxy {
+    wayland_context: &'static WaylandContext,
+    evt_queue: Arc<Mutex<VecDeque<Event>>>,
+}
 
 impl WindowProxy {
     #[inline]
     pub fn wakeup_event_loop(&self) {
-        unimplemented!()
+        self.evt_queue.lock().unwrap().push_back(Event::Awakened);
+        self.wayland_context.wakeup();
     }
 }
 
@@ -34,6 +41,8 @@ pub struct Window {
     evt_queue: Arc<Mutex<VecDeque<Event>>>,
     inner_size: Mutex<(i32, i32)>,
     resize_callback: Option<fn(u32, u32)>,
+    // `None` if the repeat of the keys is disabled
+    key_repeat: Option<Mutex<KeyRepeat>>,
     pub context: EglContext,
 }
 
@@ -80,9 +89,43 @@ impl Window {
             if let Some(f) = self.resize_callback {
                 f(w as u32, h as u32);
             }
-            Some(Event::Resized(w as u32, h as u32))
+            Some(Event::Resized(LogicalSize::new(w as u32, h as u32)))
         } else {
-            evt_queue_guard.pop_front()
+            let event = evt_queue_guard.pop_front();
+            if let (Some(event), Some(key_repeat)) = (event.as_ref(), self.key_repeat.as_ref()) {
+                key_repeat.lock().unwrap().process(event);
+            }
+            event
+        }
+    }
+
+    /// Returns the next repeat of the key that is held down, if it is due.
+    ///
+    /// Must only be called once the events of the compositor have been dispatched, so that
+    /// the key isn't repeated after it has been released.
+    fn next_repeat(&self) -> Option<Event> {
+        let key_repeat = match self.key_repeat {
+            Some(ref k) => k,
+            None => return None
+        };
+
+        let mut repeats = key_repeat.lock().unwrap().poll().into_iter();
+        let first = repeats.next();
+        // the `ReceivedText` and `ReceivedCharacter` events, if any
+        self.evt_queue.lock().unwrap().extend(repeats);
+        first
+    }
+
+    /// Makes sure that a blocking read of the events returns when the key that is held down
+    /// is due to repeat.
+    fn schedule_repeat_wakeup(&self) {
+        let next_repeat = match self.key_repeat {
+            Some(ref k) => k.lock().unwrap().next_repeat(),
+            None => return
+        };
+
+        if let Some(next_repeat) = next_repeat {
+            self.wayland_context.wakeup_at(next_repeat);
         }
     }
 }
@@ -101,7 +144,7 @@ impl<'a> Iterator for PollEventsIterator<'a> {
         }
         // the queue was empty, try a dispatch and see the result
         self.window.wayland_context.dispatch_events();
-        return self.window.next_event();
+        self.window.next_event().or_else(|| self.window.next_repeat())
     }
 }
 
@@ -118,12 +161,20 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
                 Some(evt) => return Some(evt),
                 None => {}
             }
+            match self.window.next_repeat() {
+                Some(evt) => return Some(evt),
+                None => {}
+            }
             // the queue was empty, try a dispatch & read and see the result
+            self.window.schedule_repeat_wakeup();
             self.window.wayland_context.flush_events().expect("Connexion with the wayland compositor lost.");
             match self.window.wayland_context.read_events() {
-                Ok(_) => {
+                Ok(terminated) => {
                     // events were read or dispatch is needed, in both cases, we dispatch
-                    self.window.wayland_context.dispatch_events()
+                    self.window.wayland_context.dispatch_events();
+                    if terminated {
+                        return Some(Event::Terminated);
+                    }
                 }
                 Err(_) => panic!("Connexion with the wayland compositor lost.")
             }
@@ -131,6 +182,35 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
     }
 }
 
+impl Window {
+    /// Blocks until one of the windows may have events, or until the timeout expires.
+    ///
+    /// All the windows share the connection to the compositor, so reading from it is enough.
+    pub fn wait_for_events(windows: &[&Window], timeout: Option<Duration>) {
+        let wayland_context = match windows.first() {
+            Some(window) => window.wayland_context,
+            None => return
+        };
+
+        for window in windows {
+            if !window.evt_queue.lock().unwrap().is_empty() {
+                return;
+            }
+            window.schedule_repeat_wakeup();
+        }
+
+        if let Some(timeout) = timeout {
+            wayland_context.wakeup_at(Instant::now() + timeout);
+        }
+
+        wayland_context.flush_events().expect("Connexion with the wayland compositor lost.");
+        match wayland_context.read_events() {
+            Ok(_) => wayland_context.dispatch_events(),
+            Err(_) => panic!("Connexion with the wayland compositor lost.")
+        }
+    }
+}
+
 enum ShellWindow {
     Plain(WlShellSurface, EventIterator),
     Decorated(DecoratedSurface)
@@ -141,9 +221,9 @@ impl Window {
                opengl: &GlAttributes<&Window>) -> Result<Window, CreationError>
     {
         use wayland_client::Proxy;
-        // not implemented
-        assert!(window.min_dimensions.is_none());
-        assert!(window.max_dimensions.is_none());
+
+        // TODO: wl_shell has no way to constrain the size of a surface, so
+        //       `min_dimensions` and `max_dimensions` are ignored
 
         let wayland_context = match *WAYLAND_CONTEXT {
             Some(ref c) => c,
@@ -174,7 +254,7 @@ impl Window {
             });
             try!(EglContext::new(
                 egl,
-                pf_reqs, &opengl.clone().map_sharing(|_| unimplemented!()),        // TODO: 
+                pf_reqs, &opengl.clone().map_sharing(|w| &w.context),
                 egl::NativeDisplay::Wayland(Some(wayland_context.display_ptr() as *const _)))
                 .and_then(|p| p.finish(unsafe { egl_surface.egl_surfaceptr() } as *const _))
             )
@@ -213,6 +293,7 @@ impl Window {
             evt_queue: evt_queue,
             inner_size: Mutex::new((w as i32, h as i32)),
             resize_callback: None,
+            key_repeat: if window.key_repeat { Some(Mutex::new(KeyRepeat::new())) } else { None },
             context: context
         })
     }
@@ -225,6 +306,12 @@ impl Window {
         }
     }
 
+    /// The compositor doesn't give the title back.
+    #[inline]
+    pub fn get_title(&self) -> Option<String> {
+        None
+    }
+
     #[inline]
     pub fn show(&self) {
         // TODO
@@ -235,6 +322,21 @@ impl Window {
         // TODO
     }
 
+    #[inline]
+    pub fn focus(&self) {
+        // Not possible with wayland
+    }
+
+    #[inline]
+    pub fn raise(&self) {
+        // Not possible with wayland
+    }
+
+    #[inline]
+    pub fn lower(&self) {
+        // Not possible with wayland
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<(i32, i32)> {
         // Not possible with wayland
@@ -246,6 +348,47 @@ impl Window {
         // Not possible with wayland
     }
 
+    #[inline]
+    pub fn get_lock_keys(&self) -> LockKeys {
+        LockKeys::default()
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        self.context.make_not_current()
+    }
+
+    #[inline]
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        None
+    }
+
+    #[inline]
+    pub fn set_lock_keys(&self, _lock_keys: LockKeys) -> Result<(), String> {
+        Err("the lock keys can't be changed on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, _monitor: Option<super::MonitorId>) {
+    }
+
+    #[inline]
+    pub fn set_parent(&self, _parent: Option<::WindowID>) -> Result<(), String> {
+        Err("windows can't be embedded on this platform".to_string())
+    }
+
+    #[inline]
+    pub fn set_maximized(&self, _maximized: bool) {
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) {
+    }
+
+    #[inline]
+    pub fn snap_to(&self, _position: SnapPosition, _monitor: &super::MonitorId) {
+    }
+
     pub fn get_inner_size(&self) -> Option<(u32, u32)> {
         let (w, h) = *self.inner_size.lock().unwrap();
         Some((w as u32, h as u32))
@@ -270,7 +413,10 @@ impl Window {
 
     #[inline]
     pub fn create_window_proxy(&self) -> WindowProxy {
-        WindowProxy
+        WindowProxy {
+            wayland_context: self.wayland_context,
+            evt_queue: self.evt_queue.clone(),
+        }
     }
 
     #[inline]
@@ -299,20 +445,63 @@ impl Window {
 
     #[inline]
     pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
-        use CursorState::{Grab, Normal, Hide};
+        use CursorState::{Grab, GrabRelative, Normal, Hide, Soft};
         // TODO : not yet possible on wayland to grab cursor
         match state {
-            Grab => Err("Cursor cannot be grabbed on wayland yet.".to_string()),
-            Hide => Err("Cursor cannot be hidden on wayland yet.".to_string()),
+            Grab | GrabRelative => Err("Cursor cannot be grabbed on wayland yet.".to_string()),
+            Hide | Soft => Err("Cursor cannot be hidden on wayland yet.".to_string()),
             Normal => Ok(())
         }
     }
 
+    #[inline]
+    pub fn set_variable_refresh(&self, _enabled: bool) {
+    }
+
+    #[inline]
+    pub fn set_cursor_size(&self, _size: u32) {
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, _size: Option<(u32, u32)>) {
+        // TODO: wl_shell has no way to constrain the size of a surface
+    }
+
+    #[inline]
+    pub fn set_clipboard_string(&self, _text: &str) {
+    }
+
+    #[inline]
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        None
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, _size: Option<(u32, u32)>) {
+        // TODO: wl_shell has no way to constrain the size of a surface
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
         1.0
     }
 
+    #[inline]
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        // wl_pointer only reports the position in events
+        None
+    }
+
+    #[inline]
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        self.context.set_swap_interval(interval)
+    }
+
     #[inline]
     pub fn set_cursor_position(&self, _x: i32, _y: i32) -> Result<(), ()> {
         // TODO: not yet possible on wayland
@@ -321,12 +510,13 @@ impl Window {
 
     #[inline]
     pub fn platform_display(&self) -> *mut libc::c_void {
-        unimplemented!()
+        self.wayland_context.display_ptr() as *mut _
     }
 
     #[inline]
     pub fn platform_window(&self) -> *mut libc::c_void {
-        unimplemented!()
+        use wayland_client::Proxy;
+        (*self.egl_surface).ptr() as *mut _
     }
 }
 
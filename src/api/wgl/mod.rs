@@ -6,8 +6,11 @@ use GlAttributes;
 use GlContext;
 use GlRequest;
 use GlProfile;
+use ColorSpace;
+use NativePixelFormatId;
 use PixelFormat;
 use PixelFormatRequirements;
+use SwapMethod;
 use ReleaseBehavior;
 use Robustness;
 use Api;
@@ -17,9 +20,11 @@ use self::make_current_guard::CurrentContextGuard;
 use std::ffi::{CStr, CString, OsStr};
 use std::os::raw::{c_void, c_int};
 use std::os::windows::ffi::OsStrExt;
-use std::{mem, ptr};
+use std::{cmp, mem, ptr};
 use std::io;
 
+use api::pixel_format;
+
 use winapi;
 use kernel32;
 use user32;
@@ -34,6 +39,10 @@ mod gl;
 pub struct Context {
     context: ContextWrapper,
 
+    /// The pbuffer that the context renders to, for headless contexts. Destroyed after the
+    /// context.
+    pbuffer: Option<PbufferWrapper>,
+
     hdc: winapi::HDC,
 
     /// Binded to `opengl32.dll`.
@@ -58,6 +67,23 @@ impl Drop for WindowWrapper {
     }
 }
 
+/// A pbuffer and its device context, which are destroyed with the wrapper.
+struct PbufferWrapper {
+    extra: gl::wgl_extra::Wgl,
+    pbuffer: gl::wgl_extra::types::HPBUFFERARB,
+    hdc: winapi::HDC,
+}
+
+impl Drop for PbufferWrapper {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            self.extra.ReleasePbufferDCARB(self.pbuffer, self.hdc as *const _);
+            self.extra.DestroyPbufferARB(self.pbuffer);
+        }
+    }
+}
+
 /// Wraps around a context so that it is destroyed when necessary.
 struct ContextWrapper(winapi::HGLRC);
 
@@ -80,6 +106,27 @@ impl Context {
     /// The `window` must continue to exist as long as the resulting `Context` exists.
     pub unsafe fn new(pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<winapi::HGLRC>,
                       window: winapi::HWND) -> Result<Context, CreationError>
+    {
+        Context::new_impl(pf_reqs, opengl, window, None)
+    }
+
+    /// Attempt to build a new WGL context that renders to a pbuffer of the given dimensions,
+    /// without any window.
+    ///
+    /// Returns `NotSupported` if the driver doesn't provide `WGL_ARB_pbuffer`.
+    pub unsafe fn new_pbuffer(pf_reqs: &PixelFormatRequirements,
+                              opengl: &GlAttributes<winapi::HGLRC>, dimensions: (u32, u32))
+                              -> Result<Context, CreationError>
+    {
+        // the pixel format of a pbuffer is chosen on the device context of a window, but the
+        // pbuffer doesn't depend on that window once created
+        let window = try!(create_hidden_window());
+        Context::new_impl(pf_reqs, opengl, window.0, Some(dimensions))
+    }
+
+    unsafe fn new_impl(pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<winapi::HGLRC>,
+                       window: winapi::HWND, pbuffer: Option<(u32, u32)>)
+                       -> Result<Context, CreationError>
     {
         let hdc = user32::GetDC(window);
         if hdc.is_null() {
@@ -106,20 +153,51 @@ impl Context {
             format!("")
         };
 
-        // calling SetPixelFormat
-        let pixel_format = {
-            let (id, f) = if extensions.split(' ').find(|&i| i == "WGL_ARB_pixel_format")
-                                                  .is_some()
-            {
-                try!(choose_arb_pixel_format(&extra_functions, &extensions, hdc, pf_reqs)
-                                            .map_err(|_| CreationError::NoAvailablePixelFormat))
-            } else {
-                try!(choose_native_pixel_format(hdc, pf_reqs)
-                                            .map_err(|_| CreationError::NoAvailablePixelFormat))
+        if pbuffer.is_some() && extensions.split(' ').find(|&i| i == "WGL_ARB_pbuffer").is_none() {
+            return Err(CreationError::NotSupported);
+        }
+
+        // calling SetPixelFormat, or creating the pbuffer
+        let (hdc, pbuffer, pixel_format) = {
+            let choose = |reqs: &PixelFormatRequirements| {
+                if extensions.split(' ').find(|&i| i == "WGL_ARB_pixel_format").is_some() {
+                    choose_arb_pixel_format(&extra_functions, &extensions, hdc, reqs,
+                                            pbuffer.is_some())
+                } else if pbuffer.is_some() {
+                    Err(())
+                } else {
+                    choose_native_pixel_format(hdc, reqs)
+                }
+            };
+
+            // `ChoosePixelFormat` doesn't know about sRGB, and a driver may ignore the sRGB
+            // attribute, so an sRGB request is checked against the format that we obtain
+            let (id, f) = match choose(pf_reqs) {
+                Ok((_, ref f)) if pf_reqs.color_space == ColorSpace::Srgb && !f.srgb => {
+                    return Err(CreationError::NoSrgbPixelFormat(vec![f.clone()]));
+                },
+                Ok(format) => format,
+                Err(()) if pf_reqs.color_space == ColorSpace::Srgb => {
+                    let mut relaxed = pf_reqs.clone();
+                    relaxed.color_space = ColorSpace::Default;
+                    return Err(match choose(&relaxed) {
+                        Ok((_, f)) => CreationError::NoSrgbPixelFormat(vec![f]),
+                        Err(()) => CreationError::NoAvailablePixelFormat,
+                    });
+                },
+                Err(()) => return Err(CreationError::NoAvailablePixelFormat),
             };
 
-            try!(set_pixel_format(hdc, id));
-            f
+            match pbuffer {
+                Some(dimensions) => {
+                    let pbuffer = try!(create_pbuffer(&extra_functions, hdc, id, dimensions));
+                    (pbuffer.hdc, Some(pbuffer), f)
+                },
+                None => {
+                    try!(set_pixel_format(hdc, id));
+                    (hdc, None, f)
+                },
+            }
         };
 
         // creating the OpenGL context
@@ -133,13 +211,28 @@ impl Context {
         if extensions.split(' ').find(|&i| i == "WGL_EXT_swap_control").is_some() {
             let _guard = try!(CurrentContextGuard::make_current(hdc, context.0));
 
-            if extra_functions.SwapIntervalEXT(if opengl.vsync { 1 } else { 0 }) == 0 {
+            let interval = if opengl.vsync { 1 } else { 0 };
+            if extra_functions.SwapIntervalEXT(interval) == 0 {
                 return Err(CreationError::OsError(format!("wglSwapIntervalEXT failed")));
             }
+
+            // checking that it worked, as the settings of the driver can override it
+            if opengl.strict_vsync {
+                let swap = extra_functions.GetSwapIntervalEXT();
+                if swap != interval {
+                    return Err(CreationError::OsError(format!("Couldn't setup vsync: expected \
+                                                               interval `{}` but got `{}`",
+                                                              interval, swap)));
+                }
+            }
+        } else if opengl.strict_vsync {
+            return Err(CreationError::OsError(format!("Couldn't find any available vsync \
+                                                       extension")));
         }
 
         Ok(Context {
             context: context,
+            pbuffer: pbuffer,
             hdc: hdc,
             gl_library: gl_library,
             pixel_format: pixel_format,
@@ -151,6 +244,34 @@ impl Context {
     pub fn get_hglrc(&self) -> winapi::HGLRC {
         self.context.0
     }
+
+    /// Changes the number of vertical blanks that `swap_buffers` waits for. The context must be
+    /// current.
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        let swap_interval = self.get_proc_address("wglSwapIntervalEXT");
+        if swap_interval.is_null() {
+            return Err("WGL_EXT_swap_control is not supported".to_string());
+        }
+
+        unsafe {
+            let swap_interval: extern "system" fn(c_int) -> winapi::BOOL =
+                mem::transmute(swap_interval);
+            if swap_interval(interval as c_int) == 0 {
+                return Err("wglSwapIntervalEXT failed".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Unbinds the current context of the calling thread, whichever it is.
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if gl::wgl::MakeCurrent(ptr::null(), ptr::null()) != 0 {
+            Ok(())
+        } else {
+            Err(ContextError::IoError(io::Error::last_os_error()))
+        }
+    }
 }
 
 impl GlContext for Context {
@@ -181,14 +302,24 @@ impl GlContext for Context {
 
     #[inline]
     fn swap_buffers(&self) -> Result<(), ContextError> {
-        // TODO: decide how to handle the error
-        /*if unsafe { gdi32::SwapBuffers(self.hdc) } != 0 {
+        // `SwapBuffers` does nothing on single-buffered formats
+        if !self.pixel_format.double_buffer {
+            let flush = self.get_proc_address("glFlush");
+            if !flush.is_null() {
+                unsafe {
+                    let flush: extern "system" fn() = mem::transmute(flush);
+                    flush();
+                }
+            }
+            return Ok(());
+        }
+
+        // fails once the window, and therefore the device context, has been destroyed
+        if unsafe { gdi32::SwapBuffers(self.hdc) } != 0 {
             Ok(())
         } else {
             Err(ContextError::IoError(io::Error::last_os_error()))
-        }*/
-        unsafe { gdi32::SwapBuffers(self.hdc) };
-        Ok(())
+        }
     }
 
     #[inline]
@@ -227,6 +358,14 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
             let mut attributes = Vec::new();
 
             match opengl.version {
+                // forward-compatible contexts only exist since OpenGL 3.0, and the driver is
+                // free to return a later version
+                GlRequest::Latest if opengl.forward_compatible => {
+                    attributes.push(gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB as c_int);
+                    attributes.push(3);
+                    attributes.push(gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB as c_int);
+                    attributes.push(0);
+                },
                 GlRequest::Latest => {},
                 GlRequest::Specific(Api::OpenGl, (major, minor)) => {
                     attributes.push(gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB as c_int);
@@ -306,6 +445,17 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
                     flags = flags | gl::wgl_extra::CONTEXT_DEBUG_BIT_ARB as c_int;
                 }
 
+                // the flag doesn't exist for OpenGL ES
+                if opengl.forward_compatible {
+                    match opengl.version {
+                        GlRequest::Specific(Api::OpenGlEs, _) => (),
+                        _ => {
+                            flags = flags |
+                                    gl::wgl_extra::CONTEXT_FORWARD_COMPATIBLE_BIT_ARB as c_int;
+                        },
+                    }
+                }
+
                 flags
             };
 
@@ -326,6 +476,11 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
             }
         }
 
+        // a legacy context is never forward-compatible
+        if opengl.forward_compatible {
+            return Err(CreationError::OpenGlVersionNotSupported);
+        }
+
     } else {
         share = ptr::null_mut();
     }
@@ -365,14 +520,19 @@ unsafe fn choose_native_pixel_format(hdc: winapi::HDC, reqs: &PixelFormatRequire
         Some(_) => return Err(())
     };
 
-    if reqs.stereoscopy {
+    if reqs.coverage_samples.is_some() || reqs.swap_method.is_some() {
         return Err(());
     }
 
-    if reqs.srgb {
+    if reqs.stereoscopy {
         return Err(());
     }
 
+    match reqs.color_space {
+        ColorSpace::Default | ColorSpace::Linear => (),
+        _ => return Err(()),
+    }
+
     if reqs.release_behavior != ReleaseBehavior::Flush {
         return Err(());
     }
@@ -428,6 +588,40 @@ unsafe fn choose_native_pixel_format(hdc: winapi::HDC, reqs: &PixelFormatRequire
     }
 
     // querying back the capabilities of what windows told us
+    let pf_desc = try!(describe_native_pixel_format(hdc, pf_id, reqs));
+
+    // `ChoosePixelFormat` treats the sizes as minimums, so a request for zero bits of depth or
+    // stencil can return a format that has them; we look for one that really doesn't
+    let refused = |format: &PixelFormat| {
+        (reqs.depth_bits == Some(0) && format.depth_bits != 0) ||
+        (reqs.stencil_bits == Some(0) && format.stencil_bits != 0)
+    };
+    if refused(&pf_desc) {
+        let count = gdi32::DescribePixelFormat(hdc, 1,
+                                               mem::size_of::<winapi::PIXELFORMATDESCRIPTOR>() as u32,
+                                               ptr::null_mut());
+        let alternative = (1 .. count + 1)
+            .filter_map(|id| describe_native_pixel_format(hdc, id, reqs).ok().map(|f| (id, f)))
+            .find(|&(_, ref format)| {
+                !refused(format) && format.color_bits == pf_desc.color_bits &&
+                format.alpha_bits == pf_desc.alpha_bits &&
+                format.hardware_accelerated == pf_desc.hardware_accelerated &&
+                format.double_buffer == pf_desc.double_buffer
+            });
+        if let Some(alternative) = alternative {
+            return Ok(alternative);
+        }
+    }
+
+    Ok((pf_id, pf_desc))
+}
+
+/// Queries the attributes of a pixel format without using WGL, and checks that it satisfies the
+/// requirements.
+unsafe fn describe_native_pixel_format(hdc: winapi::HDC, pf_id: c_int,
+                                       reqs: &PixelFormatRequirements)
+                                       -> Result<PixelFormat, ()>
+{
     let mut output: winapi::PIXELFORMATDESCRIPTOR = mem::zeroed();
     if gdi32::DescribePixelFormat(hdc, pf_id, mem::size_of::<winapi::PIXELFORMATDESCRIPTOR>() as u32,
                                   &mut output) == 0
@@ -456,7 +650,17 @@ unsafe fn choose_native_pixel_format(hdc: winapi::HDC, reqs: &PixelFormatRequire
         stereoscopy: (output.dwFlags & winapi::PFD_STEREO) != 0,
         double_buffer: (output.dwFlags & winapi::PFD_DOUBLEBUFFER) != 0,
         multisampling: None,
+        coverage_samples: None,
         srgb: false,
+        color_space: ColorSpace::Linear,
+        swap_method: if (output.dwFlags & winapi::PFD_SWAP_COPY) != 0 {
+            Some(SwapMethod::Copy)
+        } else if (output.dwFlags & winapi::PFD_SWAP_EXCHANGE) != 0 {
+            Some(SwapMethod::Exchange)
+        } else {
+            None
+        },
+        native_id: NativePixelFormatId::Wgl(pf_id),
     };
 
     if pf_desc.alpha_bits < reqs.alpha_bits.unwrap_or(0) {
@@ -482,20 +686,24 @@ unsafe fn choose_native_pixel_format(hdc: winapi::HDC, reqs: &PixelFormatRequire
         }
     }
 
-    Ok((pf_id, pf_desc))
+    Ok(pf_desc)
 }
 
 /// Enumerates the list of pixel formats by using extra WGL functions.
 ///
 /// Gives more precise results than `enumerate_native_pixel_formats`.
 unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
-                                  hdc: winapi::HDC, reqs: &PixelFormatRequirements)
+                                  hdc: winapi::HDC, reqs: &PixelFormatRequirements, pbuffer: bool)
                                   -> Result<(c_int, PixelFormat), ()>
 {
     let descriptor = {
         let mut out: Vec<c_int> = Vec::with_capacity(37);
 
-        out.push(gl::wgl_extra::DRAW_TO_WINDOW_ARB as c_int);
+        if pbuffer {
+            out.push(gl::wgl_extra::DRAW_TO_PBUFFER_ARB as c_int);
+        } else {
+            out.push(gl::wgl_extra::DRAW_TO_WINDOW_ARB as c_int);
+        }
         out.push(1);
 
         out.push(gl::wgl_extra::SUPPORT_OPENGL_ARB as c_int);
@@ -546,7 +754,19 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
         out.push(gl::wgl_extra::DOUBLE_BUFFER_ARB as c_int);
         out.push(if double_buffer { 1 } else { 0 });
 
-        if let Some(multisampling) = reqs.multisampling {
+        if let Some(coverage) = reqs.coverage_samples {
+            // WGL_COVERAGE_SAMPLES_NV is an alias of WGL_SAMPLES_ARB
+            if extensions.split(' ').find(|&i| i == "WGL_NV_multisample_coverage").is_some() {
+                out.push(gl::wgl_extra::SAMPLE_BUFFERS_ARB as c_int);
+                out.push(1);
+                out.push(gl::wgl_extra::COVERAGE_SAMPLES_NV as c_int);
+                out.push(coverage as c_int);
+                out.push(gl::wgl_extra::COLOR_SAMPLES_NV as c_int);
+                out.push(reqs.multisampling.unwrap_or(coverage) as c_int);
+            } else {
+                return Err(());
+            }
+        } else if let Some(multisampling) = reqs.multisampling {
             if extensions.split(' ').find(|&i| i == "WGL_ARB_multisample").is_some() {
                 out.push(gl::wgl_extra::SAMPLE_BUFFERS_ARB as c_int);
                 out.push(if multisampling == 0 { 0 } else { 1 });
@@ -560,16 +780,32 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
         out.push(gl::wgl_extra::STEREO_ARB as c_int);
         out.push(if reqs.stereoscopy { 1 } else { 0 });
 
-        if reqs.srgb {
-            if extensions.split(' ').find(|&i| i == "WGL_ARB_framebuffer_sRGB").is_some() {
-                out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int);
-                out.push(1);
-            } else if extensions.split(' ').find(|&i| i == "WGL_EXT_framebuffer_sRGB").is_some() {
-                out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int);
-                out.push(1);
-            } else {
-                return Err(());
-            }
+        if let Some(swap_method) = reqs.swap_method {
+            out.push(gl::wgl_extra::SWAP_METHOD_ARB as c_int);
+            out.push(match swap_method {
+                SwapMethod::Exchange => gl::wgl_extra::SWAP_EXCHANGE_ARB,
+                SwapMethod::Copy => gl::wgl_extra::SWAP_COPY_ARB,
+                SwapMethod::Undefined => gl::wgl_extra::SWAP_UNDEFINED_ARB,
+            } as c_int);
+        }
+
+        match reqs.color_space {
+            ColorSpace::Default => (),
+            ColorSpace::Srgb | ColorSpace::Linear => {
+                let value = if reqs.color_space == ColorSpace::Srgb { 1 } else { 0 };
+                if extensions.split(' ').find(|&i| i == "WGL_ARB_framebuffer_sRGB").is_some() {
+                    out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int);
+                    out.push(value);
+                } else if extensions.split(' ').find(|&i| i == "WGL_EXT_framebuffer_sRGB").is_some() {
+                    out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int);
+                    out.push(value);
+                } else if reqs.color_space == ColorSpace::Srgb {
+                    return Err(());
+                }
+            },
+            // wide gamut and HDR output on Windows require a DXGI swap chain, which WGL can't
+            // provide
+            _ => return Err(()),
         }
 
         match reqs.release_behavior {
@@ -586,27 +822,47 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
         out
     };
 
-    let mut format_id = mem::uninitialized();
-    let mut num_formats = mem::uninitialized();
-    if extra.ChoosePixelFormatARB(hdc as *const _, descriptor.as_ptr(), ptr::null(), 1,
-                                  &mut format_id, &mut num_formats) == 0
+    let mut format_ids: [c_int; 256] = mem::zeroed();
+    let mut num_formats = mem::zeroed();
+    if extra.ChoosePixelFormatARB(hdc as *const _, descriptor.as_ptr(), ptr::null(),
+                                  format_ids.len() as u32, format_ids.as_mut_ptr(),
+                                  &mut num_formats) == 0
     {
         return Err(());
     }
 
-    if num_formats == 0 {
-        return Err(());
+    // the formats are sorted by decreasing sizes, we prefer the closest to the requirements
+    let format_ids = &format_ids[.. cmp::min(num_formats as usize, format_ids.len())];
+    let formats: Vec<_> = format_ids.iter()
+                                    .map(|&id| describe_arb_pixel_format(extra, extensions, hdc, id))
+                                    .collect();
+    match pixel_format::choose_best(&formats, reqs) {
+        Some(index) => Ok((format_ids[index], formats[index].clone())),
+        None => Err(()),
     }
+}
 
+/// Queries the attributes of a pixel format by using extra WGL functions.
+unsafe fn describe_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
+                                    hdc: winapi::HDC, format_id: c_int) -> PixelFormat
+{
     let get_info = |attrib: u32| {
-        let mut value = mem::uninitialized();
+        let mut value = mem::zeroed();
         extra.GetPixelFormatAttribivARB(hdc as *const _, format_id as c_int,
                                         0, 1, [attrib as c_int].as_ptr(),
                                         &mut value);
         value as u32
     };
 
-    let pf_desc = PixelFormat {
+    let srgb = if extensions.split(' ').find(|&i| i == "WGL_ARB_framebuffer_sRGB").is_some() {
+        get_info(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB) != 0
+    } else if extensions.split(' ').find(|&i| i == "WGL_EXT_framebuffer_sRGB").is_some() {
+        get_info(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT) != 0
+    } else {
+        false
+    };
+
+    PixelFormat {
         hardware_accelerated: get_info(gl::wgl_extra::ACCELERATION_ARB) !=
                                                                 gl::wgl_extra::NO_ACCELERATION_ARB,
         color_bits: get_info(gl::wgl_extra::RED_BITS_ARB) as u8 + 
@@ -618,7 +874,12 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
         stereoscopy: get_info(gl::wgl_extra::STEREO_ARB) != 0,
         double_buffer: get_info(gl::wgl_extra::DOUBLE_BUFFER_ARB) != 0,
         multisampling: {
-            if extensions.split(' ').find(|&i| i == "WGL_ARB_multisample").is_some() {
+            if extensions.split(' ').find(|&i| i == "WGL_NV_multisample_coverage").is_some() {
+                match get_info(gl::wgl_extra::COLOR_SAMPLES_NV) {
+                    0 => None,
+                    a => Some(a as u16),
+                }
+            } else if extensions.split(' ').find(|&i| i == "WGL_ARB_multisample").is_some() {
                 match get_info(gl::wgl_extra::SAMPLES_ARB) {
                     0 => None,
                     a => Some(a as u16),
@@ -627,16 +888,24 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
                 None
             }
         },
-        srgb: if extensions.split(' ').find(|&i| i == "WGL_ARB_framebuffer_sRGB").is_some() {
-            get_info(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB) != 0
-        } else if extensions.split(' ').find(|&i| i == "WGL_EXT_framebuffer_sRGB").is_some() {
-            get_info(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT) != 0
-        } else {
-            false
+        coverage_samples: {
+            if extensions.split(' ').find(|&i| i == "WGL_NV_multisample_coverage").is_some() {
+                let coverage = get_info(gl::wgl_extra::COVERAGE_SAMPLES_NV);
+                let color = get_info(gl::wgl_extra::COLOR_SAMPLES_NV);
+                if coverage > color { Some(coverage as u16) } else { None }
+            } else {
+                None
+            }
         },
-    };
-
-    Ok((format_id, pf_desc))
+        srgb: srgb,
+        color_space: if srgb { ColorSpace::Srgb } else { ColorSpace::Linear },
+        swap_method: match get_info(gl::wgl_extra::SWAP_METHOD_ARB) {
+            gl::wgl_extra::SWAP_EXCHANGE_ARB => Some(SwapMethod::Exchange),
+            gl::wgl_extra::SWAP_COPY_ARB => Some(SwapMethod::Copy),
+            _ => Some(SwapMethod::Undefined),
+        },
+        native_id: NativePixelFormatId::Wgl(format_id as i32),
+    }
 }
 
 /// Calls `SetPixelFormat` on a window.
@@ -658,6 +927,78 @@ unsafe fn set_pixel_format(hdc: winapi::HDC, id: c_int) -> Result<(), CreationEr
     Ok(())
 }
 
+/// Creates a pbuffer with a pixel format of the device context of a window.
+unsafe fn create_pbuffer(extra: &gl::wgl_extra::Wgl, hdc: winapi::HDC, id: c_int,
+                         (width, height): (u32, u32)) -> Result<PbufferWrapper, CreationError>
+{
+    let attributes = [0];
+    let pbuffer = extra.CreatePbufferARB(hdc as *const _, id, width as c_int, height as c_int,
+                                         attributes.as_ptr());
+    if pbuffer.is_null() {
+        return Err(CreationError::OsError(format!("wglCreatePbufferARB failed: {}",
+                                                  format!("{}", io::Error::last_os_error()))));
+    }
+
+    let pbuffer_hdc = extra.GetPbufferDCARB(pbuffer);
+    if pbuffer_hdc.is_null() {
+        let err = io::Error::last_os_error();
+        extra.DestroyPbufferARB(pbuffer);
+        return Err(CreationError::OsError(format!("wglGetPbufferDCARB failed: {}", err)));
+    }
+
+    Ok(PbufferWrapper {
+        extra: extra.clone(),
+        pbuffer: pbuffer,
+        hdc: pbuffer_hdc as winapi::HDC,
+    })
+}
+
+/// Creates an invisible window that doesn't receive any event, to choose the pixel format of
+/// a pbuffer.
+unsafe fn create_hidden_window() -> Result<WindowWrapper, CreationError> {
+    let class_name = OsStr::new("Pbuffer Window Class").encode_wide().chain(Some(0).into_iter())
+                                                       .collect::<Vec<_>>();
+
+    let class = winapi::WNDCLASSEXW {
+        cbSize: mem::size_of::<winapi::WNDCLASSEXW>() as winapi::UINT,
+        style: winapi::CS_OWNDC,
+        lpfnWndProc: Some(user32::DefWindowProcW),
+        cbClsExtra: 0,
+        cbWndExtra: 0,
+        hInstance: kernel32::GetModuleHandleW(ptr::null()),
+        hIcon: ptr::null_mut(),
+        hCursor: ptr::null_mut(),
+        hbrBackground: ptr::null_mut(),
+        lpszMenuName: ptr::null(),
+        lpszClassName: class_name.as_ptr(),
+        hIconSm: ptr::null_mut(),
+    };
+
+    // registering the class a second time fails, which is detected by `CreateWindowEx` anyway
+    user32::RegisterClassExW(&class);
+
+    let title = OsStr::new("pbuffer window").encode_wide().chain(Some(0).into_iter())
+                                            .collect::<Vec<_>>();
+    let win = user32::CreateWindowExW(0, class_name.as_ptr(), title.as_ptr() as winapi::LPCWSTR,
+                                      winapi::WS_POPUP | winapi::WS_CLIPSIBLINGS |
+                                      winapi::WS_CLIPCHILDREN,
+                                      0, 0, 1, 1, ptr::null_mut(), ptr::null_mut(),
+                                      kernel32::GetModuleHandleW(ptr::null()), ptr::null_mut());
+    if win.is_null() {
+        return Err(CreationError::OsError(format!("CreateWindowEx function failed: {}",
+                                          format!("{}", io::Error::last_os_error()))));
+    }
+
+    let hdc = user32::GetDC(win);
+    if hdc.is_null() {
+        user32::DestroyWindow(win);
+        return Err(CreationError::OsError(format!("GetDC function failed: {}",
+                                          format!("{}", io::Error::last_os_error()))));
+    }
+
+    Ok(WindowWrapper(win, hdc))
+}
+
 /// Loads the `opengl32.dll` library.
 unsafe fn load_opengl32_dll() -> Result<winapi::HMODULE, CreationError> {
     let name = OsStr::new("opengl32.dll").encode_wide().chain(Some(0).into_iter())
@@ -3,13 +3,18 @@ use std::ptr;
 use std::cell::RefCell;
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStringExt;
 
 use WindowAttributes;
 use CursorState;
 use Event;
+use FullscreenFocusPolicy;
+use LogicalSize;
+use TileState;
 use super::event;
+use super::init;
 use super::WindowState;
 
 use user32;
@@ -24,6 +29,8 @@ thread_local!(pub static CONTEXT_STASH: RefCell<Option<ThreadLocalData>> = RefCe
 pub struct ThreadLocalData {
     pub win: winapi::HWND,
     pub sender: Sender<Event>,
+    /// Incremented for every event that is sent, see `Window::wait_for_events`.
+    pub queued_events: Arc<AtomicUsize>,
     pub window_state: Arc<Mutex<WindowState>>
 }
 
@@ -44,16 +51,145 @@ fn send_event(input_window: winapi::HWND, event: Event) {
             Some(ref v) => v
         };
 
-        let &ThreadLocalData { ref win, ref sender, .. } = stored;
+        let &ThreadLocalData { ref win, ref sender, ref queued_events, .. } = stored;
 
         if win != &input_window {
             return;
         }
 
-        sender.send(event).ok();  // ignoring if closed
+        // ignoring if closed
+        if sender.send(event).is_ok() {
+            queued_events.fetch_add(1, Ordering::SeqCst);
+            super::notify_event();
+        }
     });
 }
 
+/// Sends a `LockKeysChanged` event if the state of the lock keys has changed.
+fn update_lock_keys(window: winapi::HWND) {
+    let lock_keys = event::get_lock_keys();
+    let changed = CONTEXT_STASH.with(|context_stash| {
+        match context_stash.borrow().as_ref() {
+            Some(cstash) if cstash.win == window => {
+                let mut window_state = cstash.window_state.lock().unwrap();
+                let changed = window_state.lock_keys != lock_keys;
+                window_state.lock_keys = lock_keys;
+                changed
+            },
+            _ => false
+        }
+    });
+    if changed {
+        send_event(window, Event::LockKeysChanged(lock_keys));
+    }
+}
+
+/// Determines whether the window is snapped with Aero Snap.
+///
+/// Windows doesn't tell it directly, but a snapped window keeps its previous size as its
+/// "normal" position, which is restored when it is unsnapped.
+unsafe fn get_tile_state(window: winapi::HWND) -> TileState {
+    if user32::IsZoomed(window) != 0 || user32::IsIconic(window) != 0 {
+        return TileState::Untiled;
+    }
+
+    let mut placement: winapi::WINDOWPLACEMENT = mem::zeroed();
+    placement.length = mem::size_of::<winapi::WINDOWPLACEMENT>() as winapi::UINT;
+    let mut rect: winapi::RECT = mem::zeroed();
+    if user32::GetWindowPlacement(window, &mut placement) == 0 ||
+       user32::GetWindowRect(window, &mut rect) == 0
+    {
+        return TileState::Untiled;
+    }
+
+    // the normal position is in workspace coordinates, so only the sizes are compared
+    let normal = placement.rcNormalPosition;
+    if normal.right - normal.left == rect.right - rect.left &&
+       normal.bottom - normal.top == rect.bottom - rect.top
+    {
+        return TileState::Untiled;
+    }
+
+    let mut monitor_info: winapi::MONITORINFO = mem::zeroed();
+    monitor_info.cbSize = mem::size_of::<winapi::MONITORINFO>() as winapi::DWORD;
+    let monitor = user32::MonitorFromWindow(window, winapi::MONITOR_DEFAULTTONEAREST);
+    if user32::GetMonitorInfoW(monitor, &mut monitor_info) == 0 {
+        return TileState::Other;
+    }
+
+    // the invisible borders of the window make it a bit larger than the work area
+    let work_area = monitor_info.rcWork;
+    if rect.bottom - rect.top < work_area.bottom - work_area.top {
+        return TileState::Other;
+    }
+
+    if (rect.left + rect.right) / 2 < (work_area.left + work_area.right) / 2 {
+        TileState::Left
+    } else {
+        TileState::Right
+    }
+}
+
+/// Returns the refresh rate of the monitor that contains most of the window, in millihertz.
+pub unsafe fn get_refresh_rate(window: winapi::HWND) -> Option<u32> {
+    let mut monitor_info: winapi::MONITORINFOEXW = mem::zeroed();
+    monitor_info.cbSize = mem::size_of::<winapi::MONITORINFOEXW>() as winapi::DWORD;
+    let monitor = user32::MonitorFromWindow(window, winapi::MONITOR_DEFAULTTONEAREST);
+    if user32::GetMonitorInfoW(monitor, &mut monitor_info as *mut _ as *mut _) == 0 {
+        return None;
+    }
+
+    let mut dev: winapi::DEVMODEW = mem::zeroed();
+    dev.dmSize = mem::size_of::<winapi::DEVMODEW>() as winapi::WORD;
+    if user32::EnumDisplaySettingsExW(monitor_info.szDevice.as_ptr(),
+                                      winapi::ENUM_CURRENT_SETTINGS, &mut dev, 0) == 0
+    {
+        return None;
+    }
+
+    // `0` and `1` mean that the hardware uses its default rate
+    match dev.dmDisplayFrequency {
+        0 | 1 => None,
+        frequency => Some(frequency * 1000),
+    }
+}
+
+/// Restores the resolution of the desktop and minimizes a fullscreen window that loses the focus,
+/// or changes the resolution again when the window gets the focus back.
+unsafe fn apply_fullscreen_focus_policy(window: winapi::HWND, focused: bool) {
+    let fullscreen = CONTEXT_STASH.with(|context_stash| {
+        let cstash = context_stash.borrow();
+        let cstash = match *cstash {
+            Some(ref cstash) if cstash.win == window => cstash,
+            _ => return None,
+        };
+
+        let window_state = cstash.window_state.lock().unwrap();
+        if window_state.attributes.fullscreen_focus_policy != FullscreenFocusPolicy::Minimize {
+            return None;
+        }
+        match (window_state.attributes.monitor.clone(), window_state.fullscreen_mode) {
+            (Some(monitor), Some(mode)) => Some((monitor, mode)),
+            _ => None,
+        }
+    });
+
+    let (monitor, mode) = match fullscreen {
+        Some(fullscreen) => fullscreen,
+        None => return,
+    };
+
+    if focused {
+        let _ = init::set_display_mode(&monitor, Some(mode));
+        if user32::IsIconic(window) != 0 {
+            user32::ShowWindow(window, winapi::SW_RESTORE);
+        }
+    } else {
+        let _ = init::set_display_mode(&monitor, None);
+        user32::ShowWindow(window, winapi::SW_MINIMIZE);
+    }
+}
+
 /// This is the callback that is called by `DispatchMessage` in the events loop.
 ///
 /// Returning 0 tells the Win32 API that the message has been processed.
@@ -61,10 +197,17 @@ fn send_event(input_window: winapi::HWND, event: Event) {
 pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
                                        wparam: winapi::WPARAM, lparam: winapi::LPARAM)
                                        -> winapi::LRESULT
+{
+    ::api::panic::abort_on_panic("WNDPROC", || handle_message(window, msg, wparam, lparam))
+}
+
+unsafe fn handle_message(window: winapi::HWND, msg: winapi::UINT,
+                         wparam: winapi::WPARAM, lparam: winapi::LPARAM)
+                         -> winapi::LRESULT
 {
     match msg {
         winapi::WM_DESTROY => {
-            use events::Event::Closed;
+            use events::Event::{Closed, Destroyed};
 
             CONTEXT_STASH.with(|context_stash| {
                 let context_stash = context_stash.borrow();
@@ -81,6 +224,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             });
 
             send_event(window, Closed);
+            send_event(window, Destroyed);
             0
         },
 
@@ -92,7 +236,36 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::Resized;
             let w = winapi::LOWORD(lparam as winapi::DWORD) as u32;
             let h = winapi::HIWORD(lparam as winapi::DWORD) as u32;
-            send_event(window, Resized(w, h));
+            // hidpi is not supported on win32 yet, so points and pixels are the same
+            send_event(window, Resized(LogicalSize::new(w, h)));
+
+            let tile_state = get_tile_state(window);
+            let minimized = wparam == winapi::SIZE_MINIMIZED;
+            let maximized = wparam == winapi::SIZE_MAXIMIZED;
+            let events = CONTEXT_STASH.with(|context_stash| {
+                let mut events = Vec::new();
+                if let Some(cstash) = context_stash.borrow().as_ref() {
+                    if cstash.win == window {
+                        let mut window_state = cstash.window_state.lock().unwrap();
+                        if window_state.tile_state != tile_state {
+                            window_state.tile_state = tile_state;
+                            events.push(Event::Tiled(tile_state));
+                        }
+                        if window_state.minimized != minimized {
+                            window_state.minimized = minimized;
+                            events.push(Event::Minimized(minimized));
+                        }
+                        if !minimized && window_state.maximized != maximized {
+                            window_state.maximized = maximized;
+                            events.push(Event::Maximized(maximized));
+                        }
+                    }
+                }
+                events
+            });
+            for event in events {
+                send_event(window, event);
+            }
             0
         },
 
@@ -101,17 +274,83 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             let x = winapi::LOWORD(lparam as winapi::DWORD) as i32;
             let y = winapi::HIWORD(lparam as winapi::DWORD) as i32;
             send_event(window, Moved(x, y));
+
+            // the window may have moved to another monitor
+            let refresh_rate = get_refresh_rate(window);
+            let changed = CONTEXT_STASH.with(|context_stash| {
+                match context_stash.borrow().as_ref() {
+                    Some(cstash) if cstash.win == window => {
+                        let mut window_state = cstash.window_state.lock().unwrap();
+                        let changed = window_state.refresh_rate != refresh_rate;
+                        window_state.refresh_rate = refresh_rate;
+                        changed
+                    },
+                    _ => false
+                }
+            });
+            if let (true, Some(refresh_rate)) = (changed, refresh_rate) {
+                send_event(window, Event::RefreshRateChanged(refresh_rate));
+            }
             0
         },
 
         winapi::WM_CHAR => {
-            use std::mem;
-            use events::Event::ReceivedCharacter;
-            let chr: char = mem::transmute(wparam as u32);
-            send_event(window, ReceivedCharacter(chr));
+            use std::char;
+            use events::Event::{ReceivedCharacter, ReceivedText};
+
+            // the characters outside of the BMP are sent as two messages, one per surrogate
+            let unit = wparam as u16;
+            let chr = CONTEXT_STASH.with(|context_stash| {
+                let context_stash = context_stash.borrow();
+                let cstash = match context_stash.as_ref() {
+                    Some(cstash) if cstash.win == window => cstash,
+                    _ => return None,
+                };
+                let mut window_state = cstash.window_state.lock().unwrap();
+
+                match unit {
+                    0xD800 ... 0xDBFF => {
+                        window_state.high_surrogate = Some(unit);
+                        None
+                    },
+                    0xDC00 ... 0xDFFF => {
+                        window_state.high_surrogate.take().and_then(|high| {
+                            let code = 0x10000 + (((high as u32) - 0xD800) << 10) +
+                                       ((unit as u32) - 0xDC00);
+                            char::from_u32(code)
+                        })
+                    },
+                    _ => {
+                        window_state.high_surrogate = None;
+                        char::from_u32(unit as u32)
+                    },
+                }
+            });
+
+            if let Some(chr) = chr {
+                send_event(window, ReceivedText(chr.to_string()));
+                send_event(window, ReceivedCharacter(chr));
+            }
             0
         },
 
+        super::ime::WM_IME_COMPOSITION => {
+            use events::Event::{ReceivedCharacter, ReceivedText};
+
+            match super::ime::get_result_string(window, lparam) {
+                Some(text) => {
+                    send_event(window, ReceivedText(text.clone()));
+                    for chr in text.chars() {
+                        send_event(window, ReceivedCharacter(chr));
+                    }
+
+                    // `DefWindowProc` would send the same text again as `WM_CHAR` messages
+                    0
+                },
+                None => user32::DefWindowProcW(window, msg, wparam, lparam),
+            }
+        },
+
         // Prevents default windows menu hotkeys playing unwanted
         // "ding" sounds. Alternatively could check for WM_SYSCOMMAND
         // with wparam being SC_KEYMENU, but this may prevent some
@@ -140,7 +379,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             let value = value as i32;
             let value = value as f32 / winapi::WHEEL_DELTA as f32;
 
-            send_event(window, MouseWheel(LineDelta(0.0, value), TouchPhase::Moved));
+            send_event(window, MouseWheel(LineDelta(0.0, value), TouchPhase::Moved, false));
 
             0
         },
@@ -152,7 +391,8 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
                 user32::DefWindowProcW(window, msg, wparam, lparam)
             } else {
                 let (scancode, vkey) = event::vkeycode_to_element(wparam, lparam);
-                send_event(window, KeyboardInput(Pressed, scancode, vkey));
+                send_event(window, KeyboardInput(Pressed, scancode, vkey, event::get_modifiers()));
+                update_lock_keys(window);
                 0
             }
         },
@@ -161,7 +401,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::KeyboardInput;
             use events::ElementState::Released;
             let (scancode, vkey) = event::vkeycode_to_element(wparam, lparam);
-            send_event(window, KeyboardInput(Released, scancode, vkey));
+            send_event(window, KeyboardInput(Released, scancode, vkey, event::get_modifiers()));
             0
         },
 
@@ -169,7 +409,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::MouseInput;
             use events::MouseButton::Left;
             use events::ElementState::Pressed;
-            send_event(window, MouseInput(Pressed, Left, None));
+            send_event(window, MouseInput(Pressed, Left, None, event::get_modifiers()));
             0
         },
 
@@ -177,7 +417,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::MouseInput;
             use events::MouseButton::Left;
             use events::ElementState::Released;
-            send_event(window, MouseInput(Released, Left, None));
+            send_event(window, MouseInput(Released, Left, None, event::get_modifiers()));
             0
         },
 
@@ -185,7 +425,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::MouseInput;
             use events::MouseButton::Right;
             use events::ElementState::Pressed;
-            send_event(window, MouseInput(Pressed, Right, None));
+            send_event(window, MouseInput(Pressed, Right, None, event::get_modifiers()));
             0
         },
 
@@ -193,7 +433,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::MouseInput;
             use events::MouseButton::Right;
             use events::ElementState::Released;
-            send_event(window, MouseInput(Released, Right, None));
+            send_event(window, MouseInput(Released, Right, None, event::get_modifiers()));
             0
         },
 
@@ -201,7 +441,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::MouseInput;
             use events::MouseButton::Middle;
             use events::ElementState::Pressed;
-            send_event(window, MouseInput(Pressed, Middle, None));
+            send_event(window, MouseInput(Pressed, Middle, None, event::get_modifiers()));
             0
         },
 
@@ -209,7 +449,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::MouseInput;
             use events::MouseButton::Middle;
             use events::ElementState::Released;
-            send_event(window, MouseInput(Released, Middle, None));
+            send_event(window, MouseInput(Released, Middle, None, event::get_modifiers()));
             0
         },
 
@@ -218,7 +458,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::MouseButton::Other;
             use events::ElementState::Pressed;
             let xbutton = winapi::HIWORD(wparam as winapi::DWORD) as winapi::c_int; // waiting on PR for winapi to add GET_XBUTTON_WPARAM
-            send_event(window, MouseInput(Pressed, Other(xbutton as u8), None));
+            send_event(window, MouseInput(Pressed, Other(xbutton as u8), None, event::get_modifiers()));
             0
         },
 
@@ -227,22 +467,25 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::MouseButton::Other;
             use events::ElementState::Released;
             let xbutton = winapi::HIWORD(wparam as winapi::DWORD) as winapi::c_int; 
-            send_event(window, MouseInput(Released, Other(xbutton as u8), None));
+            send_event(window, MouseInput(Released, Other(xbutton as u8), None, event::get_modifiers()));
             0
         },
 
         winapi::WM_INPUT => {
-            let mut data: winapi::RAWINPUT = mem::uninitialized();
+            let mut data: winapi::RAWINPUT = mem::zeroed();
             let mut data_size = mem::size_of::<winapi::RAWINPUT>() as winapi::UINT;
             user32::GetRawInputData(mem::transmute(lparam), winapi::RID_INPUT,
                                     mem::transmute(&mut data), &mut data_size,
                                     mem::size_of::<winapi::RAWINPUTHEADER>() as winapi::UINT);
 
             if data.header.dwType == winapi::RIM_TYPEMOUSE {
-                let _x = data.mouse.lLastX;  // FIXME: this is not always the relative movement
-                let _y = data.mouse.lLastY;
-                // TODO:
-                //send_event(window, Event::MouseRawMovement { x: x, y: y });
+                // tablets and remote desktops report absolute positions, which are not movements
+                if data.mouse.usFlags & winapi::MOUSE_MOVE_ABSOLUTE == 0 &&
+                   (data.mouse.lLastX != 0 || data.mouse.lLastY != 0)
+                {
+                    send_event(window, Event::MouseRawMovement(data.mouse.lLastX as f64,
+                                                               data.mouse.lLastY as f64));
+                }
 
                 0
 
@@ -253,13 +496,17 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
 
         winapi::WM_SETFOCUS => {
             use events::Event::Focused;
+            // the resolution is changed again before the application learns about the focus
+            apply_fullscreen_focus_policy(window, true);
             send_event(window, Focused(true));
+            update_lock_keys(window);
             0
         },
 
         winapi::WM_KILLFOCUS => {
             use events::Event::Focused;
             send_event(window, Focused(false));
+            apply_fullscreen_focus_policy(window, false);
             0
         },
 
@@ -270,15 +517,26 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
                 // there's a very bizarre borrow checker bug
                 // possibly related to rust-lang/rust/#23338
                 let _cursor_state = if let Some(cstash) = cstash {
-                    if let Ok(window_state) = cstash.window_state.lock() {
+                    if let Ok(mut window_state) = cstash.window_state.lock() {
+                        window_state.check_cursor_release();
                         match window_state.cursor_state {
                             CursorState::Normal => {
                                 user32::SetCursor(user32::LoadCursorW(
                                         ptr::null_mut(),
                                         window_state.cursor));
                             },
-                            CursorState::Grab | CursorState::Hide => {
+                            CursorState::Grab | CursorState::GrabRelative | CursorState::Hide => {
                                 user32::SetCursor(ptr::null_mut());
+                            },
+                            CursorState::Soft => {
+                                // only hide the cursor while the window has the focus
+                                if user32::GetForegroundWindow() == window {
+                                    user32::SetCursor(ptr::null_mut());
+                                } else {
+                                    user32::SetCursor(user32::LoadCursorW(
+                                            ptr::null_mut(),
+                                            window_state.cursor));
+                                }
                             }
                         }
                     }
@@ -295,7 +553,7 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             use events::Event::DroppedFile;
 
             let hdrop = wparam as winapi::HDROP;
-            let mut pathbuf: [u16; winapi::MAX_PATH] = mem::uninitialized();
+            let mut pathbuf: [u16; winapi::MAX_PATH] = [0; winapi::MAX_PATH];
             let num_drops = shell32::DragQueryFileW(hdrop, 0xFFFFFFFF, ptr::null_mut(), 0);
 
             for i in 0..num_drops {
@@ -310,6 +568,53 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             0
         },
 
+        winapi::WM_COMMAND => {
+            // the high word is `0` for the menus, and the notification code for the controls
+            if winapi::HIWORD(wparam as winapi::DWORD) == 0 && lparam == 0 {
+                let id = winapi::LOWORD(wparam as winapi::DWORD) as u32;
+                send_event(window, Event::MenuItem(id));
+                0
+            } else {
+                user32::DefWindowProcW(window, msg, wparam, lparam)
+            }
+        },
+
+        winapi::WM_SETTEXT => {
+            let text = lparam as *const u16;
+            let title = if text.is_null() {
+                String::new()
+            } else {
+                let mut len = 0;
+                while *text.offset(len) != 0 {
+                    len += 1;
+                }
+                let text = ::std::slice::from_raw_parts(text, len as usize);
+                OsString::from_wide(text).to_string_lossy().into_owned()
+            };
+
+            // `set_title` stores the title before changing it, so only the changes made by other
+            // programs are reported
+            let changed = CONTEXT_STASH.with(|context_stash| {
+                match context_stash.borrow().as_ref() {
+                    Some(cstash) if cstash.win == window => {
+                        let mut window_state = cstash.window_state.lock().unwrap();
+                        if window_state.attributes.title != title {
+                            window_state.attributes.title = title.clone();
+                            true
+                        } else {
+                            false
+                        }
+                    },
+                    _ => false
+                }
+            });
+            if changed {
+                send_event(window, Event::TitleChanged(title));
+            }
+
+            user32::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
         winapi::WM_GETMINMAXINFO => {
             let mut mmi = lparam as *mut MinMaxInfo;
             //(*mmi).max_position = winapi::POINT { x: -8, y: -8 }; // The upper left corner of the window if it were maximized on the primary monitor.
@@ -340,6 +645,40 @@ pub unsafe extern "system" fn callback(window: winapi::HWND, msg: winapi::UINT,
             0
         },
 
+        super::thumbnail::WM_DWMSENDICONICTHUMBNAIL |
+        super::thumbnail::WM_DWMSENDICONICLIVEPREVIEWBITMAP => {
+            CONTEXT_STASH.with(|context_stash| {
+                if let Some(cstash) = context_stash.borrow().as_ref() {
+                    if cstash.win != window {
+                        return;
+                    }
+
+                    let window_state = cstash.window_state.lock().unwrap();
+                    let thumbnail = match window_state.thumbnail {
+                        Some(ref thumbnail) => thumbnail,
+                        None => return
+                    };
+
+                    if msg == super::thumbnail::WM_DWMSENDICONICTHUMBNAIL {
+                        // the maximum dimensions are packed in lparam
+                        let max_width = winapi::HIWORD(lparam as winapi::DWORD) as u32;
+                        let max_height = winapi::LOWORD(lparam as winapi::DWORD) as u32;
+                        // the thumbnail was checked when it was set
+                        let _ = super::thumbnail::send_thumbnail(window, thumbnail, max_width,
+                                                                 max_height);
+                    } else {
+                        let mut rect: winapi::RECT = mem::zeroed();
+                        if user32::GetClientRect(window, &mut rect) != 0 {
+                            let _ = super::thumbnail::send_live_preview(
+                                window, thumbnail, (rect.right - rect.left) as u32,
+                                (rect.bottom - rect.top) as u32);
+                        }
+                    }
+                }
+            });
+            0
+        },
+
         x if x == *super::WAKEUP_MSG_ID => {
             use events::Event::Awakened;
             send_event(window, Awakened);
@@ -1,10 +1,37 @@
 use events::VirtualKeyCode;
 use winapi;
 use user32;
+use LockKeys;
+use ModifiersState;
 use ScanCode;
 
 const MAPVK_VSC_TO_VK_EX: u32 = 3;
 
+/// Returns the state of the modifiers at the time of the message that is being processed.
+pub fn get_modifiers() -> ModifiersState {
+    // the high-order bit is set when the key is down
+    let is_down = |vk| unsafe { user32::GetKeyState(vk) < 0 };
+
+    ModifiersState {
+        shift: is_down(winapi::VK_SHIFT),
+        ctrl: is_down(winapi::VK_CONTROL),
+        alt: is_down(winapi::VK_MENU),
+        logo: is_down(winapi::VK_LWIN) || is_down(winapi::VK_RWIN),
+    }
+}
+
+/// Returns the state of the lock keys at the time of the message that is being processed.
+pub fn get_lock_keys() -> LockKeys {
+    // the low-order bit is set when the key is toggled
+    let is_toggled = |vk| unsafe { user32::GetKeyState(vk) & 1 != 0 };
+
+    LockKeys {
+        caps_lock: is_toggled(winapi::VK_CAPITAL),
+        num_lock: is_toggled(winapi::VK_NUMLOCK),
+        scroll_lock: is_toggled(winapi::VK_SCROLL),
+    }
+}
+
 pub fn vkeycode_to_element(wparam: winapi::WPARAM, lparam: winapi::LPARAM) -> (ScanCode, Option<VirtualKeyCode>) {
     let scancode = ((lparam >> 16) & 0xff) as u8;
     let extended = (lparam & 0x01000000) != 0;
@@ -0,0 +1,54 @@
+//! Text committed by the input methods, such as the ones of Chinese, Japanese or Korean.
+//!
+//! By default, `DefWindowProc` splits the result of a composition into one `WM_CHAR` per UTF-16
+//! unit, which loses the boundaries between the strings. The result is read as a whole instead.
+
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
+use std::ptr;
+
+use winapi;
+
+pub const WM_IME_COMPOSITION: winapi::UINT = 0x010F;
+pub const GCS_RESULTSTR: winapi::LPARAM = 0x0800;
+
+type HIMC = winapi::HANDLE;
+
+#[link(name = "imm32")]
+extern "system" {
+    fn ImmGetContext(hwnd: winapi::HWND) -> HIMC;
+    fn ImmReleaseContext(hwnd: winapi::HWND, himc: HIMC) -> winapi::BOOL;
+    fn ImmGetCompositionStringW(himc: HIMC, index: winapi::DWORD, buffer: winapi::LPVOID,
+                                length: winapi::DWORD) -> winapi::LONG;
+}
+
+/// Returns the string that the input method has committed, if the message carries one.
+pub unsafe fn get_result_string(hwnd: winapi::HWND, lparam: winapi::LPARAM) -> Option<String> {
+    if lparam & GCS_RESULTSTR == 0 {
+        return None;
+    }
+
+    let himc = ImmGetContext(hwnd);
+    if himc.is_null() {
+        return None;
+    }
+
+    // the length is in bytes, and the string isn't null-terminated
+    let len = ImmGetCompositionStringW(himc, GCS_RESULTSTR as winapi::DWORD, ptr::null_mut(), 0);
+    let result = if len > 0 {
+        let mut buffer: Vec<u16> = vec![0; len as usize / 2];
+        let len = ImmGetCompositionStringW(himc, GCS_RESULTSTR as winapi::DWORD,
+                                           buffer.as_mut_ptr() as winapi::LPVOID, len as winapi::DWORD);
+        if len > 0 {
+            buffer.truncate(len as usize / 2);
+            Some(OsString::from_wide(&buffer).to_string_lossy().into_owned())
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    ImmReleaseContext(hwnd, himc);
+    result
+}
@@ -1,4 +1,5 @@
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicUsize;
 use std::io;
 use std::ptr;
 use std::mem;
@@ -17,7 +18,9 @@ use CreationError::OsError;
 use CursorState;
 use GlAttributes;
 use GlRequest;
+use LockKeys;
 use PixelFormatRequirements;
+use TileState;
 use WindowAttributes;
 
 use std::ffi::{OsStr};
@@ -75,7 +78,7 @@ pub fn new_window(window: &WindowAttributes, pf_reqs: &PixelFormatRequirements,
             // now that the `Window` struct is initialized, the main `Window::new()` function will
             //  return and this events loop will run in parallel
             loop {
-                let mut msg = mem::uninitialized();
+                let mut msg = mem::zeroed();
 
                 if user32::GetMessageW(&mut msg, ptr::null_mut(), 0, 0) == 0 {
                     break;
@@ -113,25 +116,58 @@ unsafe fn init(title: Vec<u16>, window: &WindowAttributes, pf_reqs: &PixelFormat
     // switching to fullscreen if necessary
     // this means adjusting the window's position so that it overlaps the right monitor,
     //  and change the monitor's resolution if necessary
-    if window.monitor.is_some() {
-        let monitor = window.monitor.as_ref().unwrap();
-        try!(switch_to_fullscreen(&mut rect, monitor));
+    let fullscreen_mode = match window.monitor {
+        Some(ref monitor) => Some(try!(switch_to_fullscreen(&mut rect, monitor))),
+        None => None,
+    };
+
+    // embedded windows are children of a window of the host application, unless they are
+    // fullscreen
+    let parent = match (window.monitor.as_ref(), window.parent.as_ref()) {
+        (None, Some(parent)) => Some(parent.window as winapi::HWND),
+        _ => None,
+    };
+
+    // children fill the client area of their parent by default
+    if let Some(parent) = parent {
+        if window.dimensions.is_none() {
+            let mut client_rect = mem::zeroed();
+            if user32::GetClientRect(parent, &mut client_rect) == 0 {
+                return Err(OsError(format!("GetClientRect function failed on the parent window: {}",
+                                           format!("{}", io::Error::last_os_error()))));
+            }
+            rect = client_rect;
+        }
     }
 
     // computing the style and extended style of the window
-    let (ex_style, style) = if window.monitor.is_some() || window.decorations == false {
+    //
+    // a child created by this thread attaches its input queue to the one of the thread of the
+    // parent, and each thread blocks when it sends a message to the window of the other one.
+    // `WS_EX_NOPARENTNOTIFY` avoids sending `WM_PARENTNOTIFY` to the parent during
+    // `CreateWindowEx`, while the thread of the parent waits for `Window::new` to return
+    let (ex_style, style) = if parent.is_some() {
+        (winapi::WS_EX_NOPARENTNOTIFY,
+         winapi::WS_CHILD | winapi::WS_CLIPSIBLINGS | winapi::WS_CLIPCHILDREN)
+    } else if window.monitor.is_some() || window.decorations == false {
         (winapi::WS_EX_APPWINDOW, winapi::WS_POPUP | winapi::WS_CLIPSIBLINGS | winapi::WS_CLIPCHILDREN)
     } else {
         (winapi::WS_EX_APPWINDOW | winapi::WS_EX_WINDOWEDGE,
             winapi::WS_OVERLAPPEDWINDOW | winapi::WS_CLIPSIBLINGS | winapi::WS_CLIPCHILDREN)
     };
 
-    // adjusting the window coordinates using the style
-    user32::AdjustWindowRectEx(&mut rect, style, 0, ex_style);
+    // adjusting the window coordinates using the style, unless the dimensions already include
+    // the decorations
+    if !window.outer_dimensions && parent.is_none() {
+        user32::AdjustWindowRectEx(&mut rect, style, 0, ex_style);
+    }
 
     // creating the real window this time, by using the functions in `extra_functions`
     let real_window = {
-        let (width, height) = if window.monitor.is_some() || window.dimensions.is_some() {
+        // `CW_USEDEFAULT` is only valid for top-level windows
+        let (width, height) = if window.monitor.is_some() || window.dimensions.is_some() ||
+                                 parent.is_some()
+        {
             (Some(rect.right - rect.left), Some(rect.bottom - rect.top))
         } else {
             (None, None)
@@ -139,6 +175,8 @@ unsafe fn init(title: Vec<u16>, window: &WindowAttributes, pf_reqs: &PixelFormat
 
         let (x, y) = if window.monitor.is_some() {
             (Some(rect.left), Some(rect.top))
+        } else if parent.is_some() {
+            (Some(0), Some(0))
         } else {
             (None, None)
         };
@@ -155,7 +193,8 @@ unsafe fn init(title: Vec<u16>, window: &WindowAttributes, pf_reqs: &PixelFormat
             style | winapi::WS_CLIPSIBLINGS | winapi::WS_CLIPCHILDREN,
             x.unwrap_or(winapi::CW_USEDEFAULT), y.unwrap_or(winapi::CW_USEDEFAULT),
             width.unwrap_or(winapi::CW_USEDEFAULT), height.unwrap_or(winapi::CW_USEDEFAULT),
-            ptr::null_mut(), ptr::null_mut(), kernel32::GetModuleHandleW(ptr::null()),
+            parent.unwrap_or(ptr::null_mut()), ptr::null_mut(),
+            kernel32::GetModuleHandleW(ptr::null()),
             ptr::null_mut());
 
         if handle.is_null() {
@@ -219,10 +258,21 @@ unsafe fn init(title: Vec<u16>, window: &WindowAttributes, pf_reqs: &PixelFormat
     let window_state = Arc::new(Mutex::new(WindowState {
         cursor: winapi::IDC_ARROW, // use arrow by default
         cursor_state: CursorState::Normal,
-        attributes: window.clone()
+        attributes: window.clone(),
+        thumbnail: None,
+        tile_state: TileState::Untiled,
+        maximized: false,
+        minimized: false,
+        refresh_rate: callback::get_refresh_rate(real_window.0),
+        fullscreen_mode: fullscreen_mode,
+        saved_window: None,
+        lock_keys: LockKeys::default(),
+        high_surrogate: None,
+        cursor_releases: 0,
     }));
 
     // filling the CONTEXT_STASH task-local storage so that we can start receiving events
+    let queued_events = Arc::new(AtomicUsize::new(0));
     let events_receiver = {
         let (tx, rx) = channel();
         let mut tx = Some(tx);
@@ -230,6 +280,7 @@ unsafe fn init(title: Vec<u16>, window: &WindowAttributes, pf_reqs: &PixelFormat
             let data = callback::ThreadLocalData {
                 win: real_window.0,
                 sender: tx.take().unwrap(),
+                queued_events: queued_events.clone(),
                 window_state: window_state.clone()
             };
             (*context_stash.borrow_mut()) = Some(data);
@@ -242,6 +293,7 @@ unsafe fn init(title: Vec<u16>, window: &WindowAttributes, pf_reqs: &PixelFormat
         window: real_window,
         context: context,
         events_receiver: events_receiver,
+        queued_events: queued_events,
         window_state: window_state,
     })
 }
@@ -274,8 +326,10 @@ unsafe fn register_window_class() -> Vec<u16> {
     class_name
 }
 
+/// Moves the rectangle to the monitor and changes the resolution of the monitor to the size of
+/// the rectangle. Returns this resolution.
 unsafe fn switch_to_fullscreen(rect: &mut winapi::RECT, monitor: &MonitorId)
-                               -> Result<(), CreationError>
+                               -> Result<(u32, u32), CreationError>
 {
     // adjusting the rect
     {
@@ -286,17 +340,35 @@ unsafe fn switch_to_fullscreen(rect: &mut winapi::RECT, monitor: &MonitorId)
         rect.bottom += pos.1 as winapi::LONG;
     }
 
-    // changing device settings
-    let mut screen_settings: winapi::DEVMODEW = mem::zeroed();
-    screen_settings.dmSize = mem::size_of::<winapi::DEVMODEW>() as winapi::WORD;
-    screen_settings.dmPelsWidth = (rect.right - rect.left) as winapi::DWORD;
-    screen_settings.dmPelsHeight = (rect.bottom - rect.top) as winapi::DWORD;
-    screen_settings.dmBitsPerPel = 32;      // TODO: ?
-    screen_settings.dmFields = winapi::DM_BITSPERPEL | winapi::DM_PELSWIDTH | winapi::DM_PELSHEIGHT;
-
-    let result = user32::ChangeDisplaySettingsExW(monitor.get_adapter_name().as_ptr(),
-                                                  &mut screen_settings, ptr::null_mut(),
-                                                  winapi::CDS_FULLSCREEN, ptr::null_mut());
+    let mode = ((rect.right - rect.left) as u32, (rect.bottom - rect.top) as u32);
+    try!(set_display_mode(monitor, Some(mode)));
+    Ok(mode)
+}
+
+/// Changes the resolution of the monitor, or restores the resolution of the desktop if `mode`
+/// is `None`.
+pub unsafe fn set_display_mode(monitor: &MonitorId, mode: Option<(u32, u32)>)
+                               -> Result<(), CreationError>
+{
+    let result = match mode {
+        Some((width, height)) => {
+            let mut screen_settings: winapi::DEVMODEW = mem::zeroed();
+            screen_settings.dmSize = mem::size_of::<winapi::DEVMODEW>() as winapi::WORD;
+            screen_settings.dmPelsWidth = width as winapi::DWORD;
+            screen_settings.dmPelsHeight = height as winapi::DWORD;
+            screen_settings.dmBitsPerPel = 32;      // TODO: ?
+            screen_settings.dmFields = winapi::DM_BITSPERPEL | winapi::DM_PELSWIDTH |
+                                       winapi::DM_PELSHEIGHT;
+
+            user32::ChangeDisplaySettingsExW(monitor.get_adapter_name().as_ptr(),
+                                             &mut screen_settings, ptr::null_mut(),
+                                             winapi::CDS_FULLSCREEN, ptr::null_mut())
+        },
+        None => {
+            user32::ChangeDisplaySettingsExW(monitor.get_adapter_name().as_ptr(),
+                                             ptr::null_mut(), ptr::null_mut(), 0, ptr::null_mut())
+        },
+    };
 
     if result != winapi::DISP_CHANGE_SUCCESSFUL {
         return Err(OsError(format!("ChangeDisplaySettings failed: {}", result)));
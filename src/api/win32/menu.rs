@@ -0,0 +1,83 @@
+//! Menu bars of the windows.
+//!
+//! The items report their ID with `WM_COMMAND`. The accelerators are only shown next to the
+//! labels, since they are triggered by glutin on every platform but OS X.
+
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+
+use winapi;
+
+use Menu;
+use MenuItem;
+
+const MF_STRING: winapi::UINT = 0x0000;
+const MF_GRAYED: winapi::UINT = 0x0001;
+const MF_POPUP: winapi::UINT = 0x0010;
+const MF_SEPARATOR: winapi::UINT = 0x0800;
+
+type HMENU = winapi::HANDLE;
+
+// these functions are missing from the `user32` crate
+#[link(name = "user32")]
+extern "system" {
+    fn CreateMenu() -> HMENU;
+    fn CreatePopupMenu() -> HMENU;
+    fn DestroyMenu(menu: HMENU) -> winapi::BOOL;
+    fn AppendMenuW(menu: HMENU, flags: winapi::UINT, id: usize,
+                   item: winapi::LPCWSTR) -> winapi::BOOL;
+    fn GetMenu(hwnd: winapi::HWND) -> HMENU;
+    fn SetMenu(hwnd: winapi::HWND, menu: HMENU) -> winapi::BOOL;
+    fn DrawMenuBar(hwnd: winapi::HWND) -> winapi::BOOL;
+}
+
+/// Replaces the menu bar of the window, or removes it if there are no menus.
+pub unsafe fn set_menu_bar(hwnd: winapi::HWND, menus: &[Menu]) {
+    let old_menu = GetMenu(hwnd);
+
+    let menu_bar = if menus.is_empty() {
+        0 as HMENU
+    } else {
+        let menu_bar = CreateMenu();
+        for menu in menus.iter() {
+            append_menu(menu_bar, menu);
+        }
+        menu_bar
+    };
+
+    // the menus that are attached to a window are destroyed with it, but not the replaced ones
+    SetMenu(hwnd, menu_bar);
+    DrawMenuBar(hwnd);
+    if !old_menu.is_null() {
+        DestroyMenu(old_menu);
+    }
+}
+
+/// Appends the menu and its submenus to a menu bar or to a parent menu.
+unsafe fn append_menu(parent: HMENU, menu: &Menu) {
+    let popup = CreatePopupMenu();
+
+    for item in menu.items.iter() {
+        match *item {
+            MenuItem::Action { id, ref label, accelerator, enabled } => {
+                // the text after a tab is aligned to the right
+                let text = match accelerator {
+                    Some(accelerator) => format!("{}\t{}", label, accelerator),
+                    None => label.clone(),
+                };
+                let flags = if enabled { MF_STRING } else { MF_STRING | MF_GRAYED };
+                AppendMenuW(popup, flags, (id & 0xFFFF) as usize, to_wide(&text).as_ptr());
+            },
+            MenuItem::Separator => {
+                AppendMenuW(popup, MF_SEPARATOR, 0, 0 as winapi::LPCWSTR);
+            },
+            MenuItem::Submenu(ref submenu) => append_menu(popup, submenu),
+        }
+    }
+
+    AppendMenuW(parent, MF_POPUP, popup as usize, to_wide(&menu.title).as_ptr());
+}
+
+fn to_wide(text: &str) -> Vec<u16> {
+    OsStr::new(text).encode_wide().chain(Some(0).into_iter()).collect()
+}
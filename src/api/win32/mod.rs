@@ -1,25 +1,36 @@
 #![cfg(target_os = "windows")]
 
+use std::cmp;
 use std::mem;
 use std::ptr;
+use std::slice;
 use std::ffi::OsStr;
 use std::os::windows::ffi::OsStrExt;
 use std::sync::{
     Arc,
+    Condvar,
     Mutex
 };
 use std::sync::mpsc::Receiver;
+use std::sync::{Once, ONCE_INIT};
+use std::sync::atomic::{AtomicBool, AtomicUsize, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT, Ordering};
+use std::time::Duration;
 use libc;
 use ContextError;
 use {CreationError, Event, MouseCursor};
 use CursorState;
 use GlAttributes;
 use GlContext;
+use LockKeys;
+use PointerAcceleration;
 
 use Api;
 use PixelFormat;
 use PixelFormatRequirements;
+use SnapPosition;
+use TileState;
 use WindowAttributes;
+use WindowID;
 
 pub use self::monitor::{MonitorId, get_available_monitors, get_primary_monitor};
 
@@ -27,6 +38,7 @@ use winapi;
 use user32;
 use kernel32;
 
+use api::panic::add_panic_cleanup;
 use api::wgl::Context as WglContext;
 use api::egl::Context as EglContext;
 use api::egl::ffi::egl::Egl;
@@ -35,11 +47,52 @@ use self::init::RawContext;
 
 mod callback;
 mod event;
+mod ime;
 mod init;
+mod menu;
 mod monitor;
+mod thumbnail;
 
 lazy_static! {
     static ref WAKEUP_MSG_ID: u32 = unsafe { user32::RegisterWindowMessageA("Glutin::EventID".as_ptr() as *const i8) };
+
+    /// Notified every time that the thread of a window sends an event, for `wait_for_events`.
+    static ref EVENT_SIGNAL: (Mutex<()>, Condvar) = (Mutex::new(()), Condvar::new());
+}
+
+/// Wakes up the threads blocked in `wait_for_events`, once an event has been sent.
+fn notify_event() {
+    let _guard = EVENT_SIGNAL.0.lock().unwrap();
+    EVENT_SIGNAL.1.notify_all();
+}
+
+/// Whether a window has confined the cursor with `ClipCursor`. The confinement is global and
+/// outlives a panic of the window thread, so it is released by a panic hook.
+static CURSOR_CLIPPED: AtomicBool = ATOMIC_BOOL_INIT;
+
+/// The number of times that the panic hook released the cursor. The hook runs for every panic,
+/// including the ones that are caught, so the windows compare it with the value that they saw
+/// when their cursor was grabbed or hidden, and go back to the normal state if it changed.
+static CURSOR_RELEASES: AtomicUsize = ATOMIC_USIZE_INIT;
+
+static INSTALL_CLEANUP: Once = ONCE_INIT;
+
+const SPI_GETMOUSE: winapi::UINT = 0x0003;
+const SPI_GETMOUSESPEED: winapi::UINT = 0x0070;
+
+/// Starts or stops receiving the raw movements of the mouse in `WM_INPUT`.
+fn register_raw_mouse(window: winapi::HWND, enabled: bool) {
+    let device = winapi::RAWINPUTDEVICE {
+        usUsagePage: 0x01,  // generic desktop controls
+        usUsage: 0x02,      // mouse
+        dwFlags: if enabled { 0 } else { winapi::RIDEV_REMOVE },
+        hwndTarget: if enabled { window } else { ptr::null_mut() },
+    };
+
+    unsafe {
+        user32::RegisterRawInputDevices(&device, 1,
+                                        mem::size_of::<winapi::RAWINPUTDEVICE>() as winapi::UINT);
+    }
 }
 
 /// Cursor
@@ -50,7 +103,43 @@ pub type Cursor = *const winapi::wchar_t;
 pub struct WindowState {
     pub cursor: Cursor,
     pub cursor_state: CursorState,
-    pub attributes: WindowAttributes
+    pub attributes: WindowAttributes,
+    pub thumbnail: Option<thumbnail::Thumbnail>,
+    /// Last reported Aero Snap state
+    pub tile_state: TileState,
+    /// Whether the window is maximized, as last reported. A minimized window keeps the value it
+    /// had before, because it is restored to that state
+    pub maximized: bool,
+    /// Whether the window is minimized, as last reported
+    pub minimized: bool,
+    /// Refresh rate of the monitor of the window, in millihertz
+    pub refresh_rate: Option<u32>,
+    /// The resolution that the monitor was switched to for the fullscreen window
+    pub fullscreen_mode: Option<(u32, u32)>,
+    /// The style, extended style and rectangle of the window before `set_fullscreen`
+    pub saved_window: Option<(winapi::LONG, winapi::LONG, winapi::RECT)>,
+    /// Last reported state of the lock keys
+    pub lock_keys: LockKeys,
+    /// High surrogate of a `WM_CHAR` message, waiting for the low surrogate of the next one
+    pub high_surrogate: Option<u16>,
+    /// The value of `CURSOR_RELEASES` when `cursor_state` was last set
+    pub cursor_releases: usize,
+}
+
+impl WindowState {
+    /// Resets the state of the cursor to `Normal` if a panic has released it since it was set.
+    ///
+    /// Returns `true` if the state was reset.
+    pub fn check_cursor_release(&mut self) -> bool {
+        if self.cursor_state != CursorState::Normal &&
+           self.cursor_releases != CURSOR_RELEASES.load(Ordering::SeqCst)
+        {
+            self.cursor_state = CursorState::Normal;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 /// The Win32 implementation of the main `Window` object.
@@ -64,6 +153,9 @@ pub struct Window {
     /// Receiver for the events dispatched by the window callback.
     events_receiver: Receiver<Event>,
 
+    /// The number of events that have been sent but not received yet.
+    queued_events: Arc<AtomicUsize>,
+
     /// The current window state.
     window_state: Arc<Mutex<WindowState>>,
 }
@@ -127,6 +219,8 @@ impl Window {
     ///
     /// Calls SetWindowText on the HWND.
     pub fn set_title(&self, text: &str) {
+        self.window_state.lock().unwrap().attributes.title = text.to_owned();
+
         let text = OsStr::new(text).encode_wide().chain(Some(0).into_iter())
                                    .collect::<Vec<_>>();
 
@@ -135,6 +229,32 @@ impl Window {
         }
     }
 
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn set_menu_bar(&self, menus: &[::Menu]) {
+        unsafe { menu::set_menu_bar(self.window.0, menus) }
+    }
+
+    /// The menu bar is in the non-client area, so it doesn't cover the client area.
+    #[inline]
+    pub fn get_menu_bar_height(&self) -> u32 {
+        0
+    }
+
+    pub fn get_title(&self) -> Option<String> {
+        unsafe {
+            let len = user32::GetWindowTextLengthW(self.window.0);
+            let mut buffer: Vec<u16> = vec![0; len as usize + 1];
+            let len = user32::GetWindowTextW(self.window.0, buffer.as_mut_ptr(),
+                                             buffer.len() as libc::c_int);
+            if len == 0 && kernel32::GetLastError() != 0 {
+                return None;
+            }
+
+            Some(String::from_utf16_lossy(&buffer[.. len as usize]))
+        }
+    }
+
     #[inline]
     pub fn show(&self) {
         unsafe {
@@ -149,6 +269,34 @@ impl Window {
         }
     }
 
+    /// See the docs in the crate root file.
+    ///
+    /// Calls SetForegroundWindow on the HWND, which Windows may refuse if the process is not
+    /// allowed to steal the focus. In this case the taskbar button flashes instead.
+    pub fn focus(&self) {
+        unsafe {
+            user32::SetForegroundWindow(self.window.0);
+        }
+    }
+
+    #[inline]
+    pub fn raise(&self) {
+        unsafe {
+            // a null `hWndInsertAfter` is `HWND_TOP`
+            user32::SetWindowPos(self.window.0, ptr::null_mut(), 0, 0, 0, 0,
+                winapi::SWP_NOMOVE | winapi::SWP_NOSIZE | winapi::SWP_NOACTIVATE);
+        }
+    }
+
+    #[inline]
+    pub fn lower(&self) {
+        unsafe {
+            // `1` is `HWND_BOTTOM`
+            user32::SetWindowPos(self.window.0, 1 as winapi::HWND, 0, 0, 0, 0,
+                winapi::SWP_NOMOVE | winapi::SWP_NOSIZE | winapi::SWP_NOACTIVATE);
+        }
+    }
+
     /// See the docs in the crate root file.
     pub fn get_position(&self) -> Option<(i32, i32)> {
         use std::mem;
@@ -164,6 +312,226 @@ impl Window {
         Some((rect.left as i32, rect.top as i32))
     }
 
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn get_lock_keys(&self) -> LockKeys {
+        event::get_lock_keys()
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        match self.context {
+            Context::Wgl(ref c) => c.make_not_current(),
+            Context::Egl(ref c) => c.make_not_current(),
+        }
+    }
+
+    /// Reads the "Enhance pointer precision" setting and the pointer speed slider.
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        // the multipliers of the 20 positions of the pointer speed slider
+        const SPEEDS: [f64; 20] = [1.0 / 32.0, 1.0 / 16.0, 1.0 / 8.0, 2.0 / 8.0, 3.0 / 8.0,
+                                   4.0 / 8.0, 5.0 / 8.0, 6.0 / 8.0, 7.0 / 8.0, 1.0,
+                                   1.25, 1.5, 1.75, 2.0, 2.25, 2.5, 2.75, 3.0, 3.25, 3.5];
+
+        unsafe {
+            // the two thresholds and the acceleration
+            let mut mouse: [libc::c_int; 3] = [0; 3];
+            if user32::SystemParametersInfoW(SPI_GETMOUSE, 0, mouse.as_mut_ptr() as *mut _,
+                                             0) == 0
+            {
+                return None;
+            }
+
+            let mut speed: libc::c_int = 10;
+            if user32::SystemParametersInfoW(SPI_GETMOUSESPEED, 0,
+                                             &mut speed as *mut libc::c_int as *mut _, 0) == 0
+            {
+                return None;
+            }
+
+            let speed = cmp::max(1, cmp::min(speed, 20));
+            Some(PointerAcceleration {
+                enabled: mouse[2] != 0,
+                speed: SPEEDS[speed as usize - 1],
+                // "Enhance pointer precision" uses a curve since Windows XP
+                threshold: None,
+            })
+        }
+    }
+
+    /// See the docs in the crate root file.
+    pub fn set_lock_keys(&self, lock_keys: LockKeys) -> Result<(), String> {
+        let current = event::get_lock_keys();
+        let keys = [(lock_keys.caps_lock, current.caps_lock, winapi::VK_CAPITAL),
+                    (lock_keys.num_lock, current.num_lock, winapi::VK_NUMLOCK),
+                    (lock_keys.scroll_lock, current.scroll_lock, winapi::VK_SCROLL)];
+
+        // the lock keys can only be toggled by simulating a press
+        for &(wanted, current, vk) in keys.iter() {
+            if wanted != current {
+                unsafe {
+                    user32::keybd_event(vk as winapi::BYTE, 0, 0, 0);
+                    user32::keybd_event(vk as winapi::BYTE, 0, winapi::KEYEVENTF_KEYUP, 0);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// See the docs in the crate root file.
+    pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
+        // the window state must not be locked while the window is resized, because the
+        // callback locks it as well
+        let saved_window = {
+            let mut window_state = self.window_state.lock().unwrap();
+            match monitor {
+                Some(_) if window_state.saved_window.is_none() => unsafe {
+                    let mut rect = mem::zeroed();
+                    user32::GetWindowRect(self.window.0, &mut rect);
+                    window_state.saved_window = Some((
+                        user32::GetWindowLongW(self.window.0, winapi::GWL_STYLE),
+                        user32::GetWindowLongW(self.window.0, winapi::GWL_EXSTYLE),
+                        rect,
+                    ));
+                    window_state.saved_window
+                },
+                Some(_) => window_state.saved_window,
+                None => window_state.saved_window.take(),
+            }
+        };
+
+        let (style, ex_style, rect) = match saved_window {
+            Some(saved_window) => saved_window,
+            None => return,
+        };
+
+        unsafe {
+            match monitor {
+                Some(monitor) => {
+                    let (x, y) = monitor.get_position();
+                    let (width, height) = monitor.get_dimensions();
+                    let style = (style & !(winapi::WS_OVERLAPPEDWINDOW as winapi::LONG)) |
+                                winapi::WS_POPUP as winapi::LONG;
+                    user32::SetWindowLongW(self.window.0, winapi::GWL_STYLE, style);
+                    user32::SetWindowPos(self.window.0, winapi::HWND_TOP, x as i32, y as i32,
+                                         width as i32, height as i32,
+                                         winapi::SWP_FRAMECHANGED | winapi::SWP_NOOWNERZORDER);
+                },
+                None => {
+                    user32::SetWindowLongW(self.window.0, winapi::GWL_STYLE, style);
+                    user32::SetWindowLongW(self.window.0, winapi::GWL_EXSTYLE, ex_style);
+                    user32::SetWindowPos(self.window.0, ptr::null_mut(), rect.left, rect.top,
+                                         rect.right - rect.left, rect.bottom - rect.top,
+                                         winapi::SWP_FRAMECHANGED | winapi::SWP_NOZORDER |
+                                         winapi::SWP_NOOWNERZORDER);
+                },
+            }
+        }
+    }
+
+    /// See the docs in the crate root file.
+    pub fn set_parent(&self, parent: Option<WindowID>) -> Result<(), String> {
+        let parent = parent.map(|parent| parent.window as winapi::HWND);
+        let decorations = self.window_state.lock().unwrap().attributes.decorations;
+
+        unsafe {
+            let style = user32::GetWindowLongW(self.window.0, winapi::GWL_STYLE);
+            let ex_style = user32::GetWindowLongW(self.window.0, winapi::GWL_EXSTYLE);
+            let top_level_style = if decorations {
+                winapi::WS_OVERLAPPEDWINDOW
+            } else {
+                winapi::WS_POPUP
+            } as winapi::LONG;
+            let top_level_ex_style = (winapi::WS_EX_APPWINDOW | winapi::WS_EX_WINDOWEDGE) as winapi::LONG;
+
+            // a window must have the `WS_CHILD` style while it has a parent, and only then
+            let (style, ex_style) = match parent {
+                Some(_) => ((style & !(winapi::WS_OVERLAPPEDWINDOW | winapi::WS_POPUP) as winapi::LONG) |
+                            winapi::WS_CHILD as winapi::LONG,
+                            ex_style & !top_level_ex_style),
+                None => ((style & !(winapi::WS_CHILD as winapi::LONG)) | top_level_style,
+                         ex_style | top_level_ex_style),
+            };
+            user32::SetWindowLongW(self.window.0, winapi::GWL_STYLE, style);
+            user32::SetWindowLongW(self.window.0, winapi::GWL_EXSTYLE, ex_style);
+
+            // the previous parent of a top-level window is null as well
+            kernel32::SetLastError(0);
+            if user32::SetParent(self.window.0, parent.unwrap_or(ptr::null_mut())).is_null() &&
+               kernel32::GetLastError() != 0
+            {
+                return Err(format!("SetParent function failed: {}",
+                                   ::std::io::Error::last_os_error()));
+            }
+
+            // children fill the client area of their new parent
+            let mut flags = winapi::SWP_FRAMECHANGED | winapi::SWP_NOZORDER |
+                            winapi::SWP_NOOWNERZORDER | winapi::SWP_NOACTIVATE;
+            let mut rect: winapi::RECT = mem::zeroed();
+            match parent {
+                Some(parent) => { user32::GetClientRect(parent, &mut rect); },
+                None => flags |= winapi::SWP_NOMOVE | winapi::SWP_NOSIZE,
+            }
+            user32::SetWindowPos(self.window.0, ptr::null_mut(), 0, 0,
+                                 rect.right - rect.left, rect.bottom - rect.top, flags);
+        }
+
+        self.window_state.lock().unwrap().attributes.parent =
+            parent.map(|parent| WindowID::new(parent as *mut _));
+        Ok(())
+    }
+
+    /// See the docs in the crate root file.
+    pub fn set_maximized(&self, maximized: bool) {
+        unsafe {
+            if maximized {
+                user32::ShowWindow(self.window.0, winapi::SW_MAXIMIZE);
+            } else if user32::IsZoomed(self.window.0) != 0 {
+                user32::ShowWindow(self.window.0, winapi::SW_RESTORE);
+            }
+        }
+    }
+
+    /// See the docs in the crate root file.
+    pub fn set_minimized(&self, minimized: bool) {
+        unsafe {
+            if minimized {
+                user32::ShowWindow(self.window.0, winapi::SW_MINIMIZE);
+            } else if user32::IsIconic(self.window.0) != 0 {
+                // a window that was maximized before being minimized is maximized again
+                user32::ShowWindow(self.window.0, winapi::SW_RESTORE);
+            }
+        }
+    }
+
+    /// See the docs in the crate root file.
+    pub fn snap_to(&self, position: SnapPosition, monitor: &MonitorId) {
+        let (x, y, width, height) = match monitor.get_workarea() {
+            Some(workarea) => workarea,
+            None => return,
+        };
+        let (width, height) = (width as i32, height as i32);
+        let (half_width, half_height) = (width / 2, height / 2);
+        let (x, y, width, height) = match position {
+            SnapPosition::Left => (x, y, half_width, height),
+            SnapPosition::Right => (x + half_width, y, width - half_width, height),
+            SnapPosition::Top => (x, y, width, half_height),
+            SnapPosition::Bottom => (x, y + half_height, width, height - half_height),
+            SnapPosition::TopLeft => (x, y, half_width, half_height),
+            SnapPosition::TopRight => (x + half_width, y, width - half_width, half_height),
+            SnapPosition::BottomLeft => (x, y + half_height, half_width, height - half_height),
+            SnapPosition::BottomRight => (x + half_width, y + half_height,
+                                          width - half_width, height - half_height),
+        };
+
+        // a maximized window would keep covering the whole monitor
+        unsafe {
+            user32::ShowWindow(self.window.0, winapi::SW_RESTORE);
+            user32::SetWindowPos(self.window.0, ptr::null_mut(), x, y, width, height,
+                                 winapi::SWP_NOZORDER | winapi::SWP_NOACTIVATE);
+        }
+    }
+
     /// See the docs in the crate root file.
     pub fn set_position(&self, x: i32, y: i32) {
         use libc;
@@ -178,7 +546,7 @@ impl Window {
     /// See the docs in the crate root file.
     #[inline]
     pub fn get_inner_size(&self) -> Option<(u32, u32)> {
-        let mut rect: winapi::RECT = unsafe { mem::uninitialized() };
+        let mut rect: winapi::RECT = unsafe { mem::zeroed() };
 
         if unsafe { user32::GetClientRect(self.window.0, &mut rect) } == 0 {
             return None
@@ -193,7 +561,7 @@ impl Window {
     /// See the docs in the crate root file.
     #[inline]
     pub fn get_outer_size(&self) -> Option<(u32, u32)> {
-        let mut rect: winapi::RECT = unsafe { mem::uninitialized() };
+        let mut rect: winapi::RECT = unsafe { mem::zeroed() };
 
         if unsafe { user32::GetWindowRect(self.window.0, &mut rect) } == 0 {
             return None
@@ -246,6 +614,23 @@ impl Window {
         }
     }
 
+    /// Blocks until one of the windows has events, or until the timeout expires.
+    ///
+    /// Every window runs its own thread, which notifies a global condition variable when it
+    /// sends an event.
+    pub fn wait_for_events(windows: &[&Window], timeout: Option<Duration>) {
+        let guard = EVENT_SIGNAL.0.lock().unwrap();
+        if windows.iter().any(|w| w.queued_events.load(Ordering::SeqCst) != 0) {
+            return;
+        }
+
+        // the events are checked again by the caller, so spurious wake-ups don't matter
+        match timeout {
+            Some(timeout) => { let _ = EVENT_SIGNAL.1.wait_timeout(guard, timeout).unwrap(); },
+            None => { let _ = EVENT_SIGNAL.1.wait(guard).unwrap(); }
+        }
+    }
+
     #[inline]
     pub fn platform_display(&self) -> *mut libc::c_void {
         // What should this return on win32?
@@ -290,6 +675,29 @@ impl Window {
     pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
         let mut current_state = self.window_state.lock().unwrap();
 
+        if current_state.check_cursor_release() {
+            // the clip has already been removed by the panic hook
+            register_raw_mouse(self.window.0, false);
+        }
+
+        if state != CursorState::Normal {
+            INSTALL_CLEANUP.call_once(|| add_panic_cleanup(|| {
+                CURSOR_RELEASES.fetch_add(1, Ordering::SeqCst);
+                if CURSOR_CLIPPED.swap(false, Ordering::SeqCst) {
+                    unsafe { user32::ClipCursor(ptr::null()) };
+                }
+                // the hidden cursors are shown again by the next `WM_SETCURSOR` of their window
+            }));
+        }
+        current_state.cursor_releases = CURSOR_RELEASES.load(Ordering::SeqCst);
+
+        // the cursor can't be recentered without generating a `WM_MOUSEMOVE`, so the relative
+        // mode is a regular grab, during which the raw movements are reported anyway
+        let state = match state {
+            CursorState::GrabRelative => CursorState::Grab,
+            state => state,
+        };
+
         let foreground_thread_id = unsafe { user32::GetWindowThreadProcessId(self.window.0, ptr::null_mut()) };
         let current_thread_id = unsafe { kernel32::GetCurrentThreadId() };
 
@@ -310,9 +718,17 @@ impl Window {
                 Ok(())
             },
 
+            // the cursor is hidden or shown by the window callback in `WM_SETCURSOR`
+            (CursorState::Soft, CursorState::Normal) | (CursorState::Soft, CursorState::Hide) |
+            (CursorState::Normal, CursorState::Soft) | (CursorState::Hide, CursorState::Soft) |
+            (CursorState::Soft, CursorState::Soft) => {
+                current_state.cursor_state = state;
+                Ok(())
+            },
+
             (CursorState::Grab, CursorState::Normal) | (CursorState::Grab, CursorState::Hide) => {
                 unsafe {
-                    let mut rect = mem::uninitialized();
+                    let mut rect = mem::zeroed();
                     if user32::GetClientRect(self.window.0, &mut rect) == 0 {
                         return Err(format!("GetWindowRect failed"));
                     }
@@ -321,6 +737,8 @@ impl Window {
                     if user32::ClipCursor(&rect) == 0 {
                         return Err(format!("ClipCursor failed"));
                     }
+                    CURSOR_CLIPPED.store(true, Ordering::SeqCst);
+                    register_raw_mouse(self.window.0, true);
                     current_state.cursor_state = CursorState::Grab;
                     Ok(())
                 }
@@ -331,6 +749,8 @@ impl Window {
                     if user32::ClipCursor(ptr::null()) == 0 {
                         return Err(format!("ClipCursor failed"));
                     }
+                    CURSOR_CLIPPED.store(false, Ordering::SeqCst);
+                    register_raw_mouse(self.window.0, false);
                     current_state.cursor_state = CursorState::Normal;
                     Ok(())
                 }
@@ -344,11 +764,155 @@ impl Window {
         res
     }
 
+    #[inline]
+    pub fn set_variable_refresh(&self, _enabled: bool) {
+    }
+
+    /// Replaces the taskbar thumbnail and the alt-tab live preview of the window with an
+    /// RGBA image, or restores the default behavior if `None`.
+    pub fn set_taskbar_thumbnail(&self, thumbnail: Option<(u32, u32, Vec<u8>)>)
+                                 -> Result<(), String>
+    {
+        let thumbnail = match thumbnail {
+            Some((width, height, pixels)) => {
+                Some(try!(thumbnail::Thumbnail::new(width, height, pixels)))
+            },
+            None => None,
+        };
+        let enabled = thumbnail.is_some();
+
+        self.window_state.lock().unwrap().thumbnail = thumbnail;
+
+        unsafe {
+            thumbnail::set_iconic_representation(self.window.0, enabled);
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_cursor_size(&self, _size: u32) {
+        // the size of the cursor is a system-wide setting that applications can't override
+    }
+
+    pub fn set_clipboard_string(&self, text: &str) {
+        let text: Vec<u16> = OsStr::new(text).encode_wide().chain(Some(0)).collect();
+
+        unsafe {
+            if user32::OpenClipboard(self.window.0) == 0 {
+                return;
+            }
+            user32::EmptyClipboard();
+
+            let memory = kernel32::GlobalAlloc(winapi::GMEM_MOVEABLE,
+                                               (text.len() * mem::size_of::<u16>()) as winapi::SIZE_T);
+            if !memory.is_null() {
+                let dest = kernel32::GlobalLock(memory) as *mut u16;
+                ptr::copy_nonoverlapping(text.as_ptr(), dest, text.len());
+                kernel32::GlobalUnlock(memory);
+
+                // on success, the memory belongs to the system
+                if user32::SetClipboardData(winapi::CF_UNICODETEXT, memory).is_null() {
+                    kernel32::GlobalFree(memory);
+                }
+            }
+
+            user32::CloseClipboard();
+        }
+    }
+
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        unsafe {
+            if user32::OpenClipboard(self.window.0) == 0 {
+                return None;
+            }
+
+            let memory = user32::GetClipboardData(winapi::CF_UNICODETEXT);
+            let source = if memory.is_null() {
+                ptr::null()
+            } else {
+                kernel32::GlobalLock(memory) as *const u16
+            };
+
+            let text = if source.is_null() {
+                None
+            } else {
+                let len = (0..).take_while(|&i| *source.offset(i) != 0).count();
+                let text = String::from_utf16_lossy(slice::from_raw_parts(source, len));
+                kernel32::GlobalUnlock(memory);
+                Some(text)
+            };
+
+            user32::CloseClipboard();
+            text
+        }
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, size: Option<(u32, u32)>) {
+        self.window_state.lock().unwrap().attributes.min_dimensions = size;
+        self.apply_size_constraints();
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, size: Option<(u32, u32)>) {
+        self.window_state.lock().unwrap().attributes.max_dimensions = size;
+        self.apply_size_constraints();
+    }
+
+    /// Moves the window without changing its size or position, which makes Windows send
+    /// `WM_GETMINMAXINFO` and resize the window if it doesn't satisfy the new constraints.
+    fn apply_size_constraints(&self) {
+        unsafe {
+            let mut rect: winapi::RECT = mem::zeroed();
+            if user32::GetWindowRect(self.window.0, &mut rect) == 0 {
+                return;
+            }
+
+            user32::SetWindowPos(self.window.0, ptr::null_mut(), 0, 0,
+                                 rect.right - rect.left, rect.bottom - rect.top,
+                                 winapi::SWP_NOZORDER | winapi::SWP_NOMOVE |
+                                 winapi::SWP_NOACTIVATE);
+        }
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
         1.0
     }
 
+    /// See the docs in the crate root file.
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        let (x, y) = match self.get_cursor_screen_position() {
+            Some(position) => position,
+            None => return None
+        };
+
+        let mut point = winapi::POINT { x: x, y: y };
+        if unsafe { user32::ScreenToClient(self.window.0, &mut point) } == 0 {
+            return None;
+        }
+        Some((point.x, point.y))
+    }
+
+    /// See the docs in the crate root file.
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        let mut point = winapi::POINT { x: 0, y: 0 };
+        if unsafe { user32::GetCursorPos(&mut point) } == 0 {
+            return None;
+        }
+        Some((point.x, point.y))
+    }
+
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        match self.context {
+            Context::Wgl(ref c) => c.set_swap_interval(interval),
+            Context::Egl(ref c) => c.set_swap_interval(interval),
+        }
+    }
+
     pub fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), ()> {
         let mut point = winapi::POINT {
             x: x,
@@ -428,7 +992,11 @@ impl<'a> Iterator for PollEventsIterator<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Event> {
-        self.window.events_receiver.try_recv().ok()
+        let event = self.window.events_receiver.try_recv().ok();
+        if event.is_some() {
+            self.window.queued_events.fetch_sub(1, Ordering::SeqCst);
+        }
+        event
     }
 }
 
@@ -441,7 +1009,11 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Event> {
-        self.window.events_receiver.recv().ok()
+        let event = self.window.events_receiver.recv().ok();
+        if event.is_some() {
+            self.window.queued_events.fetch_sub(1, Ordering::SeqCst);
+        }
+        event
     }
 }
 
@@ -2,10 +2,24 @@ use winapi;
 use user32;
 
 use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Read;
 use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+use std::slice;
 
 use native_monitor::NativeMonitorId;
 
+#[link(name = "gdi32")]
+extern "system" {
+    fn CreateDCW(driver: winapi::LPCWSTR, device: winapi::LPCWSTR, output: winapi::LPCWSTR,
+                 init_data: *const winapi::DEVMODEW) -> winapi::HDC;
+    fn DeleteDC(hdc: winapi::HDC) -> winapi::BOOL;
+    fn GetICMProfileW(hdc: winapi::HDC, size: *mut winapi::DWORD,
+                      filename: winapi::LPWSTR) -> winapi::BOOL;
+}
+
 /// Win32 implementation of the main `MonitorId` object.
 #[derive(Clone)]
 pub struct MonitorId {
@@ -15,6 +29,9 @@ pub struct MonitorId {
     /// The system name of the monitor.
     monitor_name: String,
 
+    /// The path of the device interface of the monitor, which identifies it in SetupAPI.
+    interface_path: Vec<winapi::WCHAR>,
+
     /// Name to give to the user.
     readable_name: String,
 
@@ -37,6 +54,7 @@ pub struct MonitorId {
 struct DeviceEnumerator {
     parent_device: *const winapi::WCHAR,
     current_index: u32,
+    flags: winapi::DWORD,
 }
 
 impl DeviceEnumerator {
@@ -44,14 +62,17 @@ impl DeviceEnumerator {
         use std::ptr;
         DeviceEnumerator {
             parent_device: ptr::null(),
-            current_index: 0
+            current_index: 0,
+            flags: 0,
         }
     }
 
+    /// The `DeviceID` of the monitors is the path of their device interface.
     fn monitors(adapter_name: *const winapi::WCHAR) -> DeviceEnumerator {
         DeviceEnumerator {
             parent_device: adapter_name,
-            current_index: 0
+            current_index: 0,
+            flags: EDD_GET_DEVICE_INTERFACE_NAME,
         }
     }
 }
@@ -65,7 +86,7 @@ impl Iterator for DeviceEnumerator {
             output.cb = mem::size_of::<winapi::DISPLAY_DEVICEW>() as winapi::DWORD;
 
             if unsafe { user32::EnumDisplayDevicesW(self.parent_device,
-                self.current_index as winapi::DWORD, &mut output, 0) } == 0
+                self.current_index as winapi::DWORD, &mut output, self.flags) } == 0
             {
                 // the device doesn't exist, which means we have finished enumerating
                 break;
@@ -92,6 +113,122 @@ fn wchar_as_string(wchar: &[winapi::WCHAR]) -> String {
         .to_string()
 }
 
+/// Asks `EnumDisplayDevicesW` for the path of the device interface instead of the device ID.
+const EDD_GET_DEVICE_INTERFACE_NAME: winapi::DWORD = 0x00000001;
+
+/// `{E6F07B5F-EE97-4A90-B076-33F57BF4EAA7}`
+const GUID_DEVINTERFACE_MONITOR: winapi::GUID = winapi::GUID {
+    Data1: 0xe6f07b5f,
+    Data2: 0xee97,
+    Data3: 0x4a90,
+    Data4: [0xb0, 0x76, 0x33, 0xf5, 0x7b, 0xf4, 0xea, 0xa7],
+};
+
+const DIGCF_PRESENT: winapi::DWORD = 0x00000002;
+const DIGCF_DEVICEINTERFACE: winapi::DWORD = 0x00000010;
+const DICS_FLAG_GLOBAL: winapi::DWORD = 0x00000001;
+const DIREG_DEV: winapi::DWORD = 0x00000001;
+const KEY_READ: winapi::DWORD = 0x00020019;
+const INVALID_HANDLE_VALUE: HDevInfo = -1isize as HDevInfo;
+
+/// `SP_DEVICE_INTERFACE_DETAIL_DATA_W::cbSize`, which is the size of the fixed part of the
+/// structure: SetupAPI packs its structures on 32 bits.
+#[cfg(target_pointer_width = "64")]
+const DEVICE_INTERFACE_DETAIL_SIZE: winapi::DWORD = 8;
+#[cfg(not(target_pointer_width = "64"))]
+const DEVICE_INTERFACE_DETAIL_SIZE: winapi::DWORD = 6;
+
+type HDevInfo = *mut c_void;
+
+#[repr(C)]
+struct SpDeviceInterfaceData {
+    cb_size: winapi::DWORD,
+    interface_class_guid: winapi::GUID,
+    flags: winapi::DWORD,
+    reserved: winapi::ULONG_PTR,
+}
+
+#[repr(C)]
+struct SpDevinfoData {
+    cb_size: winapi::DWORD,
+    class_guid: winapi::GUID,
+    dev_inst: winapi::DWORD,
+    reserved: winapi::ULONG_PTR,
+}
+
+#[link(name = "setupapi")]
+extern "system" {
+    fn SetupDiGetClassDevsW(class_guid: *const winapi::GUID, enumerator: winapi::LPCWSTR,
+                            parent: winapi::HWND, flags: winapi::DWORD) -> HDevInfo;
+    fn SetupDiEnumDeviceInterfaces(devices: HDevInfo, device: *mut SpDevinfoData,
+                                   class_guid: *const winapi::GUID, index: winapi::DWORD,
+                                   interface: *mut SpDeviceInterfaceData) -> winapi::BOOL;
+    fn SetupDiGetDeviceInterfaceDetailW(devices: HDevInfo,
+                                        interface: *mut SpDeviceInterfaceData,
+                                        detail: *mut c_void, size: winapi::DWORD,
+                                        required_size: *mut winapi::DWORD,
+                                        device: *mut SpDevinfoData) -> winapi::BOOL;
+    fn SetupDiOpenDevRegKey(devices: HDevInfo, device: *mut SpDevinfoData,
+                            scope: winapi::DWORD, hw_profile: winapi::DWORD,
+                            key_type: winapi::DWORD, access: winapi::DWORD) -> winapi::HKEY;
+    fn SetupDiDestroyDeviceInfoList(devices: HDevInfo) -> winapi::BOOL;
+}
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegQueryValueExW(key: winapi::HKEY, name: winapi::LPCWSTR, reserved: *mut winapi::DWORD,
+                        value_type: *mut winapi::DWORD, data: *mut u8,
+                        size: *mut winapi::DWORD) -> winapi::LONG;
+    fn RegCloseKey(key: winapi::HKEY) -> winapi::LONG;
+}
+
+/// Returns the path of a device interface, and fills the information about its device.
+unsafe fn get_interface_path(devices: HDevInfo, interface: *mut SpDeviceInterfaceData,
+                             device: *mut SpDevinfoData) -> Option<String>
+{
+    let mut size = 0;
+    SetupDiGetDeviceInterfaceDetailW(devices, interface, ptr::null_mut(), 0, &mut size,
+                                     ptr::null_mut());
+    if (size as usize) < DEVICE_INTERFACE_DETAIL_SIZE as usize {
+        return None;
+    }
+
+    // `DWORD`s keep the alignment of the structure
+    let mut detail = vec![0 as winapi::DWORD; (size as usize + 3) / 4];
+    *detail.as_mut_ptr() = DEVICE_INTERFACE_DETAIL_SIZE;
+    if SetupDiGetDeviceInterfaceDetailW(devices, interface, detail.as_mut_ptr() as *mut _, size,
+                                        ptr::null_mut(), device) == 0
+    {
+        return None;
+    }
+
+    // the path follows `cbSize`, and is null-terminated
+    let path = (detail.as_ptr() as *const u8).offset(4) as *const winapi::WCHAR;
+    let len = (size as usize - 4) / 2;
+    Some(wchar_as_string(slice::from_raw_parts(path, len)))
+}
+
+/// Reads a binary value of a registry key.
+unsafe fn read_registry_value(key: winapi::HKEY, name: &str) -> Option<Vec<u8>> {
+    let name: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+
+    let mut size = 0;
+    if RegQueryValueExW(key, name.as_ptr(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut(),
+                        &mut size) != 0 || size == 0
+    {
+        return None;
+    }
+
+    let mut data = vec![0u8; size as usize];
+    if RegQueryValueExW(key, name.as_ptr(), ptr::null_mut(), ptr::null_mut(),
+                        data.as_mut_ptr(), &mut size) != 0
+    {
+        return None;
+    }
+    data.truncate(size as usize);
+    Some(data)
+}
+
 /// Win32 implementation of the main `get_available_monitors` function.
 pub fn get_available_monitors() -> VecDeque<MonitorId> {
     // return value
@@ -123,6 +260,7 @@ pub fn get_available_monitors() -> VecDeque<MonitorId> {
             result.push_back(MonitorId {
                 adapter_name: adapter.DeviceName,
                 monitor_name: wchar_as_string(&monitor.DeviceName),
+                interface_path: monitor.DeviceID.to_vec(),
                 readable_name: wchar_as_string(&monitor.DeviceString),
                 flags: monitor.StateFlags,
                 primary: (adapter.StateFlags & winapi::DISPLAY_DEVICE_PRIMARY_DEVICE) != 0 &&
@@ -169,6 +307,92 @@ impl MonitorId {
         self.dimensions
     }
 
+    /// See the docs if the crate root file.
+    ///
+    /// Reads the `EDID` value of the registry key of the monitor, which is found through
+    /// SetupAPI.
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        let path = wchar_as_string(&self.interface_path).to_lowercase();
+        if path.is_empty() {
+            return None;
+        }
+
+        unsafe {
+            let devices = SetupDiGetClassDevsW(&GUID_DEVINTERFACE_MONITOR, ptr::null(),
+                                               ptr::null_mut(),
+                                               DIGCF_PRESENT | DIGCF_DEVICEINTERFACE);
+            if devices == INVALID_HANDLE_VALUE {
+                return None;
+            }
+
+            let mut result = None;
+            let mut index = 0;
+            loop {
+                let mut interface: SpDeviceInterfaceData = mem::zeroed();
+                interface.cb_size = mem::size_of::<SpDeviceInterfaceData>() as winapi::DWORD;
+                if SetupDiEnumDeviceInterfaces(devices, ptr::null_mut(), &GUID_DEVINTERFACE_MONITOR,
+                                               index, &mut interface) == 0
+                {
+                    break;
+                }
+                index += 1;
+
+                let mut device: SpDevinfoData = mem::zeroed();
+                device.cb_size = mem::size_of::<SpDevinfoData>() as winapi::DWORD;
+                match get_interface_path(devices, &mut interface, &mut device) {
+                    Some(ref p) if p.to_lowercase() == path => (),
+                    _ => continue,
+                }
+
+                let key = SetupDiOpenDevRegKey(devices, &mut device, DICS_FLAG_GLOBAL, 0,
+                                               DIREG_DEV, KEY_READ);
+                if key as isize != INVALID_HANDLE_VALUE as isize {
+                    result = read_registry_value(key, "EDID");
+                    RegCloseKey(key);
+                }
+                break;
+            }
+
+            SetupDiDestroyDeviceInfoList(devices);
+            result
+        }
+    }
+
+    /// See the docs if the crate root file.
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        false
+    }
+
+    /// See the docs if the crate root file.
+    ///
+    /// The profile associated to the adapter is read from the file that WCS returns.
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        let path = unsafe {
+            let hdc = CreateDCW(self.adapter_name.as_ptr(), ptr::null(), ptr::null(), ptr::null());
+            if hdc.is_null() {
+                return None;
+            }
+
+            // the first call returns the length of the path, including the null terminator
+            let mut size = 0;
+            GetICMProfileW(hdc, &mut size, ptr::null_mut());
+            let mut path = vec![0 as winapi::WCHAR; size as usize];
+            let success = size != 0 && GetICMProfileW(hdc, &mut size, path.as_mut_ptr()) != 0;
+            DeleteDC(hdc);
+            if !success {
+                return None;
+            }
+            wchar_as_string(&path)
+        };
+
+        let mut profile = Vec::new();
+        match File::open(path).and_then(|mut file| file.read_to_end(&mut profile)) {
+            Ok(_) => Some(profile),
+            Err(_) => None,
+        }
+    }
+
     /// This is a Win32-only function for `MonitorId` that returns the system name of the adapter
     /// device.
     #[inline]
@@ -183,4 +407,27 @@ impl MonitorId {
     pub fn get_position(&self) -> (u32, u32) {
         self.position
     }
+
+    /// Returns the part of the monitor that is not covered by the taskbar and the docked
+    /// toolbars, as `(x, y, width, height)` in desktop coordinates.
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        // the position is negative for the monitors that are on the left of the primary one
+        let (x, y) = (self.position.0 as i32, self.position.1 as i32);
+        let (width, height) = self.dimensions;
+
+        unsafe {
+            let center = winapi::POINT { x: x + width as i32 / 2, y: y + height as i32 / 2 };
+            let monitor = user32::MonitorFromPoint(center, winapi::MONITOR_DEFAULTTONEAREST);
+
+            let mut monitor_info: winapi::MONITORINFO = mem::zeroed();
+            monitor_info.cbSize = mem::size_of::<winapi::MONITORINFO>() as winapi::DWORD;
+            if user32::GetMonitorInfoW(monitor, &mut monitor_info) == 0 {
+                return Some((x, y, width, height));
+            }
+
+            let work = monitor_info.rcWork;
+            Some((work.left, work.top, (work.right - work.left) as u32,
+                  (work.bottom - work.top) as u32))
+        }
+    }
 }
@@ -0,0 +1,154 @@
+//! Custom images for the taskbar thumbnail and the alt-tab live preview.
+//!
+//! By default DWM captures the content of the window, which is black for most OpenGL windows.
+
+use std::mem;
+use std::ptr;
+
+use winapi;
+use gdi32;
+
+pub const WM_DWMSENDICONICTHUMBNAIL: winapi::UINT = 0x0323;
+pub const WM_DWMSENDICONICLIVEPREVIEWBITMAP: winapi::UINT = 0x0326;
+
+const DWMWA_FORCE_ICONIC_REPRESENTATION: winapi::DWORD = 7;
+const DWMWA_HAS_ICONIC_BITMAP: winapi::DWORD = 10;
+
+// these functions are too recent for the `dwmapi` crate
+#[link(name = "dwmapi")]
+extern "system" {
+    fn DwmSetWindowAttribute(hwnd: winapi::HWND, attribute: winapi::DWORD,
+                             value: winapi::LPCVOID, size: winapi::DWORD) -> winapi::HRESULT;
+    fn DwmSetIconicThumbnail(hwnd: winapi::HWND, hbmp: winapi::HBITMAP,
+                             flags: winapi::DWORD) -> winapi::HRESULT;
+    fn DwmSetIconicLivePreviewBitmap(hwnd: winapi::HWND, hbmp: winapi::HBITMAP,
+                                     client: *const winapi::POINT,
+                                     flags: winapi::DWORD) -> winapi::HRESULT;
+    fn DwmInvalidateIconicBitmaps(hwnd: winapi::HWND) -> winapi::HRESULT;
+}
+
+/// An RGBA image to show instead of the content of the window.
+#[derive(Clone)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Checks that the pixels cover the given dimensions.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Thumbnail, String> {
+        let len = match rgba_len(width, height) {
+            Some(len) => len,
+            None => return Err(format!("The thumbnail is too large: {}x{}", width, height)),
+        };
+        if pixels.len() < len {
+            return Err(format!("The thumbnail has {} bytes of pixels instead of {}",
+                               pixels.len(), len));
+        }
+
+        Ok(Thumbnail { width: width, height: height, pixels: pixels })
+    }
+}
+
+/// Returns the number of bytes of an RGBA image, or `None` if it doesn't fit in a `usize`.
+fn rgba_len(width: u32, height: u32) -> Option<usize> {
+    (width as usize).checked_mul(height as usize).and_then(|len| len.checked_mul(4))
+}
+
+/// Tells DWM whether it should ask us for the thumbnail and live preview instead of
+/// capturing the window.
+pub unsafe fn set_iconic_representation(hwnd: winapi::HWND, enabled: bool) {
+    let value: winapi::BOOL = if enabled { winapi::TRUE } else { winapi::FALSE };
+    let size = mem::size_of::<winapi::BOOL>() as winapi::DWORD;
+
+    DwmSetWindowAttribute(hwnd, DWMWA_FORCE_ICONIC_REPRESENTATION,
+                          &value as *const _ as winapi::LPCVOID, size);
+    DwmSetWindowAttribute(hwnd, DWMWA_HAS_ICONIC_BITMAP, &value as *const _ as winapi::LPCVOID,
+                          size);
+
+    if enabled {
+        DwmInvalidateIconicBitmaps(hwnd);
+    }
+}
+
+/// Answers a `WM_DWMSENDICONICTHUMBNAIL` message. The image must not be larger than the
+/// maximum size given by DWM.
+pub unsafe fn send_thumbnail(hwnd: winapi::HWND, thumbnail: &Thumbnail, max_width: u32,
+                             max_height: u32) -> Result<(), String>
+{
+    let bitmap = try!(create_bitmap(thumbnail, max_width, max_height));
+    DwmSetIconicThumbnail(hwnd, bitmap, 0);
+    gdi32::DeleteObject(bitmap as winapi::HGDIOBJ);
+    Ok(())
+}
+
+/// Answers a `WM_DWMSENDICONICLIVEPREVIEWBITMAP` message. The image is scaled to the size of
+/// the client area.
+pub unsafe fn send_live_preview(hwnd: winapi::HWND, thumbnail: &Thumbnail, width: u32,
+                                height: u32) -> Result<(), String>
+{
+    let bitmap = try!(create_bitmap(thumbnail, width, height));
+    DwmSetIconicLivePreviewBitmap(hwnd, bitmap, ptr::null(), 0);
+    gdi32::DeleteObject(bitmap as winapi::HGDIOBJ);
+    Ok(())
+}
+
+/// Creates a 32 bits premultiplied BGRA bitmap containing the thumbnail, scaled to fit in the
+/// given dimensions while keeping its aspect ratio.
+unsafe fn create_bitmap(thumbnail: &Thumbnail, max_width: u32, max_height: u32)
+                        -> Result<winapi::HBITMAP, String>
+{
+    if thumbnail.width == 0 || thumbnail.height == 0 || max_width == 0 || max_height == 0 {
+        return Err("The thumbnail or the requested bitmap is empty".to_owned());
+    }
+    match rgba_len(thumbnail.width, thumbnail.height) {
+        Some(len) if thumbnail.pixels.len() >= len => (),
+        _ => return Err("The pixels of the thumbnail don't cover its dimensions".to_owned()),
+    }
+
+    let scale = (max_width as f32 / thumbnail.width as f32)
+                    .min(max_height as f32 / thumbnail.height as f32);
+    let width = ((thumbnail.width as f32 * scale) as u32).max(1);
+    let height = ((thumbnail.height as f32 * scale) as u32).max(1);
+    let len = match rgba_len(width, height) {
+        Some(len) if width <= i32::max_value() as u32 && height <= i32::max_value() as u32 => len,
+        _ => return Err(format!("The bitmap is too large: {}x{}", width, height)),
+    };
+
+    let mut info: winapi::BITMAPINFO = mem::zeroed();
+    info.bmiHeader.biSize = mem::size_of::<winapi::BITMAPINFOHEADER>() as winapi::DWORD;
+    info.bmiHeader.biWidth = width as winapi::LONG;
+    info.bmiHeader.biHeight = -(height as winapi::LONG);      // top-down
+    info.bmiHeader.biPlanes = 1;
+    info.bmiHeader.biBitCount = 32;
+    info.bmiHeader.biCompression = winapi::BI_RGB;
+
+    let mut bits: *mut winapi::c_void = ptr::null_mut();
+    let bitmap = gdi32::CreateDIBSection(ptr::null_mut(), &info, winapi::DIB_RGB_COLORS,
+                                         &mut bits, ptr::null_mut(), 0);
+    if bitmap.is_null() || bits.is_null() {
+        if !bitmap.is_null() {
+            gdi32::DeleteObject(bitmap as winapi::HGDIOBJ);
+        }
+        return Err(format!("CreateDIBSection function failed: {}",
+                           ::std::io::Error::last_os_error()));
+    }
+
+    let dest = ::std::slice::from_raw_parts_mut(bits as *mut u8, len);
+    for y in 0 .. height as usize {
+        let src_y = y * thumbnail.height as usize / height as usize;
+        for x in 0 .. width as usize {
+            let src_x = x * thumbnail.width as usize / width as usize;
+            let src = &thumbnail.pixels[(src_y * thumbnail.width as usize + src_x) * 4 ..];
+            let dst = &mut dest[(y * width as usize + x) * 4 ..];
+            let alpha = src[3] as u32;
+            dst[0] = (src[2] as u32 * alpha / 255) as u8;
+            dst[1] = (src[1] as u32 * alpha / 255) as u8;
+            dst[2] = (src[0] as u32 * alpha / 255) as u8;
+            dst[3] = src[3];
+        }
+    }
+
+    Ok(bitmap)
+}
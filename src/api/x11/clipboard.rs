@@ -0,0 +1,389 @@
+//! Reading and writing the clipboard, through the `CLIPBOARD` selection.
+//!
+//! See the ICCCM, section 2, for the specification of selections. Large transfers are split with
+//! the `INCR` mechanism in both directions.
+//!
+//! When the owner is another window of the process, its content is read directly: the owner may
+//! be served by the very thread that is waiting for the answer.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::mem;
+use std::ptr;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+
+use libc::{self, c_char, c_int, c_long};
+
+use super::ffi;
+use super::XConnection;
+
+/// How long to wait for the owner of the clipboard to answer.
+const TIMEOUT_MS: u64 = 1000;
+
+lazy_static! {
+    /// The text that the windows of the process own, in UTF-8.
+    static ref OWNED: Mutex<HashMap<ffi::Window, String>> = Mutex::new(HashMap::new());
+}
+
+/// The content of the clipboard, while we own it.
+pub struct Clipboard {
+    display: Arc<XConnection>,
+    window: ffi::Window,
+    /// The transfers of large contents that are in progress
+    transfers: Vec<IncrTransfer>,
+}
+
+struct IncrTransfer {
+    requestor: ffi::Window,
+    property: ffi::Atom,
+    ty: ffi::Atom,
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl Clipboard {
+    #[inline]
+    pub fn new(display: &Arc<XConnection>, window: ffi::Window) -> Clipboard {
+        Clipboard {
+            display: display.clone(),
+            window: window,
+            transfers: Vec::new(),
+        }
+    }
+
+    /// Takes the ownership of the clipboard. The content is served to the other clients when
+    /// they request it, which requires the events of the window to be processed.
+    pub fn set(&mut self, text: &str, time: ffi::Time) {
+        let clipboard = self.display.get_atom("CLIPBOARD");
+        unsafe {
+            (self.display.xlib.XSetSelectionOwner)(self.display.display, clipboard, self.window,
+                                                   time);
+            if (self.display.xlib.XGetSelectionOwner)(self.display.display, clipboard) != self.window {
+                // another client took the clipboard at a later time
+                OWNED.lock().unwrap().remove(&self.window);
+                return;
+            }
+        }
+        OWNED.lock().unwrap().insert(self.window, text.to_owned());
+    }
+
+    /// Returns the content of the clipboard, as text.
+    ///
+    /// This blocks until the owner of the clipboard answers, or at most one second.
+    pub fn get(&self) -> Option<String> {
+        let owner = unsafe {
+            (self.display.xlib.XGetSelectionOwner)(self.display.display,
+                                                   self.display.get_atom("CLIPBOARD"))
+        };
+        if let Some(owned) = OWNED.lock().unwrap().get(&owner) {
+            return Some(owned.clone());
+        }
+
+        let utf8_string = self.display.get_atom("UTF8_STRING");
+        if let Some(data) = self.convert(utf8_string) {
+            return Some(String::from_utf8_lossy(&data).into_owned());
+        }
+
+        // old clients may only provide Latin-1
+        self.convert(ffi::XA_STRING).map(|data| data.into_iter().map(|b| b as char).collect())
+    }
+
+    /// Handles a `SelectionRequest` event: another client wants the content of the clipboard.
+    pub fn handle_request(&mut self, request: &ffi::XSelectionRequestEvent) {
+        let display = self.display.clone();
+
+        // obsolete clients don't specify a property
+        let property = if request.property == 0 { request.target } else { request.property };
+
+        let targets = display.get_atom("TARGETS");
+        let utf8_string = display.get_atom("UTF8_STRING");
+        let text = display.get_atom("TEXT");
+
+        let owned = OWNED.lock().unwrap().get(&self.window).cloned();
+        let accepted = match owned {
+            Some(owned) if request.owner == self.window &&
+                           request.selection == display.get_atom("CLIPBOARD") => {
+                if request.target == targets {
+                    let supported = [targets, utf8_string, text, ffi::XA_STRING];
+                    unsafe {
+                        (display.xlib.XChangeProperty)(display.display, request.requestor, property,
+                                                       ffi::XA_ATOM, 32, ffi::PropModeReplace,
+                                                       supported.as_ptr() as *const _,
+                                                       supported.len() as c_int);
+                    }
+                    true
+                } else if request.target == utf8_string || request.target == text {
+                    let data = owned.as_bytes().to_vec();
+                    self.send_data(request.requestor, property, utf8_string, data);
+                    true
+                } else if request.target == ffi::XA_STRING {
+                    let data = owned.chars().map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+                                    .collect();
+                    self.send_data(request.requestor, property, ffi::XA_STRING, data);
+                    true
+                } else {
+                    false
+                }
+            },
+            _ => false
+        };
+
+        let notify_event = ffi::XSelectionEvent {
+            type_: ffi::SelectionNotify,
+            serial: 0,
+            send_event: ffi::True,
+            display: display.display,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property: if accepted { property } else { 0 },
+            time: request.time,
+        };
+        let mut x_event = ffi::XEvent::from(notify_event);
+
+        // the requestor may already be gone
+        display.push_error_trap();
+        unsafe {
+            (display.xlib.XSendEvent)(display.display, request.requestor, ffi::False,
+                                      ffi::NoEventMask, &mut x_event);
+            (display.xlib.XFlush)(display.display);
+        }
+        let _ = display.pop_error_trap();
+    }
+
+    /// Handles a `SelectionClear` event: another client took the clipboard.
+    #[inline]
+    pub fn handle_clear(&mut self, selection: ffi::Atom) {
+        if selection == self.display.get_atom("CLIPBOARD") {
+            OWNED.lock().unwrap().remove(&self.window);
+        }
+    }
+
+    /// Handles the deletion of a property of another window, which means that the requestor of
+    /// an `INCR` transfer is ready for the next chunk.
+    pub fn handle_property_deleted(&mut self, window: ffi::Window, property: ffi::Atom) {
+        let index = match self.transfers.iter().position(|t| t.requestor == window &&
+                                                              t.property == property)
+        {
+            Some(i) => i,
+            None => return
+        };
+
+        let chunk_size = self.chunk_size();
+        let display = self.display.clone();
+        let finished = {
+            let transfer = &mut self.transfers[index];
+            let end = cmp::min(transfer.offset + chunk_size, transfer.data.len());
+
+            // the last chunk is empty
+            display.push_error_trap();
+            unsafe {
+                (display.xlib.XChangeProperty)(display.display, window, property, transfer.ty, 8,
+                                               ffi::PropModeReplace,
+                                               transfer.data[transfer.offset..].as_ptr(),
+                                               (end - transfer.offset) as c_int);
+                (display.xlib.XFlush)(display.display);
+            }
+            let failed = display.pop_error_trap().is_err();
+
+            let finished = transfer.offset == end;
+            transfer.offset = end;
+            finished || failed
+        };
+
+        if finished {
+            self.transfers.remove(index);
+        }
+    }
+
+    /// Writes the data in the property of the requestor, or starts an `INCR` transfer if the
+    /// data is too large for a single request.
+    fn send_data(&mut self, requestor: ffi::Window, property: ffi::Atom, ty: ffi::Atom,
+                 data: Vec<u8>)
+    {
+        let display = self.display.clone();
+
+        if data.len() <= self.chunk_size() {
+            unsafe {
+                (display.xlib.XChangeProperty)(display.display, requestor, property, ty, 8,
+                                               ffi::PropModeReplace, data.as_ptr(),
+                                               data.len() as c_int);
+            }
+            return;
+        }
+
+        // the requestor deletes the property every time it has read a chunk, and may be one of
+        // our windows, whose other events must still be selected
+        let size = data.len() as c_long;
+        display.push_error_trap();
+        display.add_event_mask(requestor, ffi::PropertyChangeMask);
+        unsafe {
+            (display.xlib.XChangeProperty)(display.display, requestor, property,
+                                           display.get_atom("INCR"), 32, ffi::PropModeReplace,
+                                           &size as *const c_long as *const _, 1);
+        }
+        if display.pop_error_trap().is_err() {
+            // the requestor is already gone
+            return;
+        }
+
+        self.transfers.retain(|t| t.requestor != requestor || t.property != property);
+        self.transfers.push(IncrTransfer {
+            requestor: requestor,
+            property: property,
+            ty: ty,
+            data: data,
+            offset: 0,
+        });
+    }
+
+    /// Asks the owner of the clipboard to convert it to the given type, and waits for the
+    /// result.
+    fn convert(&self, ty: ffi::Atom) -> Option<Vec<u8>> {
+        let display = &self.display;
+        let clipboard = display.get_atom("CLIPBOARD");
+        let property = display.get_atom("GLUTIN_CLIPBOARD");
+
+        unsafe {
+            (display.xlib.XConvertSelection)(display.display, clipboard, ty, property, self.window,
+                                             ffi::CurrentTime);
+            (display.xlib.XFlush)(display.display);
+        }
+
+        let notify = match self.wait_event(is_selection_notify, clipboard) {
+            Some(event) => ffi::XSelectionEvent::from(event),
+            None => return None
+        };
+        if notify.property == 0 {
+            return None;
+        }
+
+        let (actual_type, data) = match self.read_property(property) {
+            Some(p) => p,
+            None => return None
+        };
+
+        if actual_type != display.get_atom("INCR") {
+            return if actual_type == ty { Some(data) } else { None };
+        }
+
+        // large contents are sent in chunks, the owner writes the next one every time the
+        // property is deleted, which `read_property` does, and the last chunk is empty
+        let mut result = Vec::new();
+        loop {
+            if self.wait_event(is_new_property_value, property).is_none() {
+                return None;
+            }
+
+            match self.read_property(property) {
+                Some((_, ref chunk)) if chunk.is_empty() => return Some(result),
+                Some((_, chunk)) => result.extend(chunk),
+                None => return None
+            }
+        }
+    }
+
+    /// Reads and deletes a property of the window. Returns its type and its content.
+    fn read_property(&self, property: ffi::Atom) -> Option<(ffi::Atom, Vec<u8>)> {
+        unsafe {
+            let mut actual_type = mem::zeroed();
+            let mut actual_format = mem::zeroed();
+            let mut nitems = mem::zeroed();
+            let mut bytes_after = mem::zeroed();
+            let mut data: *mut libc::c_uchar = ptr::null_mut();
+
+            (self.display.xlib.XGetWindowProperty)(self.display.display, self.window, property,
+                                                   0, c_long::max_value() / 4, ffi::True,
+                                                   ffi::AnyPropertyType as ffi::Atom,
+                                                   &mut actual_type, &mut actual_format,
+                                                   &mut nitems, &mut bytes_after, &mut data);
+
+            if data.is_null() {
+                return None;
+            }
+
+            let len = match actual_format {
+                8 => nitems as usize,
+                16 => nitems as usize * mem::size_of::<libc::c_short>(),
+                _ => nitems as usize * mem::size_of::<c_long>(),
+            };
+            let content = ::std::slice::from_raw_parts(data, len).to_vec();
+            (self.display.xlib.XFree)(data as *mut _);
+
+            Some((actual_type, content))
+        }
+    }
+
+    /// Waits for an event of the window that matches the predicate, without touching the other
+    /// events.
+    fn wait_event(&self, predicate: unsafe extern "C" fn(*mut ffi::Display, *mut ffi::XEvent,
+                                                          *mut c_char) -> ffi::Bool,
+                  atom: ffi::Atom) -> Option<ffi::XEvent>
+    {
+        let deadline = Instant::now() + Duration::from_millis(TIMEOUT_MS);
+        let mut arg = (self.window, atom);
+
+        loop {
+            let mut xev: ffi::XEvent = unsafe { mem::zeroed() };
+            let found = unsafe {
+                (self.display.xlib.XCheckIfEvent)(self.display.display, &mut xev,
+                                                  Some(predicate),
+                                                  &mut arg as *mut _ as *mut c_char)
+            };
+            if found != 0 {
+                return Some(xev);
+            }
+
+            if Instant::now() >= deadline {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// The maximum number of bytes that can be written in a single request.
+    fn chunk_size(&self) -> usize {
+        unsafe {
+            let extended = (self.display.xlib.XExtendedMaxRequestSize)(self.display.display);
+            let max = if extended > 0 {
+                extended
+            } else {
+                (self.display.xlib.XMaxRequestSize)(self.display.display)
+            };
+            // the size is in units of 4 bytes, and includes the header of the request
+            cmp::max(max as usize * 4, 1024) - 100
+        }
+    }
+}
+
+impl Drop for Clipboard {
+    #[inline]
+    fn drop(&mut self) {
+        OWNED.lock().unwrap().remove(&self.window);
+    }
+}
+
+unsafe extern "C" fn is_selection_notify(_: *mut ffi::Display, event: *mut ffi::XEvent,
+                                         arg: *mut c_char) -> ffi::Bool
+{
+    let &(window, selection) = &*(arg as *const (ffi::Window, ffi::Atom));
+    if (*event).get_type() != ffi::SelectionNotify {
+        return ffi::False;
+    }
+    let event = ffi::XSelectionEvent::from(*event);
+    (event.requestor == window && event.selection == selection) as ffi::Bool
+}
+
+unsafe extern "C" fn is_new_property_value(_: *mut ffi::Display, event: *mut ffi::XEvent,
+                                           arg: *mut c_char) -> ffi::Bool
+{
+    let &(window, property) = &*(arg as *const (ffi::Window, ffi::Atom));
+    if (*event).get_type() != ffi::PropertyNotify {
+        return ffi::False;
+    }
+    let event = ffi::XPropertyEvent::from(*event);
+    (event.window == window && event.atom == property &&
+     event.state == ffi::PropertyNewValue) as ffi::Bool
+}
@@ -0,0 +1,1078 @@
+//! Global menus, exported on the session bus with the `com.canonical.dbusmenu` protocol.
+//!
+//! The panel of Unity, and the global menu applets of the other desktops, show the menus of the
+//! windows that are registered with `com.canonical.AppMenu.Registrar`. When nobody owns this
+//! name, the menus are drawn in the window instead (see `menu.rs`).
+//!
+//! The menus are served by a thread of their own, which owns a private connection to the bus.
+//! The chosen items are sent to the window as client messages, so that they are reported by the
+//! event loop of the window like its other events.
+//!
+//! libdbus is loaded at runtime, since glutin doesn't need it otherwise.
+
+#![allow(non_camel_case_types)]
+
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{mem, ptr, thread};
+
+use libc::{self, c_char, c_int, c_uint, c_void};
+
+use Accelerator;
+use Menu;
+use MenuItem;
+
+use super::ffi;
+use super::WindowProxy;
+
+type DBusConnection = c_void;
+type DBusMessage = c_void;
+type dbus_bool_t = u32;
+
+#[repr(C)]
+struct DBusError {
+    name: *const c_char,
+    message: *const c_char,
+    // the five one-bit fields of libdbus
+    dummy: c_uint,
+    padding: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct DBusMessageIter {
+    dummy1: *mut c_void,
+    dummy2: *mut c_void,
+    dummy3: u32,
+    dummy4: c_int,
+    dummy5: c_int,
+    dummy6: c_int,
+    dummy7: c_int,
+    dummy8: c_int,
+    dummy9: c_int,
+    dummy10: c_int,
+    dummy11: c_int,
+    pad1: c_int,
+    pad2: *mut c_void,
+    pad3: *mut c_void,
+}
+
+#[repr(C)]
+struct DBusObjectPathVTable {
+    unregister_function: Option<extern "C" fn(*mut DBusConnection, *mut c_void)>,
+    message_function: Option<extern "C" fn(*mut DBusConnection, *mut DBusMessage,
+                                           *mut c_void) -> c_int>,
+    pad1: Option<extern "C" fn()>,
+    pad2: Option<extern "C" fn()>,
+    pad3: Option<extern "C" fn()>,
+    pad4: Option<extern "C" fn()>,
+}
+
+const DBUS_BUS_SESSION: c_int = 0;
+const DBUS_DISPATCH_DATA_REMAINS: c_int = 0;
+const DBUS_HANDLER_RESULT_HANDLED: c_int = 0;
+const DBUS_HANDLER_RESULT_NOT_YET_HANDLED: c_int = 1;
+const DBUS_MESSAGE_TYPE_METHOD_CALL: c_int = 1;
+
+const DBUS_TYPE_INVALID: c_int = 0;
+const DBUS_TYPE_INT32: c_int = 'i' as c_int;
+const DBUS_TYPE_UINT32: c_int = 'u' as c_int;
+const DBUS_TYPE_BOOLEAN: c_int = 'b' as c_int;
+const DBUS_TYPE_STRING: c_int = 's' as c_int;
+const DBUS_TYPE_OBJECT_PATH: c_int = 'o' as c_int;
+const DBUS_TYPE_ARRAY: c_int = 'a' as c_int;
+const DBUS_TYPE_VARIANT: c_int = 'v' as c_int;
+const DBUS_TYPE_STRUCT: c_int = 'r' as c_int;
+const DBUS_TYPE_DICT_ENTRY: c_int = 'e' as c_int;
+
+shared_library!(LibDbus, "libdbus-1.so.3",
+    pub fn dbus_threads_init_default() -> dbus_bool_t,
+    pub fn dbus_error_init(error: *mut DBusError),
+    pub fn dbus_error_free(error: *mut DBusError),
+    pub fn dbus_error_is_set(error: *const DBusError) -> dbus_bool_t,
+    pub fn dbus_bus_get_private(type_: c_int, error: *mut DBusError) -> *mut DBusConnection,
+    pub fn dbus_bus_name_has_owner(connection: *mut DBusConnection, name: *const c_char,
+                                   error: *mut DBusError) -> dbus_bool_t,
+    pub fn dbus_connection_set_exit_on_disconnect(connection: *mut DBusConnection,
+                                                  exit_on_disconnect: dbus_bool_t),
+    pub fn dbus_connection_close(connection: *mut DBusConnection),
+    pub fn dbus_connection_unref(connection: *mut DBusConnection),
+    pub fn dbus_connection_get_unix_fd(connection: *mut DBusConnection,
+                                       fd: *mut c_int) -> dbus_bool_t,
+    pub fn dbus_connection_read_write(connection: *mut DBusConnection,
+                                      timeout_milliseconds: c_int) -> dbus_bool_t,
+    pub fn dbus_connection_dispatch(connection: *mut DBusConnection) -> c_int,
+    pub fn dbus_connection_send(connection: *mut DBusConnection, message: *mut DBusMessage,
+                                serial: *mut u32) -> dbus_bool_t,
+    pub fn dbus_connection_flush(connection: *mut DBusConnection),
+    pub fn dbus_connection_register_object_path(connection: *mut DBusConnection,
+                                                path: *const c_char,
+                                                vtable: *const DBusObjectPathVTable,
+                                                user_data: *mut c_void) -> dbus_bool_t,
+    pub fn dbus_message_new_method_call(destination: *const c_char, path: *const c_char,
+                                        interface: *const c_char,
+                                        method: *const c_char) -> *mut DBusMessage,
+    pub fn dbus_message_new_method_return(method_call: *mut DBusMessage) -> *mut DBusMessage,
+    pub fn dbus_message_new_error(reply_to: *mut DBusMessage, error_name: *const c_char,
+                                  error_message: *const c_char) -> *mut DBusMessage,
+    pub fn dbus_message_new_signal(path: *const c_char, interface: *const c_char,
+                                   name: *const c_char) -> *mut DBusMessage,
+    pub fn dbus_message_unref(message: *mut DBusMessage),
+    pub fn dbus_message_get_type(message: *mut DBusMessage) -> c_int,
+    pub fn dbus_message_get_interface(message: *mut DBusMessage) -> *const c_char,
+    pub fn dbus_message_get_member(message: *mut DBusMessage) -> *const c_char,
+    pub fn dbus_message_iter_init(message: *mut DBusMessage,
+                                  iter: *mut DBusMessageIter) -> dbus_bool_t,
+    pub fn dbus_message_iter_init_append(message: *mut DBusMessage, iter: *mut DBusMessageIter),
+    pub fn dbus_message_iter_get_arg_type(iter: *mut DBusMessageIter) -> c_int,
+    pub fn dbus_message_iter_get_basic(iter: *mut DBusMessageIter, value: *mut c_void),
+    pub fn dbus_message_iter_next(iter: *mut DBusMessageIter) -> dbus_bool_t,
+    pub fn dbus_message_iter_recurse(iter: *mut DBusMessageIter, sub: *mut DBusMessageIter),
+    pub fn dbus_message_iter_append_basic(iter: *mut DBusMessageIter, type_: c_int,
+                                          value: *const c_void) -> dbus_bool_t,
+    pub fn dbus_message_iter_open_container(iter: *mut DBusMessageIter, type_: c_int,
+                                            contained_signature: *const c_char,
+                                            sub: *mut DBusMessageIter) -> dbus_bool_t,
+    pub fn dbus_message_iter_close_container(iter: *mut DBusMessageIter,
+                                             sub: *mut DBusMessageIter) -> dbus_bool_t,
+);
+
+// the library only contains function pointers
+unsafe impl Sync for LibDbus {}
+
+lazy_static! {
+    static ref LIBDBUS: Option<LibDbus> = LibDbus::open(&Path::new("libdbus-1.so.3")).ok();
+}
+
+const REGISTRAR_NAME: &'static str = "com.canonical.AppMenu.Registrar";
+const REGISTRAR_PATH: &'static str = "/com/canonical/AppMenu/Registrar";
+const DBUSMENU_INTERFACE: &'static str = "com.canonical.dbusmenu";
+const PROPERTIES_INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
+const INVALID_ARGS: &'static str = "org.freedesktop.DBus.Error.InvalidArgs";
+
+/// The menus of a window, exported on the session bus.
+pub struct GlobalMenu {
+    shared: Arc<Shared>,
+    thread: Option<thread::JoinHandle<()>>,
+    /// The read and write ends of the pipe that wakes the thread up
+    wakeup: [c_int; 2],
+}
+
+/// The state shared with the thread that serves the menus.
+struct Shared {
+    layout: Mutex<Layout>,
+    /// Set when the layout has been replaced and the clients must be told
+    layout_changed: AtomicBool,
+    stop: AtomicBool,
+}
+
+/// The private connection of a menu, which is only used by one thread at a time.
+struct Connection(*mut DBusConnection);
+
+unsafe impl Send for Connection {}
+
+impl GlobalMenu {
+    /// Exports the menus and registers the window with the registrar.
+    ///
+    /// Returns `None` if libdbus or the session bus is missing, or if nobody owns the name of
+    /// the registrar.
+    pub fn new(window: ffi::Window, proxy: WindowProxy, menus: &[Menu]) -> Option<GlobalMenu> {
+        let lib = match LIBDBUS.as_ref() {
+            Some(lib) => lib,
+            None => return None,
+        };
+
+        let path = format!("/com/canonical/menu/{:X}", window);
+        let shared = Arc::new(Shared {
+            layout: Mutex::new(Layout::new(menus, 1)),
+            layout_changed: AtomicBool::new(false),
+            stop: AtomicBool::new(false),
+        });
+
+        unsafe {
+            // other libraries of the process may use libdbus on other threads
+            (lib.dbus_threads_init_default)();
+
+            let mut error: DBusError = mem::zeroed();
+            (lib.dbus_error_init)(&mut error);
+            let connection = (lib.dbus_bus_get_private)(DBUS_BUS_SESSION, &mut error);
+            if connection.is_null() {
+                (lib.dbus_error_free)(&mut error);
+                return None;
+            }
+            // by default, libdbus exits the process when the bus goes away
+            (lib.dbus_connection_set_exit_on_disconnect)(connection, 0);
+
+            let registrar = CString::new(REGISTRAR_NAME).unwrap();
+            let has_registrar = (lib.dbus_bus_name_has_owner)(connection, registrar.as_ptr(),
+                                                              &mut error) != 0;
+            if (lib.dbus_error_is_set)(&error) != 0 || !has_registrar {
+                (lib.dbus_error_free)(&mut error);
+                (lib.dbus_connection_close)(connection);
+                (lib.dbus_connection_unref)(connection);
+                return None;
+            }
+
+            let mut wakeup = [0; 2];
+            if libc::pipe(wakeup.as_mut_ptr()) != 0 {
+                (lib.dbus_connection_close)(connection);
+                (lib.dbus_connection_unref)(connection);
+                return None;
+            }
+            for &fd in wakeup.iter() {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+
+            // the exporter is owned by the thread, which closes the connection before dropping
+            // it, so the pointer given to libdbus stays valid
+            let exporter = Box::new(Exporter {
+                lib: lib,
+                shared: shared.clone(),
+                proxy: proxy,
+            });
+            let c_path = CString::new(path.clone()).unwrap();
+            (lib.dbus_connection_register_object_path)(connection, c_path.as_ptr(), &VTABLE,
+                                                       &*exporter as *const Exporter as *mut _);
+
+            let message = new_method_call(lib, REGISTRAR_NAME, REGISTRAR_PATH, REGISTRAR_NAME,
+                                          "RegisterWindow");
+            {
+                let mut args = Writer::new(lib, message);
+                args.append_u32(window as u32);
+                args.append_object_path(&path);
+            }
+            (lib.dbus_connection_send)(connection, message, ptr::null_mut());
+            (lib.dbus_message_unref)(message);
+            (lib.dbus_connection_flush)(connection);
+
+            let connection = Connection(connection);
+            let thread_shared = shared.clone();
+            let read_fd = wakeup[0];
+            let thread = thread::spawn(move || {
+                serve(lib, connection, exporter, &thread_shared, read_fd, window as u32, &path)
+            });
+
+            Some(GlobalMenu {
+                shared: shared,
+                thread: Some(thread),
+                wakeup: wakeup,
+            })
+        }
+    }
+
+    /// Replaces the menus, and tells the clients that they changed.
+    pub fn set_menus(&self, menus: &[Menu]) {
+        {
+            let mut layout = self.shared.layout.lock().unwrap();
+            let revision = layout.revision.wrapping_add(1);
+            *layout = Layout::new(menus, revision);
+        }
+        self.shared.layout_changed.store(true, Ordering::SeqCst);
+        self.wake_up();
+    }
+
+    fn wake_up(&self) {
+        let byte = 1u8;
+        unsafe { libc::write(self.wakeup[1], &byte as *const u8 as *const _, 1) };
+    }
+}
+
+impl Drop for GlobalMenu {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::SeqCst);
+        self.wake_up();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+
+        unsafe {
+            libc::close(self.wakeup[0]);
+            libc::close(self.wakeup[1]);
+        }
+    }
+}
+
+/// The loop of the thread that serves the menus, until the menus are dropped or the bus goes
+/// away.
+fn serve(lib: &'static LibDbus, connection: Connection, exporter: Box<Exporter>, shared: &Shared,
+         wakeup_fd: c_int, window: u32, path: &str)
+{
+    let connection = connection.0;
+
+    unsafe {
+        let mut fd = -1;
+        (lib.dbus_connection_get_unix_fd)(connection, &mut fd);
+
+        loop {
+            while (lib.dbus_connection_dispatch)(connection) == DBUS_DISPATCH_DATA_REMAINS {}
+            (lib.dbus_connection_flush)(connection);
+
+            let mut fds = [
+                libc::pollfd { fd: fd, events: libc::POLLIN, revents: 0 },
+                libc::pollfd { fd: wakeup_fd, events: libc::POLLIN, revents: 0 },
+            ];
+            if libc::poll(fds.as_mut_ptr(), 2, -1) <= 0 {
+                continue;
+            }
+
+            if fds[1].revents & libc::POLLIN != 0 {
+                let mut buffer = [0u8; 64];
+                while libc::read(wakeup_fd, buffer.as_mut_ptr() as *mut _, buffer.len()) > 0 {}
+
+                if shared.stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if shared.layout_changed.swap(false, Ordering::SeqCst) {
+                    let revision = shared.layout.lock().unwrap().revision;
+                    send_layout_updated(lib, connection, path, revision);
+                }
+            }
+
+            if fds[0].revents != 0 && (lib.dbus_connection_read_write)(connection, 0) == 0 {
+                // disconnected from the bus
+                break;
+            }
+        }
+
+        let message = new_method_call(lib, REGISTRAR_NAME, REGISTRAR_PATH, REGISTRAR_NAME,
+                                      "UnregisterWindow");
+        Writer::new(lib, message).append_u32(window);
+        (lib.dbus_connection_send)(connection, message, ptr::null_mut());
+        (lib.dbus_message_unref)(message);
+        (lib.dbus_connection_flush)(connection);
+
+        (lib.dbus_connection_close)(connection);
+        (lib.dbus_connection_unref)(connection);
+    }
+
+    drop(exporter);
+}
+
+unsafe fn send_layout_updated(lib: &LibDbus, connection: *mut DBusConnection, path: &str,
+                              revision: u32)
+{
+    let path = CString::new(path).unwrap();
+    let interface = CString::new(DBUSMENU_INTERFACE).unwrap();
+    let name = CString::new("LayoutUpdated").unwrap();
+    let message = (lib.dbus_message_new_signal)(path.as_ptr(), interface.as_ptr(),
+                                                name.as_ptr());
+    if message.is_null() {
+        return;
+    }
+
+    {
+        let mut args = Writer::new(lib, message);
+        args.append_u32(revision);
+        // the whole tree has changed
+        args.append_i32(0);
+    }
+    (lib.dbus_connection_send)(connection, message, ptr::null_mut());
+    (lib.dbus_message_unref)(message);
+}
+
+unsafe fn new_method_call(lib: &LibDbus, destination: &str, path: &str, interface: &str,
+                          method: &str) -> *mut DBusMessage
+{
+    let destination = CString::new(destination).unwrap();
+    let path = CString::new(path).unwrap();
+    let interface = CString::new(interface).unwrap();
+    let method = CString::new(method).unwrap();
+    (lib.dbus_message_new_method_call)(destination.as_ptr(), path.as_ptr(), interface.as_ptr(),
+                                       method.as_ptr())
+}
+
+static VTABLE: DBusObjectPathVTable = DBusObjectPathVTable {
+    unregister_function: None,
+    message_function: Some(handle_message),
+    pad1: None,
+    pad2: None,
+    pad3: None,
+    pad4: None,
+};
+
+extern "C" fn handle_message(connection: *mut DBusConnection, message: *mut DBusMessage,
+                             user_data: *mut c_void) -> c_int
+{
+    ::api::panic::abort_on_panic("dbusmenu message handler", || unsafe {
+        let exporter = &*(user_data as *const Exporter);
+        exporter.handle(connection, message)
+    })
+}
+
+/// Answers the calls of the clients of the menus.
+struct Exporter {
+    lib: &'static LibDbus,
+    shared: Arc<Shared>,
+    proxy: WindowProxy,
+}
+
+impl Exporter {
+    unsafe fn handle(&self, connection: *mut DBusConnection, message: *mut DBusMessage) -> c_int {
+        let lib = self.lib;
+        if (lib.dbus_message_get_type)(message) != DBUS_MESSAGE_TYPE_METHOD_CALL {
+            return DBUS_HANDLER_RESULT_NOT_YET_HANDLED;
+        }
+
+        let interface = c_string((lib.dbus_message_get_interface)(message));
+        let member = match c_string((lib.dbus_message_get_member)(message)) {
+            Some(member) => member,
+            None => return DBUS_HANDLER_RESULT_NOT_YET_HANDLED,
+        };
+
+        let reply = (lib.dbus_message_new_method_return)(message);
+        if reply.is_null() {
+            return DBUS_HANDLER_RESULT_NOT_YET_HANDLED;
+        }
+
+        let result = {
+            let layout = self.shared.layout.lock().unwrap();
+            let mut args = Reader::new(lib, message);
+            let mut out = Writer::new(lib, reply);
+
+            // the interface is optional in method calls
+            match (interface.as_ref().map(|i| &i[..]), &member[..]) {
+                (Some(DBUSMENU_INTERFACE), _) | (None, _) if is_dbusmenu_method(&member) => {
+                    self.call_dbusmenu(&layout, &member, &mut args, &mut out)
+                },
+                (Some(PROPERTIES_INTERFACE), "Get") => get_property(&mut args, &mut out),
+                (Some(PROPERTIES_INTERFACE), "GetAll") => get_all_properties(&mut args, &mut out),
+                _ => {
+                    (lib.dbus_message_unref)(reply);
+                    return DBUS_HANDLER_RESULT_NOT_YET_HANDLED;
+                },
+            }
+        };
+
+        let reply = match result {
+            Ok(()) => reply,
+            Err(()) => {
+                (lib.dbus_message_unref)(reply);
+                let name = CString::new(INVALID_ARGS).unwrap();
+                let text = CString::new(format!("Invalid arguments for {}", member)).unwrap();
+                (lib.dbus_message_new_error)(message, name.as_ptr(), text.as_ptr())
+            },
+        };
+
+        if !reply.is_null() {
+            (lib.dbus_connection_send)(connection, reply, ptr::null_mut());
+            (lib.dbus_message_unref)(reply);
+        }
+        DBUS_HANDLER_RESULT_HANDLED
+    }
+
+    unsafe fn call_dbusmenu(&self, layout: &Layout, member: &str, args: &mut Reader,
+                            out: &mut Writer) -> Result<(), ()>
+    {
+        match member {
+            "GetLayout" => {
+                let parent = try!(args.read_i32().ok_or(()));
+                let depth = try!(args.read_i32().ok_or(()));
+                if parent < 0 || parent as usize >= layout.nodes.len() {
+                    return Err(());
+                }
+                out.append_u32(layout.revision);
+                layout.write_node(out, parent, depth);
+                Ok(())
+            },
+            "GetGroupProperties" => {
+                let mut args = try!(args.recurse().ok_or(()));
+                let mut ids = Vec::new();
+                while let Some(id) = args.read_i32() {
+                    ids.push(id);
+                }
+                // no ID means all the items
+                if ids.is_empty() {
+                    ids = (0..layout.nodes.len() as i32).collect();
+                }
+                ids.retain(|&id| id >= 0 && (id as usize) < layout.nodes.len());
+
+                out.open(DBUS_TYPE_ARRAY, Some("(ia{sv})"), |out| {
+                    for &id in ids.iter() {
+                        out.open(DBUS_TYPE_STRUCT, None, |out| {
+                            out.append_i32(id);
+                            layout.write_properties(out, id);
+                        });
+                    }
+                });
+                Ok(())
+            },
+            "GetProperty" => {
+                let id = try!(args.read_i32().ok_or(()));
+                let name = try!(args.read_string().ok_or(()));
+                if id < 0 || id as usize >= layout.nodes.len() {
+                    return Err(());
+                }
+                match layout.properties(id).into_iter().find(|&(n, _)| n == name) {
+                    Some((_, value)) => {
+                        value.write(out);
+                        Ok(())
+                    },
+                    None => Err(()),
+                }
+            },
+            "Event" => {
+                let id = try!(args.read_i32().ok_or(()));
+                let event = try!(args.read_string().ok_or(()));
+                self.event(layout, id, &event);
+                Ok(())
+            },
+            "EventGroup" => {
+                let mut events = try!(args.recurse().ok_or(()));
+                let mut unknown = Vec::new();
+                while let Some(mut event) = events.recurse() {
+                    let id = try!(event.read_i32().ok_or(()));
+                    let name = try!(event.read_string().ok_or(()));
+                    if !self.event(layout, id, &name) {
+                        unknown.push(id);
+                    }
+                }
+                out.open(DBUS_TYPE_ARRAY, Some("i"), |out| {
+                    for &id in unknown.iter() {
+                        out.append_i32(id);
+                    }
+                });
+                Ok(())
+            },
+            "AboutToShow" => {
+                // the menus are always up to date
+                out.append_bool(false);
+                Ok(())
+            },
+            "AboutToShowGroup" => {
+                out.open(DBUS_TYPE_ARRAY, Some("i"), |_| ());
+                out.open(DBUS_TYPE_ARRAY, Some("i"), |_| ());
+                Ok(())
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Handles an event of the user on an item. Returns false if the item doesn't exist.
+    fn event(&self, layout: &Layout, id: i32, event: &str) -> bool {
+        if id < 0 || id as usize >= layout.nodes.len() {
+            return false;
+        }
+
+        if event == "clicked" {
+            if let Node { kind: NodeKind::Action(item, _), enabled: true, .. } =
+                   layout.nodes[id as usize]
+            {
+                self.proxy.send_menu_item(item);
+            }
+        }
+        true
+    }
+}
+
+fn is_dbusmenu_method(member: &str) -> bool {
+    match member {
+        "GetLayout" | "GetGroupProperties" | "GetProperty" | "Event" | "EventGroup" |
+        "AboutToShow" | "AboutToShowGroup" => true,
+        _ => false,
+    }
+}
+
+/// The properties of the `com.canonical.dbusmenu` interface itself.
+fn interface_properties() -> Vec<(&'static str, Value)> {
+    vec![
+        ("Version", Value::U32(3)),
+        ("TextDirection", Value::Str("ltr".to_owned())),
+        ("Status", Value::Str("normal".to_owned())),
+        ("IconThemePath", Value::StrArray(Vec::new())),
+    ]
+}
+
+unsafe fn get_property(args: &mut Reader, out: &mut Writer) -> Result<(), ()> {
+    let interface = try!(args.read_string().ok_or(()));
+    let name = try!(args.read_string().ok_or(()));
+    if interface != DBUSMENU_INTERFACE {
+        return Err(());
+    }
+
+    match interface_properties().into_iter().find(|&(n, _)| n == name) {
+        Some((_, value)) => {
+            value.write(out);
+            Ok(())
+        },
+        None => Err(()),
+    }
+}
+
+unsafe fn get_all_properties(args: &mut Reader, out: &mut Writer) -> Result<(), ()> {
+    let interface = try!(args.read_string().ok_or(()));
+    let properties = if interface == DBUSMENU_INTERFACE {
+        interface_properties()
+    } else {
+        Vec::new()
+    };
+    write_dict(out, &properties);
+    Ok(())
+}
+
+unsafe fn c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
+
+/// The tree of the menus, flattened so that each item has an ID. The root is `0`.
+struct Layout {
+    revision: u32,
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    label: String,
+    kind: NodeKind,
+    enabled: bool,
+    children: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NodeKind {
+    /// The ID of the `MenuItem::Action` and its accelerator
+    Action(u32, Option<Accelerator>),
+    Separator,
+    /// The root, a menu of the menu bar, or a submenu
+    Menu,
+}
+
+impl Layout {
+    fn new(menus: &[Menu], revision: u32) -> Layout {
+        let mut nodes = vec![Node {
+            label: String::new(),
+            kind: NodeKind::Menu,
+            enabled: true,
+            children: Vec::new(),
+        }];
+
+        for menu in menus.iter() {
+            let child = add_menu(&mut nodes, menu);
+            nodes[0].children.push(child);
+        }
+
+        Layout {
+            revision: revision,
+            nodes: nodes,
+        }
+    }
+
+    /// Writes an item and its descendants, up to the given depth. `-1` means all of them.
+    unsafe fn write_node(&self, out: &mut Writer, id: i32, depth: i32) {
+        out.open(DBUS_TYPE_STRUCT, None, |out| {
+            out.append_i32(id);
+            self.write_properties(out, id);
+            out.open(DBUS_TYPE_ARRAY, Some("v"), |out| {
+                if depth == 0 {
+                    return;
+                }
+                for &child in self.nodes[id as usize].children.iter() {
+                    out.open(DBUS_TYPE_VARIANT, Some("(ia{sv}av)"), |out| {
+                        self.write_node(out, child, if depth > 0 { depth - 1 } else { depth });
+                    });
+                }
+            });
+        });
+    }
+
+    unsafe fn write_properties(&self, out: &mut Writer, id: i32) {
+        write_dict(out, &self.properties(id));
+    }
+
+    fn properties(&self, id: i32) -> Vec<(&'static str, Value)> {
+        let node = &self.nodes[id as usize];
+        let mut properties = Vec::new();
+
+        match node.kind {
+            NodeKind::Separator => {
+                properties.push(("type", Value::Str("separator".to_owned())));
+                return properties;
+            },
+            NodeKind::Menu => {
+                properties.push(("children-display", Value::Str("submenu".to_owned())));
+            },
+            NodeKind::Action(_, Some(accelerator)) => {
+                if let Some(shortcut) = shortcut(accelerator) {
+                    properties.push(("shortcut", Value::Shortcut(shortcut)));
+                }
+            },
+            NodeKind::Action(_, None) => (),
+        }
+
+        if id != 0 {
+            // an underscore marks the mnemonic, a double underscore is a literal one
+            properties.push(("label", Value::Str(node.label.replace("_", "__"))));
+        }
+        if !node.enabled {
+            properties.push(("enabled", Value::Bool(false)));
+        }
+        properties
+    }
+}
+
+/// Adds a menu and its items to the layout, and returns its ID.
+fn add_menu(nodes: &mut Vec<Node>, menu: &Menu) -> i32 {
+    let id = nodes.len() as i32;
+    nodes.push(Node {
+        label: menu.title.clone(),
+        kind: NodeKind::Menu,
+        enabled: true,
+        children: Vec::new(),
+    });
+
+    for item in menu.items.iter() {
+        let child = match *item {
+            MenuItem::Action { id: action, ref label, accelerator, enabled } => {
+                nodes.push(Node {
+                    label: label.clone(),
+                    kind: NodeKind::Action(action, accelerator),
+                    enabled: enabled,
+                    children: Vec::new(),
+                });
+                nodes.len() as i32 - 1
+            },
+            MenuItem::Separator => {
+                nodes.push(Node {
+                    label: String::new(),
+                    kind: NodeKind::Separator,
+                    enabled: true,
+                    children: Vec::new(),
+                });
+                nodes.len() as i32 - 1
+            },
+            MenuItem::Submenu(ref submenu) => add_menu(nodes, submenu),
+        };
+        nodes[id as usize].children.push(child);
+    }
+
+    id
+}
+
+/// Returns the shortcut property of an accelerator: the names of the modifiers followed by the
+/// name of the keysym, or `None` if the key has no keysym.
+fn shortcut(accelerator: Accelerator) -> Option<Vec<&'static str>> {
+    use VirtualKeyCode::*;
+
+    let key = match accelerator.key {
+        A => "a", B => "b", C => "c", D => "d", E => "e", F => "f", G => "g", H => "h",
+        I => "i", J => "j", K => "k", L => "l", M => "m", N => "n", O => "o", P => "p",
+        Q => "q", R => "r", S => "s", T => "t", U => "u", V => "v", W => "w", X => "x",
+        Y => "y", Z => "z",
+        Key0 => "0", Key1 => "1", Key2 => "2", Key3 => "3", Key4 => "4",
+        Key5 => "5", Key6 => "6", Key7 => "7", Key8 => "8", Key9 => "9",
+        F1 => "F1", F2 => "F2", F3 => "F3", F4 => "F4", F5 => "F5", F6 => "F6",
+        F7 => "F7", F8 => "F8", F9 => "F9", F10 => "F10", F11 => "F11", F12 => "F12",
+        Escape => "Escape",
+        Insert => "Insert",
+        Delete => "Delete",
+        Home => "Home",
+        End => "End",
+        PageUp => "Page_Up",
+        PageDown => "Page_Down",
+        Left => "Left",
+        Up => "Up",
+        Right => "Right",
+        Down => "Down",
+        Back => "BackSpace",
+        Return => "Return",
+        Space => "space",
+        Tab => "Tab",
+        Add => "KP_Add",
+        Subtract => "KP_Subtract",
+        Multiply => "KP_Multiply",
+        Divide => "KP_Divide",
+        Minus => "minus",
+        Equals => "equal",
+        Comma => "comma",
+        Period => "period",
+        Slash => "slash",
+        _ => return None,
+    };
+
+    let modifiers = accelerator.modifiers;
+    let mut shortcut = Vec::new();
+    if modifiers.ctrl { shortcut.push("Control"); }
+    if modifiers.alt { shortcut.push("Alt"); }
+    if modifiers.shift { shortcut.push("Shift"); }
+    if modifiers.logo { shortcut.push("Super"); }
+    shortcut.push(key);
+    Some(shortcut)
+}
+
+/// The value of a property.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    U32(u32),
+    Bool(bool),
+    StrArray(Vec<String>),
+    /// A single combination of keys
+    Shortcut(Vec<&'static str>),
+}
+
+impl Value {
+    /// Writes the value in a variant.
+    unsafe fn write(&self, out: &mut Writer) {
+        let signature = match *self {
+            Value::Str(_) => "s",
+            Value::U32(_) => "u",
+            Value::Bool(_) => "b",
+            Value::StrArray(_) => "as",
+            Value::Shortcut(_) => "aas",
+        };
+
+        out.open(DBUS_TYPE_VARIANT, Some(signature), |out| {
+            match *self {
+                Value::Str(ref value) => out.append_string(value),
+                Value::U32(value) => out.append_u32(value),
+                Value::Bool(value) => out.append_bool(value),
+                Value::StrArray(ref values) => {
+                    out.open(DBUS_TYPE_ARRAY, Some("s"), |out| {
+                        for value in values.iter() {
+                            out.append_string(value);
+                        }
+                    });
+                },
+                Value::Shortcut(ref keys) => {
+                    out.open(DBUS_TYPE_ARRAY, Some("as"), |out| {
+                        out.open(DBUS_TYPE_ARRAY, Some("s"), |out| {
+                            for key in keys.iter() {
+                                out.append_string(key);
+                            }
+                        });
+                    });
+                },
+            }
+        });
+    }
+}
+
+unsafe fn write_dict(out: &mut Writer, properties: &[(&'static str, Value)]) {
+    out.open(DBUS_TYPE_ARRAY, Some("{sv}"), |out| {
+        for &(name, ref value) in properties.iter() {
+            out.open(DBUS_TYPE_DICT_ENTRY, None, |out| {
+                out.append_string(name);
+                value.write(out);
+            });
+        }
+    });
+}
+
+/// Appends the arguments of a message.
+struct Writer<'a> {
+    lib: &'a LibDbus,
+    iter: DBusMessageIter,
+}
+
+impl<'a> Writer<'a> {
+    unsafe fn new(lib: &'a LibDbus, message: *mut DBusMessage) -> Writer<'a> {
+        let mut iter: DBusMessageIter = mem::zeroed();
+        (lib.dbus_message_iter_init_append)(message, &mut iter);
+        Writer { lib: lib, iter: iter }
+    }
+
+    unsafe fn append_i32(&mut self, value: i32) {
+        (self.lib.dbus_message_iter_append_basic)(&mut self.iter, DBUS_TYPE_INT32,
+                                                  &value as *const i32 as *const _);
+    }
+
+    unsafe fn append_u32(&mut self, value: u32) {
+        (self.lib.dbus_message_iter_append_basic)(&mut self.iter, DBUS_TYPE_UINT32,
+                                                  &value as *const u32 as *const _);
+    }
+
+    unsafe fn append_bool(&mut self, value: bool) {
+        let value: dbus_bool_t = if value { 1 } else { 0 };
+        (self.lib.dbus_message_iter_append_basic)(&mut self.iter, DBUS_TYPE_BOOLEAN,
+                                                  &value as *const dbus_bool_t as *const _);
+    }
+
+    unsafe fn append_string(&mut self, value: &str) {
+        self.append_str_type(DBUS_TYPE_STRING, value);
+    }
+
+    unsafe fn append_object_path(&mut self, value: &str) {
+        self.append_str_type(DBUS_TYPE_OBJECT_PATH, value);
+    }
+
+    unsafe fn append_str_type(&mut self, ty: c_int, value: &str) {
+        // the strings of D-Bus can't contain nul characters
+        let value = CString::new(value.replace("\0", "")).unwrap();
+        let ptr = value.as_ptr();
+        (self.lib.dbus_message_iter_append_basic)(&mut self.iter, ty,
+                                                  &ptr as *const *const c_char as *const _);
+    }
+
+    /// Opens a container, lets the closure fill it, then closes it.
+    unsafe fn open<F>(&mut self, ty: c_int, signature: Option<&str>, f: F)
+        where F: FnOnce(&mut Writer)
+    {
+        let signature = signature.map(|s| CString::new(s).unwrap());
+        let mut sub = Writer { lib: self.lib, iter: mem::zeroed() };
+        (self.lib.dbus_message_iter_open_container)(&mut self.iter, ty,
+                                                    signature.as_ref().map_or(ptr::null(),
+                                                                              |s| s.as_ptr()),
+                                                    &mut sub.iter);
+        f(&mut sub);
+        (self.lib.dbus_message_iter_close_container)(&mut self.iter, &mut sub.iter);
+    }
+}
+
+/// Reads the arguments of a message, in order.
+struct Reader<'a> {
+    lib: &'a LibDbus,
+    iter: DBusMessageIter,
+    /// False if the message or the container has no argument
+    valid: bool,
+}
+
+impl<'a> Reader<'a> {
+    unsafe fn new(lib: &'a LibDbus, message: *mut DBusMessage) -> Reader<'a> {
+        let mut iter: DBusMessageIter = mem::zeroed();
+        let valid = (lib.dbus_message_iter_init)(message, &mut iter) != 0;
+        Reader { lib: lib, iter: iter, valid: valid }
+    }
+
+    fn arg_type(&mut self) -> c_int {
+        if !self.valid {
+            return DBUS_TYPE_INVALID;
+        }
+        unsafe { (self.lib.dbus_message_iter_get_arg_type)(&mut self.iter) }
+    }
+
+    fn advance(&mut self) {
+        self.valid = unsafe { (self.lib.dbus_message_iter_next)(&mut self.iter) } != 0;
+    }
+
+    fn read_i32(&mut self) -> Option<i32> {
+        if self.arg_type() != DBUS_TYPE_INT32 {
+            return None;
+        }
+        let mut value: i32 = 0;
+        unsafe {
+            (self.lib.dbus_message_iter_get_basic)(&mut self.iter,
+                                                   &mut value as *mut i32 as *mut _);
+        }
+        self.advance();
+        Some(value)
+    }
+
+    fn read_string(&mut self) -> Option<String> {
+        if self.arg_type() != DBUS_TYPE_STRING {
+            return None;
+        }
+        let mut value: *const c_char = ptr::null();
+        let value = unsafe {
+            (self.lib.dbus_message_iter_get_basic)(&mut self.iter,
+                                                   &mut value as *mut *const c_char as *mut _);
+            c_string(value)
+        };
+        self.advance();
+        value
+    }
+
+    /// Returns a reader of the content of the array or structure at the current position, and
+    /// moves after it.
+    fn recurse(&mut self) -> Option<Reader<'a>> {
+        match self.arg_type() {
+            DBUS_TYPE_ARRAY | DBUS_TYPE_STRUCT | DBUS_TYPE_VARIANT => (),
+            _ => return None,
+        }
+
+        let mut sub = Reader { lib: self.lib, iter: unsafe { mem::zeroed() }, valid: true };
+        unsafe { (self.lib.dbus_message_iter_recurse)(&mut self.iter, &mut sub.iter) };
+        // an empty container starts at the end
+        sub.valid = sub.arg_type() != DBUS_TYPE_INVALID;
+        self.advance();
+        Some(sub)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Accelerator;
+    use Menu;
+    use MenuItem;
+    use ModifiersState;
+    use VirtualKeyCode;
+
+    use super::{Layout, NodeKind, Value, shortcut};
+
+    fn menus() -> Vec<Menu> {
+        vec![
+            Menu::new("File")
+                .with_action(1, "Open", Some(Accelerator::primary(VirtualKeyCode::O)))
+                .with_separator()
+                .with_submenu(Menu::new("Recent").with_action(2, "a_b.txt", None)),
+            Menu {
+                title: "Edit".to_owned(),
+                items: vec![MenuItem::Action {
+                    id: 3,
+                    label: "Undo".to_owned(),
+                    accelerator: None,
+                    enabled: false,
+                }],
+            },
+        ]
+    }
+
+    #[test]
+    fn layout_is_flattened_in_order() {
+        let layout = Layout::new(&menus(), 7);
+        assert_eq!(layout.revision, 7);
+
+        let kinds: Vec<_> = layout.nodes.iter().map(|n| n.kind).collect();
+        assert_eq!(kinds, vec![
+            NodeKind::Menu,
+            NodeKind::Menu,
+            NodeKind::Action(1, Some(Accelerator::primary(VirtualKeyCode::O))),
+            NodeKind::Separator,
+            NodeKind::Menu,
+            NodeKind::Action(2, None),
+            NodeKind::Menu,
+            NodeKind::Action(3, None),
+        ]);
+
+        assert_eq!(layout.nodes[0].children, vec![1, 6]);
+        assert_eq!(layout.nodes[1].children, vec![2, 3, 4]);
+        assert_eq!(layout.nodes[4].children, vec![5]);
+        assert_eq!(layout.nodes[6].children, vec![7]);
+    }
+
+    #[test]
+    fn properties_of_the_items() {
+        let layout = Layout::new(&menus(), 1);
+
+        assert_eq!(layout.properties(0),
+                   vec![("children-display", Value::Str("submenu".to_owned()))]);
+        assert_eq!(layout.properties(3), vec![("type", Value::Str("separator".to_owned()))]);
+        assert_eq!(layout.properties(2), vec![
+            ("shortcut", Value::Shortcut(vec!["Control", "o"])),
+            ("label", Value::Str("Open".to_owned())),
+        ]);
+        // the underscores are not mnemonics
+        assert_eq!(layout.properties(5), vec![("label", Value::Str("a__b.txt".to_owned()))]);
+        assert_eq!(layout.properties(7), vec![
+            ("label", Value::Str("Undo".to_owned())),
+            ("enabled", Value::Bool(false)),
+        ]);
+    }
+
+    #[test]
+    fn shortcuts() {
+        let mut modifiers = ModifiersState::default();
+        modifiers.ctrl = true;
+        modifiers.shift = true;
+        assert_eq!(shortcut(Accelerator::new(VirtualKeyCode::PageDown, modifiers)),
+                   Some(vec!["Control", "Shift", "Page_Down"]));
+        assert_eq!(shortcut(Accelerator::new(VirtualKeyCode::F5, ModifiersState::default())),
+                   Some(vec!["F5"]));
+        assert_eq!(shortcut(Accelerator::new(VirtualKeyCode::Capital, modifiers)), None);
+    }
+}
@@ -0,0 +1,435 @@
+//! Decoding of raw `XEvent`s.
+//!
+//! `XEvent` is a C union, and reading the wrong variant is undefined behavior. All the reads
+//! are done here, after the type of the event has been checked, so that the rest of the code
+//! only deals with plain Rust values. None of these functions need a connection to the X server,
+//! which means that they can be fed arbitrary data.
+
+use std::mem;
+use std::ptr;
+
+use libc;
+use Event;
+use LogicalSize;
+
+use super::ffi;
+
+/// The content of an `XEvent` that glutin is interested in.
+pub enum DecodedEvent {
+    /// The keyboard mapping has changed and must be refreshed with `XRefreshKeyboardMapping`.
+    MappingNotify(ffi::XMappingEvent),
+    ClientMessage {
+        window: ffi::Window,
+        message_type: ffi::Atom,
+        data: [libc::c_long; 5],
+    },
+    /// The owner of a selection has answered a call to `XConvertSelection`. `property` is `0`
+    /// if the conversion failed.
+    SelectionNotify {
+        requestor: ffi::Window,
+        selection: ffi::Atom,
+        property: ffi::Atom,
+    },
+    Configure {
+        window: ffi::Window,
+        width: libc::c_int,
+        height: libc::c_int,
+    },
+    Reparent {
+        window: ffi::Window,
+        parent: ffi::Window,
+    },
+    Expose,
+    Destroy {
+        window: ffi::Window,
+    },
+    PropertyNotify {
+        window: ffi::Window,
+        atom: ffi::Atom,
+        /// True if the property has been deleted, false if it has been changed.
+        deleted: bool,
+    },
+    /// Another client requests the content of a selection that we own.
+    SelectionRequest(ffi::XSelectionRequestEvent),
+    /// Another client has taken the ownership of a selection.
+    SelectionClear {
+        selection: ffi::Atom,
+    },
+    Key(ffi::XKeyEvent),
+    /// The window gained or lost the keyboard focus. `mode` is `NotifyNormal`, `NotifyGrab`,
+    /// `NotifyUngrab` or `NotifyWhileGrabbed`.
+    Focus {
+        window: ffi::Window,
+        focused: bool,
+        mode: libc::c_int,
+    },
+    /// An XInput2 event, whose data has to be retreived with `XGetEventData`.
+    Generic,
+    /// An event that glutin ignores. The parameter is the type of the event.
+    Other(libc::c_int),
+}
+
+impl DecodedEvent {
+    /// Decodes an event returned by Xlib.
+    pub fn decode(xev: &ffi::XEvent) -> DecodedEvent {
+        match xev.get_type() {
+            ffi::MappingNotify => DecodedEvent::MappingNotify(ffi::XMappingEvent::from(*xev)),
+
+            ffi::ClientMessage => {
+                let client_msg = ffi::XClientMessageEvent::from(*xev);
+                let mut data = [0; 5];
+                for (i, value) in data.iter_mut().enumerate() {
+                    *value = client_msg.data.get_long(i);
+                }
+                DecodedEvent::ClientMessage {
+                    window: client_msg.window,
+                    message_type: client_msg.message_type,
+                    data: data,
+                }
+            },
+
+            ffi::SelectionNotify => {
+                let selection_event = ffi::XSelectionEvent::from(*xev);
+                DecodedEvent::SelectionNotify {
+                    requestor: selection_event.requestor,
+                    selection: selection_event.selection,
+                    property: selection_event.property,
+                }
+            },
+
+            ffi::ConfigureNotify => {
+                let cfg_event = ffi::XConfigureEvent::from(*xev);
+                DecodedEvent::Configure {
+                    window: cfg_event.window,
+                    width: cfg_event.width,
+                    height: cfg_event.height,
+                }
+            },
+
+            ffi::ReparentNotify => {
+                let reparent_event = ffi::XReparentEvent::from(*xev);
+                DecodedEvent::Reparent {
+                    window: reparent_event.window,
+                    parent: reparent_event.parent,
+                }
+            },
+
+            ffi::Expose => DecodedEvent::Expose,
+
+            ffi::DestroyNotify => {
+                let destroy_event = ffi::XDestroyWindowEvent::from(*xev);
+                DecodedEvent::Destroy { window: destroy_event.window }
+            },
+
+            ffi::PropertyNotify => {
+                let property_event = ffi::XPropertyEvent::from(*xev);
+                DecodedEvent::PropertyNotify {
+                    window: property_event.window,
+                    atom: property_event.atom,
+                    deleted: property_event.state == ffi::PropertyDelete,
+                }
+            },
+
+            ffi::SelectionRequest => {
+                DecodedEvent::SelectionRequest(ffi::XSelectionRequestEvent::from(*xev))
+            },
+
+            ffi::SelectionClear => {
+                let clear_event = ffi::XSelectionClearEvent::from(*xev);
+                DecodedEvent::SelectionClear { selection: clear_event.selection }
+            },
+
+            ffi::KeyPress | ffi::KeyRelease => DecodedEvent::Key(ffi::XKeyEvent::from(*xev)),
+
+            ffi::FocusIn | ffi::FocusOut => {
+                let focus_event = ffi::XFocusChangeEvent::from(*xev);
+                DecodedEvent::Focus {
+                    window: focus_event.window,
+                    focused: focus_event.type_ == ffi::FocusIn,
+                    mode: focus_event.mode,
+                }
+            },
+
+            ffi::GenericEvent => DecodedEvent::Generic,
+
+            ty => DecodedEvent::Other(ty),
+        }
+    }
+
+    /// Decodes an event from its raw bytes, as laid out in memory by Xlib.
+    ///
+    /// Returns `None` if there are not enough bytes. This is the entry point for fuzzing.
+    pub fn decode_bytes(bytes: &[u8]) -> Option<DecodedEvent> {
+        if bytes.len() < mem::size_of::<ffi::XEvent>() {
+            return None;
+        }
+
+        unsafe {
+            let mut xev: ffi::XEvent = mem::zeroed();
+            ptr::copy_nonoverlapping(bytes.as_ptr(), &mut xev as *mut _ as *mut u8,
+                                     mem::size_of::<ffi::XEvent>());
+            Some(DecodedEvent::decode(&xev))
+        }
+    }
+}
+
+/// Translates a client message. `wm_delete_window` is the `WM_DELETE_WINDOW` atom.
+///
+/// Every client message that is not a close request is a wake up sent by a `WindowProxy`.
+pub fn translate_client_message(data: &[libc::c_long; 5], wm_delete_window: ffi::Atom) -> Event {
+    if data[0] == wm_delete_window as libc::c_long {
+        Event::Closed
+    } else {
+        Event::Awakened
+    }
+}
+
+/// Translates a configure notification, given the last known size of the window.
+///
+/// Returns `None` if the size didn't change (the window only moved for example).
+pub fn translate_configure(current_size: (libc::c_int, libc::c_int), width: libc::c_int,
+                           height: libc::c_int) -> Option<Event>
+{
+    if current_size == (width, height) || width < 0 || height < 0 {
+        return None;
+    }
+
+    Some(Event::Resized(LogicalSize::new(width as u32, height as u32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use libc;
+    use Event;
+    use LogicalSize;
+
+    use super::{DecodedEvent, translate_client_message, translate_configure};
+    use super::super::ffi;
+
+    /// Returns a zeroed event of the given type, for the fields of the tests to be filled.
+    fn zeroed<T>() -> T {
+        unsafe { mem::zeroed() }
+    }
+
+    #[test]
+    fn decode_mapping_notify() {
+        let mut mapping: ffi::XMappingEvent = zeroed();
+        mapping.type_ = ffi::MappingNotify;
+        mapping.request = ffi::MappingKeyboard;
+        match DecodedEvent::decode(&ffi::XEvent::from(mapping)) {
+            DecodedEvent::MappingNotify(event) => assert_eq!(event.request, ffi::MappingKeyboard),
+            _ => panic!("expected a MappingNotify"),
+        }
+    }
+
+    #[test]
+    fn decode_client_message() {
+        let mut message: ffi::XClientMessageEvent = zeroed();
+        message.type_ = ffi::ClientMessage;
+        message.window = 7;
+        message.message_type = 42;
+        message.format = 32;
+        for i in 0..5 {
+            message.data.set_long(i, i as libc::c_long + 1);
+        }
+        match DecodedEvent::decode(&ffi::XEvent::from(message)) {
+            DecodedEvent::ClientMessage { window, message_type, data } => {
+                assert_eq!(window, 7);
+                assert_eq!(message_type, 42);
+                assert_eq!(data, [1, 2, 3, 4, 5]);
+            },
+            _ => panic!("expected a ClientMessage"),
+        }
+    }
+
+    #[test]
+    fn decode_selection_notify() {
+        let mut selection: ffi::XSelectionEvent = zeroed();
+        selection.type_ = ffi::SelectionNotify;
+        selection.requestor = 7;
+        selection.selection = 1;
+        selection.property = 0;
+        match DecodedEvent::decode(&ffi::XEvent::from(selection)) {
+            DecodedEvent::SelectionNotify { requestor, selection, property } => {
+                assert_eq!((requestor, selection, property), (7, 1, 0));
+            },
+            _ => panic!("expected a SelectionNotify"),
+        }
+    }
+
+    #[test]
+    fn decode_configure() {
+        let mut configure: ffi::XConfigureEvent = zeroed();
+        configure.type_ = ffi::ConfigureNotify;
+        configure.window = 7;
+        configure.width = 640;
+        configure.height = 480;
+        match DecodedEvent::decode(&ffi::XEvent::from(configure)) {
+            DecodedEvent::Configure { window, width, height } => {
+                assert_eq!((window, width, height), (7, 640, 480));
+            },
+            _ => panic!("expected a ConfigureNotify"),
+        }
+    }
+
+    #[test]
+    fn decode_reparent() {
+        let mut reparent: ffi::XReparentEvent = zeroed();
+        reparent.type_ = ffi::ReparentNotify;
+        reparent.window = 7;
+        reparent.parent = 8;
+        match DecodedEvent::decode(&ffi::XEvent::from(reparent)) {
+            DecodedEvent::Reparent { window, parent } => assert_eq!((window, parent), (7, 8)),
+            _ => panic!("expected a ReparentNotify"),
+        }
+    }
+
+    #[test]
+    fn decode_expose() {
+        let mut expose: ffi::XExposeEvent = zeroed();
+        expose.type_ = ffi::Expose;
+        match DecodedEvent::decode(&ffi::XEvent::from(expose)) {
+            DecodedEvent::Expose => (),
+            _ => panic!("expected an Expose"),
+        }
+    }
+
+    #[test]
+    fn decode_destroy() {
+        let mut destroy: ffi::XDestroyWindowEvent = zeroed();
+        destroy.type_ = ffi::DestroyNotify;
+        destroy.window = 7;
+        match DecodedEvent::decode(&ffi::XEvent::from(destroy)) {
+            DecodedEvent::Destroy { window } => assert_eq!(window, 7),
+            _ => panic!("expected a DestroyNotify"),
+        }
+    }
+
+    #[test]
+    fn decode_property_notify() {
+        let mut property: ffi::XPropertyEvent = zeroed();
+        property.type_ = ffi::PropertyNotify;
+        property.window = 7;
+        property.atom = 42;
+
+        property.state = ffi::PropertyNewValue;
+        match DecodedEvent::decode(&ffi::XEvent::from(property)) {
+            DecodedEvent::PropertyNotify { window, atom, deleted } => {
+                assert_eq!((window, atom, deleted), (7, 42, false));
+            },
+            _ => panic!("expected a PropertyNotify"),
+        }
+
+        property.state = ffi::PropertyDelete;
+        match DecodedEvent::decode(&ffi::XEvent::from(property)) {
+            DecodedEvent::PropertyNotify { deleted, .. } => assert!(deleted),
+            _ => panic!("expected a PropertyNotify"),
+        }
+    }
+
+    #[test]
+    fn decode_selection_request() {
+        let mut request: ffi::XSelectionRequestEvent = zeroed();
+        request.type_ = ffi::SelectionRequest;
+        request.owner = 7;
+        request.requestor = 8;
+        request.target = 42;
+        match DecodedEvent::decode(&ffi::XEvent::from(request)) {
+            DecodedEvent::SelectionRequest(event) => {
+                assert_eq!((event.owner, event.requestor, event.target), (7, 8, 42));
+            },
+            _ => panic!("expected a SelectionRequest"),
+        }
+    }
+
+    #[test]
+    fn decode_selection_clear() {
+        let mut clear: ffi::XSelectionClearEvent = zeroed();
+        clear.type_ = ffi::SelectionClear;
+        clear.selection = 42;
+        match DecodedEvent::decode(&ffi::XEvent::from(clear)) {
+            DecodedEvent::SelectionClear { selection } => assert_eq!(selection, 42),
+            _ => panic!("expected a SelectionClear"),
+        }
+    }
+
+    #[test]
+    fn decode_key() {
+        for &ty in [ffi::KeyPress, ffi::KeyRelease].iter() {
+            let mut key: ffi::XKeyEvent = zeroed();
+            key.type_ = ty;
+            key.keycode = 38;
+            match DecodedEvent::decode(&ffi::XEvent::from(key)) {
+                DecodedEvent::Key(event) => assert_eq!((event.type_, event.keycode), (ty, 38)),
+                _ => panic!("expected a key event"),
+            }
+        }
+    }
+
+    #[test]
+    fn decode_focus() {
+        for &(ty, expected) in [(ffi::FocusIn, true), (ffi::FocusOut, false)].iter() {
+            let mut focus: ffi::XFocusChangeEvent = zeroed();
+            focus.type_ = ty;
+            focus.window = 7;
+            focus.mode = ffi::NotifyGrab;
+            match DecodedEvent::decode(&ffi::XEvent::from(focus)) {
+                DecodedEvent::Focus { window, focused, mode } => {
+                    assert_eq!((window, focused, mode), (7, expected, ffi::NotifyGrab));
+                },
+                _ => panic!("expected a focus event"),
+            }
+        }
+    }
+
+    #[test]
+    fn decode_generic_and_ignored_events() {
+        let mut any: ffi::XAnyEvent = zeroed();
+        any.type_ = ffi::GenericEvent;
+        match DecodedEvent::decode(&ffi::XEvent::from(any)) {
+            DecodedEvent::Generic => (),
+            _ => panic!("expected a GenericEvent"),
+        }
+
+        any.type_ = ffi::MapNotify;
+        match DecodedEvent::decode(&ffi::XEvent::from(any)) {
+            DecodedEvent::Other(ty) => assert_eq!(ty, ffi::MapNotify),
+            _ => panic!("expected an ignored event"),
+        }
+    }
+
+    #[test]
+    fn decode_bytes_needs_a_whole_event() {
+        assert!(DecodedEvent::decode_bytes(&[0; 4]).is_none());
+
+        let bytes = vec![0; mem::size_of::<ffi::XEvent>()];
+        match DecodedEvent::decode_bytes(&bytes) {
+            Some(DecodedEvent::Other(0)) => (),
+            _ => panic!("expected an event of type 0"),
+        }
+    }
+
+    #[test]
+    fn client_message_translation() {
+        match translate_client_message(&[5, 0, 0, 0, 0], 5) {
+            Event::Closed => (),
+            event => panic!("expected Closed, got {:?}", event),
+        }
+        match translate_client_message(&[0, 0, 0, 0, 0], 5) {
+            Event::Awakened => (),
+            event => panic!("expected Awakened, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn configure_translation() {
+        match translate_configure((640, 480), 800, 600) {
+            Some(Event::Resized(size)) => assert_eq!(size, LogicalSize::new(800, 600)),
+            event => panic!("expected Resized, got {:?}", event),
+        }
+        assert!(translate_configure((640, 480), 640, 480).is_none());
+        assert!(translate_configure((640, 480), -1, 600).is_none());
+    }
+}
@@ -0,0 +1,328 @@
+//! Receiving files dropped on a window, with the XDND protocol.
+//!
+//! See https://www.freedesktop.org/wiki/Specifications/XDND/ for the specification. Only lists
+//! of files (`text/uri-list`) are accepted.
+
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use libc::c_long;
+
+use Event;
+
+use super::ffi;
+use super::wm::get_property;
+use super::XConnection;
+
+/// The version of the protocol that we implement.
+const XDND_VERSION: c_long = 5;
+
+pub struct Dnd {
+    display: Arc<XConnection>,
+    window: ffi::Window,
+    /// The window from which something is being dragged over ours
+    source: Option<ffi::Window>,
+    /// Whether the source offers a list of files
+    accepted: bool,
+    /// Whether the list of files has been requested from the source
+    requested: bool,
+    /// The list of files, once it has been received
+    paths: Option<Vec<PathBuf>>,
+    /// Whether the files have been dropped, in which case the drop finishes as soon as they
+    /// are received
+    dropped: bool,
+}
+
+impl Dnd {
+    /// Advertises that the window accepts drops.
+    pub fn new(display: &Arc<XConnection>, window: ffi::Window) -> Dnd {
+        unsafe {
+            let version = XDND_VERSION;
+            display.push_error_trap();
+            (display.xlib.XChangeProperty)(display.display, window, display.get_atom("XdndAware"),
+                                           ffi::XA_ATOM, 32, ffi::PropModeReplace,
+                                           &version as *const c_long as *const _, 1);
+            display.pop_error_trap().expect("Failed to call XChangeProperty");
+        }
+
+        Dnd {
+            display: display.clone(),
+            window: window,
+            source: None,
+            accepted: false,
+            requested: false,
+            paths: None,
+            dropped: false,
+        }
+    }
+
+    /// Handles a client message sent to the window.
+    ///
+    /// Returns `None` if the message is not part of the protocol.
+    pub fn handle_client_message(&mut self, message_type: ffi::Atom, data: &[c_long; 5])
+                                 -> Option<Vec<Event>>
+    {
+        let display = self.display.clone();
+        let mut events = Vec::new();
+
+        if message_type == display.get_atom("XdndEnter") {
+            self.reset();
+            self.source = Some(data[0] as ffi::Window);
+
+            let uri_list = display.get_atom("text/uri-list");
+            self.accepted = if data[1] & 1 == 0 {
+                // the source offers at most three types, which are in the message
+                data[2..].iter().any(|&ty| ty as ffi::Atom == uri_list)
+            } else {
+                display.push_error_trap();
+                let types = get_property::<ffi::Atom>(&display, data[0] as ffi::Window,
+                                                      display.get_atom("XdndTypeList"),
+                                                      ffi::XA_ATOM, 32);
+                let _ = display.pop_error_trap();
+                types.map_or(false, |types| types.contains(&uri_list))
+            };
+
+        } else if message_type == display.get_atom("XdndPosition") {
+            if self.source != Some(data[0] as ffi::Window) {
+                return Some(events);
+            }
+
+            // the files are retreived as soon as possible, in order to report them as hovered
+            if self.accepted && !self.requested {
+                self.request_paths(data[3] as ffi::Time);
+            }
+
+            let action = if self.accepted { display.get_atom("XdndActionCopy") } else { 0 };
+            self.send(display.get_atom("XdndStatus"),
+                      [self.window as c_long, self.accepted as c_long, 0, 0, action as c_long]);
+
+        } else if message_type == display.get_atom("XdndLeave") {
+            if self.paths.is_some() {
+                events.push(Event::HoveredFileCancelled);
+            }
+            self.reset();
+
+        } else if message_type == display.get_atom("XdndDrop") {
+            if self.source != Some(data[0] as ffi::Window) {
+                return Some(events);
+            }
+
+            if !self.accepted {
+                self.finish(false);
+            } else if let Some(paths) = self.paths.take() {
+                events.extend(paths.into_iter().map(Event::DroppedFile));
+                self.finish(true);
+            } else {
+                self.dropped = true;
+                if !self.requested {
+                    self.request_paths(data[2] as ffi::Time);
+                }
+            }
+
+        } else {
+            return None;
+        }
+
+        Some(events)
+    }
+
+    /// Handles the reception of the list of files.
+    ///
+    /// `property` is the property of the window where the source has written the list, or
+    /// `0` if the source couldn't provide it.
+    pub fn handle_selection(&mut self, selection: ffi::Atom, property: ffi::Atom) -> Vec<Event> {
+        if selection != self.display.get_atom("XdndSelection") || !self.requested ||
+           self.paths.is_some()
+        {
+            return Vec::new();
+        }
+
+        let paths = if property == 0 {
+            None
+        } else {
+            let data = get_property::<u8>(&self.display, self.window, property,
+                                          self.display.get_atom("text/uri-list"), 8);
+            unsafe {
+                (self.display.xlib.XDeleteProperty)(self.display.display, self.window, property);
+            }
+            data.map(|data| parse_uri_list(&data))
+        };
+
+        match (paths, self.dropped) {
+            (Some(paths), true) => {
+                self.finish(true);
+                paths.into_iter().map(Event::DroppedFile).collect()
+            },
+            (Some(paths), false) => {
+                let events = paths.iter().cloned().map(Event::HoveredFile).collect();
+                self.paths = Some(paths);
+                events
+            },
+            (None, true) => {
+                self.finish(false);
+                Vec::new()
+            },
+            (None, false) => {
+                self.accepted = false;
+                Vec::new()
+            },
+        }
+    }
+
+    /// Asks the source to write the list of files in a property of the window. The source
+    /// answers with a `SelectionNotify` event.
+    fn request_paths(&mut self, time: ffi::Time) {
+        let selection = self.display.get_atom("XdndSelection");
+        unsafe {
+            (self.display.xlib.XConvertSelection)(self.display.display, selection,
+                                                  self.display.get_atom("text/uri-list"),
+                                                  selection, self.window, time);
+        }
+        self.requested = true;
+    }
+
+    /// Tells the source that the drop is over.
+    fn finish(&mut self, accepted: bool) {
+        let action = if accepted { self.display.get_atom("XdndActionCopy") } else { 0 };
+        self.send(self.display.get_atom("XdndFinished"),
+                  [self.window as c_long, accepted as c_long, action as c_long, 0, 0]);
+        self.reset();
+    }
+
+    fn send(&self, message_type: ffi::Atom, data: [c_long; 5]) {
+        let source = match self.source {
+            Some(s) => s,
+            None => return
+        };
+
+        let client_message_event = ffi::XClientMessageEvent {
+            type_: ffi::ClientMessage,
+            serial: 0,
+            send_event: 1,
+            display: self.display.display,
+            window: source,
+            message_type: message_type,
+            format: 32,
+            data: {
+                let mut client_data = ffi::ClientMessageData::new();
+                for (i, value) in data.iter().enumerate() {
+                    client_data.set_long(i, *value);
+                }
+                client_data
+            }
+        };
+        let mut x_event = ffi::XEvent::from(client_message_event);
+
+        // the source may have been closed during the drag
+        self.display.push_error_trap();
+        unsafe {
+            (self.display.xlib.XSendEvent)(self.display.display, source, ffi::False,
+                                           ffi::NoEventMask, &mut x_event);
+            (self.display.xlib.XFlush)(self.display.display);
+        }
+        let _ = self.display.pop_error_trap();
+    }
+
+    fn reset(&mut self) {
+        self.source = None;
+        self.accepted = false;
+        self.requested = false;
+        self.paths = None;
+        self.dropped = false;
+    }
+}
+
+/// Parses a `text/uri-list`, ignoring everything that is not a local file.
+fn parse_uri_list(data: &[u8]) -> Vec<PathBuf> {
+    data.split(|&b| b == b'\n')
+        .map(|line| if line.ends_with(b"\r") { &line[..line.len() - 1] } else { line })
+        .filter(|line| !line.is_empty() && !line.starts_with(b"#"))
+        .filter_map(|uri| {
+            if !uri.starts_with(b"file://") {
+                return None;
+            }
+
+            // skipping the host name, which is usually empty or `localhost`
+            let path = &uri[b"file://".len()..];
+            let path = match path.iter().position(|&b| b == b'/') {
+                Some(start) => &path[start..],
+                None => return None,
+            };
+
+            Some(PathBuf::from(OsString::from_vec(percent_decode(path))))
+        })
+        .collect()
+}
+
+fn percent_decode(data: &[u8]) -> Vec<u8> {
+    fn hex(b: u8) -> Option<u8> {
+        match b {
+            b'0' ... b'9' => Some(b - b'0'),
+            b'a' ... b'f' => Some(b - b'a' + 10),
+            b'A' ... b'F' => Some(b - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == b'%' && i + 2 < data.len() {
+            if let (Some(h), Some(l)) = (hex(data[i + 1]), hex(data[i + 2])) {
+                out.push(h << 4 | l);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(data[i]);
+        i += 1;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse_uri_list, percent_decode};
+
+    #[test]
+    fn uri_list_skips_comments() {
+        let list = b"# a comment\nfile:///tmp/a\n#file:///tmp/b\n";
+        assert_eq!(parse_uri_list(list), vec![PathBuf::from("/tmp/a")]);
+    }
+
+    #[test]
+    fn uri_list_handles_crlf() {
+        let list = b"file:///tmp/a\r\nfile://localhost/tmp/b\r\n";
+        assert_eq!(parse_uri_list(list), vec![PathBuf::from("/tmp/a"), PathBuf::from("/tmp/b")]);
+    }
+
+    #[test]
+    fn uri_list_ignores_remote_files() {
+        let list = b"http://example.com/a\nfile://host\nfile:///tmp/a";
+        assert_eq!(parse_uri_list(list), vec![PathBuf::from("/tmp/a")]);
+    }
+
+    #[test]
+    fn uri_list_decodes_paths() {
+        let list = b"file:///tmp/a%20b";
+        assert_eq!(parse_uri_list(list), vec![PathBuf::from("/tmp/a b")]);
+    }
+
+    #[test]
+    fn percent_decode_valid_escapes() {
+        assert_eq!(percent_decode(b"%41%62%2f"), b"Ab/".to_vec());
+    }
+
+    #[test]
+    fn percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode(b"%zz"), b"%zz".to_vec());
+        assert_eq!(percent_decode(b"%4"), b"%4".to_vec());
+        assert_eq!(percent_decode(b"100%"), b"100%".to_vec());
+        assert_eq!(percent_decode(b"%%41"), b"%A".to_vec());
+    }
+}
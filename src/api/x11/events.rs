@@ -1,7 +1,18 @@
 use {events, libc};
 use super::ffi;
+use ModifiersState;
 use VirtualKeyCode;
 
+/// Reads the modifiers from the `state` field of an event.
+pub fn modifiers_from_state(state: libc::c_uint) -> ModifiersState {
+    ModifiersState {
+        shift: state & ffi::ShiftMask != 0,
+        ctrl: state & ffi::ControlMask != 0,
+        alt: state & ffi::Mod1Mask != 0,
+        logo: state & ffi::Mod4Mask != 0,
+    }
+}
+
 pub fn keycode_to_element(scancode: libc::c_uint) -> Option<VirtualKeyCode> {
     Some(match scancode {
         ffi::XK_BackSpace => events::VirtualKeyCode::Back,
@@ -0,0 +1,67 @@
+//! Release of the pointer grabs when the program panics.
+//!
+//! The X server releases the grabs of a client when its connection is closed, but a panic
+//! doesn't necessarily end the process: other threads may keep running, or the panic may be
+//! caught. Until then the cursor would stay confined to the window, and possibly invisible.
+//!
+//! The hook runs for every panic, including the ones that are caught, so the windows are
+//! forgotten once released: they notice it with `is_registered` and go back to the normal
+//! state, and a later grab works again.
+
+use std::sync::{Arc, Mutex, Once, ONCE_INIT, TryLockError};
+
+use api::panic::add_panic_cleanup;
+
+use super::ffi;
+use super::XConnection;
+
+lazy_static! {
+    /// The windows that grab the pointer or hide the cursor.
+    static ref GRABBING_WINDOWS: Mutex<Vec<(Arc<XConnection>, ffi::Window)>> =
+        Mutex::new(Vec::new());
+}
+
+static INSTALL_CLEANUP: Once = ONCE_INIT;
+
+/// Records that the window grabs the pointer or hides the cursor.
+pub fn register(display: &Arc<XConnection>, window: ffi::Window) {
+    INSTALL_CLEANUP.call_once(|| add_panic_cleanup(release_all));
+
+    let mut windows = GRABBING_WINDOWS.lock().unwrap();
+    if !windows.iter().any(|&(ref d, w)| d.display == display.display && w == window) {
+        windows.push((display.clone(), window));
+    }
+}
+
+/// Records that the window is back to a normal cursor, or has been destroyed.
+pub fn unregister(display: &XConnection, window: ffi::Window) {
+    GRABBING_WINDOWS.lock().unwrap()
+                    .retain(|&(ref d, w)| d.display != display.display || w != window);
+}
+
+/// Returns `false` if the window is back to a normal cursor, possibly because a panic released
+/// its grab.
+pub fn is_registered(display: &XConnection, window: ffi::Window) -> bool {
+    GRABBING_WINDOWS.lock().unwrap()
+                    .iter().any(|&(ref d, w)| d.display == display.display && w == window)
+}
+
+/// Ungrabs the pointer and the keyboard, and restores the default cursor of every window.
+fn release_all() {
+    // the panic may have happened while the list was locked
+    let mut windows = match GRABBING_WINDOWS.try_lock() {
+        Ok(windows) => windows,
+        Err(TryLockError::Poisoned(err)) => err.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+
+    for &(ref display, window) in windows.iter() {
+        unsafe {
+            (display.xlib.XUngrabPointer)(display.display, ffi::CurrentTime);
+            (display.xlib.XUngrabKeyboard)(display.display, ffi::CurrentTime);
+            (display.xlib.XDefineCursor)(display.display, window, 0);
+            (display.xlib.XFlush)(display.display);
+        }
+    }
+    windows.clear();
+}
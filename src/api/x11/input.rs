@@ -1,13 +1,13 @@
 use std::sync::Arc;
 
 use libc;
-use std::{mem, ptr};
+use std::{cmp, mem, ptr};
 use std::ffi::CString;
 use std::slice::from_raw_parts;
 
 use WindowAttributes;
 
-use events::Event;
+use events::{Event, TouchPhase};
 
 use super::{events, ffi};
 use super::XConnection;
@@ -40,7 +40,12 @@ struct InputState {
     cursor_pos: (f64, f64),
     /// Last-seen positions of axes, used to report delta
     /// movements when a new absolute axis value is received
-    axis_values: Vec<AxisValue>
+    axis_values: Vec<AxisValue>,
+    /// Last-seen positions of the fingers on a touchscreen,
+    /// used to recognize pinch and rotation gestures
+    touches: Vec<(u64, (f64, f64))>,
+    /// Gestures recognized while translating the last event
+    gestures: Vec<Event>,
 }
 
 pub struct XInputEventHandler {
@@ -50,6 +55,8 @@ pub struct XInputEventHandler {
     axis_list: Vec<Axis>,
     current_state: InputState,
     multitouch: bool,
+    /// Whether the raw motion events of the root window are selected and reported
+    raw_motion: bool,
 }
 
 impl XInputEventHandler {
@@ -116,14 +123,54 @@ impl XInputEventHandler {
             axis_list: read_input_axis_info(display),
             current_state: InputState {
                 cursor_pos: (0.0, 0.0),
-                axis_values: Vec::new()
+                axis_values: Vec::new(),
+                touches: Vec::new(),
+                gestures: Vec::new(),
             },
             multitouch: window_attrs.multitouch,
+            raw_motion: false,
         }
     }
 
+    /// Starts or stops reporting `MouseRawMovement` events.
+    ///
+    /// Raw events are only delivered to the root window, and require XInput 2.1. They are
+    /// routed to the last window that enabled them, and stay selected until no window wants them.
+    pub fn set_raw_motion(&mut self, enabled: bool) {
+        if self.raw_motion == enabled {
+            return;
+        }
+        self.raw_motion = enabled;
+
+        if self.display.route_raw_events(self.window, enabled) > 0 && !enabled {
+            // another window still reports them
+            return;
+        }
+
+        let mut mask: [libc::c_uchar; 3] = [0; 3];
+        if enabled {
+            ffi::XISetMask(&mut mask, ffi::XI_RawMotion);
+        }
+        let mut input_event_mask = ffi::XIEventMask {
+            deviceid: ffi::XIAllMasterDevices,
+            mask_len: mask.len() as i32,
+            mask: mask.as_mut_ptr()
+        };
+
+        unsafe {
+            // the root window of the screen of the window, which isn't always the default one
+            let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+            (self.display.xlib.XGetWindowAttributes)(self.display.display, self.window,
+                                                     &mut attributes);
+            let root = attributes.root;
+            (self.display.xinput2.XISelectEvents)(self.display.display, root,
+                                                  &mut input_event_mask, 1);
+        }
+    }
+
+
     pub fn translate_key_event(&self, event: &mut ffi::XKeyEvent) -> Vec<Event> {
-        use events::Event::{KeyboardInput, ReceivedCharacter};
+        use events::Event::{KeyboardInput, ReceivedCharacter, ReceivedText};
         use events::ElementState::{Pressed, Released};
 
         let mut translated_events = Vec::new();
@@ -142,15 +189,27 @@ impl XInputEventHandler {
         let written = unsafe {
             use std::str;
 
-            let mut buffer: [u8; 16] = [mem::uninitialized(); 16];
+            // the input methods commit whole strings, which can be longer than the buffer
+            let mut buffer: Vec<u8> = vec![0; 16];
             let raw_ev: *mut ffi::XKeyEvent = event;
-            let count = (self.display.xlib.Xutf8LookupString)(self.ic, mem::transmute(raw_ev),
+            let mut status = 0;
+            let mut count = (self.display.xlib.Xutf8LookupString)(self.ic, mem::transmute(raw_ev),
             mem::transmute(buffer.as_mut_ptr()),
-            buffer.len() as libc::c_int, &mut kp_keysym, ptr::null_mut());
+            buffer.len() as libc::c_int, &mut kp_keysym, &mut status);
+
+            if status == ffi::XBufferOverflow {
+                buffer = vec![0; count as usize];
+                count = (self.display.xlib.Xutf8LookupString)(self.ic, mem::transmute(raw_ev),
+                mem::transmute(buffer.as_mut_ptr()),
+                buffer.len() as libc::c_int, &mut kp_keysym, &mut status);
+            }
 
             str::from_utf8(&buffer[..count as usize]).unwrap_or("").to_string()
         };
 
+        if !written.is_empty() {
+            translated_events.push(ReceivedText(written.clone()));
+        }
         for chr in written.chars() {
             translated_events.push(ReceivedCharacter(chr));
         }
@@ -165,16 +224,17 @@ impl XInputEventHandler {
 
         let vkey = events::keycode_to_element(keysym as libc::c_uint);
 
-        translated_events.push(KeyboardInput(state, event.keycode as u8, vkey));
+        translated_events.push(KeyboardInput(state, event.keycode as u8, vkey,
+                                             events::modifiers_from_state(event.state)));
         translated_events
     }
 
     pub fn translate_event(&mut self, cookie: &ffi::XGenericEventCookie) -> Option<Event> {
-        use events::Event::{Focused, MouseInput, MouseMoved, MouseWheel};
+        use events::Event::{Focused, MouseEntered, MouseInput, MouseLeft, MouseMoved, MouseWheel};
         use events::ElementState::{Pressed, Released};
         use events::MouseButton::{Left, Right, Middle};
         use events::MouseScrollDelta::LineDelta;
-        use events::{Touch, TouchPhase};
+        use events::Touch;
 
         match cookie.evtype {
             ffi::XI_ButtonPress | ffi::XI_ButtonRelease => {
@@ -188,10 +248,11 @@ impl XInputEventHandler {
                 } else {
                     Released
                 };
+                let modifiers = events::modifiers_from_state(event_data.mods.effective as libc::c_uint);
                 match event_data.detail as u32 {
-                    ffi::Button1 => Some(MouseInput(state, Left, None)),
-                    ffi::Button2 => Some(MouseInput(state, Middle, None)),
-                    ffi::Button3 => Some(MouseInput(state, Right, None)),
+                    ffi::Button1 => Some(MouseInput(state, Left, None, modifiers)),
+                    ffi::Button2 => Some(MouseInput(state, Middle, None, modifiers)),
+                    ffi::Button3 => Some(MouseInput(state, Right, None, modifiers)),
                     ffi::Button4 | ffi::Button5 => {
                         if event_data.flags & ffi::XIPointerEmulated == 0 {
                             // scroll event from a traditional wheel with
@@ -201,7 +262,7 @@ impl XInputEventHandler {
                             } else {
                                 -1.0
                             };
-                            Some(MouseWheel(LineDelta(0.0, delta), TouchPhase::Moved))
+                            Some(MouseWheel(LineDelta(0.0, delta), TouchPhase::Moved, false))
                         } else {
                             // emulated button event from a touch/smooth-scroll
                             // event. Ignore these events and handle scrolling
@@ -235,8 +296,10 @@ impl XInputEventHandler {
                 }
 
                 if scroll_delta.0.abs() > 0.0 || scroll_delta.1.abs() > 0.0 {
+                    // XInput2 doesn't report when the fingers are lifted from a touchpad, nor
+                    // inertial scrolling
                     Some(MouseWheel(LineDelta(scroll_delta.0 as f32, scroll_delta.1 as f32),
-                                    TouchPhase::Moved))
+                                    TouchPhase::Moved, false))
                 } else {
                     let new_cursor_pos = (event_data.event_x, event_data.event_y);
                     if new_cursor_pos != self.current_state.cursor_pos {
@@ -254,9 +317,22 @@ impl XInputEventHandler {
                 // our window however, so clear the previous axis state whenever
                 // the cursor re-enters the window
                 self.current_state.axis_values.clear();
-                None
+
+                let event_data: &ffi::XIEnterEvent = unsafe{mem::transmute(cookie.data)};
+                if event_data.detail == ffi::NotifyInferior {
+                    // the cursor comes back from a child window
+                    return None
+                }
+                Some(MouseEntered)
+            },
+            ffi::XI_Leave => {
+                let event_data: &ffi::XILeaveEvent = unsafe{mem::transmute(cookie.data)};
+                if event_data.detail == ffi::NotifyInferior {
+                    // the cursor went into a child window, it is still above ours
+                    return None
+                }
+                Some(MouseLeft)
             },
-            ffi::XI_Leave => None,
             ffi::XI_FocusIn => Some(Focused(true)),
             ffi::XI_FocusOut => Some(Focused(false)),
             ffi::XI_TouchBegin | ffi::XI_TouchUpdate | ffi::XI_TouchEnd => {
@@ -270,15 +346,97 @@ impl XInputEventHandler {
                     ffi::XI_TouchEnd => TouchPhase::Ended,
                     _ => unreachable!()
                 };
+                self.recognize_gestures(phase, event_data.detail as u64,
+                                        (event_data.event_x, event_data.event_y));
                 Some(Event::Touch(Touch {
                     phase: phase,
                     location: (event_data.event_x, event_data.event_y),
                     id: event_data.detail as u64,
                 }))
             }
+            ffi::XI_RawMotion => {
+                if !self.raw_motion {
+                    return None;
+                }
+
+                // the first two valuators of a pointer are its X and Y axes, and the raw values
+                // are only given for the valuators that are set
+                let event_data: &ffi::XIRawEvent = unsafe{mem::transmute(cookie.data)};
+                let axis_state = event_data.valuators;
+                let mask = unsafe{ from_raw_parts(axis_state.mask, axis_state.mask_len as usize) };
+                let mut axis_count = 0;
+
+                let mut delta = (0.0, 0.0);
+                for axis_id in 0..cmp::min(2, axis_state.mask_len * 8) {
+                    if ffi::XIMaskIsSet(&mask, axis_id) {
+                        let value = unsafe{*event_data.raw_values.offset(axis_count)};
+                        if axis_id == 0 { delta.0 = value } else { delta.1 = value }
+                        axis_count += 1;
+                    }
+                }
+
+                if delta == (0.0, 0.0) {
+                    None
+                } else {
+                    Some(Event::MouseRawMovement(delta.0, delta.1))
+                }
+            }
             _ => None
         }
     }
+    /// Returns the gestures recognized while translating the last event.
+    pub fn take_gestures(&mut self) -> Vec<Event> {
+        mem::replace(&mut self.current_state.gestures, Vec::new())
+    }
+
+    /// Updates the positions of the fingers, and recognizes a pinch or a rotation when exactly
+    /// two fingers are on the screen and one of them moves.
+    fn recognize_gestures(&mut self, phase: TouchPhase, id: u64, location: (f64, f64)) {
+        let touches = &mut self.current_state.touches;
+
+        let index = touches.iter().position(|&(i, _)| i == id);
+        match (phase, index) {
+            (TouchPhase::Started, None) => touches.push((id, location)),
+            (TouchPhase::Moved, Some(index)) => {
+                let previous = touches.clone();
+                touches[index].1 = location;
+
+                if touches.len() != 2 {
+                    return;
+                }
+
+                let (old_distance, old_angle) = polar(previous[0].1, previous[1].1);
+                let (new_distance, new_angle) = polar(touches[0].1, touches[1].1);
+
+                if old_distance > 0.0 && new_distance != old_distance {
+                    let scale = new_distance / old_distance - 1.0;
+                    self.current_state.gestures.push(Event::PinchGesture(scale as f32));
+                }
+
+                // the y axis points downwards, so counterclockwise angles decrease
+                let mut rotation = (old_angle - new_angle).to_degrees();
+                if rotation > 180.0 {
+                    rotation -= 360.0;
+                } else if rotation < -180.0 {
+                    rotation += 360.0;
+                }
+                if rotation != 0.0 {
+                    self.current_state.gestures.push(Event::RotateGesture(rotation as f32));
+                }
+            },
+            (TouchPhase::Ended, Some(index)) | (TouchPhase::Cancelled, Some(index)) => {
+                touches.remove(index);
+            },
+            _ => ()
+        }
+    }
+
+}
+
+/// Returns the distance between two points, and the angle of the line that joins them.
+fn polar(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    ((dx * dx + dy * dy).sqrt(), dy.atan2(dx))
 }
 
 fn read_input_axis_info(display: &Arc<XConnection>) -> Vec<Axis> {
@@ -0,0 +1,725 @@
+//! A menu bar drawn at the top of the window, when no global menu can show the menus.
+//!
+//! The bar is a child window of the glutin window, and the menus are override-redirect windows
+//! that it opens below itself. Everything is drawn with the core protocol and the "fixed" font,
+//! which every X server has, so the labels are limited to Latin-1.
+//!
+//! While a menu is open, the bar grabs the pointer and the keyboard. The events of all these
+//! windows are routed to the glutin window, which passes them to `MenuBar::handle_event`.
+
+use std::sync::Arc;
+use std::{cmp, mem, ptr};
+
+use libc::{c_char, c_int, c_uint, c_ulong};
+
+use Menu;
+use MenuItem;
+
+use super::ffi;
+use super::XConnection;
+
+/// The space around the labels, in pixels.
+const PADDING: c_int = 4;
+/// The space between a label and its accelerator.
+const SHORTCUT_GAP: c_int = 24;
+/// The width of the column of the arrows of the submenus.
+const ARROW_WIDTH: c_int = 12;
+
+const BACKGROUND: u32 = 0xe8e8e8;
+const TEXT: u32 = 0x000000;
+const DISABLED_TEXT: u32 = 0x909090;
+const HIGHLIGHT: u32 = 0x3465a4;
+const HIGHLIGHTED_TEXT: u32 = 0xffffff;
+const BORDER: u32 = 0x808080;
+
+pub struct MenuBar {
+    display: Arc<XConnection>,
+    /// The glutin window
+    owner: ffi::Window,
+    /// The child window of the bar
+    window: ffi::Window,
+    root: ffi::Window,
+    visual: *mut ffi::Visual,
+    depth: c_int,
+    colormap: ffi::Colormap,
+    /// The white pixel of the screen, for the visuals without color masks
+    white_pixel: c_ulong,
+    gc: ffi::GC,
+    font: *mut ffi::XFontStruct,
+    menus: Vec<Menu>,
+    width: c_uint,
+    /// The horizontal span of the title of each menu in the bar
+    titles: Vec<(c_int, c_int)>,
+    /// The index of the menu of the bar that is open, if any
+    open: Option<usize>,
+    /// The open menus, from the menu of the bar to the deepest submenu
+    popups: Vec<Popup>,
+}
+
+/// An open menu.
+struct Popup {
+    window: ffi::Window,
+    /// The position of the menu in the root window
+    x: c_int,
+    y: c_int,
+    width: c_int,
+    height: c_int,
+    menu: Menu,
+    /// The vertical span of each item
+    rows: Vec<(c_int, c_int)>,
+    highlighted: Option<usize>,
+}
+
+impl MenuBar {
+    /// Creates the bar at the top of a window, whose width is given.
+    ///
+    /// Returns `None` if the "fixed" font is missing, which only happens with broken setups.
+    pub fn new(display: &Arc<XConnection>, owner: ffi::Window, width: c_uint, menus: &[Menu])
+               -> Option<MenuBar>
+    {
+        unsafe {
+            let name = b"fixed\0";
+            let font = (display.xlib.XLoadQueryFont)(display.display,
+                                                     name.as_ptr() as *const c_char);
+            if font.is_null() {
+                return None;
+            }
+
+            // the bar and the menus use the visual of the window, so that they can share a GC
+            let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+            display.push_error_trap();
+            (display.xlib.XGetWindowAttributes)(display.display, owner, &mut attributes);
+            if display.pop_error_trap().is_err() {
+                (display.xlib.XFreeFont)(display.display, font);
+                return None;
+            }
+
+            let mut menu_bar = MenuBar {
+                display: display.clone(),
+                owner: owner,
+                window: 0,
+                root: attributes.root,
+                visual: attributes.visual,
+                depth: attributes.depth,
+                colormap: attributes.colormap,
+                white_pixel: (*attributes.screen).white_pixel,
+                gc: ptr::null_mut(),
+                font: font,
+                menus: Vec::new(),
+                width: cmp::max(width, 1),
+                titles: Vec::new(),
+                open: None,
+                popups: Vec::new(),
+            };
+
+            display.push_error_trap();
+            menu_bar.window = menu_bar.create_window(owner, 0, 0, menu_bar.width,
+                                                     menu_bar.line_height() as c_uint, false);
+            menu_bar.gc = (display.xlib.XCreateGC)(display.display, menu_bar.window, 0,
+                                                   ptr::null_mut());
+            (display.xlib.XSetFont)(display.display, menu_bar.gc, (*font).fid);
+            display.pop_error_trap().expect("Failed to create the menu bar");
+
+            menu_bar.set_menus(menus);
+            (display.xlib.XMapRaised)(display.display, menu_bar.window);
+            (display.xlib.XFlush)(display.display);
+            Some(menu_bar)
+        }
+    }
+
+    /// Returns true if the window is the bar or one of its menus.
+    pub fn owns(&self, window: ffi::Window) -> bool {
+        window == self.window || self.popups.iter().any(|p| p.window == window)
+    }
+
+    /// Replaces the menus of the bar, closing the open ones.
+    pub fn set_menus(&mut self, menus: &[Menu]) {
+        self.close_all();
+        self.menus = menus.to_vec();
+
+        let mut x = 0;
+        self.titles = self.menus.iter().map(|menu| {
+            let width = self.text_width(&menu.title) + 4 * PADDING;
+            x += width;
+            (x - width, x)
+        }).collect();
+
+        self.draw_bar();
+    }
+
+    /// Follows the width of the window.
+    pub fn set_width(&mut self, width: c_uint) {
+        let width = cmp::max(width, 1);
+        if width == self.width {
+            return;
+        }
+        self.width = width;
+
+        unsafe {
+            (self.display.xlib.XResizeWindow)(self.display.display, self.window, width,
+                                              self.line_height() as c_uint);
+        }
+    }
+
+    /// Handles an event of the bar or of its menus. Returns the ID of the chosen item, if any.
+    ///
+    /// No menu is opened if `can_open` is false, for example because the application has
+    /// grabbed the cursor.
+    pub fn handle_event(&mut self, xev: &ffi::XEvent, can_open: bool) -> Option<u32> {
+        match xev.get_type() {
+            ffi::Expose => {
+                let expose = ffi::XExposeEvent::from(*xev);
+                if expose.count == 0 {
+                    if expose.window == self.window {
+                        self.draw_bar();
+                    } else if let Some(index) = self.popups.iter().position(|p| p.window == expose.window) {
+                        self.draw_popup(index);
+                    }
+                }
+                None
+            },
+
+            ffi::ButtonPress => {
+                let button = ffi::XButtonEvent::from(*xev);
+                if button.button != ffi::Button1 {
+                    return None;
+                }
+
+                match self.title_at(button.x_root, button.y_root) {
+                    Some(index) if self.open == Some(index) => self.close_all(),
+                    Some(index) if self.open.is_some() || can_open => {
+                        self.open_menu(index, button.time)
+                    },
+                    Some(_) => (),
+                    None => {
+                        if self.popup_at(button.x_root, button.y_root).is_none() {
+                            self.close_all();
+                        }
+                    },
+                }
+                None
+            },
+
+            ffi::ButtonRelease => {
+                let button = ffi::XButtonEvent::from(*xev);
+                if button.button != ffi::Button1 {
+                    return None;
+                }
+
+                match self.popup_at(button.x_root, button.y_root) {
+                    Some((popup, Some(row))) => self.activate(popup, row),
+                    _ => None,
+                }
+            },
+
+            ffi::MotionNotify => {
+                let motion = ffi::XMotionEvent::from(*xev);
+                if self.open.is_none() {
+                    return None;
+                }
+
+                if let Some(index) = self.title_at(motion.x_root, motion.y_root) {
+                    if self.open != Some(index) {
+                        self.open_menu(index, motion.time);
+                    }
+                } else if let Some((popup, row)) = self.popup_at(motion.x_root, motion.y_root) {
+                    self.highlight(popup, row);
+                    if let Some(row) = row {
+                        self.open_submenu(popup, row);
+                    }
+                }
+                None
+            },
+
+            ffi::KeyPress => {
+                let mut key = ffi::XKeyEvent::from(*xev);
+                if self.open.is_none() {
+                    return None;
+                }
+                let keysym = unsafe { (self.display.xlib.XLookupKeysym)(&mut key, 0) };
+                self.handle_key(keysym as c_uint, key.time)
+            },
+
+            _ => None,
+        }
+    }
+
+    fn handle_key(&mut self, keysym: c_uint, time: ffi::Time) -> Option<u32> {
+        let last = self.popups.len() - 1;
+
+        match keysym {
+            ffi::XK_Escape => {
+                if last == 0 {
+                    self.close_all();
+                } else {
+                    self.close_popups_after(last - 1);
+                }
+            },
+            ffi::XK_Up | ffi::XK_Down => {
+                let forward = keysym == ffi::XK_Down;
+                let row = self.next_row(last, forward);
+                self.highlight(last, row);
+            },
+            ffi::XK_Return | ffi::XK_KP_Enter | ffi::XK_space => {
+                if let Some(row) = self.popups[last].highlighted {
+                    return self.activate(last, row);
+                }
+            },
+            ffi::XK_Right => {
+                let submenu = self.popups[last].highlighted.and_then(|row| {
+                    match self.popups[last].menu.items[row] {
+                        MenuItem::Submenu(_) => Some(row),
+                        _ => None,
+                    }
+                });
+                match submenu {
+                    Some(row) => {
+                        self.open_submenu(last, row);
+                        let first = self.next_row(last + 1, true);
+                        self.highlight(last + 1, first);
+                    },
+                    None => {
+                        let next = (self.open.unwrap() + 1) % self.menus.len();
+                        self.open_menu(next, time);
+                    },
+                }
+            },
+            ffi::XK_Left => {
+                if last > 0 {
+                    self.close_popups_after(last - 1);
+                } else {
+                    let count = self.menus.len();
+                    let previous = (self.open.unwrap() + count - 1) % count;
+                    self.open_menu(previous, time);
+                }
+            },
+            _ => (),
+        }
+        None
+    }
+
+    /// Chooses an item. Returns its ID if it is an enabled action.
+    fn activate(&mut self, popup: usize, row: usize) -> Option<u32> {
+        match self.popups[popup].menu.items[row] {
+            MenuItem::Action { id, enabled: true, .. } => {
+                self.close_all();
+                Some(id)
+            },
+            MenuItem::Submenu(_) => {
+                self.open_submenu(popup, row);
+                None
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the next enabled row of a menu, after the highlighted one, or before it if
+    /// `forward` is false.
+    fn next_row(&self, popup: usize, forward: bool) -> Option<usize> {
+        let items = &self.popups[popup].menu.items;
+        let count = items.len();
+        if count == 0 {
+            return None;
+        }
+
+        let start = self.popups[popup].highlighted;
+        for step in 1..count + 1 {
+            let row = match (start, forward) {
+                (Some(row), true) => (row + step) % count,
+                (Some(row), false) => (row + count - step % count) % count,
+                (None, true) => step - 1,
+                (None, false) => count - step,
+            };
+            if is_selectable(&items[row]) {
+                return Some(row);
+            }
+        }
+        None
+    }
+
+    /// The height of the bar, in pixels.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.line_height() as u32
+    }
+
+    /// The height of the bar and of the items of the menus.
+    fn line_height(&self) -> c_int {
+        unsafe { (*self.font).ascent + (*self.font).descent + 2 * PADDING }
+    }
+
+    fn text_width(&self, text: &str) -> c_int {
+        let text = latin1(text);
+        unsafe {
+            (self.display.xlib.XTextWidth)(self.font, text.as_ptr() as *const c_char,
+                                           text.len() as c_int)
+        }
+    }
+
+    /// The menu of the bar whose title is at a point of the root window.
+    fn title_at(&self, x: c_int, y: c_int) -> Option<usize> {
+        let (bar_x, bar_y) = self.bar_origin();
+        let (x, y) = (x - bar_x, y - bar_y);
+        if y < 0 || y >= self.line_height() {
+            return None;
+        }
+        self.titles.iter().position(|&(start, end)| x >= start && x < end)
+    }
+
+    /// The deepest open menu at a point of the root window, and the selectable item under it.
+    fn popup_at(&self, x: c_int, y: c_int) -> Option<(usize, Option<usize>)> {
+        self.popups.iter().enumerate().rev().find(|&(_, p)| {
+            x >= p.x && x < p.x + p.width && y >= p.y && y < p.y + p.height
+        }).map(|(index, popup)| {
+            let row = popup.rows.iter().position(|&(top, bottom)| {
+                y - popup.y >= top && y - popup.y < bottom
+            });
+            (index, row.and_then(|row| {
+                if is_selectable(&popup.menu.items[row]) { Some(row) } else { None }
+            }))
+        })
+    }
+
+    /// The position of the bar in the root window.
+    fn bar_origin(&self) -> (c_int, c_int) {
+        let (mut x, mut y, mut child) = (0, 0, 0);
+        unsafe {
+            (self.display.xlib.XTranslateCoordinates)(self.display.display, self.window,
+                                                      self.root, 0, 0, &mut x, &mut y,
+                                                      &mut child);
+        }
+        (x, y)
+    }
+
+    /// Opens a menu of the bar, closing the other one.
+    fn open_menu(&mut self, index: usize, time: ffi::Time) {
+        let was_open = self.open.is_some();
+        self.close_popups_after(0);
+        if let Some(popup) = self.popups.pop() {
+            self.destroy_popup(popup);
+        }
+
+        let (bar_x, bar_y) = self.bar_origin();
+        let menu = self.menus[index].clone();
+        let popup = self.create_popup(menu, bar_x + self.titles[index].0, bar_y + self.line_height());
+        self.popups.push(popup);
+        self.open = Some(index);
+        self.draw_bar();
+
+        if !was_open {
+            self.grab(time);
+        }
+    }
+
+    /// Opens the submenu of an item, if it isn't already.
+    fn open_submenu(&mut self, popup: usize, row: usize) {
+        let submenu = match self.popups[popup].menu.items[row] {
+            MenuItem::Submenu(ref submenu) => submenu.clone(),
+            _ => {
+                self.close_popups_after(popup);
+                return;
+            },
+        };
+
+        if self.popups.len() > popup + 1 && self.popups[popup + 1].menu == submenu &&
+           self.popups[popup].highlighted == Some(row)
+        {
+            return;
+        }
+
+        self.close_popups_after(popup);
+        self.highlight(popup, Some(row));
+        let x = self.popups[popup].x + self.popups[popup].width;
+        let y = self.popups[popup].y + self.popups[popup].rows[row].0 - PADDING;
+        let child = self.create_popup(submenu, x, y);
+        self.popups.push(child);
+    }
+
+    /// Closes the submenus of a menu.
+    fn close_popups_after(&mut self, popup: usize) {
+        while self.popups.len() > popup + 1 {
+            let child = self.popups.pop().unwrap();
+            self.destroy_popup(child);
+        }
+    }
+
+    fn close_all(&mut self) {
+        if self.open.is_none() {
+            return;
+        }
+
+        while let Some(popup) = self.popups.pop() {
+            self.destroy_popup(popup);
+        }
+        self.open = None;
+
+        unsafe {
+            (self.display.xlib.XUngrabKeyboard)(self.display.display, ffi::CurrentTime);
+            (self.display.xlib.XUngrabPointer)(self.display.display, ffi::CurrentTime);
+            (self.display.xlib.XFlush)(self.display.display);
+        }
+        self.draw_bar();
+    }
+
+    /// Sends all the input to the bar while the menus are open.
+    fn grab(&self, time: ffi::Time) {
+        unsafe {
+            (self.display.xlib.XGrabPointer)(self.display.display, self.window, ffi::False,
+                                             (ffi::ButtonPressMask | ffi::ButtonReleaseMask |
+                                              ffi::PointerMotionMask) as c_uint,
+                                             ffi::GrabModeAsync, ffi::GrabModeAsync, 0, 0, time);
+            (self.display.xlib.XGrabKeyboard)(self.display.display, self.window, ffi::False,
+                                              ffi::GrabModeAsync, ffi::GrabModeAsync, time);
+            (self.display.xlib.XFlush)(self.display.display);
+        }
+    }
+
+    fn highlight(&mut self, popup: usize, row: Option<usize>) {
+        if self.popups[popup].highlighted != row {
+            self.popups[popup].highlighted = row;
+            self.draw_popup(popup);
+        }
+    }
+
+    /// Creates the window of a menu, at a position of the root window.
+    fn create_popup(&self, menu: Menu, x: c_int, y: c_int) -> Popup {
+        let line_height = self.line_height();
+        let separator_height = 2 * PADDING + 1;
+
+        let mut rows = Vec::with_capacity(menu.items.len());
+        let mut top = PADDING;
+        let mut width = 0;
+        for item in menu.items.iter() {
+            let height = match *item {
+                MenuItem::Separator => separator_height,
+                _ => line_height,
+            };
+            rows.push((top, top + height));
+            top += height;
+
+            width = cmp::max(width, match *item {
+                MenuItem::Action { ref label, accelerator, .. } => {
+                    self.text_width(label) + accelerator.map_or(0, |a| {
+                        SHORTCUT_GAP + self.text_width(&a.to_string())
+                    })
+                },
+                MenuItem::Submenu(ref submenu) => self.text_width(&submenu.title),
+                MenuItem::Separator => 0,
+            });
+        }
+
+        let width = width + ARROW_WIDTH + 4 * PADDING;
+        let height = top + PADDING;
+        let window = self.create_window(self.root, x, y, width as c_uint, height as c_uint, true);
+
+        unsafe {
+            let dropdown = self.display.get_atom("_NET_WM_WINDOW_TYPE_DROPDOWN_MENU");
+            (self.display.xlib.XChangeProperty)(self.display.display, window,
+                                                self.display.get_atom("_NET_WM_WINDOW_TYPE"),
+                                                ffi::XA_ATOM, 32, ffi::PropModeReplace,
+                                                &dropdown as *const ffi::Atom as *const _, 1);
+            (self.display.xlib.XMapRaised)(self.display.display, window);
+            (self.display.xlib.XFlush)(self.display.display);
+        }
+
+        Popup {
+            window: window,
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+            menu: menu,
+            rows: rows,
+            highlighted: None,
+        }
+    }
+
+    fn destroy_popup(&self, popup: Popup) {
+        self.display.unwatch_window(self.owner, popup.window);
+        unsafe {
+            (self.display.xlib.XDestroyWindow)(self.display.display, popup.window);
+        }
+    }
+
+    /// Creates the bar or a menu. Their events are reported by the glutin window.
+    fn create_window(&self, parent: ffi::Window, x: c_int, y: c_int, width: c_uint,
+                     height: c_uint, override_redirect: bool) -> ffi::Window
+    {
+        unsafe {
+            let mut swa: ffi::XSetWindowAttributes = mem::zeroed();
+            swa.colormap = self.colormap;
+            // needed when the visual differs from the one of the parent
+            swa.border_pixel = 0;
+            swa.background_pixel = self.pixel(BACKGROUND);
+            swa.override_redirect = if override_redirect { ffi::True } else { ffi::False };
+            swa.event_mask = ffi::ExposureMask | ffi::ButtonPressMask | ffi::ButtonReleaseMask |
+                             ffi::PointerMotionMask;
+
+            self.display.push_error_trap();
+            let window = (self.display.xlib.XCreateWindow)(
+                self.display.display, parent, x, y, width, height, 0, self.depth,
+                ffi::InputOutput as c_uint, self.visual,
+                ffi::CWColormap | ffi::CWBorderPixel | ffi::CWBackPixel | ffi::CWOverrideRedirect |
+                ffi::CWEventMask,
+                &mut swa);
+            self.display.pop_error_trap().expect("Failed to call XCreateWindow");
+
+            self.display.watch_window(self.owner, window);
+            window
+        }
+    }
+
+    /// Converts a color to a pixel of the visual of the window.
+    fn pixel(&self, rgb: u32) -> c_ulong {
+        let visual = unsafe { &*self.visual };
+        if visual.red_mask == 0 {
+            // not a true color visual, which doesn't happen with OpenGL
+            return if rgb > 0x808080 { self.white_pixel } else { 0 };
+        }
+
+        fn channel(mask: c_ulong, value: u32) -> c_ulong {
+            let shift = mask.trailing_zeros();
+            let bits = cmp::min((mask >> shift).count_ones(), 8);
+            (((value as c_ulong) >> (8 - bits)) << shift) & mask
+        }
+
+        let mut pixel = channel(visual.red_mask, (rgb >> 16) & 0xff) |
+                        channel(visual.green_mask, (rgb >> 8) & 0xff) |
+                        channel(visual.blue_mask, rgb & 0xff);
+        if self.depth == 32 {
+            // the remaining bits are the alpha channel of ARGB visuals
+            pixel |= 0xffff_ffff & !(visual.red_mask | visual.green_mask | visual.blue_mask);
+        }
+        pixel
+    }
+
+    fn set_color(&self, rgb: u32) {
+        unsafe { (self.display.xlib.XSetForeground)(self.display.display, self.gc, self.pixel(rgb)) };
+    }
+
+    fn fill(&self, window: ffi::Window, x: c_int, y: c_int, width: c_int, height: c_int) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        unsafe {
+            (self.display.xlib.XFillRectangle)(self.display.display, window, self.gc, x, y,
+                                               width as c_uint, height as c_uint);
+        }
+    }
+
+    /// Draws a line of text whose top is at `y`.
+    fn draw_text(&self, window: ffi::Window, x: c_int, y: c_int, text: &str) {
+        let text = latin1(text);
+        unsafe {
+            (self.display.xlib.XDrawString)(self.display.display, window, self.gc, x,
+                                            y + PADDING + (*self.font).ascent,
+                                            text.as_ptr() as *const c_char, text.len() as c_int);
+        }
+    }
+
+    fn draw_bar(&self) {
+        let height = self.line_height();
+        self.set_color(BACKGROUND);
+        self.fill(self.window, 0, 0, self.width as c_int, height);
+
+        for (index, (menu, &(start, end))) in self.menus.iter().zip(self.titles.iter()).enumerate() {
+            if self.open == Some(index) {
+                self.set_color(HIGHLIGHT);
+                self.fill(self.window, start, 0, end - start, height);
+                self.set_color(HIGHLIGHTED_TEXT);
+            } else {
+                self.set_color(TEXT);
+            }
+            self.draw_text(self.window, start + 2 * PADDING, 0, &menu.title);
+        }
+
+        self.set_color(BORDER);
+        self.fill(self.window, 0, height - 1, self.width as c_int, 1);
+        unsafe { (self.display.xlib.XFlush)(self.display.display) };
+    }
+
+    fn draw_popup(&self, index: usize) {
+        let popup = &self.popups[index];
+        let window = popup.window;
+
+        self.set_color(BORDER);
+        self.fill(window, 0, 0, popup.width, popup.height);
+        self.set_color(BACKGROUND);
+        self.fill(window, 1, 1, popup.width - 2, popup.height - 2);
+
+        for (row, (item, &(top, bottom))) in popup.menu.items.iter().zip(popup.rows.iter()).enumerate() {
+            let highlighted = popup.highlighted == Some(row);
+            if highlighted {
+                self.set_color(HIGHLIGHT);
+                self.fill(window, 1, top, popup.width - 2, bottom - top);
+            }
+
+            match *item {
+                MenuItem::Action { ref label, accelerator, enabled, .. } => {
+                    self.set_color(if !enabled { DISABLED_TEXT }
+                                   else if highlighted { HIGHLIGHTED_TEXT }
+                                   else { TEXT });
+                    self.draw_text(window, 2 * PADDING, top, label);
+                    if let Some(accelerator) = accelerator {
+                        let shortcut = accelerator.to_string();
+                        let x = popup.width - 2 * PADDING - ARROW_WIDTH - self.text_width(&shortcut);
+                        self.draw_text(window, x, top, &shortcut);
+                    }
+                },
+                MenuItem::Separator => {
+                    self.set_color(BORDER);
+                    self.fill(window, PADDING, top + PADDING, popup.width - 2 * PADDING, 1);
+                },
+                MenuItem::Submenu(ref submenu) => {
+                    self.set_color(if highlighted { HIGHLIGHTED_TEXT } else { TEXT });
+                    self.draw_text(window, 2 * PADDING, top, &submenu.title);
+                    self.draw_text(window, popup.width - 2 * PADDING - self.text_width(">"),
+                                   top, ">");
+                },
+            }
+        }
+
+        unsafe { (self.display.xlib.XFlush)(self.display.display) };
+    }
+}
+
+impl Drop for MenuBar {
+    fn drop(&mut self) {
+        let display = self.display.clone();
+        // the bar is destroyed with the glutin window, which may already be gone
+        display.push_error_trap();
+        self.close_all();
+        unsafe {
+            display.unwatch_window(self.owner, self.window);
+            (display.xlib.XDestroyWindow)(display.display, self.window);
+            (display.xlib.XFreeGC)(display.display, self.gc);
+            (display.xlib.XFreeFont)(display.display, self.font);
+        }
+        let _ = display.pop_error_trap();
+    }
+}
+
+/// Separators and disabled actions can't be highlighted.
+fn is_selectable(item: &MenuItem) -> bool {
+    match *item {
+        MenuItem::Action { enabled, .. } => enabled,
+        MenuItem::Separator => false,
+        MenuItem::Submenu(_) => true,
+    }
+}
+
+/// Converts a label to the Latin-1 encoding of the "fixed" font.
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars().map(|c| if (c as u32) < 0x100 { c as u8 } else { b'?' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::latin1;
+
+    #[test]
+    fn labels_are_converted_to_latin1() {
+        assert_eq!(latin1("Save as…"), b"Save as?".to_vec());
+        assert_eq!(latin1("Édition"), vec![0xc9, b'd', b'i', b't', b'i', b'o', b'n']);
+    }
+}
@@ -2,12 +2,55 @@
 
 pub use self::monitor::{MonitorId, get_available_monitors, get_primary_monitor};
 pub use self::window::{Window, XWindow, PollEventsIterator, WaitEventsIterator, Context, WindowProxy};
+pub use self::window::ChildView;
+pub use self::resources::ResourceDatabase;
+pub use self::wm::WmInfo;
 pub use self::xdisplay::{XConnection, XNotSupported, XError};
 
 pub mod ffi;
 
+mod clipboard;
+mod dbusmenu;
+mod decode;
+mod dnd;
 mod events;
+mod grabs;
 mod input;
+mod menu;
 mod monitor;
+mod present;
+mod resources;
 mod window;
+mod wm;
 mod xdisplay;
+
+/// Returns the delay before a held key starts repeating and the interval between its repeats,
+/// as configured in the X server.
+///
+/// Opens a connection of its own, so that the Wayland backend can read the settings of the
+/// desktop through XWayland.
+pub fn query_key_repeat() -> Option<(::std::time::Duration, ::std::time::Duration)> {
+    use std::time::Duration;
+
+    let xlib = match ffi::Xlib::open() {
+        Ok(xlib) => xlib,
+        Err(_) => return None
+    };
+
+    unsafe {
+        let display = (xlib.XOpenDisplay)(::std::ptr::null());
+        if display.is_null() {
+            return None;
+        }
+
+        let (mut delay, mut interval) = (0, 0);
+        // 0x0100 is `XkbUseCoreKbd`
+        let found = (xlib.XkbGetAutoRepeatRate)(display, 0x0100, &mut delay, &mut interval);
+        (xlib.XCloseDisplay)(display);
+
+        if found == ffi::False || interval == 0 {
+            return None;
+        }
+        Some((Duration::from_millis(delay as u64), Duration::from_millis(interval as u64)))
+    }
+}
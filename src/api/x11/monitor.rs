@@ -1,15 +1,23 @@
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::{mem, ptr, slice};
 
+use libc;
+
+use super::ffi;
+use super::wm::get_property;
 use super::XConnection;
 use native_monitor::NativeMonitorId;
 
+/// Flags of `XRRModeInfo::modeFlags`.
+const RR_INTERLACE: libc::c_ulong = 0x10;
+const RR_DOUBLE_SCAN: libc::c_ulong = 0x20;
+
 #[derive(Clone)]
 pub struct MonitorId(pub Arc<XConnection>, pub u32);
 
 pub fn get_available_monitors(x: &Arc<XConnection>) -> VecDeque<MonitorId> {
     let nb_monitors = unsafe { (x.xlib.XScreenCount)(x.display) };
-    x.check_errors().expect("Failed to call XScreenCount");
 
     let mut monitors = VecDeque::new();
     monitors.extend((0 .. nb_monitors).map(|i| MonitorId(x.clone(), i as u32)));
@@ -19,7 +27,6 @@ pub fn get_available_monitors(x: &Arc<XConnection>) -> VecDeque<MonitorId> {
 #[inline]
 pub fn get_primary_monitor(x: &Arc<XConnection>) -> MonitorId {
     let primary_monitor = unsafe { (x.xlib.XDefaultScreen)(x.display) };
-    x.check_errors().expect("Failed to call XDefaultScreen");
     MonitorId(x.clone(), primary_monitor as u32)
 }
 
@@ -38,7 +45,263 @@ impl MonitorId {
         let screen = unsafe { (self.0.xlib.XScreenOfDisplay)(self.0.display, self.1 as i32) };
         let width = unsafe { (self.0.xlib.XWidthOfScreen)(screen) };
         let height = unsafe { (self.0.xlib.XHeightOfScreen)(screen) };
-        self.0.check_errors().expect("Failed to get monitor dimensions");
         (width as u32, height as u32)
     }
+
+    /// Returns the part of the screen that is not covered by panels and docks, as
+    /// `(x, y, width, height)`.
+    ///
+    /// Read from the `_NET_WORKAREA` property for the current desktop, or the whole screen if
+    /// the window manager doesn't set it.
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        let root = unsafe { (self.0.xlib.XRootWindow)(self.0.display, self.1 as libc::c_int) };
+
+        let desktop = get_property::<libc::c_ulong>(&self.0, root,
+                                                    self.0.get_atom("_NET_CURRENT_DESKTOP"),
+                                                    ffi::XA_CARDINAL, 32)
+                          .and_then(|d| d.first().cloned())
+                          .unwrap_or(0) as usize;
+        let workareas = get_property::<libc::c_ulong>(&self.0, root,
+                                                      self.0.get_atom("_NET_WORKAREA"),
+                                                      ffi::XA_CARDINAL, 32)
+                            .unwrap_or(Vec::new());
+
+        match workareas.chunks(4).nth(desktop) {
+            Some(area) if area.len() == 4 && area[2] > 0 && area[3] > 0 => {
+                Some((area[0] as i32, area[1] as i32, area[2] as u32, area[3] as u32))
+            },
+            _ => {
+                let (width, height) = self.get_dimensions();
+                Some((0, 0, width, height))
+            },
+        }
+    }
+
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        match self.get_output_property(b"EDID\0") {
+            Some((8, data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        // the `vrr_capable` property is set by the amdgpu and nouveau drivers
+        match self.get_output_property(b"vrr_capable\0") {
+            Some((32, data)) => data.iter().any(|&b| b != 0),
+            _ => false,
+        }
+    }
+
+    /// Reads a property of the XRandR outputs of the screen. The primary output is tried first,
+    /// then the others in order. Returns the format of the property and its raw content.
+    fn get_output_property(&self, property_name: &[u8]) -> Option<(libc::c_int, Vec<u8>)> {
+        let xrandr = match self.0.xrandr {
+            Some(ref xrandr) => xrandr,
+            None => return None,
+        };
+
+        unsafe {
+            let property = (self.0.xlib.XInternAtom)(self.0.display, property_name.as_ptr() as *const _, ffi::True);
+            if property == 0 {
+                // no output has ever had this property on this server
+                return None;
+            }
+
+            let root = (self.0.xlib.XRootWindow)(self.0.display, self.1 as libc::c_int);
+            self.0.push_error_trap();
+            let resources = (xrandr.XRRGetScreenResources)(self.0.display, root);
+            if resources.is_null() {
+                let _ = self.0.pop_error_trap();
+                return None;
+            }
+
+            let primary = (xrandr.XRRGetOutputPrimary)(self.0.display, root);
+            let outputs = slice::from_raw_parts((*resources).outputs, (*resources).noutput as usize);
+            let mut candidates = vec![primary];
+            candidates.extend(outputs.iter().cloned().filter(|&o| o != primary));
+
+            let mut result = None;
+            for output in candidates.into_iter().filter(|&o| o != 0) {
+                let mut actual_type = mem::zeroed();
+                let mut actual_format = mem::zeroed();
+                let mut nitems = mem::zeroed();
+                let mut bytes_after = mem::zeroed();
+                let mut data: *mut libc::c_uchar = ptr::null_mut();
+
+                (xrandr.XRRGetOutputProperty)(self.0.display, output, property, 0, 128,
+                                              ffi::False, ffi::False, ffi::AnyPropertyType as ffi::Atom,
+                                              &mut actual_type, &mut actual_format, &mut nitems,
+                                              &mut bytes_after, &mut data);
+
+                if data.is_null() {
+                    continue;
+                }
+
+                // xlib returns properties of format 32 as arrays of `long`
+                let item_size = match actual_format {
+                    8 => 1,
+                    16 => mem::size_of::<libc::c_short>(),
+                    32 => mem::size_of::<libc::c_long>(),
+                    _ => 0,
+                };
+
+                if item_size != 0 && nitems > 0 {
+                    let len = nitems as usize * item_size;
+                    result = Some((actual_format, slice::from_raw_parts(data, len).to_vec()));
+                }
+
+                (self.0.xlib.XFree)(data as *mut _);
+
+                if result.is_some() {
+                    break;
+                }
+            }
+
+            (xrandr.XRRFreeScreenResources)(resources);
+            if self.0.pop_error_trap().is_err() {
+                return None;
+            }
+            result
+        }
+    }
+
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        unsafe {
+            let icc_atom = (self.0.xlib.XInternAtom)(self.0.display, b"_ICC_PROFILE\0".as_ptr() as *const _, ffi::True);
+            if icc_atom == 0 {
+                return None;
+            }
+
+            let root = (self.0.xlib.XRootWindow)(self.0.display, self.1 as libc::c_int);
+
+            let mut actual_type = mem::zeroed();
+            let mut actual_format = mem::zeroed();
+            let mut nitems = mem::zeroed();
+            let mut bytes_after = mem::zeroed();
+            let mut data: *mut libc::c_uchar = ptr::null_mut();
+
+            self.0.push_error_trap();
+            (self.0.xlib.XGetWindowProperty)(self.0.display, root, icc_atom, 0,
+                                             libc::c_long::max_value() / 4, ffi::False,
+                                             ffi::AnyPropertyType as ffi::Atom, &mut actual_type,
+                                             &mut actual_format, &mut nitems, &mut bytes_after,
+                                             &mut data);
+            let failed = self.0.pop_error_trap().is_err();
+
+            if data.is_null() {
+                return None;
+            }
+            if failed {
+                (self.0.xlib.XFree)(data as *mut _);
+                return None;
+            }
+
+            let result = if actual_format == 8 && nitems > 0 {
+                Some(slice::from_raw_parts(data, nitems as usize).to_vec())
+            } else {
+                None
+            };
+
+            (self.0.xlib.XFree)(data as *mut _);
+            result
+        }
+    }
+}
+
+/// The areas of the enabled XRandR CRTCs of a screen, with their refresh rates in millihertz.
+pub struct CrtcLayout {
+    /// Time of the last change of the configuration of the screen when the layout was read
+    timestamp: ffi::Time,
+    crtcs: Vec<((i32, i32, i32, i32), Option<u32>)>,
+}
+
+/// Returns the refresh rate, in millihertz, of the XRandR CRTC that displays a point of the
+/// root window of a screen, or `None` if an X error occurs.
+///
+/// The CRTCs are only queried again if the configuration of the screen has changed since the
+/// `layout` was read.
+pub fn get_refresh_rate_at(x: &XConnection, screen_id: libc::c_int, point: (i32, i32),
+                           layout: &mut Option<CrtcLayout>) -> Option<u32>
+{
+    let xrandr = match x.xrandr {
+        Some(ref xrandr) => xrandr,
+        None => return None,
+    };
+
+    unsafe {
+        let root = (x.xlib.XRootWindow)(x.display, screen_id);
+        x.push_error_trap();
+        let resources = (xrandr.XRRGetScreenResourcesCurrent)(x.display, root);
+        if resources.is_null() {
+            let _ = x.pop_error_trap();
+            *layout = None;
+            return None;
+        }
+
+        let timestamp = (*resources).timestamp;
+        if layout.as_ref().map_or(true, |layout| layout.timestamp != timestamp) {
+            *layout = Some(read_crtc_layout(x, xrandr, resources));
+        }
+        (xrandr.XRRFreeScreenResources)(resources);
+
+        if x.pop_error_trap().is_err() {
+            *layout = None;
+            return None;
+        }
+
+        let layout = match *layout {
+            Some(ref layout) => layout,
+            None => return None,
+        };
+        layout.crtcs.iter()
+              .find(|&&((left, top, width, height), _)| {
+                  point.0 >= left && point.1 >= top &&
+                  point.0 < left + width && point.1 < top + height
+              })
+              .and_then(|&(_, refresh_rate)| refresh_rate)
+    }
+}
+
+unsafe fn read_crtc_layout(x: &XConnection, xrandr: &ffi::Xrandr,
+                           resources: *mut ffi::XRRScreenResources) -> CrtcLayout
+{
+    let crtcs = slice::from_raw_parts((*resources).crtcs, (*resources).ncrtc as usize);
+    let modes = slice::from_raw_parts((*resources).modes, (*resources).nmode as usize);
+
+    let mut layout = Vec::with_capacity(crtcs.len());
+    for &crtc in crtcs {
+        let info = (xrandr.XRRGetCrtcInfo)(x.display, resources, crtc);
+        if info.is_null() {
+            continue;
+        }
+
+        // disabled CRTCs have no mode
+        let mode = (*info).mode;
+        if mode != 0 {
+            let area = ((*info).x, (*info).y, (*info).width as i32, (*info).height as i32);
+            layout.push((area, modes.iter().find(|m| m.id == mode).and_then(mode_refresh_rate)));
+        }
+        (xrandr.XRRFreeCrtcInfo)(info);
+    }
+
+    CrtcLayout {
+        timestamp: (*resources).timestamp,
+        crtcs: layout,
+    }
+}
+
+/// Computes the refresh rate of a mode, in millihertz.
+fn mode_refresh_rate(mode: &ffi::XRRModeInfo) -> Option<u32> {
+    let mut v_total = mode.vTotal as u64;
+    if mode.modeFlags & RR_DOUBLE_SCAN != 0 {
+        v_total *= 2;
+    }
+    if mode.modeFlags & RR_INTERLACE != 0 {
+        v_total /= 2;
+    }
+
+    match mode.hTotal as u64 * v_total {
+        0 => None,
+        total => Some((mode.dotClock as u64 * 1000 / total) as u32),
+    }
 }
@@ -0,0 +1,145 @@
+//! Notifications of the Present extension, which tell when each frame has been displayed.
+//!
+//! Mesa displays the frames of GLX and EGL windows with the Present extension, and every client
+//! that selects the `PresentCompleteNotify` events of a window receives them. They carry the
+//! time and the counter of vertical retraces when the frame was displayed, which is more reliable
+//! than `GLX_OML_sync_control` with the recent drivers.
+//!
+//! libXpresent is loaded at runtime since it is rarely installed.
+
+#![allow(non_snake_case)]
+
+use std::path::Path;
+use std::sync::atomic::{AtomicIsize, ATOMIC_ISIZE_INIT, Ordering};
+
+use libc;
+
+use Event;
+use FrameTiming;
+
+use super::ffi;
+use super::XConnection;
+
+const PRESENT_COMPLETE_NOTIFY: libc::c_int = 1;
+const PRESENT_COMPLETE_NOTIFY_MASK: libc::c_uint = 1 << 1;
+const PRESENT_COMPLETE_KIND_PIXMAP: u8 = 0;
+const PRESENT_COMPLETE_MODE_FLIP: u8 = 1;
+const PRESENT_COMPLETE_MODE_SKIP: u8 = 2;
+
+shared_library!(LibXpresent, "libXpresent.so.1",
+    pub fn XPresentQueryExtension(display: *mut ffi::Display, major_opcode: *mut libc::c_int,
+                                  event_base: *mut libc::c_int,
+                                  error_base: *mut libc::c_int) -> ffi::Bool,
+    pub fn XPresentSelectInput(display: *mut ffi::Display, window: ffi::Window,
+                               event_mask: libc::c_uint) -> ffi::XID,
+);
+
+/// The major opcode of the extension once a window has selected its events, or `0`.
+static OPCODE: AtomicIsize = ATOMIC_ISIZE_INIT;
+
+// the library only contains function pointers
+unsafe impl Sync for LibXpresent {}
+
+lazy_static! {
+    /// libXpresent registers the conversion of its events in Xlib, so it must never be
+    /// unloaded.
+    static ref LIBXPRESENT: Option<LibXpresent> =
+        LibXpresent::open(&Path::new("libXpresent.so.1")).ok();
+}
+
+/// `XPresentCompleteNotifyEvent`, the data of the cookie of a `PresentCompleteNotify` event.
+#[repr(C)]
+struct XPresentCompleteNotifyEvent {
+    type_: libc::c_int,
+    serial: libc::c_ulong,
+    send_event: ffi::Bool,
+    display: *mut ffi::Display,
+    extension: libc::c_int,
+    evtype: libc::c_int,
+    eid: u32,
+    window: ffi::Window,
+    serial_number: u32,
+    ust: u64,
+    msc: u64,
+    kind: u8,
+    mode: u8,
+}
+
+/// Returns the window of an event of the extension, whose data has been retreived with
+/// `XGetEventData`.
+pub fn event_window(cookie: &ffi::XGenericEventCookie) -> Option<ffi::Window> {
+    if cookie.extension as isize != OPCODE.load(Ordering::SeqCst) ||
+       cookie.evtype != PRESENT_COMPLETE_NOTIFY || cookie.data.is_null()
+    {
+        return None;
+    }
+
+    let event = unsafe { &*(cookie.data as *const XPresentCompleteNotifyEvent) };
+    Some(event.window)
+}
+
+/// The selection of the completion events of a window.
+pub struct PresentInput {
+    /// The major opcode of the extension, which identifies its generic events
+    opcode: libc::c_int,
+}
+
+impl PresentInput {
+    /// Selects the completion events of the window.
+    ///
+    /// Returns `None` if libXpresent is missing or if the server doesn't support the extension.
+    pub fn select(display: &XConnection, window: ffi::Window) -> Option<PresentInput> {
+        let lib = match *LIBXPRESENT {
+            Some(ref lib) => lib,
+            None => return None,
+        };
+
+        unsafe {
+            let (mut opcode, mut event_base, mut error_base) = (0, 0, 0);
+            if (lib.XPresentQueryExtension)(display.display, &mut opcode, &mut event_base,
+                                            &mut error_base) == ffi::False
+            {
+                return None;
+            }
+
+            display.push_error_trap();
+            (lib.XPresentSelectInput)(display.display, window, PRESENT_COMPLETE_NOTIFY_MASK);
+        }
+
+        if display.pop_error_trap().is_err() {
+            return None;
+        }
+
+        OPCODE.store(opcode as isize, Ordering::SeqCst);
+        Some(PresentInput { opcode: opcode })
+    }
+
+    /// Returns true if the generic event comes from the Present extension.
+    #[inline]
+    pub fn owns(&self, cookie: &ffi::XGenericEventCookie) -> bool {
+        cookie.extension == self.opcode
+    }
+
+    /// Translates an event of the extension, whose data has been retreived with
+    /// `XGetEventData`.
+    pub fn translate_event(&self, cookie: &ffi::XGenericEventCookie) -> Option<Event> {
+        if cookie.evtype != PRESENT_COMPLETE_NOTIFY || cookie.data.is_null() {
+            return None;
+        }
+
+        let event = unsafe { &*(cookie.data as *const XPresentCompleteNotifyEvent) };
+
+        // the other kind is the answer to a `PresentNotifyMSC` request, which isn't a frame
+        if event.kind != PRESENT_COMPLETE_KIND_PIXMAP {
+            return None;
+        }
+
+        Some(Event::FramePresented(FrameTiming {
+            serial: event.serial_number,
+            timestamp: event.ust,
+            msc: event.msc,
+            flipped: event.mode == PRESENT_COMPLETE_MODE_FLIP,
+            skipped: event.mode == PRESENT_COMPLETE_MODE_SKIP,
+        }))
+    }
+}
@@ -0,0 +1,78 @@
+//! The X resource database, which holds the defaults that the user has loaded with `xrdb`.
+//!
+//! The database is the content of the `RESOURCE_MANAGER` property of the root window, as it was
+//! when the connection was opened. Each entry is matched by a name such as `myapp.geometry` and a
+//! class such as `Myapp.Geometry`, with wildcards on the side of the user.
+
+use std::ffi::{CStr, CString};
+use std::{mem, ptr};
+use std::sync::{Arc, Once, ONCE_INIT};
+
+use libc;
+
+use super::ffi;
+use super::XConnection;
+
+static XRM_INIT: Once = ONCE_INIT;
+
+pub struct ResourceDatabase {
+    display: Arc<XConnection>,
+    database: ffi::XrmDatabase,
+}
+
+impl ResourceDatabase {
+    /// Parses the resources of the server. Returns `None` if the user hasn't loaded any.
+    pub fn load(display: &Arc<XConnection>) -> Option<ResourceDatabase> {
+        unsafe {
+            XRM_INIT.call_once(|| (display.xlib.XrmInitialize)());
+
+            let resources = (display.xlib.XResourceManagerString)(display.display);
+            if resources.is_null() {
+                return None;
+            }
+
+            let database = (display.xlib.XrmGetStringDatabase)(resources);
+            if database.is_null() {
+                return None;
+            }
+
+            Some(ResourceDatabase {
+                display: display.clone(),
+                database: database,
+            })
+        }
+    }
+
+    /// Returns the value of the most specific entry that matches the name and the class.
+    pub fn get(&self, name: &str, class: &str) -> Option<String> {
+        let (name, class) = match (CString::new(name), CString::new(class)) {
+            (Ok(name), Ok(class)) => (name, class),
+            _ => return None,
+        };
+
+        unsafe {
+            let mut kind: *mut libc::c_char = ptr::null_mut();
+            let mut value: ffi::XrmValue = mem::zeroed();
+            if (self.display.xlib.XrmGetResource)(self.database, name.as_ptr(), class.as_ptr(),
+                                                  &mut kind, &mut value) == ffi::False ||
+               value.addr.is_null()
+            {
+                return None;
+            }
+
+            // the value belongs to the database
+            let value = CStr::from_ptr(value.addr as *const libc::c_char);
+            Some(value.to_string_lossy().into_owned())
+        }
+    }
+}
+
+impl Drop for ResourceDatabase {
+    fn drop(&mut self) {
+        unsafe { (self.display.xlib.XrmDestroyDatabase)(self.database) };
+    }
+}
+
+// the database is only read, and Xlib serializes the accesses to the connection
+unsafe impl Send for ResourceDatabase {}
+unsafe impl Sync for ResourceDatabase {}
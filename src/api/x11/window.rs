@@ -1,17 +1,19 @@
-use {Event, MouseCursor};
+use {Event, FullscreenFocusPolicy, LockKeys, MouseCursor, PointerAcceleration, SnapPosition,
+     TileState};
 use CreationError;
 use CreationError::OsError;
 use libc;
 use std::borrow::Borrow;
-use std::{mem, ptr, cmp};
+use std::ffi::CString;
+use std::{io, mem, ptr, cmp};
 use std::cell::Cell;
 use std::sync::atomic::AtomicBool;
 use std::collections::VecDeque;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::os::raw::c_long;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use Api;
 use ContextError;
@@ -28,15 +30,21 @@ use api::egl;
 use api::egl::Context as EglContext;
 
 use platform::MonitorId as PlatformMonitorId;
+use platform::PlatformSpecificWindowBuilderAttributes;
 
+use super::clipboard::Clipboard;
+use super::dbusmenu::GlobalMenu;
+use super::decode::{self, DecodedEvent};
+use super::dnd::Dnd;
 use super::input::XInputEventHandler;
-use super::{ffi};
-use super::{MonitorId, XConnection};
+use super::menu::MenuBar;
+use super::{ffi, grabs, monitor, wm};
+use super::present::PresentInput;
+use termination;
+use super::{MonitorId, WmInfo, XConnection};
 
-// XOpenIM doesn't seem to be thread-safe
-lazy_static! {      // TODO: use a static mutex when that's possible, and put me back in my function
-    static ref GLOBAL_XOPENIM_LOCK: Mutex<()> = Mutex::new(());
-}
+/// The device specification of the core keyboard, for the XKB functions.
+const XKB_USE_CORE_KBD: libc::c_uint = 0x0100;
 
 // TODO: remove me
 fn with_c_str<F, T>(s: &str, f: F) -> T where F: FnOnce(*const libc::c_char) -> T {
@@ -56,13 +64,20 @@ pub struct XWindow {
     display: Arc<XConnection>,
     window: ffi::Window,
     pub context: Context,
+    /// Visual and depth the window was created with, needed to create child views
+    visual: *mut ffi::Visual,
+    depth: libc::c_int,
     is_fullscreen: bool,
     screen_id: libc::c_int,
     xf86_desk_mode: Option<ffi::XF86VidModeModeInfo>,
+    /// The video mode that was switched to for the fullscreen window
+    xf86_fullscreen_mode: Option<ffi::XF86VidModeModeInfo>,
     ic: ffi::XIC,
     im: ffi::XIM,
     colormap: ffi::Colormap,
     window_proxy_data: Arc<Mutex<Option<WindowProxyData>>>,
+    /// Whether the window has been created by another library, which stays in charge of it
+    foreign: bool,
 }
 
 pub enum Context {
@@ -88,7 +103,10 @@ impl Drop for XWindow {
             // is still the current one
             self.context = Context::None;
 
-            let _lock = GLOBAL_XOPENIM_LOCK.lock().unwrap();
+            let _lock = self.display.im_lock.lock().unwrap();
+
+            grabs::unregister(&self.display, self.window);
+            self.display.unregister_window(self.window);
 
             if self.is_fullscreen {
                 if let Some(mut xf86_desk_mode) = self.xf86_desk_mode {
@@ -99,8 +117,15 @@ impl Drop for XWindow {
 
             (self.display.xlib.XDestroyIC)(self.ic);
             (self.display.xlib.XCloseIM)(self.im);
-            (self.display.xlib.XDestroyWindow)(self.display.display, self.window);
-            (self.display.xlib.XFreeColormap)(self.display.display, self.colormap);
+            if self.foreign {
+                // the window may have been destroyed by its owner already
+                self.display.push_error_trap();
+                (self.display.xlib.XSelectInput)(self.display.display, self.window, 0);
+                let _ = self.display.pop_error_trap();
+            } else {
+                (self.display.xlib.XDestroyWindow)(self.display.display, self.window);
+                (self.display.xlib.XFreeColormap)(self.display.display, self.colormap);
+            }
         }
     }
 }
@@ -127,14 +152,50 @@ impl WindowProxy {
             };
 
             unsafe {
+                data.display.push_error_trap();
+                (data.display.xlib.XSendEvent)(data.display.display, data.window, 0, 0, mem::transmute(&mut xev));
+                (data.display.xlib.XFlush)(data.display.display);
+                data.display.pop_error_trap().expect("Failed to call XSendEvent after wakeup");
+            }
+        }
+    }
+
+    /// Makes the event loop of the window return an `Event::MenuItem` with the given ID.
+    ///
+    /// Used by the global menu, whose items are chosen on the thread that serves it.
+    pub fn send_menu_item(&self, id: u32) {
+        let window_proxy_data = self.data.lock().unwrap();
+
+        if let Some(ref data) = *window_proxy_data {
+            let mut xev = ffi::XClientMessageEvent {
+                type_: ffi::ClientMessage,
+                window: data.window,
+                format: 32,
+                message_type: data.display.get_atom(MENU_ITEM_ATOM),
+                serial: 0,
+                send_event: 0,
+                display: data.display.display,
+                data: {
+                    let mut message = ffi::ClientMessageData::new();
+                    message.set_long(0, id as c_long);
+                    message
+                },
+            };
+
+            unsafe {
+                data.display.push_error_trap();
                 (data.display.xlib.XSendEvent)(data.display.display, data.window, 0, 0, mem::transmute(&mut xev));
                 (data.display.xlib.XFlush)(data.display.display);
-                data.display.check_errors().expect("Failed to call XSendEvent after wakeup");
             }
+            // the window may have been destroyed in the meantime
+            let _ = data.display.pop_error_trap();
         }
     }
 }
 
+/// The type of the client messages that report the items chosen in the global menu.
+const MENU_ITEM_ATOM: &'static str = "_GLUTIN_MENU_ITEM";
+
 // XEvents of type GenericEvent store their actual data
 // in an XGenericEventCookie data structure. This is a wrapper
 // to extract the cookie from a GenericEvent XEvent and release
@@ -146,13 +207,12 @@ struct GenericEventCookie<'a> {
 
 impl<'a> GenericEventCookie<'a> {
     fn from_event<'b>(display: &'b XConnection, event: ffi::XEvent) -> Option<GenericEventCookie<'b>> {
-        unsafe {
-            let mut cookie: ffi::XGenericEventCookie = From::from(event);
-            if (display.xlib.XGetEventData)(display.display, &mut cookie) == ffi::True {
-                Some(GenericEventCookie{display: display, cookie: cookie})
-            } else {
-                None
-            }
+        // the data has been retrieved by `XConnection::next_event`
+        let cookie: ffi::XGenericEventCookie = From::from(event);
+        if cookie.data.is_null() {
+            None
+        } else {
+            Some(GenericEventCookie{display: display, cookie: cookie})
         }
     }
 }
@@ -181,73 +241,264 @@ impl<'a> Iterator for PollEventsIterator<'a> {
                 return Some(ev);
             }
 
-            let mut xev = unsafe { mem::uninitialized() };
-            let res = unsafe { (xlib.XCheckMaskEvent)(self.window.x.display.display, -1, &mut xev) };
-
-            if res == 0 {
-                let res = unsafe { (xlib.XCheckTypedEvent)(self.window.x.display.display, ffi::ClientMessage, &mut xev) };
+            // the connection is shared by all the windows, which only get their own events
+            let xev = match self.window.x.display.next_event(self.window.x.window) {
+                Some(xev) => xev,
+                None => return None,
+            };
 
-                if res == 0 {
-                    let res = unsafe { (xlib.XCheckTypedEvent)(self.window.x.display.display, ffi::GenericEvent, &mut xev) };
-                    if res == 0 {
-                        return None;
-                    }
-                }
+            if self.window.handle_menu_bar_event(&xev) {
+                continue;
             }
 
-            match xev.get_type() {
-                ffi::MappingNotify => {
-                    unsafe { (xlib.XRefreshKeyboardMapping)(mem::transmute(&xev)); }
-                    self.window.x.display.check_errors().expect("Failed to call XRefreshKeyboardMapping");
+            match DecodedEvent::decode(&xev) {
+                DecodedEvent::MappingNotify(mut mapping_event) => {
+                    self.window.x.display.push_error_trap();
+                    unsafe { (xlib.XRefreshKeyboardMapping)(&mut mapping_event); }
+                    self.window.x.display.pop_error_trap().expect("Failed to call XRefreshKeyboardMapping");
                 },
 
-                ffi::ClientMessage => {
-                    use events::Event::{Closed, Awakened};
+                DecodedEvent::ClientMessage { window, message_type, data } => {
                     use std::sync::atomic::Ordering::Relaxed;
 
-                    let client_msg: &ffi::XClientMessageEvent = unsafe { mem::transmute(&xev) };
+                    // the messages of the protocol are only meaningful for the window that
+                    // advertises `XdndAware`, not for the child views and embedders
+                    if window == self.window.x.window {
+                        let dnd_events = self.window.dnd.lock().unwrap().handle_client_message(message_type, &data);
+                        if let Some(dnd_events) = dnd_events {
+                            self.window.pending_events.lock().unwrap().extend(dnd_events);
+                            continue;
+                        }
+                    }
+
+                    if message_type == self.window.x.display.get_atom(MENU_ITEM_ATOM) {
+                        // the ID was sent as a `long`, its upper bits are irrelevant
+                        return Some(Event::MenuItem(data[0] as u32));
+                    }
 
-                    if client_msg.data.get_long(0) == self.window.wm_delete_window as libc::c_long {
+                    let event = decode::translate_client_message(&data, self.window.wm_delete_window);
+                    if let Event::Closed = event {
                         self.window.is_closed.store(true, Relaxed);
-                        return Some(Closed);
-                    } else {
-                        return Some(Awakened);
+                    }
+                    return Some(event);
+                },
+
+                DecodedEvent::Configure { window, width, height } => {
+                    if window == self.window.x.window {
+                        if let Some(ref mut menu_bar) = *self.window.menu_bar.lock().unwrap() {
+                            menu_bar.set_width(width as libc::c_uint);
+                        }
+
+                        // the window may have moved to another monitor
+                        let refresh_rate = self.window.refresh_rate_at_center(width, height);
+                        if refresh_rate != self.window.refresh_rate.get() {
+                            self.window.refresh_rate.set(refresh_rate);
+                            if let Some(refresh_rate) = refresh_rate {
+                                let mut pending_events = self.window.pending_events.lock().unwrap();
+                                pending_events.push_back(Event::RefreshRateChanged(refresh_rate));
+                            }
+                        }
+
+                        let event = decode::translate_configure(self.window.current_size.get(), width, height);
+                        if event.is_some() {
+                            self.window.current_size.set((width, height));
+                            return event;
+                        }
+                    } else if Some(window) == self.window.embedder.get() && width > 0 && height > 0 {
+                        // embedded windows follow the size of their parent, and the resulting
+                        // ConfigureNotify produces the `Resized` event
+                        self.window.x.display.push_error_trap();
+                        unsafe {
+                            (xlib.XResizeWindow)(self.window.x.display.display, self.window.x.window,
+                                                 width as libc::c_uint, height as libc::c_uint);
+                        }
+                        self.window.x.display.pop_error_trap().expect("Failed to call XResizeWindow");
+                    }
+                },
+
+                DecodedEvent::Reparent { window, parent } => {
+                    if window == self.window.x.window && self.window.embedder.get().is_some() {
+                        if let Some(old_parent) = self.window.embedder.get() {
+                            self.window.x.display.unwatch_window(window, old_parent);
+                        }
+                        self.window.embedder.set(Some(parent));
+                        self.window.x.display.watch_window(window, parent);
+
+                        // the new parent belongs to the host application and may already be
+                        // gone, in which case there is nothing to follow
+                        self.window.x.display.push_error_trap();
+                        self.window.x.display.add_event_mask(parent, ffi::StructureNotifyMask);
+                        let _ = self.window.x.display.pop_error_trap();
                     }
                 },
 
-                ffi::ConfigureNotify => {
-                    use events::Event::Resized;
-                    let cfg_event: &ffi::XConfigureEvent = unsafe { mem::transmute(&xev) };
-                    let (current_width, current_height) = self.window.current_size.get();
-                    if current_width != cfg_event.width || current_height != cfg_event.height {
-                        self.window.current_size.set((cfg_event.width, cfg_event.height));
-                        return Some(Resized(cfg_event.width as u32, cfg_event.height as u32));
+                DecodedEvent::SelectionNotify { requestor, selection, property } => {
+                    if requestor == self.window.x.window {
+                        let dnd_events = self.window.dnd.lock().unwrap().handle_selection(selection, property);
+                        self.window.pending_events.lock().unwrap().extend(dnd_events);
                     }
                 },
 
-                ffi::Expose => {
+                DecodedEvent::SelectionRequest(request) => {
+                    self.window.clipboard.lock().unwrap().handle_request(&request);
+                },
+
+                DecodedEvent::SelectionClear { selection } => {
+                    self.window.clipboard.lock().unwrap().handle_clear(selection);
+                },
+
+                DecodedEvent::Expose => {
                     use events::Event::Refresh;
                     return Some(Refresh);
                 },
 
-                ffi::KeyPress | ffi::KeyRelease => {
-                    let mut event: &mut ffi::XKeyEvent = unsafe { mem::transmute(&mut xev) };
+                DecodedEvent::Destroy { window } => {
+                    use events::Event::Destroyed;
+                    use std::sync::atomic::Ordering::Relaxed;
+
+                    if window == self.window.x.window {
+                        self.window.is_closed.store(true, Relaxed);
+
+                        // without a WM, the window can't receive `WM_DELETE_WINDOW`, and being
+                        // destroyed by another client is the only way for it to be closed
+                        if !self.window.wm_present {
+                            use events::Event::Closed;
+                            self.window.pending_events.lock().unwrap().push_back(Destroyed);
+                            return Some(Closed);
+                        }
+
+                        return Some(Destroyed);
+                    }
+                },
+
+                DecodedEvent::PropertyNotify { window, atom, deleted } => {
+                    use events::Event::{IccProfileChanged, Fullscreen, Maximized, Minimized,
+                                        TitleChanged};
+                    if window != self.window.x.window {
+                        if deleted {
+                            self.window.clipboard.lock().unwrap().handle_property_deleted(window, atom);
+                        }
+                        // the property of the root window that `MonitorId::get_icc_profile` reads
+                        if atom == self.window.x.display.get_atom("_ICC_PROFILE") {
+                            return Some(IccProfileChanged);
+                        }
+                    } else if atom == self.window.x.display.get_atom("_NET_WM_STATE") {
+                        // the WM can change the state on its own, so we compare with the last
+                        // known state in order to only report actual changes
+                        let (old_fullscreen, old_maximized) = self.window.wm_state.get();
+                        let (fullscreen, maximized) = self.window.get_wm_state();
+                        self.window.wm_state.set((fullscreen, maximized));
+
+                        if fullscreen != old_fullscreen && self.window.bypass_compositor {
+                            wm::set_bypass_compositor(&self.window.x.display,
+                                                      self.window.x.window, fullscreen);
+                        }
+
+                        let mut pending_events = self.window.pending_events.lock().unwrap();
+                        if fullscreen != old_fullscreen {
+                            pending_events.push_back(Fullscreen(fullscreen));
+                        }
+                        if maximized != old_maximized {
+                            pending_events.push_back(Maximized(maximized));
+                        }
+                        self.window.update_tile_state(&mut pending_events);
+                    } else if atom == self.window.x.display.get_atom("_GTK_EDGE_CONSTRAINTS") {
+                        let mut pending_events = self.window.pending_events.lock().unwrap();
+                        self.window.update_tile_state(&mut pending_events);
+                    } else if atom == self.window.x.display.get_atom("WM_STATE") {
+                        let minimized = self.window.is_iconified();
+                        if minimized != self.window.minimized.get() {
+                            self.window.minimized.set(minimized);
+                            return Some(Minimized(minimized));
+                        }
+                    } else if atom == self.window.x.display.get_atom("_NET_WM_NAME") ||
+                              atom == ffi::XA_WM_NAME
+                    {
+                        // `set_title` changes both properties, so its own changes are ignored
+                        // by comparing with the last known values. A client that only knows
+                        // the ICCCM may change `WM_NAME` and leave a stale `_NET_WM_NAME` behind
+                        let title = if atom == ffi::XA_WM_NAME {
+                            let wm_name = self.window.get_wm_name().unwrap_or_else(String::new);
+                            let mut last_wm_name = self.window.wm_name.lock().unwrap();
+                            if wm_name == *last_wm_name {
+                                continue;
+                            }
+                            *last_wm_name = wm_name.clone();
+                            wm_name
+                        } else {
+                            self.window.get_title().unwrap_or_else(String::new)
+                        };
+                        let mut last_title = self.window.title.lock().unwrap();
+                        if title != *last_title {
+                            *last_title = title.clone();
+                            return Some(TitleChanged(title));
+                        }
+                    }
+                },
+
+                DecodedEvent::Key(mut event) => {
+                    self.window.last_user_time.set(event.time);
                     let events = self.window.input_handler.lock().unwrap().translate_key_event(&mut event);
                     for event in events {
                         self.window.pending_events.lock().unwrap().push_back(event);
                     }
+
+                    // the lock keys change their state when they are pressed
+                    if event.type_ == ffi::KeyPress {
+                        self.window.update_lock_keys();
+                    }
+                },
+
+                DecodedEvent::Focus { window, focused, mode } => {
+                    // focus changes caused by keyboard grabs (for example while a menu of the
+                    // WM is open) are only temporary
+                    if window == self.window.x.window && mode != ffi::NotifyGrab &&
+                       mode != ffi::NotifyUngrab
+                    {
+                        self.window.focus_changed(focused);
+                    }
                 },
 
-                ffi::GenericEvent => {
+                DecodedEvent::Generic => {
                     if let Some(cookie) = GenericEventCookie::from_event(self.window.x.display.borrow(), xev) {
                         match cookie.cookie.evtype {
+                            // the event types of the extensions overlap
+                            _ if self.window.present.as_ref()
+                                                   .map_or(false, |p| p.owns(&cookie.cookie)) =>
+                            {
+                                let present = self.window.present.as_ref().unwrap();
+                                if let Some(event) = present.translate_event(&cookie.cookie) {
+                                    self.window.pending_events.lock().unwrap().push_back(event);
+                                }
+                            },
                             ffi::XI_DeviceChanged...ffi::XI_LASTEVENT => {
+                                if cookie.cookie.evtype == ffi::XI_ButtonPress {
+                                    let device_event: &ffi::XIDeviceEvent = unsafe { mem::transmute(cookie.cookie.data) };
+                                    self.window.last_user_time.set(device_event.time);
+                                }
+
                                 match self.window.input_handler.lock() {
                                     Ok(mut handler) => {
                                         match handler.translate_event(&cookie.cookie) {
-                                            Some(event) => self.window.pending_events.lock().unwrap().push_back(event),
+                                            Some(Event::Focused(focused)) => {
+                                                self.window.focus_changed(focused);
+                                            },
+                                            Some(Event::MouseMoved(x, y)) if *self.window.cursor_state.lock().unwrap() == CursorState::GrabRelative &&
+                                                                             grabs::is_registered(&self.window.x.display, self.window.x.window) => {
+                                                // the position is meaningless in this mode, and
+                                                // the motion is reported by the raw events
+                                                let (width, height) = self.window.current_size.get();
+                                                if (x, y) != (width / 2, height / 2) {
+                                                    self.window.warp_cursor_to_center();
+                                                }
+                                            },
+                                            Some(event) => {
+                                                self.window.pending_events.lock().unwrap().push_back(event)
+                                            },
                                             None => {}
                                         }
+                                        let gestures = handler.take_gestures();
+                                        self.window.pending_events.lock().unwrap().extend(gestures);
                                     },
                                     Err(_) => {}
                                 }
@@ -263,6 +514,101 @@ impl<'a> Iterator for PollEventsIterator<'a> {
     }
 }
 
+/// A child window of a `Window` that the OpenGL context of the `Window` can render to.
+pub struct ChildView {
+    // keeps the parent window and its context alive
+    parent: Arc<XWindow>,
+    window: ffi::Window,
+}
+
+impl ChildView {
+    pub fn new(parent: &Window, x: i32, y: i32, width: u32, height: u32)
+               -> Result<ChildView, CreationError>
+    {
+        match parent.x.context {
+            Context::Glx(_) => (),
+            _ => return Err(CreationError::NotSupported),
+        }
+
+        let display = &parent.x.display;
+
+        let window = unsafe {
+            let mut swa: ffi::XSetWindowAttributes = mem::zeroed();
+            swa.colormap = parent.x.colormap;
+            swa.event_mask = ffi::ExposureMask;
+
+            display.push_error_trap();
+            let win = (display.xlib.XCreateWindow)(display.display, parent.x.window, x, y,
+                                                   width as libc::c_uint, height as libc::c_uint,
+                                                   0, parent.x.depth,
+                                                   ffi::InputOutput as libc::c_uint,
+                                                   parent.x.visual,
+                                                   ffi::CWColormap | ffi::CWEventMask, &mut swa);
+            display.pop_error_trap().expect("Failed to call XCreateWindow");
+
+            display.push_error_trap();
+            (display.xlib.XMapWindow)(display.display, win);
+            display.pop_error_trap().expect("Failed to call XMapWindow");
+            win
+        };
+
+        // the `Expose` events of the view are reported by the parent
+        display.watch_window(parent.x.window, window);
+
+        Ok(ChildView {
+            parent: parent.x.clone(),
+            window: window,
+        })
+    }
+
+    /// Makes the OpenGL context of the parent window current, rendering to this view.
+    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
+        match self.parent.context {
+            Context::Glx(ref ctxt) => ctxt.make_current_on(self.window),
+            _ => unreachable!()
+        }
+    }
+
+    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        swap_with_error_trap(&self.parent.display, || match self.parent.context {
+            Context::Glx(ref ctxt) => ctxt.swap_buffers_on(self.window),
+            _ => unreachable!()
+        })
+    }
+
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        match self.parent.context {
+            Context::Glx(ref ctxt) => ctxt.set_swap_interval_on(self.window, interval),
+            _ => unreachable!()
+        }
+    }
+
+    /// Moves and resizes the view, in pixels relative to the top-left corner of the parent.
+    pub fn set_rect(&self, x: i32, y: i32, width: u32, height: u32) {
+        unsafe {
+            self.parent.display.push_error_trap();
+            (self.parent.display.xlib.XMoveResizeWindow)(self.parent.display.display, self.window,
+                                                         x, y, width as libc::c_uint,
+                                                         height as libc::c_uint);
+            self.parent.display.pop_error_trap().expect("Failed to call XMoveResizeWindow");
+        }
+    }
+
+    #[inline]
+    pub fn get_xlib_window(&self) -> *mut libc::c_void {
+        self.window as *mut libc::c_void
+    }
+}
+
+impl Drop for ChildView {
+    fn drop(&mut self) {
+        self.parent.display.unwatch_window(self.parent.window, self.window);
+        unsafe {
+            (self.parent.display.xlib.XDestroyWindow)(self.parent.display.display, self.window);
+        }
+    }
+}
+
 pub struct WaitEventsIterator<'a> {
     window: &'a Window,
 }
@@ -272,18 +618,23 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
 
     fn next(&mut self) -> Option<Event> {
         use std::sync::atomic::Ordering::Relaxed;
-        use std::mem;
 
         while !self.window.is_closed.load(Relaxed) {
             if let Some(ev) = self.window.pending_events.lock().unwrap().pop_front() {
                 return Some(ev);
             }
 
-            // this will block until an event arrives, but doesn't remove
-            // it from the queue
-            let mut xev = unsafe { mem::uninitialized() };
-            unsafe { (self.window.x.display.xlib.XPeekEvent)(self.window.x.display.display, &mut xev) };
-            self.window.x.display.check_errors().expect("Failed to call XPeekEvent");
+            // blocks until an event arrives or a termination is requested, which is signaled
+            // by a pipe that is watched along with the connection
+            let display = &self.window.x.display;
+            if !display.has_queued_events(self.window.x.window) &&
+               unsafe { (display.xlib.XPending)(display.display) } == 0
+            {
+                if wait_for_connection(display, -1) {
+                    return Some(Event::Terminated);
+                }
+                continue;
+            }
 
             // calling poll_events()
             if let Some(ev) = self.window.poll_events().next() {
@@ -295,23 +646,145 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
     }
 }
 
+/// Blocks until the connection has data to read, a termination is requested or the timeout
+/// expires, in milliseconds or `-1` for none.
+///
+/// Returns `true` if a termination has been requested.
+fn wait_for_connection(display: &XConnection, timeout: libc::c_int) -> bool {
+    let mut fds = [
+        libc::pollfd {
+            fd: unsafe { (display.xlib.XConnectionNumber)(display.display) },
+            events: libc::POLLIN,
+            revents: 0,
+        },
+        libc::pollfd {
+            // negative descriptors are ignored
+            fd: termination::wakeup_fd().unwrap_or(-1),
+            events: libc::POLLIN,
+            revents: 0,
+        },
+    ];
+
+    // interrupted by a signal if `poll` returns `-1`, in which case the handler may have
+    // written to the pipe, which is checked by the next call
+    if unsafe { libc::poll(fds.as_mut_ptr(), 2, timeout) } > 0 &&
+       fds[1].revents & libc::POLLIN != 0
+    {
+        termination::drain();
+        return true;
+    }
+    false
+}
+
 pub struct Window {
     pub x: Arc<XWindow>,
     is_closed: AtomicBool,
     wm_delete_window: ffi::Atom,
     current_size: Cell<(libc::c_int, libc::c_int)>,
+    /// Timestamp of the last key or button press, used when asking the WM for the focus
+    last_user_time: Cell<ffi::Time>,
+    /// Last known `(fullscreen, maximized)` state, as reported by the `_NET_WM_STATE` property
+    wm_state: Cell<(bool, bool)>,
+    /// Whether the window was iconified, as last reported by the `WM_STATE` property
+    minimized: Cell<bool>,
+    /// Last title set by `set_title` or reported by a `TitleChanged` event
+    title: Mutex<String>,
+    /// Last known `WM_NAME`, which is the Latin-1 approximation of the title set by `set_title`
+    wm_name: Mutex<String>,
+    /// Last reported tiling of the window
+    tile_state: Cell<TileState>,
+    /// Refresh rate of the monitor under the window, in millihertz
+    refresh_rate: Cell<Option<u32>>,
+    /// Center of the window in the root window when the refresh rate was computed
+    refresh_point: Cell<Option<(i32, i32)>>,
+    /// Areas of the CRTCs of the screen, read when computing the refresh rate
+    crtc_layout: Mutex<Option<monitor::CrtcLayout>>,
+    /// Last reported state of the lock keys
+    lock_keys: Cell<LockKeys>,
+    /// Last cursor passed to `set_cursor`
+    current_cursor: Cell<MouseCursor>,
+    /// Parent window whose size is followed, if the window was created with a parent
+    embedder: Cell<Option<ffi::Window>>,
+    /// Whether the window has the keyboard focus, as last reported by a `Focused` event
+    focused: Cell<bool>,
+    /// Whether a window manager was running when the window was created. If not, glutin has
+    /// to position, raise and focus the window by itself
+    wm_present: bool,
+    fullscreen_focus_policy: FullscreenFocusPolicy,
+    /// Whether the compositor is asked to stop compositing while the window is fullscreen
+    bypass_compositor: bool,
+    /// Current `(min, max)` dimensions of the window
+    size_constraints: Cell<(Option<(u32, u32)>, Option<(u32, u32)>)>,
     /// Events that have been retreived with XLib but not dispatched with iterators yet
     pending_events: Mutex<VecDeque<Event>>,
     cursor_state: Mutex<CursorState>,
-    input_handler: Mutex<XInputEventHandler>
+    input_handler: Mutex<XInputEventHandler>,
+    /// The selection of the completion events of the Present extension, if frame timings are
+    /// requested and supported
+    present: Option<PresentInput>,
+    /// State of the files being dragged over the window
+    dnd: Mutex<Dnd>,
+    clipboard: Mutex<Clipboard>,
+    /// The menus exported on the session bus, if a global menu shows them
+    global_menu: Mutex<Option<GlobalMenu>>,
+    /// The menu bar drawn at the top of the window when there is no global menu
+    menu_bar: Mutex<Option<MenuBar>>,
 }
 
 impl Window {
+    /// Creates a window on the screen of the fullscreen monitor if there is one, otherwise on
+    /// the screen of `pl_attribs`, or on the default screen if `None`.
     pub fn new(display: &Arc<XConnection>, window_attrs: &WindowAttributes,
-               pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<&Window>)
+               pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<&Window>,
+               pl_attribs: &PlatformSpecificWindowBuilderAttributes)
                -> Result<Window, CreationError>
     {
-        let dimensions = {
+        Window::new_impl(display, window_attrs, pf_reqs, opengl, pl_attribs, None)
+    }
+
+    /// Creates an OpenGL context for a window that another library has created, and receives
+    /// its events. The window is neither configured nor destroyed by glutin.
+    ///
+    /// The visual of the window must be compatible with the pixel format of the context.
+    pub unsafe fn from_existing(display: &Arc<XConnection>, window: ffi::Window,
+                                window_attrs: &WindowAttributes,
+                                pf_reqs: &PixelFormatRequirements,
+                                opengl: &GlAttributes<&Window>,
+                                pl_attribs: &PlatformSpecificWindowBuilderAttributes)
+                                -> Result<Window, CreationError>
+    {
+        Window::new_impl(display, window_attrs, pf_reqs, opengl, pl_attribs, Some(window))
+    }
+
+    fn new_impl(display: &Arc<XConnection>, window_attrs: &WindowAttributes,
+                pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<&Window>,
+                pl_attribs: &PlatformSpecificWindowBuilderAttributes,
+                existing: Option<ffi::Window>)
+                -> Result<Window, CreationError>
+    {
+        let foreign = existing.is_some();
+
+        // the screen, size and visual of a foreign window are given by its owner
+        let existing_attributes = match existing {
+            Some(window) => unsafe {
+                let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+                display.push_error_trap();
+                let status = (display.xlib.XGetWindowAttributes)(display.display, window,
+                                                                 &mut attributes);
+                if let Err(err) = display.pop_error_trap() {
+                    return Err(OsError(format!("Failed to query the existing window: {}", err)));
+                }
+                if status == 0 {
+                    return Err(OsError(format!("The existing window doesn't exist")));
+                }
+                Some(attributes)
+            },
+            None => None,
+        };
+
+        let dimensions = if let Some(ref attributes) = existing_attributes {
+            (attributes.width as u32, attributes.height as u32)
+        } else {
 
             // x11 only applies constraints when the window is actively resized
             // by the user, so we have to manually apply the initial constraints
@@ -329,15 +802,22 @@ impl Window {
 
         };
 
-        let screen_id = match window_attrs.monitor {
-            Some(PlatformMonitorId::X(MonitorId(_, monitor))) => monitor as i32,
+        let screen_id = match (&window_attrs.monitor, pl_attribs.x11_screen) {
+            _ if existing_attributes.is_some() => unsafe {
+                (display.xlib.XScreenNumberOfScreen)(existing_attributes.as_ref().unwrap().screen)
+            },
+            (&Some(PlatformMonitorId::X(MonitorId(_, monitor))), _) => monitor as i32,
+            (_, Some(screen_id)) => screen_id,
             _ => unsafe { (display.xlib.XDefaultScreen)(display.display) },
         };
+        if screen_id < 0 || screen_id >= unsafe { (display.xlib.XScreenCount)(display.display) } {
+            return Err(OsError(format!("The X screen {} doesn't exist", screen_id)));
+        }
 
         // finding the mode to switch to if necessary
         let (mode_to_switch_to, xf86_desk_mode) = unsafe {
-            let mut mode_num: libc::c_int = mem::uninitialized();
-            let mut modes: *mut *mut ffi::XF86VidModeModeInfo = mem::uninitialized();
+            let mut mode_num: libc::c_int = mem::zeroed();
+            let mut modes: *mut *mut ffi::XF86VidModeModeInfo = mem::zeroed();
             if (display.xf86vmode.XF86VidModeGetAllModeLines)(display.display, screen_id, &mut mode_num, &mut modes) == 0 {
                 (None, None)
             } else {
@@ -366,6 +846,11 @@ impl Window {
             }
         };
 
+        // the context of a foreign window must use the visual that it was created with
+        let existing_visual_id = existing_attributes.as_ref().map(|attributes| unsafe {
+            (display.xlib.XVisualIDFromVisual)(attributes.visual)
+        });
+
         // start the context building process
         enum Prototype<'a> {
             Glx(::api::glx::ContextPrototype<'a>),
@@ -373,12 +858,49 @@ impl Window {
         }
         let builder_clone_opengl_glx = opengl.clone().map_sharing(|_| unimplemented!());      // FIXME:
         let builder_clone_opengl_egl = opengl.clone().map_sharing(|_| unimplemented!());      // FIXME:
+        let builder_clone_opengl_egl_es = match opengl.version {
+            GlRequest::GlThenGles { opengles_version, .. } => {
+                let mut attrs = builder_clone_opengl_egl.clone();
+                attrs.version = GlRequest::Specific(Api::OpenGlEs, opengles_version);
+                attrs
+            },
+            _ => builder_clone_opengl_egl.clone(),
+        };
         let context = match opengl.version {
-            GlRequest::Latest | GlRequest::Specific(Api::OpenGl, _) | GlRequest::GlThenGles { .. } => {
+            GlRequest::GlThenGles { .. } => {
+                // GLX should be preferred over EGL, otherwise crashes may occur
+                // on X11 – issue #314
+                let glx = display.glx.as_ref().map(|glx| {
+                    GlxContext::new(glx.clone(), &display.xlib, pf_reqs, &builder_clone_opengl_glx,
+                                    display.display, screen_id, window_attrs.transparent,
+                                    existing_visual_id)
+                });
+
+                match (glx, display.egl.as_ref()) {
+                    (Some(Ok(prototype)), _) => Prototype::Glx(prototype),
+                    // many drivers can't create OpenGL contexts through GLX but can create
+                    // OpenGL ES contexts through EGL on the same display
+                    (glx, Some(egl)) => {
+                        match EglContext::new(egl.clone(), pf_reqs, &builder_clone_opengl_egl_es,
+                                              egl::NativeDisplay::X11(Some(display.display as *const _)))
+                        {
+                            Ok(prototype) => Prototype::Egl(prototype),
+                            Err(err) => match glx {
+                                // reporting the GLX error, which is more relevant
+                                Some(Err(glx_err)) => return Err(glx_err),
+                                _ => return Err(err),
+                            },
+                        }
+                    },
+                    (Some(Err(err)), None) => return Err(err),
+                    (None, None) => return Err(CreationError::NotSupported),
+                }
+            },
+            GlRequest::Latest | GlRequest::Specific(Api::OpenGl, _) => {
                 // GLX should be preferred over EGL, otherwise crashes may occur
                 // on X11 – issue #314
                 if let Some(ref glx) = display.glx {
-                    Prototype::Glx(try!(GlxContext::new(glx.clone(), &display.xlib, pf_reqs, &builder_clone_opengl_glx, display.display, screen_id)))
+                    Prototype::Glx(try!(GlxContext::new(glx.clone(), &display.xlib, pf_reqs, &builder_clone_opengl_glx, display.display, screen_id, window_attrs.transparent, existing_visual_id)))
                 } else if let Some(ref egl) = display.egl {
                     Prototype::Egl(try!(EglContext::new(egl.clone(), pf_reqs, &builder_clone_opengl_egl, egl::NativeDisplay::X11(Some(display.display as *const _)))))
                 } else {
@@ -408,8 +930,11 @@ impl Window {
                     let mut num_visuals = 0;
                     let vi = (display.xlib.XGetVisualInfo)(display.display, ffi::VisualIDMask,
                                                            &mut template, &mut num_visuals);
-                    display.check_errors().expect("Failed to call XGetVisualInfo");
-                    assert!(!vi.is_null());
+
+                    // some EGL configs are not associated to any X visual
+                    if vi.is_null() {
+                        return Err(CreationError::NoAvailablePixelFormat);
+                    }
                     assert!(num_visuals == 1);
 
                     let vi_copy = ptr::read(vi as *const _);
@@ -418,26 +943,64 @@ impl Window {
                 }
             },
         };
+        if existing_visual_id.map_or(false, |id| id != visual_infos.visualid) {
+            return Err(CreationError::NoAvailablePixelFormat);
+        }
 
         // getting the parent window; root if None
         let parent = match window_attrs.parent {
             Some(ref w) => w.window as ffi::Window,
-            None => {
-                let parent = unsafe { (display.xlib.XDefaultRootWindow)(display.display) };
-                display.check_errors().expect("Failed to get root window");
-                parent
-            }
+            None => unsafe { (display.xlib.XRootWindow)(display.display, screen_id) },
         };
 
         // creating the color map
-        let cmap = unsafe {
+        let cmap = if foreign { 0 } else { unsafe {
+            display.push_error_trap();
             let cmap = (display.xlib.XCreateColormap)(display.display, parent,
                                                       visual_infos.visual as *mut _,
                                                       ffi::AllocNone);
-            display.check_errors().expect("Failed to call XCreateColormap");
+            display.pop_error_trap().expect("Failed to call XCreateColormap");
             cmap
+        } };
+
+        let is_fullscreen = window_attrs.monitor.is_some();
+        let wm_present = display.is_wm_present(screen_id);
+
+        // the offsets of `-geometry` can be from the right and bottom edges of the screen
+        let user_position = match pl_attribs.x11_geometry {
+            Some(geometry) if !is_fullscreen && window_attrs.parent.is_none() => {
+                geometry.offsets.map(|(x, y)| unsafe {
+                    let screen_width = (display.xlib.XDisplayWidth)(display.display, screen_id);
+                    let screen_height = (display.xlib.XDisplayHeight)(display.display, screen_id);
+                    let width = dimensions.0 as libc::c_int;
+                    let height = dimensions.1 as libc::c_int;
+                    (if geometry.from_right { screen_width - width - x } else { x },
+                     if geometry.from_bottom { screen_height - height - y } else { y })
+                })
+            },
+            _ => None,
+        };
+
+        // without a WM, nobody places the window on the screen, so we center it ourselves
+        let position = if let Some(position) = user_position {
+            position
+        } else if !wm_present && !is_fullscreen && window_attrs.parent.is_none() {
+            unsafe {
+                let screen_width = (display.xlib.XDisplayWidth)(display.display, screen_id);
+                let screen_height = (display.xlib.XDisplayHeight)(display.display, screen_id);
+                (cmp::max(0, (screen_width - dimensions.0 as libc::c_int) / 2),
+                 cmp::max(0, (screen_height - dimensions.1 as libc::c_int) / 2))
+            }
+        } else {
+            (0, 0)
         };
 
+        // without a WM that supports `_NET_WM_STATE_FULLSCREEN`, fullscreen windows bypass the
+        // WM and cover the screen by themselves
+        let fullscreen_override_redirect = is_fullscreen && !WmInfo::query(display, screen_id).map_or(false, |wm| {
+            wm.supports(display.get_atom("_NET_WM_STATE_FULLSCREEN"))
+        });
+
         // creating
         let mut set_win_attr = {
             let mut swa: ffi::XSetWindowAttributes = unsafe { mem::zeroed() };
@@ -445,58 +1008,125 @@ impl Window {
             swa.event_mask = ffi::ExposureMask | ffi::StructureNotifyMask |
                 ffi::VisibilityChangeMask | ffi::KeyPressMask | ffi::PointerMotionMask |
                 ffi::KeyReleaseMask | ffi::ButtonPressMask |
-                ffi::ButtonReleaseMask | ffi::KeymapStateMask;
+                ffi::ButtonReleaseMask | ffi::KeymapStateMask | ffi::PropertyChangeMask |
+                ffi::FocusChangeMask;
             swa.border_pixel = 0;
             if window_attrs.transparent {
                 swa.background_pixel = 0;
             }
-            swa.override_redirect = 0;
+            swa.override_redirect = if fullscreen_override_redirect { 1 } else { 0 };
             swa
         };
 
         let mut window_attributes = ffi::CWBorderPixel | ffi::CWEventMask | ffi::CWColormap;
 
+        if fullscreen_override_redirect {
+            window_attributes |= ffi::CWOverrideRedirect;
+        }
+
         if window_attrs.transparent {
             window_attributes |= ffi::CWBackPixel;
         }
 
         // finally creating the window
-        let window = unsafe {
-            let win = (display.xlib.XCreateWindow)(display.display, parent, 0, 0, dimensions.0 as libc::c_uint,
+        let window = if let Some(window) = existing {
+            // only one client can select the button presses of a window, which the owner usually
+            // does, and the buttons are reported through XInput anyway
+            display.push_error_trap();
+            unsafe {
+                (display.xlib.XSelectInput)(display.display, window,
+                                            set_win_attr.event_mask & !ffi::ButtonPressMask);
+            }
+            if let Err(err) = display.pop_error_trap() {
+                return Err(OsError(format!("Failed to listen to the existing window: {}", err)));
+            }
+            window
+        } else { unsafe {
+            display.push_error_trap();
+            let win = (display.xlib.XCreateWindow)(display.display, parent, position.0, position.1, dimensions.0 as libc::c_uint,
                 dimensions.1 as libc::c_uint, 0, visual_infos.depth, ffi::InputOutput as libc::c_uint,
                 visual_infos.visual as *mut _, window_attributes,
                 &mut set_win_attr);
-            display.check_errors().expect("Failed to call XCreateWindow");
+            display.pop_error_trap().expect("Failed to call XCreateWindow");
             win
+        } };
+
+        // from now on, the events of the window are kept for it until it polls them
+        display.register_window(window);
+
+        // the size of the decorations is only known once the WM has reparented the window, but
+        // it can be asked for beforehand
+        let extents = if window_attrs.outer_dimensions && !is_fullscreen && !foreign {
+            wm::request_frame_extents(display, screen_id, window)
+        } else {
+            None
+        };
+        let dimensions = match extents {
+            Some((left, right, top, bottom)) => {
+                let inner = (cmp::max(dimensions.0.saturating_sub(left + right), 1),
+                             cmp::max(dimensions.1.saturating_sub(top + bottom), 1));
+                display.push_error_trap();
+                unsafe {
+                    (display.xlib.XResizeWindow)(display.display, window, inner.0, inner.1);
+                }
+                display.pop_error_trap().expect("Failed to call XResizeWindow");
+                inner
+            },
+            None => dimensions,
         };
 
+        // `-iconic` asks the WM to map the window in the minimized state, which must be known
+        // before the window is mapped
+        if pl_attribs.x11_iconic && !foreign {
+            display.push_error_trap();
+            unsafe {
+                let hints = (display.xlib.XAllocWMHints)();
+                (*hints).flags = ffi::StateHint;
+                (*hints).initial_state = ffi::IconicState;
+                (display.xlib.XSetWMHints)(display.display, window, hints);
+                (display.xlib.XFree)(hints as *mut _);
+            }
+            display.pop_error_trap().expect("Failed to call XSetWMHints");
+        }
+
         // set visibility
-        if window_attrs.visible {
+        if window_attrs.visible && !foreign {
+            display.push_error_trap();
             unsafe {
                 (display.xlib.XMapRaised)(display.display, window);
                 (display.xlib.XFlush)(display.display);
             }
 
-            display.check_errors().expect("Failed to set window visibility");
+            display.pop_error_trap().expect("Failed to set window visibility");
+
+            // without a WM, nobody gives the focus to new windows
+            if !wm_present {
+                display.push_error_trap();
+                unsafe {
+                    (display.xlib.XSetInputFocus)(display.display, window, ffi::RevertToParent,
+                                                  ffi::CurrentTime);
+                }
+                let _ = display.pop_error_trap();
+            }
         }
 
         // creating window, step 2
         let wm_delete_window = unsafe {
-            let mut wm_delete_window = with_c_str("WM_DELETE_WINDOW", |delete_window|
-                (display.xlib.XInternAtom)(display.display, delete_window, 0)
-            );
-            display.check_errors().expect("Failed to call XInternAtom");
-            (display.xlib.XSetWMProtocols)(display.display, window, &mut wm_delete_window, 1);
-            display.check_errors().expect("Failed to call XSetWMProtocols");
+            let mut wm_delete_window = display.get_atom("WM_DELETE_WINDOW");
+            // the owner of a foreign window decides what to do when it is closed
+            if !foreign {
+                display.push_error_trap();
+                (display.xlib.XSetWMProtocols)(display.display, window, &mut wm_delete_window, 1);
+                display.pop_error_trap().expect("Failed to call XSetWMProtocols");
+            }
             (display.xlib.XFlush)(display.display);
-            display.check_errors().expect("Failed to call XFlush");
 
             wm_delete_window
         };
 
         // creating IM
         let im = unsafe {
-            let _lock = GLOBAL_XOPENIM_LOCK.lock().unwrap();
+            let _lock = display.im_lock.lock().unwrap();
 
             let im = (display.xlib.XOpenIM)(display.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
             if im.is_null() {
@@ -519,11 +1149,33 @@ impl Window {
             if ic.is_null() {
                 return Err(OsError(format!("XCreateIC failed")));
             }
+            display.push_error_trap();
             (display.xlib.XSetICFocus)(ic);
-            display.check_errors().expect("Failed to call XSetICFocus");
+            display.pop_error_trap().expect("Failed to call XSetICFocus");
             ic
         };
 
+        // listening to the resizes of the parent window, so that embedded windows follow the
+        // layout of their host
+        if window_attrs.parent.is_some() {
+            display.watch_window(window, parent);
+            display.push_error_trap();
+            display.add_event_mask(parent, ffi::StructureNotifyMask);
+            if let Err(err) = display.pop_error_trap() {
+                return Err(OsError(format!("Failed to listen to the parent window: {}", err)));
+            }
+        }
+
+        // listening to the property changes of the root window, so that we get notified when
+        // the ICC profile of a monitor changes, without replacing the events that the other
+        // windows and clients select on it
+        let root = unsafe { (display.xlib.XRootWindow)(display.display, screen_id) };
+        display.push_error_trap();
+        display.add_event_mask(root, ffi::PropertyChangeMask);
+        if let Err(err) = display.pop_error_trap() {
+            return Err(OsError(format!("Failed to listen to the root window: {}", err)));
+        }
+
         // Attempt to make keyboard input repeat detectable
         unsafe {
             let mut supported_ptr = ffi::False;
@@ -533,83 +1185,101 @@ impl Window {
             }
         }
 
-        // Set ICCCM WM_CLASS property based on initial window title
-        unsafe {
-            with_c_str(&*window_attrs.title, |c_name| {
+        // Set ICCCM WM_CLASS property based on initial window title, unless a resource name has
+        // been given
+        if !foreign { unsafe {
+            let res_name = pl_attribs.x11_resource_name.as_ref().unwrap_or(&window_attrs.title);
+            with_c_str(&*window_attrs.title, |c_class| with_c_str(res_name, |c_name| {
                 let hint = (display.xlib.XAllocClassHint)();
                 (*hint).res_name = c_name as *mut libc::c_char;
-                (*hint).res_class = c_name as *mut libc::c_char;
+                (*hint).res_class = c_class as *mut libc::c_char;
+                display.push_error_trap();
                 (display.xlib.XSetClassHint)(display.display, window, hint);
-                display.check_errors().expect("Failed to call XSetClassHint");
+                display.pop_error_trap().expect("Failed to call XSetClassHint");
                 (display.xlib.XFree)(hint as *mut _);
-            });
+            }));
+        } }
+
+        // there is no standard way to remove the decorations, but all the common window
+        // managers follow the Motif hints
+        if !window_attrs.decorations && !foreign {
+            // flags, functions, decorations, input mode and status, where the flags tell that
+            // only the decorations are set
+            const MWM_HINTS_DECORATIONS: c_long = 1 << 1;
+            let hints: [c_long; 5] = [MWM_HINTS_DECORATIONS, 0, 0, 0, 0];
+            let motif_hints = display.get_atom("_MOTIF_WM_HINTS");
+            display.push_error_trap();
+            unsafe {
+                (display.xlib.XChangeProperty)(display.display, window, motif_hints, motif_hints,
+                                               32, ffi::PropModeReplace,
+                                               hints.as_ptr() as *const _, hints.len() as libc::c_int);
+            }
+            display.pop_error_trap().expect("Failed to set the _MOTIF_WM_HINTS property");
         }
 
-        let is_fullscreen = window_attrs.monitor.is_some();
-
         if is_fullscreen {
-            let state_atom = unsafe {
-                with_c_str("_NET_WM_STATE", |state|
-                    (display.xlib.XInternAtom)(display.display, state, 0)
-                )
-            };
-            display.check_errors().expect("Failed to call XInternAtom");
-            let fullscreen_atom = unsafe {
-                with_c_str("_NET_WM_STATE_FULLSCREEN", |state_fullscreen|
-                    (display.xlib.XInternAtom)(display.display, state_fullscreen, 0)
-                )
-            };
-            display.check_errors().expect("Failed to call XInternAtom");
+            // set before the window is mapped, so that it is never composited
+            if window_attrs.bypass_compositor {
+                wm::set_bypass_compositor(display, window, true);
+            }
 
-            let client_message_event = ffi::XClientMessageEvent {
-                type_: ffi::ClientMessage,
-                serial: 0,
-                send_event: 1,            // true because we are sending this through `XSendEvent`
-                display: display.display,
-                window: window,
-                message_type: state_atom, // the _NET_WM_STATE atom is sent to change the state of a window
-                format: 32,               // view `data` as `c_long`s
-                data: {
-                    let mut data = ffi::ClientMessageData::new();
-                    // This first `long` is the action; `1` means add/set following property.
-                    data.set_long(0, 1);
-                    // This second `long` is the property to set (fullscreen)
-                    data.set_long(1, fullscreen_atom as c_long);
-                    data
-                }
-            };
-            let mut x_event = ffi::XEvent::from(client_message_event);
+            if !fullscreen_override_redirect {
+                let state_atom = display.get_atom("_NET_WM_STATE");
+                let fullscreen_atom = display.get_atom("_NET_WM_STATE_FULLSCREEN");
+
+                let client_message_event = ffi::XClientMessageEvent {
+                    type_: ffi::ClientMessage,
+                    serial: 0,
+                    send_event: 1,            // true because we are sending this through `XSendEvent`
+                    display: display.display,
+                    window: window,
+                    message_type: state_atom, // the _NET_WM_STATE atom is sent to change the state of a window
+                    format: 32,               // view `data` as `c_long`s
+                    data: {
+                        let mut data = ffi::ClientMessageData::new();
+                        // This first `long` is the action; `1` means add/set following property.
+                        data.set_long(0, 1);
+                        // This second `long` is the property to set (fullscreen)
+                        data.set_long(1, fullscreen_atom as c_long);
+                        data
+                    }
+                };
+                let mut x_event = ffi::XEvent::from(client_message_event);
 
-            unsafe {
-                (display.xlib.XSendEvent)(
-                    display.display,
-                    parent,
-                    0,
-                    ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask,
-                    &mut x_event as *mut _
-                );
-                display.check_errors().expect("Failed to call XSendEvent");
+                display.push_error_trap();
+                unsafe {
+                    (display.xlib.XSendEvent)(
+                        display.display,
+                        parent,
+                        0,
+                        ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask,
+                        &mut x_event as *mut _
+                    );
+                }
+                display.pop_error_trap().expect("Failed to call XSendEvent");
             }
 
             if let Some(mut mode_to_switch_to) = mode_to_switch_to {
+                display.push_error_trap();
                 unsafe {
                     (display.xf86vmode.XF86VidModeSwitchToMode)(
                         display.display,
                         screen_id,
                         &mut mode_to_switch_to
                     );
-                    display.check_errors().expect("Failed to call XF86VidModeSwitchToMode");
                 }
+                display.pop_error_trap().expect("Failed to call XF86VidModeSwitchToMode");
             }
             else {
                 println!("[glutin] Unexpected state: `mode` is None creating fullscreen window");
             }
+            display.push_error_trap();
             unsafe {
                 (display.xf86vmode.XF86VidModeSetViewPort)(display.display, screen_id, 0, 0);
-                display.check_errors().expect("Failed to call XF86VidModeSetViewPort");
             }
+            display.pop_error_trap().expect("Failed to call XF86VidModeSetViewPort");
 
-        } else {
+        } else if !foreign {
 
             // set size hints
             let mut size_hints: ffi::XSizeHints = unsafe { mem::zeroed() };
@@ -629,25 +1299,41 @@ impl Window {
                 size_hints.max_height = dimensions.1 as i32;
             }
 
+            // the WMs honor the geometry of the user, but may ignore the one of the program
+            if let Some(geometry) = pl_attribs.x11_geometry {
+                if geometry.width.is_some() || geometry.height.is_some() {
+                    size_hints.flags |= ffi::USSize;
+                }
+                if let Some(position) = user_position {
+                    size_hints.flags |= ffi::USPosition | ffi::PWinGravity;
+                    size_hints.x = position.0;
+                    size_hints.y = position.1;
+                    size_hints.win_gravity = match (geometry.from_right, geometry.from_bottom) {
+                        (false, false) => ffi::NorthWestGravity,
+                        (true, false) => ffi::NorthEastGravity,
+                        (false, true) => ffi::SouthWestGravity,
+                        (true, true) => ffi::SouthEastGravity,
+                    };
+                }
+            }
+
+            display.push_error_trap();
             unsafe {
                 (display.xlib.XSetNormalHints)(display.display, window, &mut size_hints);
-                display.check_errors().expect("Failed to call XSetNormalHints");
             }
+            display.pop_error_trap().expect("Failed to call XSetNormalHints");
 
         }
 
-        // finish creating the OpenGL context
+        // finish creating the OpenGL context, which can produce errors, but since everything is
+        // checked we ignore them
+        display.push_error_trap();
         let context = match context {
-            Prototype::Glx(ctxt) => {
-                Context::Glx(try!(ctxt.finish(window)))
-            },
-            Prototype::Egl(ctxt) => {
-                Context::Egl(try!(ctxt.finish(window as *const libc::c_void)))
-            },
+            Prototype::Glx(ctxt) => ctxt.finish(window).map(Context::Glx),
+            Prototype::Egl(ctxt) => ctxt.finish(window as *const libc::c_void).map(Context::Egl),
         };
-
-        // creating the OpenGL can produce errors, but since everything is checked we ignore
-        display.ignore_error();
+        let _ = display.pop_error_trap();
+        let context = try!(context);
 
         // creating the window object
         let window_proxy_data = WindowProxyData {
@@ -663,20 +1349,68 @@ impl Window {
                 im: im,
                 ic: ic,
                 context: context,
+                visual: existing_attributes.as_ref().map_or(visual_infos.visual, |a| a.visual),
+                depth: existing_attributes.as_ref().map_or(visual_infos.depth, |a| a.depth),
                 screen_id: screen_id,
                 is_fullscreen: is_fullscreen,
                 xf86_desk_mode: xf86_desk_mode,
+                xf86_fullscreen_mode: mode_to_switch_to,
                 colormap: cmap,
                 window_proxy_data: window_proxy_data,
+                foreign: foreign,
             }),
             is_closed: AtomicBool::new(false),
             wm_delete_window: wm_delete_window,
             current_size: Cell::new((0, 0)),
+            last_user_time: Cell::new(ffi::CurrentTime),
+            wm_state: Cell::new((is_fullscreen, false)),
+            minimized: Cell::new(false),
+            title: Mutex::new(String::new()),
+            wm_name: Mutex::new(String::new()),
+            tile_state: Cell::new(TileState::Untiled),
+            refresh_rate: Cell::new(None),
+            refresh_point: Cell::new(None),
+            crtc_layout: Mutex::new(None),
+            lock_keys: Cell::new(LockKeys::default()),
+            wm_present: wm_present,
+            fullscreen_focus_policy: window_attrs.fullscreen_focus_policy,
+            bypass_compositor: window_attrs.bypass_compositor,
+            focused: Cell::new(false),
+            current_cursor: Cell::new(MouseCursor::Default),
+            embedder: Cell::new(window_attrs.parent.as_ref().map(|_| parent)),
+            size_constraints: Cell::new((window_attrs.min_dimensions, window_attrs.max_dimensions)),
             pending_events: Mutex::new(VecDeque::new()),
             cursor_state: Mutex::new(CursorState::Normal),
-            input_handler: Mutex::new(XInputEventHandler::new(display, window, ic, window_attrs))
+            input_handler: Mutex::new({
+                // the owner may already have selected the XInput button presses, which only one
+                // client can do
+                if foreign {
+                    display.push_error_trap();
+                }
+                let handler = XInputEventHandler::new(display, window, ic, window_attrs);
+                if foreign {
+                    let _ = display.pop_error_trap();
+                }
+                handler
+            }),
+            present: if window_attrs.frame_timing {
+                PresentInput::select(display, window)
+            } else {
+                None
+            },
+            dnd: Mutex::new(Dnd::new(display, window)),
+            clipboard: Mutex::new(Clipboard::new(display, window)),
+            global_menu: Mutex::new(None),
+            menu_bar: Mutex::new(None),
         };
 
+        if foreign {
+            *window.title.lock().unwrap() = window.get_title().unwrap_or_else(String::new);
+            *window.wm_name.lock().unwrap() = window.get_wm_name().unwrap_or_else(String::new);
+            window.refresh_rate.set(window.get_refresh_rate());
+            return Ok(window);
+        }
+
         window.set_title(&window_attrs.title);
 
         if window_attrs.visible {
@@ -686,18 +1420,20 @@ impl Window {
                 // XSetInputFocus generates an error if the window is not visible,
                 // therefore we wait until it's the case.
                 loop {
-                    let mut window_attributes = mem::uninitialized();
+                    let mut window_attributes = mem::zeroed();
+                    display.push_error_trap();
                     (display.xlib.XGetWindowAttributes)(display.display, x_window.window, &mut window_attributes);
-                    display.check_errors().expect("Failed to call XGetWindowAttributes");
+                    display.pop_error_trap().expect("Failed to call XGetWindowAttributes");
 
                     if window_attributes.map_state == ffi::IsViewable {
+                        display.push_error_trap();
                         (display.xlib.XSetInputFocus)(
                             display.display,
                             x_window.window,
                             ffi::RevertToParent,
                             ffi::CurrentTime
                         );
-                        display.check_errors().expect("Failed to call XSetInputFocus");
+                        display.pop_error_trap().expect("Failed to call XSetInputFocus");
                         break;
                     }
 
@@ -711,6 +1447,12 @@ impl Window {
             window.set_icon(icon_path);
         }
 
+        if window_attrs.variable_refresh {
+            window.set_variable_refresh(true);
+        }
+
+        window.refresh_rate.set(window.get_refresh_rate());
+
         // returning
         Ok(window)
     }
@@ -751,59 +1493,623 @@ impl Window {
         assert!(buff.len() == (width * height + 2) as usize);
 
         unsafe {
-            let net_wm_icon = (self.x.display.xlib.XInternAtom)(self.x.display.display,
-                                                                b"_NET_WM_ICON\0".as_ptr() as *const _, 0);
-
-            let cardinal = (self.x.display.xlib.XInternAtom)(self.x.display.display,
-                                                             b"CARDINAL\0".as_ptr() as *const _, 0);
+            let net_wm_icon = self.x.display.get_atom("_NET_WM_ICON");
+            let cardinal = self.x.display.get_atom("CARDINAL");
 
+            self.x.display.push_error_trap();
             (self.x.display.xlib.XChangeProperty)(self.x.display.display,
                                                   self.x.window,
                                                   net_wm_icon,
                                                   cardinal, 32, ffi::PropModeReplace,
                                                   buff.as_ptr() as *const _,
                                                   buff.len() as libc::c_int);
-            self.x.display.check_errors().expect("Failed to set the icon");
+            self.x.display.pop_error_trap().expect("Failed to set the icon");
         }
     }
 
+    /// Sets the title in UTF-8 with `_NET_WM_NAME` and `_NET_WM_ICON_NAME`, which are read by
+    /// all the modern window managers and taskbars. The legacy `WM_NAME` and `WM_ICON_NAME`
+    /// properties are Latin-1, so they receive the characters that Latin-1 can represent.
     pub fn set_title(&self, title: &str) {
-        let wm_name = unsafe {
-            (self.x.display.xlib.XInternAtom)(self.x.display.display, b"_NET_WM_NAME\0".as_ptr() as *const _, 0)
-        };
-        self.x.display.check_errors().expect("Failed to call XInternAtom");
+        *self.title.lock().unwrap() = title.to_owned();
 
-        let wm_utf8_string = unsafe {
-            (self.x.display.xlib.XInternAtom)(self.x.display.display, b"UTF8_STRING\0".as_ptr() as *const _, 0)
-        };
-        self.x.display.check_errors().expect("Failed to call XInternAtom");
+        let wm_name = self.x.display.get_atom("_NET_WM_NAME");
+        let wm_icon_name = self.x.display.get_atom("_NET_WM_ICON_NAME");
+        let wm_utf8_string = self.x.display.get_atom("UTF8_STRING");
 
-        with_c_str(title, |c_title| unsafe {
-            (self.x.display.xlib.XStoreName)(self.x.display.display, self.x.window, c_title);
+        // the `char`s below 256 are the Latin-1 characters with the same code
+        let latin1: Vec<u8> = title.chars()
+                                   .filter(|&c| c != '\0')
+                                   .map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+                                   .collect();
+        *self.wm_name.lock().unwrap() = latin1.iter().map(|&c| c as char).collect();
+        let latin1 = CString::new(latin1).unwrap();
 
-            let len = title.as_bytes().len();
-            (self.x.display.xlib.XChangeProperty)(self.x.display.display, self.x.window,
-                                            wm_name, wm_utf8_string, 8, ffi::PropModeReplace,
-                                            c_title as *const u8, len as libc::c_int);
+        self.x.display.push_error_trap();
+        unsafe {
+            (self.x.display.xlib.XStoreName)(self.x.display.display, self.x.window,
+                                             latin1.as_ptr());
+            (self.x.display.xlib.XSetIconName)(self.x.display.display, self.x.window,
+                                               latin1.as_ptr());
+
+            for &property in [wm_name, wm_icon_name].iter() {
+                (self.x.display.xlib.XChangeProperty)(self.x.display.display, self.x.window,
+                                                      property, wm_utf8_string, 8,
+                                                      ffi::PropModeReplace, title.as_ptr(),
+                                                      title.len() as libc::c_int);
+            }
             (self.x.display.xlib.XFlush)(self.x.display.display);
-        });
-        self.x.display.check_errors().expect("Failed to set window title");
+        }
+        self.x.display.pop_error_trap().expect("Failed to set window title");
+    }
+
+    /// Reads the title back from `_NET_WM_NAME`, or from `WM_NAME` if the title has been set by
+    /// another client that only knows about the legacy property.
+    pub fn get_title(&self) -> Option<String> {
+        let display = &self.x.display;
+        let utf8 = wm::get_property::<u8>(display, self.x.window, display.get_atom("_NET_WM_NAME"),
+                                          display.get_atom("UTF8_STRING"), 8);
+        if let Some(utf8) = utf8 {
+            return Some(String::from_utf8_lossy(&utf8).into_owned());
+        }
+
+        self.get_wm_name()
+    }
+
+    /// Reads the ICCCM `WM_NAME` property, which is usually Latin-1 but may be UTF-8.
+    fn get_wm_name(&self) -> Option<String> {
+        let display = &self.x.display;
+        let utf8 = wm::get_property::<u8>(display, self.x.window, ffi::XA_WM_NAME,
+                                          display.get_atom("UTF8_STRING"), 8);
+        if let Some(utf8) = utf8 {
+            return Some(String::from_utf8_lossy(&utf8).into_owned());
+        }
 
+        // Latin-1 maps directly to the first 256 code points
+        wm::get_property::<u8>(display, self.x.window, ffi::XA_WM_NAME, ffi::XA_STRING, 8)
+            .map(|latin1| latin1.iter().map(|&c| c as char).collect())
     }
 
     pub fn show(&self) {
         unsafe {
+            self.x.display.push_error_trap();
             (self.x.display.xlib.XMapRaised)(self.x.display.display, self.x.window);
             (self.x.display.xlib.XFlush)(self.x.display.display);
-            self.x.display.check_errors().expect("Failed to call XMapRaised");
+            self.x.display.pop_error_trap().expect("Failed to call XMapRaised");
         }
     }
 
     pub fn hide(&self) {
         unsafe {
+            self.x.display.push_error_trap();
             (self.x.display.xlib.XUnmapWindow)(self.x.display.display, self.x.window);
             (self.x.display.xlib.XFlush)(self.x.display.display);
-            self.x.display.check_errors().expect("Failed to call XUnmapWindow");
+            self.x.display.pop_error_trap().expect("Failed to call XUnmapWindow");
+        }
+    }
+
+    /// Replaces the menus of the window, or removes them if `menus` is empty.
+    ///
+    /// The menus are exported to the global menu of the desktop if there is one, otherwise they
+    /// are drawn in a bar at the top of the window.
+    pub fn set_menu_bar(&self, menus: &[::Menu]) {
+        let mut global_menu = self.global_menu.lock().unwrap();
+        let mut menu_bar = self.menu_bar.lock().unwrap();
+
+        if menus.is_empty() {
+            *global_menu = None;
+            *menu_bar = None;
+            return;
+        }
+
+        if let Some(ref global_menu) = *global_menu {
+            global_menu.set_menus(menus);
+            return;
+        }
+
+        *global_menu = GlobalMenu::new(self.x.window, self.create_window_proxy(), menus);
+        if global_menu.is_some() {
+            *menu_bar = None;
+            return;
+        }
+
+        if let Some(ref mut menu_bar) = *menu_bar {
+            menu_bar.set_menus(menus);
+            return;
+        }
+        let width = self.get_inner_size().map_or(1, |(width, _)| width) as libc::c_uint;
+        *menu_bar = MenuBar::new(&self.x.display, self.x.window, width, menus);
+    }
+
+    /// Returns the height of the menu bar that is drawn over the top of the window, or `0` if
+    /// the menus are exported to the global menu.
+    pub fn get_menu_bar_height(&self) -> u32 {
+        self.menu_bar.lock().unwrap().as_ref().map_or(0, |menu_bar| menu_bar.height())
+    }
+
+    /// Passes the events of the menu bar and of its menus to it, and queues the `MenuItem`
+    /// events that they produce.
+    ///
+    /// Returns `false` if the event concerns another window.
+    fn handle_menu_bar_event(&self, xev: &ffi::XEvent) -> bool {
+        let mut menu_bar = self.menu_bar.lock().unwrap();
+        let menu_bar = match *menu_bar {
+            Some(ref mut menu_bar) => menu_bar,
+            None => return false,
+        };
+
+        if xev.get_type() == ffi::GenericEvent ||
+           !menu_bar.owns(ffi::XAnyEvent::from(*xev).window)
+        {
+            return false;
+        }
+
+        // the menus would take the pointer from the application
+        let can_open = match *self.cursor_state.lock().unwrap() {
+            CursorState::Grab | CursorState::GrabRelative => false,
+            _ => true,
+        };
+        if let Some(id) = menu_bar.handle_event(xev, can_open) {
+            self.pending_events.lock().unwrap().push_back(Event::MenuItem(id));
+        }
+        true
+    }
+
+    /// Embeds the window in another window, or turns it back into a top-level window with
+    /// `None`. The embedded window follows the size of its new parent.
+    pub fn set_parent(&self, parent: Option<ffi::Window>) -> Result<(), String> {
+        let display = &self.x.display;
+
+        display.push_error_trap();
+        unsafe {
+            let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+            (display.xlib.XGetWindowAttributes)(display.display, self.x.window, &mut attributes);
+            let mapped = attributes.map_state != ffi::IsUnmapped;
+
+            // the WM gives up a top-level window once it has been withdrawn, and only manages a
+            // window that becomes top-level once it is mapped again
+            if mapped {
+                if self.embedder.get().is_some() {
+                    (display.xlib.XUnmapWindow)(display.display, self.x.window);
+                } else {
+                    (display.xlib.XWithdrawWindow)(display.display, self.x.window, self.x.screen_id);
+                    // the WM takes the window out of its frame asynchronously, and would move it
+                    // back to the root window after our reparenting otherwise
+                    self.wait_for_withdrawal();
+                }
+            }
+
+            let new_parent = match parent {
+                Some(parent) => {
+                    display.add_event_mask(parent, ffi::StructureNotifyMask);
+                    parent
+                },
+                None => (display.xlib.XRootWindow)(display.display, self.x.screen_id),
+            };
+            (display.xlib.XReparentWindow)(display.display, self.x.window, new_parent, 0, 0);
+
+            if parent.is_some() {
+                let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+                if (display.xlib.XGetWindowAttributes)(display.display, new_parent,
+                                                       &mut attributes) != 0 &&
+                   attributes.width > 0 && attributes.height > 0
+                {
+                    (display.xlib.XResizeWindow)(display.display, self.x.window,
+                                                 attributes.width as libc::c_uint,
+                                                 attributes.height as libc::c_uint);
+                }
+            }
+
+            if mapped {
+                (display.xlib.XMapWindow)(display.display, self.x.window);
+            }
+        }
+
+        if let Err(err) = display.pop_error_trap() {
+            return Err(format!("Failed to reparent the window: {}", err));
+        }
+
+        if let Some(old_parent) = self.embedder.get() {
+            display.unwatch_window(self.x.window, old_parent);
+        }
+        if let Some(parent) = parent {
+            display.watch_window(self.x.window, parent);
+        }
+        self.embedder.set(parent);
+        Ok(())
+    }
+
+    /// Waits until the window is unmapped and is a child of the root window, which is how the
+    /// WM acknowledges a withdrawal, or at most one second.
+    ///
+    /// The state is queried instead of waiting for the `UnmapNotify` and `ReparentNotify` events
+    /// themselves, which are left to the event loop.
+    fn wait_for_withdrawal(&self) {
+        let display = &self.x.display;
+        let root = unsafe { (display.xlib.XRootWindow)(display.display, self.x.screen_id) };
+        let deadline = Instant::now() + Duration::from_secs(1);
+
+        loop {
+            let withdrawn = unsafe {
+                let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+                (display.xlib.XGetWindowAttributes)(display.display, self.x.window, &mut attributes);
+
+                let (mut tree_root, mut parent) = (0, 0);
+                let mut children = ptr::null_mut();
+                let mut num_children = 0;
+                let found = (display.xlib.XQueryTree)(display.display, self.x.window,
+                                                      &mut tree_root, &mut parent, &mut children,
+                                                      &mut num_children);
+                if !children.is_null() {
+                    (display.xlib.XFree)(children as *mut _);
+                }
+
+                found == 0 || (attributes.map_state == ffi::IsUnmapped && parent == root)
+            };
+
+            if withdrawn || Instant::now() >= deadline {
+                return;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Reads the `_NET_WM_STATE` property of the window and returns whether the window is
+    /// `(fullscreen, maximized)`.
+    fn get_wm_state(&self) -> (bool, bool) {
+        unsafe {
+            let state_atom = self.x.display.get_atom("_NET_WM_STATE");
+            let fullscreen_atom = self.x.display.get_atom("_NET_WM_STATE_FULLSCREEN");
+            let maximized_horz_atom = self.x.display.get_atom("_NET_WM_STATE_MAXIMIZED_HORZ");
+            let maximized_vert_atom = self.x.display.get_atom("_NET_WM_STATE_MAXIMIZED_VERT");
+
+            let mut actual_type = mem::zeroed();
+            let mut actual_format = mem::zeroed();
+            let mut nitems = mem::zeroed();
+            let mut bytes_after = mem::zeroed();
+            let mut data: *mut libc::c_uchar = ptr::null_mut();
+
+            self.x.display.push_error_trap();
+            (self.x.display.xlib.XGetWindowProperty)(self.x.display.display, self.x.window,
+                                                     state_atom, 0, 1024, ffi::False,
+                                                     ffi::XA_ATOM, &mut actual_type,
+                                                     &mut actual_format, &mut nitems,
+                                                     &mut bytes_after, &mut data);
+            self.x.display.pop_error_trap().expect("Failed to call XGetWindowProperty");
+
+            if data.is_null() {
+                return (false, false);
+            }
+
+            let atoms: &[ffi::Atom] = if actual_format == 32 {
+                ::std::slice::from_raw_parts(data as *const ffi::Atom, nitems as usize)
+            } else {
+                &[]
+            };
+
+            let fullscreen = atoms.contains(&fullscreen_atom);
+            let maximized = atoms.contains(&maximized_horz_atom) &&
+                            atoms.contains(&maximized_vert_atom);
+
+            (self.x.display.xlib.XFree)(data as *mut _);
+            (fullscreen, maximized)
+        }
+    }
+
+    /// Reads the ICCCM `WM_STATE` property of the window, which the window manager sets to
+    /// `IconicState` when the window is minimized.
+    fn is_iconified(&self) -> bool {
+        let wm_state = self.x.display.get_atom("WM_STATE");
+        wm::get_property::<libc::c_ulong>(&self.x.display, self.x.window, wm_state, wm_state, 32)
+            .and_then(|state| state.first().cloned())
+            .map_or(false, |state| state == ffi::IconicState as libc::c_ulong)
+    }
+
+    /// Reads the tiling of the window, and queues a `Tiled` event if it changed.
+    fn update_tile_state(&self, pending_events: &mut VecDeque<Event>) {
+        let tile_state = self.get_tile_state();
+        if tile_state != self.tile_state.get() {
+            self.tile_state.set(tile_state);
+            pending_events.push_back(Event::Tiled(tile_state));
+        }
+    }
+
+    fn get_tile_state(&self) -> TileState {
+        const TOP_TILED: libc::c_ulong = 1 << 0;
+        const RIGHT_TILED: libc::c_ulong = 1 << 2;
+        const BOTTOM_TILED: libc::c_ulong = 1 << 4;
+        const LEFT_TILED: libc::c_ulong = 1 << 6;
+
+        let display = &self.x.display;
+
+        let state = wm::get_property::<ffi::Atom>(display, self.x.window,
+                                                  display.get_atom("_NET_WM_STATE"),
+                                                  ffi::XA_ATOM, 32).unwrap_or(Vec::new());
+        let maximized_horz = state.contains(&display.get_atom("_NET_WM_STATE_MAXIMIZED_HORZ"));
+        let maximized_vert = state.contains(&display.get_atom("_NET_WM_STATE_MAXIMIZED_VERT"));
+        if state.contains(&display.get_atom("_NET_WM_STATE_FULLSCREEN")) ||
+           (maximized_horz && maximized_vert)
+        {
+            return TileState::Untiled;
+        }
+
+        // Mutter tells which edges of the window are stuck to another window or to the screen
+        let constraints = wm::get_property::<libc::c_ulong>(display, self.x.window,
+                                                            display.get_atom("_GTK_EDGE_CONSTRAINTS"),
+                                                            ffi::XA_CARDINAL, 32);
+        if let Some(&constraints) = constraints.as_ref().and_then(|c| c.first()) {
+            let vertical = TOP_TILED | BOTTOM_TILED;
+            return match constraints & (vertical | LEFT_TILED | RIGHT_TILED) {
+                0 => TileState::Untiled,
+                c if c == vertical | LEFT_TILED => TileState::Left,
+                c if c == vertical | RIGHT_TILED => TileState::Right,
+                _ => TileState::Other,
+            };
+        }
+
+        // other WMs maximize tiled windows vertically only, and stick them to the left or
+        // right edge of the workarea; a window that the user merely maximized vertically is
+        // usually away from both edges
+        if !maximized_vert {
+            return TileState::Untiled;
+        }
+
+        let monitor = MonitorId(display.clone(), self.x.screen_id as u32);
+        let (area_x, _, area_width, _) = match monitor.get_workarea() {
+            Some(workarea) => workarea,
+            None => return TileState::Untiled,
+        };
+        let width = match self.get_geometry() {
+            Some((_, _, width, _, _)) => width as i32,
+            None => return TileState::Untiled,
+        };
+        let x = unsafe {
+            let root = (display.xlib.XRootWindow)(display.display, self.x.screen_id);
+            let (mut x, mut y, mut child) = (0, 0, 0);
+            (display.xlib.XTranslateCoordinates)(display.display, self.x.window, root, 0, 0,
+                                                 &mut x, &mut y, &mut child);
+            x
+        };
+
+        // the workarea contains the frame of the window, not only its content
+        let (left, right, _, _) = wm::get_frame_extents(display, self.x.window)
+                                      .unwrap_or((0, 0, 0, 0));
+        let frame_left = x - left as i32;
+        let frame_right = x + width + right as i32;
+        let area_right = area_x + area_width as i32;
+
+        // a few pixels of slack for the WMs that leave a gap between the window and the edge
+        const SLACK: i32 = 2;
+        let at_left = (frame_left - area_x).abs() <= SLACK;
+        let at_right = (frame_right - area_right).abs() <= SLACK;
+        match (at_left, at_right) {
+            (true, false) => TileState::Left,
+            (false, true) => TileState::Right,
+            _ => TileState::Untiled,
+        }
+    }
+
+    /// Records a change of focus and dispatches a `Focused` event.
+    ///
+    /// Focus changes are reported by both XInput2 and the core protocol, so they are only
+    /// dispatched if the state actually changed.
+    fn focus_changed(&self, focused: bool) {
+        if self.focused.get() == focused {
+            return;
+        }
+        self.focused.set(focused);
+
+        // the video mode is applied again before the application learns about the focus
+        if focused {
+            self.apply_fullscreen_focus_policy(true);
+        }
+
+        // the soft cursor mode shows the system cursor while the window doesn't have the focus
+        if *self.lock_cursor_state() == CursorState::Soft {
+            self.set_cursor_hidden(focused);
+        }
+
+        self.pending_events.lock().unwrap().push_back(Event::Focused(focused));
+
+        if focused {
+            self.update_lock_keys();
+        } else {
+            self.apply_fullscreen_focus_policy(false);
+        }
+    }
+
+    /// Gives the video mode of the desktop back and minimizes the window when it loses the focus
+    /// while fullscreen, or switches to the video mode of the window again when it gets the
+    /// focus back.
+    fn apply_fullscreen_focus_policy(&self, focused: bool) {
+        if self.fullscreen_focus_policy != FullscreenFocusPolicy::Minimize {
+            return;
+        }
+
+        let display = &self.x.display;
+        let mut mode = match (self.x.xf86_fullscreen_mode, self.x.xf86_desk_mode) {
+            (Some(fullscreen_mode), _) if focused => fullscreen_mode,
+            (Some(_), Some(desk_mode)) => desk_mode,
+            _ => return,
+        };
+
+        display.push_error_trap();
+        unsafe {
+            (display.xf86vmode.XF86VidModeSwitchToMode)(display.display, self.x.screen_id,
+                                                        &mut mode);
+            (display.xf86vmode.XF86VidModeSetViewPort)(display.display, self.x.screen_id, 0, 0);
+        }
+        display.pop_error_trap().expect("Failed to call XF86VidModeSwitchToMode");
+
+        if !focused {
+            self.set_minimized(true);
+        }
+    }
+
+    /// Queues a `LockKeysChanged` event if the state of the lock keys has changed.
+    fn update_lock_keys(&self) {
+        let lock_keys = self.get_lock_keys();
+        if lock_keys != self.lock_keys.get() {
+            self.lock_keys.set(lock_keys);
+            self.pending_events.lock().unwrap().push_back(Event::LockKeysChanged(lock_keys));
+        }
+    }
+
+    /// Reads the state of the lock keys from the indicators of the core keyboard.
+    pub fn get_lock_keys(&self) -> LockKeys {
+        let display = &self.x.display;
+        let indicator = |name: &str| unsafe {
+            let mut state = 0;
+            let found = (display.xlib.XkbGetNamedIndicator)(display.display, XKB_USE_CORE_KBD,
+                                                            display.get_atom(name), ptr::null_mut(),
+                                                            &mut state, ptr::null_mut(),
+                                                            ptr::null_mut());
+            found != 0 && state != 0
+        };
+
+        LockKeys {
+            caps_lock: indicator("Caps Lock"),
+            num_lock: indicator("Num Lock"),
+            scroll_lock: indicator("Scroll Lock"),
+        }
+    }
+
+    /// Unbinds the current context of the calling thread.
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        match self.x.context {
+            Context::Glx(ref ctxt) => ctxt.make_not_current(),
+            Context::Egl(ref ctxt) => ctxt.make_not_current(),
+            Context::None => Ok(())
+        }
+    }
+
+    /// Reads the acceleration of the core pointer.
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        let display = &self.x.display;
+        let (mut numerator, mut denominator, mut threshold) = (0, 0, 0);
+        unsafe {
+            (display.xlib.XGetPointerControl)(display.display, &mut numerator, &mut denominator,
+                                              &mut threshold);
+        }
+
+        if denominator <= 0 {
+            return None;
+        }
+
+        let factor = numerator as f64 / denominator as f64;
+        Some(PointerAcceleration {
+            enabled: factor != 1.0,
+            speed: factor,
+            threshold: Some(threshold.max(0) as u32),
+        })
+    }
+
+    /// Locks or unlocks the Caps Lock and Num Lock modifiers, and turns the Scroll Lock LED on
+    /// or off, since Scroll Lock isn't a modifier.
+    pub fn set_lock_keys(&self, lock_keys: LockKeys) -> Result<(), String> {
+        let display = &self.x.display;
+
+        // Num Lock is conventionally bound to Mod2
+        let affect = ffi::LockMask | ffi::Mod2Mask;
+        let mut values = 0;
+        if lock_keys.caps_lock { values |= ffi::LockMask; }
+        if lock_keys.num_lock { values |= ffi::Mod2Mask; }
+
+        display.push_error_trap();
+        let result = unsafe {
+            if (display.xlib.XkbLockModifiers)(display.display, XKB_USE_CORE_KBD, affect,
+                                               values) == 0
+            {
+                Err("the lock keys could not be changed".to_string())
+            } else if (display.xlib.XkbSetNamedIndicator)(display.display, XKB_USE_CORE_KBD,
+                                                          display.get_atom("Scroll Lock"),
+                                                          ffi::True,
+                                                          lock_keys.scroll_lock as ffi::Bool,
+                                                          ffi::False, ptr::null_mut()) == 0
+            {
+                Err("the Scroll Lock LED could not be changed".to_string())
+            } else {
+                (display.xlib.XFlush)(display.display);
+                Ok(())
+            }
+        };
+
+        let trapped = display.pop_error_trap().map_err(|e| e.to_string());
+        result.and(trapped)
+    }
+
+    /// Sends an EWMH client message about this window to the root window, where the window
+    /// manager will pick it up.
+    fn send_client_message(&self, message_type: ffi::Atom, data: [c_long; 5]) {
+        let client_message_event = ffi::XClientMessageEvent {
+            type_: ffi::ClientMessage,
+            serial: 0,
+            send_event: 1,
+            display: self.x.display.display,
+            window: self.x.window,
+            message_type: message_type,
+            format: 32,
+            data: {
+                let mut client_data = ffi::ClientMessageData::new();
+                for (i, value) in data.iter().enumerate() {
+                    client_data.set_long(i, *value);
+                }
+                client_data
+            }
+        };
+        let mut x_event = ffi::XEvent::from(client_message_event);
+
+        self.x.display.push_error_trap();
+        unsafe {
+            let root = (self.x.display.xlib.XRootWindow)(self.x.display.display, self.x.screen_id);
+            (self.x.display.xlib.XSendEvent)(
+                self.x.display.display,
+                root,
+                0,
+                ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask,
+                &mut x_event as *mut _
+            );
+            (self.x.display.xlib.XFlush)(self.x.display.display);
+        }
+        self.x.display.pop_error_trap().expect("Failed to call XSendEvent");
+    }
+
+    pub fn focus(&self) {
+        if !self.wm_present {
+            self.x.display.push_error_trap();
+            unsafe {
+                (self.x.display.xlib.XRaiseWindow)(self.x.display.display, self.x.window);
+                (self.x.display.xlib.XSetInputFocus)(self.x.display.display, self.x.window,
+                                                     ffi::RevertToParent, ffi::CurrentTime);
+            }
+            let _ = self.x.display.pop_error_trap();
+            return;
+        }
+
+        let net_active_window = self.x.display.get_atom("_NET_ACTIVE_WINDOW");
+
+        // The source indication `1` means that the request comes from a regular application.
+        // The WM's focus stealing prevention compares the timestamp with the last user
+        // interaction, so we pass the time of the last input event received by this window.
+        let time = self.last_user_time.get();
+        self.send_client_message(net_active_window, [1, time as c_long, 0, 0, 0]);
+    }
+
+    pub fn raise(&self) {
+        unsafe {
+            self.x.display.push_error_trap();
+            (self.x.display.xlib.XRaiseWindow)(self.x.display.display, self.x.window);
+            (self.x.display.xlib.XFlush)(self.x.display.display);
+            self.x.display.pop_error_trap().expect("Failed to call XRaiseWindow");
+        }
+    }
+
+    pub fn lower(&self) {
+        unsafe {
+            self.x.display.push_error_trap();
+            (self.x.display.xlib.XLowerWindow)(self.x.display.display, self.x.window);
+            (self.x.display.xlib.XFlush)(self.x.display.display);
+            self.x.display.pop_error_trap().expect("Failed to call XLowerWindow");
         }
     }
 
@@ -811,13 +2117,13 @@ impl Window {
         unsafe {
             use std::mem;
 
-            let mut root: ffi::Window = mem::uninitialized();
-            let mut x: libc::c_int = mem::uninitialized();
-            let mut y: libc::c_int = mem::uninitialized();
-            let mut width: libc::c_uint = mem::uninitialized();
-            let mut height: libc::c_uint = mem::uninitialized();
-            let mut border: libc::c_uint = mem::uninitialized();
-            let mut depth: libc::c_uint = mem::uninitialized();
+            let mut root: ffi::Window = mem::zeroed();
+            let mut x: libc::c_int = mem::zeroed();
+            let mut y: libc::c_int = mem::zeroed();
+            let mut width: libc::c_uint = mem::zeroed();
+            let mut height: libc::c_uint = mem::zeroed();
+            let mut border: libc::c_uint = mem::zeroed();
+            let mut depth: libc::c_uint = mem::zeroed();
 
             if (self.x.display.xlib.XGetGeometry)(self.x.display.display, self.x.window,
                 &mut root, &mut x, &mut y, &mut width, &mut height,
@@ -835,9 +2141,129 @@ impl Window {
         self.get_geometry().map(|(x, y, _, _, _)| (x, y))
     }
 
+    /// Returns the refresh rate of the monitor under the center of the window, in millihertz.
+    fn get_refresh_rate(&self) -> Option<u32> {
+        match self.get_geometry() {
+            Some((_, _, width, height, _)) => self.refresh_rate_at_center(width as i32, height as i32),
+            None => None,
+        }
+    }
+
+    /// Returns the refresh rate of the monitor under the center of the window, given its size.
+    ///
+    /// XRandR is only queried if the center has moved since the previous call.
+    fn refresh_rate_at_center(&self, width: i32, height: i32) -> Option<u32> {
+        let display = &self.x.display;
+        let center = unsafe {
+            let root = (display.xlib.XRootWindow)(display.display, self.x.screen_id);
+            let mut x = 0;
+            let mut y = 0;
+            let mut child = 0;
+            (display.xlib.XTranslateCoordinates)(display.display, self.x.window, root,
+                                                 width / 2, height / 2, &mut x, &mut y,
+                                                 &mut child);
+            (x, y)
+        };
+
+        if self.refresh_point.get() == Some(center) {
+            return self.refresh_rate.get();
+        }
+        self.refresh_point.set(Some(center));
+
+        let mut layout = self.crtc_layout.lock().unwrap();
+        monitor::get_refresh_rate_at(display, self.x.screen_id, center, &mut *layout)
+    }
+
     pub fn set_position(&self, x: i32, y: i32) {
+        self.x.display.push_error_trap();
         unsafe { (self.x.display.xlib.XMoveWindow)(self.x.display.display, self.x.window, x as libc::c_int, y as libc::c_int); }
-        self.x.display.check_errors().expect("Failed to call XMoveWindow");
+        self.x.display.pop_error_trap().expect("Failed to call XMoveWindow");
+    }
+
+    /// Switches the window to fullscreen or back to windowed mode, through the window manager.
+    ///
+    /// The window stays on its X screen, so the monitor is only checked against it. The video
+    /// mode is not changed, contrary to the fullscreen windows created with `with_fullscreen`.
+    pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
+        if let Some(ref monitor) = monitor {
+            if monitor.1 as libc::c_int != self.x.screen_id {
+                return;
+            }
+        }
+
+        // `_NET_WM_STATE_ADD` or `_NET_WM_STATE_REMOVE`, and `1` tells that the request comes
+        // from a normal application
+        let fullscreen = self.x.display.get_atom("_NET_WM_STATE_FULLSCREEN");
+        self.send_client_message(self.x.display.get_atom("_NET_WM_STATE"),
+                                 [monitor.is_some() as c_long, fullscreen as c_long, 0, 1, 0]);
+    }
+
+    /// Asks the window manager to maximize the window in both directions, or to restore it.
+    pub fn set_maximized(&self, maximized: bool) {
+        let horz = self.x.display.get_atom("_NET_WM_STATE_MAXIMIZED_HORZ");
+        let vert = self.x.display.get_atom("_NET_WM_STATE_MAXIMIZED_VERT");
+        self.send_client_message(self.x.display.get_atom("_NET_WM_STATE"),
+                                 [maximized as c_long, horz as c_long, vert as c_long, 1, 0]);
+    }
+
+    /// Iconifies the window, or maps it again which restores it according to the ICCCM.
+    pub fn set_minimized(&self, minimized: bool) {
+        if !minimized {
+            // a window that was hidden with `hide` must stay hidden
+            if self.is_iconified() {
+                self.show();
+            }
+            return;
+        }
+
+        self.x.display.push_error_trap();
+        unsafe {
+            (self.x.display.xlib.XIconifyWindow)(self.x.display.display, self.x.window,
+                                                 self.x.screen_id);
+            (self.x.display.xlib.XFlush)(self.x.display.display);
+        }
+        self.x.display.pop_error_trap().expect("Failed to call XIconifyWindow");
+    }
+
+    /// Moves and resizes the window to fill a half or a quarter of the workarea of the monitor.
+    ///
+    /// Windows can't be moved to another X screen, so nothing happens if the monitor is on
+    /// another screen than the window.
+    pub fn snap_to(&self, position: SnapPosition, monitor: &MonitorId) {
+        if monitor.1 as libc::c_int != self.x.screen_id {
+            return;
+        }
+
+        let (x, y, width, height) = match monitor.get_workarea() {
+            Some(workarea) => workarea,
+            None => return,
+        };
+        let (half_width, half_height) = (width / 2, height / 2);
+        let (x, y, width, height) = match position {
+            SnapPosition::Left => (x, y, half_width, height),
+            SnapPosition::Right => (x + half_width as i32, y, width - half_width, height),
+            SnapPosition::Top => (x, y, width, half_height),
+            SnapPosition::Bottom => (x, y + half_height as i32, width, height - half_height),
+            SnapPosition::TopLeft => (x, y, half_width, half_height),
+            SnapPosition::TopRight => (x + half_width as i32, y, width - half_width, half_height),
+            SnapPosition::BottomLeft => (x, y + half_height as i32, half_width, height - half_height),
+            SnapPosition::BottomRight => (x + half_width as i32, y + half_height as i32,
+                                          width - half_width, height - half_height),
+        };
+
+        // the decorations of the window manager are drawn around the window, and the position
+        // is the one of the frame
+        let display = &self.x.display;
+        let (left, right, top, bottom) = wm::get_frame_extents(display, self.x.window)
+                                             .unwrap_or((0, 0, 0, 0));
+
+        display.push_error_trap();
+        unsafe {
+            (display.xlib.XMoveResizeWindow)(display.display, self.x.window, x, y,
+                                             cmp::max(width.saturating_sub(left + right), 1),
+                                             cmp::max(height.saturating_sub(top + bottom), 1));
+        }
+        display.pop_error_trap().expect("Failed to call XMoveResizeWindow");
     }
 
     #[inline]
@@ -852,8 +2278,78 @@ impl Window {
 
     #[inline]
     pub fn set_inner_size(&self, x: u32, y: u32) {
+        self.x.display.push_error_trap();
         unsafe { (self.x.display.xlib.XResizeWindow)(self.x.display.display, self.x.window, x as libc::c_uint, y as libc::c_uint); }
-        self.x.display.check_errors().expect("Failed to call XResizeWindow");
+        self.x.display.pop_error_trap().expect("Failed to call XResizeWindow");
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, size: Option<(u32, u32)>) {
+        let (_, max) = self.size_constraints.get();
+        self.size_constraints.set((size, max));
+        self.update_size_constraints();
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, size: Option<(u32, u32)>) {
+        let (min, _) = self.size_constraints.get();
+        self.size_constraints.set((min, size));
+        self.update_size_constraints();
+    }
+
+    /// Sends the current size constraints to the WM through `WM_NORMAL_HINTS`, and resizes the
+    /// window if it doesn't satisfy them.
+    fn update_size_constraints(&self) {
+        let (min, max) = self.size_constraints.get();
+
+        // the other hints, such as the position given with `-geometry` or the resize
+        // increments, are kept
+        let mut size_hints: ffi::XSizeHints = unsafe { mem::zeroed() };
+        let mut supplied = 0;
+        self.x.display.push_error_trap();
+        unsafe {
+            (self.x.display.xlib.XGetWMNormalHints)(self.x.display.display, self.x.window,
+                                                    &mut size_hints, &mut supplied);
+        }
+        self.x.display.pop_error_trap().expect("Failed to call XGetWMNormalHints");
+
+        size_hints.flags &= !(ffi::PMinSize | ffi::PMaxSize);
+
+        if let Some((width, height)) = min {
+            size_hints.flags |= ffi::PMinSize;
+            size_hints.min_width = width as i32;
+            size_hints.min_height = height as i32;
+        }
+
+        if let Some((width, height)) = max {
+            size_hints.flags |= ffi::PMaxSize;
+            size_hints.max_width = width as i32;
+            size_hints.max_height = height as i32;
+        }
+
+        self.x.display.push_error_trap();
+        unsafe {
+            (self.x.display.xlib.XSetWMNormalHints)(self.x.display.display, self.x.window,
+                                                    &mut size_hints);
+        }
+        self.x.display.pop_error_trap().expect("Failed to call XSetWMNormalHints");
+
+        // the WM only applies the constraints when the window is resized by the user
+        if let Some((width, height)) = self.get_inner_size() {
+            let mut new_size = (width, height);
+            if let Some(max) = max {
+                new_size.0 = cmp::min(new_size.0, max.0);
+                new_size.1 = cmp::min(new_size.1, max.1);
+            }
+            if let Some(min) = min {
+                new_size.0 = cmp::max(new_size.0, min.0);
+                new_size.1 = cmp::max(new_size.1, min.1);
+            }
+
+            if new_size != (width, height) {
+                self.set_inner_size(new_size.0, new_size.1);
+            }
+        }
     }
 
     #[inline]
@@ -877,11 +2373,64 @@ impl Window {
         }
     }
 
+    /// Blocks until one of the windows may have events, or until the timeout expires.
+    ///
+    /// All the windows of the process share the same connection, so watching it is enough.
+    /// A request of termination also returns, and is reported by the next poll of the events.
+    pub fn wait_for_events(windows: &[&Window], timeout: Option<Duration>) {
+        let display = match windows.first() {
+            Some(window) => &window.x.display,
+            None => return
+        };
+
+        for window in windows {
+            if !window.pending_events.lock().unwrap().is_empty() ||
+               display.has_queued_events(window.x.window)
+            {
+                return;
+            }
+        }
+
+        if unsafe { (display.xlib.XPending)(display.display) } != 0 {
+            return;
+        }
+
+        let timeout = match timeout {
+            // rounded up, so that the deadline has passed once we return
+            Some(timeout) => cmp::min(timeout.as_secs() * 1000 +
+                                      (timeout.subsec_nanos() as u64 + 999_999) / 1_000_000,
+                                      libc::c_int::max_value() as u64) as libc::c_int,
+            None => -1
+        };
+        wait_for_connection(display, timeout);
+    }
+
     #[inline]
     pub fn get_xlib_display(&self) -> *mut libc::c_void {
         self.x.display.display as *mut libc::c_void
     }
 
+    /// Returns the number of the X screen of the window.
+    #[inline]
+    pub fn get_xlib_screen_id(&self) -> libc::c_int {
+        self.x.screen_id
+    }
+
+    #[inline]
+    pub fn get_xlib_fd(&self) -> libc::c_int {
+        unsafe {
+            // flushing so that nobody ends up waiting for the answer to a request that was
+            // never sent
+            (self.x.display.xlib.XFlush)(self.x.display.display);
+            (self.x.display.xlib.XConnectionNumber)(self.x.display.display)
+        }
+    }
+
+    #[inline]
+    pub fn get_atom(&self, name: &str) -> ffi::Atom {
+        self.x.display.get_atom(name)
+    }
+
     #[inline]
     pub fn platform_display(&self) -> *mut libc::c_void {
         self.x.display.display as *mut libc::c_void
@@ -902,9 +2451,11 @@ impl Window {
     }
 
     pub fn set_cursor(&self, cursor: MouseCursor) {
+        self.current_cursor.set(cursor);
+
         unsafe {
             let load = |name: &str| {
-                self.load_cursor(name)
+                self.x.display.load_cursor(name)
             };
 
             let loadn = |names: &[&str]| {
@@ -962,25 +2513,19 @@ impl Window {
                 MouseCursor::NoneCursor => self.create_empty_cursor(),
             };
 
+            self.x.display.push_error_trap();
             (self.x.display.xlib.XDefineCursor)(self.x.display.display, self.x.window, xcursor);
-            if xcursor != 0 {
+            // the other cursors are owned by the cache of the connection
+            if cursor == MouseCursor::NoneCursor && xcursor != 0 {
                 (self.x.display.xlib.XFreeCursor)(self.x.display.display, xcursor);
             }
-            self.x.display.check_errors().expect("Failed to set or free the cursor");
-        }
-    }
-
-    fn load_cursor(&self, name: &str) -> ffi::Cursor {
-        use std::ffi::CString;
-        unsafe {
-            let c_string = CString::new(name.as_bytes()).unwrap();
-            (self.x.display.xcursor.XcursorLibraryLoadCursor)(self.x.display.display, c_string.as_ptr())
+            self.x.display.pop_error_trap().expect("Failed to set or free the cursor");
         }
     }
 
     fn load_first_existing_cursor(&self, names :&[&str]) -> ffi::Cursor {
         for name in names.iter() {
-            let xcursor = self.load_cursor(name);
+            let xcursor = self.x.display.load_cursor(name);
             if xcursor != 0 {
                 return xcursor;
             }
@@ -1004,7 +2549,7 @@ impl Window {
 
             // We don't care about this color, since it only fills bytes
             // in the pixmap which are not 0 in the mask.
-            let dummy_color: ffi::XColor = mem::uninitialized();
+            let dummy_color: ffi::XColor = mem::zeroed();
             let cursor = (self.x.display.xlib.XCreatePixmapCursor)(self.x.display.display,
                                                                    pixmap,
                                                                    pixmap,
@@ -1015,48 +2560,80 @@ impl Window {
         }
     }
 
-    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
-        use CursorState::{ Grab, Normal, Hide };
+    /// Replaces the cursor of the window with an empty one, or restores the default one.
+    fn set_cursor_hidden(&self, hidden: bool) {
+        unsafe {
+            if hidden {
+                let cursor = self.create_empty_cursor();
+                self.x.display.push_error_trap();
+                (self.x.display.xlib.XDefineCursor)(self.x.display.display, self.x.window, cursor);
+                if cursor != 0 {
+                    (self.x.display.xlib.XFreeCursor)(self.x.display.display, cursor);
+                }
+                self.x.display.pop_error_trap().expect("Failed to call XDefineCursor or free the empty cursor");
+            } else {
+                // NB: Calling XDefineCursor with None (aka 0)
+                // as a value resets the cursor to the default.
+                (self.x.display.xlib.XDefineCursor)(self.x.display.display, self.x.window, 0);
+            }
+        }
+    }
 
+    /// Locks the state of the cursor, which is reset to `Normal` if a panic has released the
+    /// grabs in the meantime.
+    fn lock_cursor_state(&self) -> MutexGuard<CursorState> {
         let mut cursor_state = self.cursor_state.lock().unwrap();
-        match (state, *cursor_state) {
-            (Normal, Normal) | (Hide, Hide) | (Grab, Grab) => return Ok(()),
-            _ => {},
+        if *cursor_state != CursorState::Normal &&
+           !grabs::is_registered(&self.x.display, self.x.window)
+        {
+            self.input_handler.lock().unwrap().set_raw_motion(false);
+            self.set_cursor_hidden(false);
+            *cursor_state = CursorState::Normal;
+        }
+        cursor_state
+    }
+
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
+        use CursorState::{ Grab, GrabRelative, Normal, Hide, Soft };
+
+        let mut cursor_state = self.lock_cursor_state();
+        if state == *cursor_state {
+            return Ok(());
         }
 
         match *cursor_state {
-            Grab => {
+            Grab | GrabRelative => {
                 unsafe {
+                    self.x.display.push_error_trap();
                     (self.x.display.xlib.XUngrabPointer)(self.x.display.display, ffi::CurrentTime);
-                    self.x.display.check_errors().expect("Failed to call XUngrabPointer");
+                    self.x.display.pop_error_trap().expect("Failed to call XUngrabPointer");
                 }
-            },
-            Normal => {},
-            Hide => {
-                // NB: Calling XDefineCursor with None (aka 0)
-                // as a value resets the cursor to the default.
-                unsafe {
-                    (self.x.display.xlib.XDefineCursor)(self.x.display.display, self.x.window, 0);
+                self.input_handler.lock().unwrap().set_raw_motion(false);
+                if *cursor_state == GrabRelative {
+                    self.set_cursor_hidden(false);
                 }
             },
+            Normal => {},
+            Hide | Soft => self.set_cursor_hidden(false),
         }
 
         *cursor_state = state;
+        if state == Normal {
+            grabs::unregister(&self.x.display, self.x.window);
+        } else {
+            grabs::register(&self.x.display, self.x.window);
+        }
+
         match state {
             Normal => Ok(()),
-            Hide => {
-                unsafe {
-                    let cursor = self.create_empty_cursor();
-                    (self.x.display.xlib.XDefineCursor)(self.x.display.display, self.x.window, cursor);
-                    if cursor != 0 {
-                        (self.x.display.xlib.XFreeCursor)(self.x.display.display, cursor);
-                    }
-                    self.x.display.check_errors().expect("Failed to call XDefineCursor or free the empty cursor");
-                }
+            Hide | Soft => {
+                // in soft mode, `set_cursor_state` is normally called while the window has the
+                // focus, and the focus events take care of the rest
+                self.set_cursor_hidden(true);
                 Ok(())
             },
-            Grab => {
-                unsafe {
+            Grab | GrabRelative => {
+                let grabbed = unsafe {
                     match (self.x.display.xlib.XGrabPointer)(
                         self.x.display.display, self.x.window, ffi::True,
                         (ffi::ButtonPressMask | ffi::ButtonReleaseMask | ffi::EnterWindowMask |
@@ -1073,20 +2650,116 @@ impl Window {
                             => Err("cursor could not be grabbed".to_string()),
                         _ => unreachable!(),
                     }
+                };
+                if grabbed.is_err() {
+                    *cursor_state = Normal;
+                    grabs::unregister(&self.x.display, self.x.window);
+                    return grabbed;
                 }
+
+                self.input_handler.lock().unwrap().set_raw_motion(true);
+                if state == GrabRelative {
+                    self.set_cursor_hidden(true);
+                    self.warp_cursor_to_center();
+                }
+                Ok(())
             },
         }
     }
 
+    /// Moves the cursor back to the center of the window, in `GrabRelative` mode.
+    fn warp_cursor_to_center(&self) {
+        let (width, height) = self.current_size.get();
+        let _ = self.set_cursor_position(width / 2, height / 2);
+    }
+
+    pub fn set_variable_refresh(&self, enabled: bool) {
+        unsafe {
+            let variable_refresh = self.x.display.get_atom("_VARIABLE_REFRESH");
+            let cardinal = self.x.display.get_atom("CARDINAL");
+            let value: libc::c_ulong = if enabled { 1 } else { 0 };
+
+            self.x.display.push_error_trap();
+            (self.x.display.xlib.XChangeProperty)(self.x.display.display, self.x.window,
+                                                  variable_refresh, cardinal, 32,
+                                                  ffi::PropModeReplace,
+                                                  &value as *const _ as *const _, 1);
+            (self.x.display.xlib.XFlush)(self.x.display.display);
+            self.x.display.pop_error_trap().expect("Failed to set _VARIABLE_REFRESH");
+        }
+    }
+
+    pub fn set_cursor_size(&self, size: u32) {
+        unsafe {
+            (self.x.display.xcursor.XcursorSetDefaultSize)(self.x.display.display,
+                                                           size as libc::c_int);
+        }
+
+        // cursors are loaded with the default size, so we have to load the current one again
+        self.x.display.clear_cursor_cache();
+        self.set_cursor(self.current_cursor.get());
+    }
+
+    pub fn set_clipboard_string(&self, text: &str) {
+        self.clipboard.lock().unwrap().set(text, self.last_user_time.get());
+    }
+
+    #[inline]
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        self.clipboard.lock().unwrap().get()
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
         1.0
     }
 
+    /// Returns the position of the cursor relative to the window and to the root window, or
+    /// `None` if the cursor is on another X screen.
+    fn query_pointer(&self) -> Option<((i32, i32), (i32, i32))> {
+        let display = &self.x.display;
+        unsafe {
+            let (mut root, mut child) = (0, 0);
+            let (mut root_x, mut root_y, mut win_x, mut win_y) = (0, 0, 0, 0);
+            let mut mask = 0;
+            display.push_error_trap();
+            let same_screen = (display.xlib.XQueryPointer)(display.display, self.x.window,
+                                                           &mut root, &mut child,
+                                                           &mut root_x, &mut root_y,
+                                                           &mut win_x, &mut win_y, &mut mask);
+            display.pop_error_trap().expect("Failed to call XQueryPointer");
+
+            if same_screen == ffi::False {
+                None
+            } else {
+                Some(((win_x, win_y), (root_x, root_y)))
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        self.query_pointer().map(|(window, _)| window)
+    }
+
+    #[inline]
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        self.query_pointer().map(|(_, root)| root)
+    }
+
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        match self.x.context {
+            Context::Glx(ref ctxt) => ctxt.set_swap_interval(interval),
+            Context::Egl(ref ctxt) => ctxt.set_swap_interval(interval),
+            Context::None => Err("the window doesn't have an OpenGL context".to_string())
+        }
+    }
+
     pub fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), ()> {
         unsafe {
+            self.x.display.push_error_trap();
             (self.x.display.xlib.XWarpPointer)(self.x.display.display, 0, self.x.window, 0, 0, 0, 0, x, y);
-            self.x.display.check_errors().map_err(|_| ())
+            self.x.display.pop_error_trap().map_err(|_| ())
         }
     }
 }
@@ -1121,11 +2794,11 @@ impl GlContext for Window {
 
     #[inline]
     fn swap_buffers(&self) -> Result<(), ContextError> {
-        match self.x.context {
+        swap_with_error_trap(&self.x.display, || match self.x.context {
             Context::Glx(ref ctxt) => ctxt.swap_buffers(),
             Context::Egl(ref ctxt) => ctxt.swap_buffers(),
             Context::None => Ok(())
-        }
+        })
     }
 
     #[inline]
@@ -1146,3 +2819,21 @@ impl GlContext for Window {
         }
     }
 }
+
+/// Swaps the buffers inside an error trap, so that the X errors caused by the swap, such as a
+/// `GLXBadDrawable` once the window has been destroyed by another client, are reported by this
+/// call and not mixed up with the errors of the other requests of the connection.
+///
+/// The trap waits for the server, which costs a round trip on every frame.
+fn swap_with_error_trap<F>(display: &XConnection, swap: F) -> Result<(), ContextError>
+    where F: FnOnce() -> Result<(), ContextError>
+{
+    display.push_error_trap();
+    let result = swap();
+    let trapped = display.pop_error_trap();
+
+    try!(result);
+    trapped.map_err(|err| {
+        ContextError::IoError(io::Error::new(io::ErrorKind::Other, err.to_string()))
+    })
+}
@@ -0,0 +1,215 @@
+//! Detection of the window manager, through the EWMH properties of the root window, and of the
+//! size of its decorations. Also sets the hints of the window for the compositor.
+
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libc;
+
+use super::ffi;
+use super::XConnection;
+
+/// How long to wait for the window manager to report the size of the decorations.
+const FRAME_EXTENTS_TIMEOUT_MS: u64 = 200;
+
+/// Information about the running window manager.
+#[derive(Debug, Clone)]
+pub struct WmInfo {
+    /// The name of the window manager, as reported by `_NET_WM_NAME`.
+    pub name: Option<String>,
+    /// The hints supported by the window manager, as reported by `_NET_SUPPORTED`.
+    pub supported: Vec<ffi::Atom>,
+}
+
+impl WmInfo {
+    /// Queries the window manager of a screen. With independent X screens, each screen can have
+    /// its own window manager.
+    ///
+    /// Returns `None` if no EWMH-compliant window manager is running.
+    pub fn query(display: &XConnection, screen_id: libc::c_int) -> Option<WmInfo> {
+        let root = unsafe { (display.xlib.XRootWindow)(display.display, screen_id) };
+
+        let check_atom = display.get_atom("_NET_SUPPORTING_WM_CHECK");
+        let supported_atom = display.get_atom("_NET_SUPPORTED");
+        let name_atom = display.get_atom("_NET_WM_NAME");
+        let utf8_string = display.get_atom("UTF8_STRING");
+
+        // the check window may have been destroyed along with a WM that crashed
+        display.push_error_trap();
+
+        let info = get_property::<libc::c_ulong>(display, root, check_atom, ffi::XA_WINDOW, 32)
+            .and_then(|windows| windows.first().cloned())
+            .and_then(|check| {
+                // the WM sets the same property on the check window itself, which lets us know
+                // that the property of the root window isn't a leftover of a previous WM
+                let check_self = get_property::<libc::c_ulong>(display, check, check_atom,
+                                                               ffi::XA_WINDOW, 32);
+                if check_self.as_ref().and_then(|w| w.first()) != Some(&check) {
+                    return None;
+                }
+
+                let name = get_property::<u8>(display, check, name_atom, utf8_string, 8)
+                    .map(|name| String::from_utf8_lossy(&name).into_owned());
+
+                let supported = get_property::<libc::c_ulong>(display, root, supported_atom,
+                                                              ffi::XA_ATOM, 32)
+                    .unwrap_or(Vec::new());
+
+                Some(WmInfo {
+                    name: name,
+                    supported: supported,
+                })
+            });
+
+        match display.pop_error_trap() {
+            Ok(()) => info,
+            Err(_) => None,
+        }
+    }
+
+    /// Returns true if the window manager supports the given hint.
+    #[inline]
+    pub fn supports(&self, atom: ffi::Atom) -> bool {
+        self.supported.contains(&atom)
+    }
+}
+
+/// Reads a property of a window.
+///
+/// `T` must match the format of the property: `u8` for 8, `c_ulong` for 32. Returns `None` if
+/// the property doesn't exist or doesn't have the expected type.
+pub fn get_property<T: Copy>(display: &XConnection, window: ffi::Window, property: ffi::Atom,
+                         ty: ffi::Atom, format: libc::c_int) -> Option<Vec<T>>
+{
+    unsafe {
+        let mut actual_type = mem::zeroed();
+        let mut actual_format = mem::zeroed();
+        let mut nitems = mem::zeroed();
+        let mut bytes_after = mem::zeroed();
+        let mut data: *mut libc::c_uchar = ptr::null_mut();
+
+        // the window may belong to another client and be destroyed at any time
+        display.push_error_trap();
+        (display.xlib.XGetWindowProperty)(display.display, window, property, 0, 4096,
+                                          ffi::False, ty, &mut actual_type, &mut actual_format,
+                                          &mut nitems, &mut bytes_after, &mut data);
+        let failed = display.pop_error_trap().is_err();
+
+        if data.is_null() {
+            return None;
+        }
+        if failed {
+            (display.xlib.XFree)(data as *mut _);
+            return None;
+        }
+
+        let result = if actual_type == ty && actual_format == format {
+            Some(slice::from_raw_parts(data as *const T, nitems as usize).to_vec())
+        } else {
+            None
+        };
+
+        (display.xlib.XFree)(data as *mut _);
+        result
+    }
+}
+
+/// Reads the size of the decorations that the window manager draws around a window, as
+/// `(left, right, top, bottom)`.
+///
+/// Returns `None` if the window manager doesn't report it.
+pub fn get_frame_extents(display: &XConnection, window: ffi::Window)
+                         -> Option<(u32, u32, u32, u32)>
+{
+    let extents = get_property::<libc::c_ulong>(display, window,
+                                                display.get_atom("_NET_FRAME_EXTENTS"),
+                                                ffi::XA_CARDINAL, 32);
+    match extents {
+        Some(ref e) if e.len() == 4 => Some((e[0] as u32, e[1] as u32, e[2] as u32, e[3] as u32)),
+        _ => None,
+    }
+}
+
+/// Asks the window manager to compute the size of the decorations of a window that isn't
+/// mapped yet, and waits for the answer.
+///
+/// The window must have selected `PropertyChangeMask`. Returns `None` if the window manager
+/// doesn't support `_NET_REQUEST_FRAME_EXTENTS` or doesn't answer in time.
+pub fn request_frame_extents(display: &XConnection, screen_id: libc::c_int, window: ffi::Window)
+                             -> Option<(u32, u32, u32, u32)>
+{
+    let request_atom = display.get_atom("_NET_REQUEST_FRAME_EXTENTS");
+    if !WmInfo::query(display, screen_id).map_or(false, |wm| wm.supports(request_atom)) {
+        return None;
+    }
+
+    let client_message_event = ffi::XClientMessageEvent {
+        type_: ffi::ClientMessage,
+        serial: 0,
+        send_event: 1,
+        display: display.display,
+        window: window,
+        message_type: request_atom,
+        format: 32,
+        data: ffi::ClientMessageData::new(),
+    };
+    let mut x_event = ffi::XEvent::from(client_message_event);
+
+    unsafe {
+        let root = (display.xlib.XRootWindow)(display.display, screen_id);
+        (display.xlib.XSendEvent)(display.display, root, ffi::False,
+                                  ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask,
+                                  &mut x_event);
+        (display.xlib.XFlush)(display.display);
+    }
+
+    // the window isn't mapped, so the only property changes are the ones of the WM
+    let extents_atom = display.get_atom("_NET_FRAME_EXTENTS");
+    let deadline = Instant::now() + Duration::from_millis(FRAME_EXTENTS_TIMEOUT_MS);
+    while Instant::now() < deadline {
+        let mut xev: ffi::XEvent = unsafe { mem::zeroed() };
+        let found = unsafe {
+            (display.xlib.XCheckTypedWindowEvent)(display.display, window, ffi::PropertyNotify,
+                                                  &mut xev)
+        };
+
+        if found == 0 {
+            thread::sleep(Duration::from_millis(1));
+        } else if ffi::XPropertyEvent::from(xev).atom == extents_atom {
+            return get_frame_extents(display, window);
+        }
+    }
+
+    None
+}
+
+/// Asks the compositor to stop compositing the screen while the window is shown, or removes the
+/// request.
+///
+/// `_NET_WM_BYPASS_COMPOSITOR` is honored by KWin and Mutter, and `_KDE_NET_WM_BLOCK_COMPOSITING`
+/// by the older versions of KWin. Compositors that don't know the hints ignore them.
+///
+/// There is no equivalent hint for the minimize animation, which is played by the compositor
+/// once the window no longer covers the screen.
+pub fn set_bypass_compositor(display: &XConnection, window: ffi::Window, bypass: bool) {
+    display.push_error_trap();
+    for &name in ["_NET_WM_BYPASS_COMPOSITOR", "_KDE_NET_WM_BLOCK_COMPOSITING"].iter() {
+        let atom = display.get_atom(name);
+        unsafe {
+            if bypass {
+                // `1` means that the window must not be composited, `0` that it doesn't care
+                let value: libc::c_long = 1;
+                (display.xlib.XChangeProperty)(display.display, window, atom, ffi::XA_CARDINAL,
+                                               32, ffi::PropModeReplace,
+                                               &value as *const libc::c_long as *const u8, 1);
+            } else {
+                (display.xlib.XDeleteProperty)(display.display, window, atom);
+            }
+        }
+    }
+
+    display.pop_error_trap().expect("Failed to set the compositor hints");
+}
@@ -1,12 +1,15 @@
+use std::mem;
 use std::ptr;
 use std::fmt;
 use std::error::Error;
-use std::ffi::CString;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{CStr, CString};
 use std::sync::Mutex;
 
 use libc;
 
 use super::ffi;
+use super::present;
 use api::egl::ffi::egl::Egl;
 use api::dlopen;
 
@@ -16,27 +19,93 @@ pub struct XConnection {
     pub xf86vmode: ffi::Xf86vmode,
     pub xcursor: ffi::Xcursor,
     pub xinput2: ffi::XInput2,
+    pub xrandr: Option<ffi::Xrandr>,
     pub glx: Option<ffi::glx::Glx>,
     pub egl: Option<Egl>,
     pub display: *mut ffi::Display,
-    pub latest_error: Mutex<Option<XError>>,
+    /// Must be locked when opening or closing an input method, as `XOpenIM` doesn't seem to be
+    /// thread-safe.
+    pub im_lock: Mutex<()>,
+    /// Cursors loaded with `load_cursor`, by name.
+    cursors: Mutex<HashMap<String, ffi::Cursor>>,
+    /// Atoms interned with `get_atom`, by name.
+    atoms: Mutex<HashMap<String, ffi::Atom>>,
+    /// The major opcode of XInput, which identifies its generic events, or `-1`.
+    xinput_opcode: libc::c_int,
+    /// The events that have been read for other windows than the one being polled.
+    routing: Mutex<EventRouting>,
+}
+
+/// The windows share the connection, so each event read from it is routed to the window that it
+/// concerns.
+struct EventRouting {
+    /// The events waiting to be polled by each glutin window.
+    queues: HashMap<ffi::Window, VecDeque<ffi::XEvent>>,
+    /// The glutin windows that receive the events of another window, such as their child views
+    /// or the window they are embedded in.
+    watchers: HashMap<ffi::Window, Vec<ffi::Window>>,
+    /// The glutin windows that report the raw XInput events, which don't concern any window,
+    /// from the oldest to the most recent.
+    raw_event_windows: Vec<ffi::Window>,
 }
 
 unsafe impl Send for XConnection {}
 unsafe impl Sync for XConnection {}
 
+/// Atoms that are used by most windows, and that are interned when the connection is opened.
+const COMMON_ATOMS: &'static [&'static str] = &[
+    "WM_DELETE_WINDOW",
+    "UTF8_STRING",
+    "CARDINAL",
+    "_NET_WM_NAME",
+    "_NET_WM_ICON",
+    "_NET_WM_STATE",
+    "_NET_WM_STATE_FULLSCREEN",
+    "_NET_WM_STATE_MAXIMIZED_HORZ",
+    "_NET_WM_STATE_MAXIMIZED_VERT",
+    "_NET_ACTIVE_WINDOW",
+    "_ICC_PROFILE",
+];
+
 pub type XErrorHandler = Option<unsafe extern fn(*mut ffi::Display, *mut ffi::XErrorEvent) -> libc::c_int>;
 
+/// The error handler is global to the process, so it is only replaced by glutin's while a
+/// connection traps its errors, and the errors of the other Xlib users (GTK for example) are
+/// forwarded to the handler that was installed before.
+struct ErrorTraps {
+    /// The handlers replaced by `push_error_trap`, to be restored by `pop_error_trap`.
+    previous_handlers: Vec<XErrorHandler>,
+    /// The last error received by each trap of the connections that are trapping their errors,
+    /// by address of their display.
+    displays: HashMap<usize, Vec<Option<TrappedError>>>,
+}
+
+/// The fields of an `XErrorEvent` that are reported once the trap is popped.
+#[derive(Copy, Clone)]
+struct TrappedError {
+    error_code: u8,
+    request_code: u8,
+    minor_code: u8,
+}
+
+lazy_static! {
+    static ref ERROR_TRAPS: Mutex<ErrorTraps> = Mutex::new(ErrorTraps {
+        previous_handlers: Vec::new(),
+        displays: HashMap::new(),
+    });
+}
+
 impl XConnection {
-    pub fn new(error_handler: XErrorHandler) -> Result<XConnection, XNotSupported> {
+    pub fn new() -> Result<XConnection, XNotSupported> {
         // opening the libraries
         let xlib = try!(ffi::Xlib::open());
         let xcursor = try!(ffi::Xcursor::open());
         let xf86vmode = try!(ffi::Xf86vmode::open());
         let xinput2 = try!(ffi::XInput2::open());
+        // XRandR is only used for optional features, so we don't fail if it's missing
+        let xrandr = ffi::Xrandr::open().ok();
 
         unsafe { (xlib.XInitThreads)() };
-        unsafe { (xlib.XSetErrorHandler)(error_handler) };
 
         // TODO: use something safer than raw "dlopen"
         let glx = {
@@ -81,40 +150,451 @@ impl XConnection {
             display
         };
 
-        Ok(XConnection {
+        let xinput_opcode = unsafe {
+            let (mut opcode, mut event, mut error) = (0, 0, 0);
+            let name = CString::new("XInputExtension").unwrap();
+            if (xlib.XQueryExtension)(display, name.as_ptr(), &mut opcode, &mut event,
+                                      &mut error) == ffi::False
+            {
+                -1
+            } else {
+                opcode
+            }
+        };
+
+        let connection = XConnection {
             xlib: xlib,
             xf86vmode: xf86vmode,
             xcursor: xcursor,
             xinput2: xinput2,
+            xrandr: xrandr,
             glx: glx,
             egl: egl,
             display: display,
-            latest_error: Mutex::new(None),
-        })
+            im_lock: Mutex::new(()),
+            cursors: Mutex::new(HashMap::new()),
+            atoms: Mutex::new(HashMap::new()),
+            xinput_opcode: xinput_opcode,
+            routing: Mutex::new(EventRouting {
+                queues: HashMap::new(),
+                watchers: HashMap::new(),
+                raw_event_windows: Vec::new(),
+            }),
+        };
+
+        connection.intern_atoms(COMMON_ATOMS);
+        Ok(connection)
     }
 
-    /// Checks whether an error has been triggered by the previous function calls.
-    #[inline]
-    pub fn check_errors(&self) -> Result<(), XError> {
-        let error = self.latest_error.lock().unwrap().take();
+    /// Selects more events on a window, keeping the ones that this connection already selects.
+    ///
+    /// `XSelectInput` replaces the whole mask, which would silently break the handling of the
+    /// events that were selected elsewhere.
+    pub fn add_event_mask(&self, window: ffi::Window, mask: libc::c_long) {
+        unsafe {
+            let mut attributes = mem::zeroed();
+            if (self.xlib.XGetWindowAttributes)(self.display, window, &mut attributes) != 0 {
+                (self.xlib.XSelectInput)(self.display, window, attributes.your_event_mask | mask);
+            }
+        }
+    }
+
+    /// Returns the atom with the given name, creating it if it doesn't exist.
+    ///
+    /// Atoms are cached, so only the first call for a given name makes a round trip to the
+    /// server.
+    pub fn get_atom(&self, name: &str) -> ffi::Atom {
+        if let Some(&atom) = self.atoms.lock().unwrap().get(name) {
+            return atom;
+        }
+
+        self.push_error_trap();
+        let atom = unsafe {
+            let c_name = CString::new(name).unwrap();
+            (self.xlib.XInternAtom)(self.display, c_name.as_ptr(), ffi::False)
+        };
+        self.pop_error_trap().expect("Failed to call XInternAtom");
+
+        self.atoms.lock().unwrap().insert(name.to_owned(), atom);
+        atom
+    }
+
+    /// Interns several atoms at once, with a single round trip to the server.
+    fn intern_atoms(&self, names: &[&str]) {
+        let c_names: Vec<CString> = names.iter().map(|n| CString::new(*n).unwrap()).collect();
+        let mut c_ptrs: Vec<*mut libc::c_char> = c_names.iter()
+                                                        .map(|n| n.as_ptr() as *mut _)
+                                                        .collect();
+        let mut atoms = vec![0; names.len()];
+
+        self.push_error_trap();
+        let status = unsafe {
+            (self.xlib.XInternAtoms)(self.display, c_ptrs.as_mut_ptr(),
+                                     c_ptrs.len() as libc::c_int, ffi::False,
+                                     atoms.as_mut_ptr())
+        };
+
+        // the atoms will be interned one by one by `get_atom` if this fails
+        if self.pop_error_trap().is_err() || status == 0 {
+            return;
+        }
+
+        let mut cache = self.atoms.lock().unwrap();
+        for (name, atom) in names.iter().zip(atoms.into_iter()) {
+            cache.insert((*name).to_owned(), atom);
+        }
+    }
+
+    /// Starts routing the events of a glutin window to its own queue.
+    pub fn register_window(&self, window: ffi::Window) {
+        self.routing.lock().unwrap().queues.insert(window, VecDeque::new());
+    }
+
+    /// Stops routing the events of a window that is being destroyed, and discards the ones that
+    /// it hasn't polled.
+    pub fn unregister_window(&self, window: ffi::Window) {
+        let mut routing = self.routing.lock().unwrap();
+
+        if let Some(queue) = routing.queues.remove(&window) {
+            for mut xev in queue {
+                unsafe { self.free_event_data(&mut xev) };
+            }
+        }
+
+        routing.watchers.remove(&window);
+        for watchers in routing.watchers.values_mut() {
+            watchers.retain(|&w| w != window);
+        }
+        routing.raw_event_windows.retain(|&w| w != window);
+    }
+
+    /// Starts or stops routing the raw XInput events to a glutin window.
+    ///
+    /// Returns the number of windows that still receive them, since the selection of these
+    /// events on the root window is shared by all the windows of the connection.
+    pub fn route_raw_events(&self, window: ffi::Window, enabled: bool) -> usize {
+        let mut routing = self.routing.lock().unwrap();
+        routing.raw_event_windows.retain(|&w| w != window);
+        if enabled {
+            routing.raw_event_windows.push(window);
+        }
+        routing.raw_event_windows.len()
+    }
+
+    /// Routes the events of `window` to the glutin window `owner` as well.
+    pub fn watch_window(&self, owner: ffi::Window, window: ffi::Window) {
+        let mut routing = self.routing.lock().unwrap();
+        let watchers = routing.watchers.entry(window).or_insert_with(Vec::new);
+        if !watchers.contains(&owner) {
+            watchers.push(owner);
+        }
+    }
+
+    /// Undoes `watch_window`.
+    pub fn unwatch_window(&self, owner: ffi::Window, window: ffi::Window) {
+        let mut routing = self.routing.lock().unwrap();
+        let empty = match routing.watchers.get_mut(&window) {
+            Some(watchers) => {
+                watchers.retain(|&w| w != owner);
+                watchers.is_empty()
+            },
+            None => false,
+        };
+        if empty {
+            routing.watchers.remove(&window);
+        }
+    }
+
+    /// Returns true if events have been routed to the window but not polled yet.
+    pub fn has_queued_events(&self, window: ffi::Window) -> bool {
+        self.routing.lock().unwrap().queues.get(&window).map_or(false, |q| !q.is_empty())
+    }
+
+    /// Returns the next event of a glutin window without blocking.
+    ///
+    /// The events read from the connection for the other glutin windows are put in their queue.
+    /// The events of the windows that nobody watches, such as the root window, are received by
+    /// all the glutin windows. The data of the generic events is already retrieved, and must be
+    /// freed with `XFreeEventData`.
+    pub fn next_event(&self, window: ffi::Window) -> Option<ffi::XEvent> {
+        let mut routing = self.routing.lock().unwrap();
+
+        loop {
+            if let Some(xev) = routing.queues.get_mut(&window).and_then(|q| q.pop_front()) {
+                return Some(xev);
+            }
+
+            let mut xev: ffi::XEvent = unsafe { mem::zeroed() };
+            unsafe {
+                if (self.xlib.XPending)(self.display) == 0 {
+                    return None;
+                }
+                (self.xlib.XNextEvent)(self.display, &mut xev);
+            }
+
+            // the data of a generic event can only be retrieved until the next event is read
+            let generic = xev.get_type() == ffi::GenericEvent;
+            if generic {
+                let mut cookie = ffi::XGenericEventCookie::from(xev);
+                if unsafe { (self.xlib.XGetEventData)(self.display, &mut cookie) } == ffi::True {
+                    xev = ffi::XEvent::from(cookie);
+                }
+            }
+
+            let mut targets = Vec::new();
+            if let Some(event_window) = self.event_window(&xev) {
+                if routing.queues.contains_key(&event_window) {
+                    targets.push(event_window);
+                }
+                if let Some(watchers) = routing.watchers.get(&event_window) {
+                    for &watcher in watchers.iter() {
+                        if !targets.contains(&watcher) {
+                            targets.push(watcher);
+                        }
+                    }
+                }
+            }
 
-        if let Some(error) = error {
-            Err(error)
+            if targets.is_empty() {
+                if generic && self.is_raw_event(&xev) {
+                    // only the window that grabs the pointer enables them, and the data of the
+                    // event can't be shared, so the most recent one receives them
+                    targets.push(*routing.raw_event_windows.last().unwrap_or(&window));
+                } else if generic {
+                    targets.push(window);
+                } else {
+                    targets.extend(routing.queues.keys().cloned());
+                }
+            } else if generic {
+                // the data can't be shared
+                targets.truncate(1);
+            }
+
+            let mine = targets.contains(&window);
+            for target in targets.into_iter().filter(|&w| w != window) {
+                if let Some(queue) = routing.queues.get_mut(&target) {
+                    queue.push_back(xev);
+                }
+            }
+
+            if mine {
+                return Some(xev);
+            }
+        }
+    }
+
+    /// Returns true if the event is a raw XInput event, which is reported by the root window.
+    fn is_raw_event(&self, xev: &ffi::XEvent) -> bool {
+        let cookie = ffi::XGenericEventCookie::from(*xev);
+        if cookie.data.is_null() || cookie.extension != self.xinput_opcode {
+            return false;
+        }
+
+        match cookie.evtype {
+            ffi::XI_RawKeyPress...ffi::XI_RawMotion |
+            ffi::XI_RawTouchBegin...ffi::XI_RawTouchEnd => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the window that an event concerns, if it is known.
+    fn event_window(&self, xev: &ffi::XEvent) -> Option<ffi::Window> {
+        if xev.get_type() != ffi::GenericEvent {
+            return Some(ffi::XAnyEvent::from(*xev).window);
+        }
+
+        let cookie = ffi::XGenericEventCookie::from(*xev);
+        if cookie.data.is_null() {
+            return None;
+        }
+
+        if cookie.extension == self.xinput_opcode {
+            match cookie.evtype {
+                ffi::XI_KeyPress...ffi::XI_Motion |
+                ffi::XI_TouchBegin...ffi::XI_TouchEnd => unsafe {
+                    Some((*(cookie.data as *const ffi::XIDeviceEvent)).event)
+                },
+                ffi::XI_Enter...ffi::XI_FocusOut => unsafe {
+                    Some((*(cookie.data as *const ffi::XIEnterEvent)).event)
+                },
+                // the raw events and the device changes don't concern a window
+                _ => None,
+            }
         } else {
-            Ok(())
+            present::event_window(&cookie)
         }
     }
 
-    /// Ignores any previous error.
-    #[inline]
-    pub fn ignore_error(&self) {
-        *self.latest_error.lock().unwrap() = None;
+    /// Frees the data of a generic event returned by `next_event`.
+    pub unsafe fn free_event_data(&self, xev: &mut ffi::XEvent) {
+        if xev.get_type() == ffi::GenericEvent {
+            let mut cookie = ffi::XGenericEventCookie::from(*xev);
+            if !cookie.data.is_null() {
+                (self.xlib.XFreeEventData)(self.display, &mut cookie);
+            }
+        }
+    }
+
+    /// Returns true if a window manager is running on the screen.
+    ///
+    /// Window managers always select `SubstructureRedirectMask` on the root window, which the
+    /// server reports in the union of the masks that all the clients select. This works for the
+    /// window managers that don't implement the EWMH as well, and doesn't take the redirection
+    /// from a window manager that would start at the same time.
+    pub fn is_wm_present(&self, screen_id: libc::c_int) -> bool {
+        unsafe {
+            let root = (self.xlib.XRootWindow)(self.display, screen_id);
+            let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+            if (self.xlib.XGetWindowAttributes)(self.display, root, &mut attributes) == 0 {
+                return false;
+            }
+            attributes.all_event_masks & ffi::SubstructureRedirectMask != 0
+        }
+    }
+
+    /// Loads a cursor from the cursor theme, or returns it from the cache if it was already
+    /// loaded.
+    ///
+    /// Returns 0 if the theme doesn't have any cursor with this name. The cursor must not be
+    /// freed.
+    pub fn load_cursor(&self, name: &str) -> ffi::Cursor {
+        let mut cursors = self.cursors.lock().unwrap();
+
+        if let Some(&cursor) = cursors.get(name) {
+            return cursor;
+        }
+
+        let cursor = unsafe {
+            let c_string = CString::new(name.as_bytes()).unwrap();
+            (self.xcursor.XcursorLibraryLoadCursor)(self.display, c_string.as_ptr())
+        };
+
+        if cursor != 0 {
+            cursors.insert(name.to_owned(), cursor);
+        }
+
+        cursor
+    }
+
+    /// Frees all the cursors loaded with `load_cursor`, so that they are loaded again with the
+    /// current default size.
+    ///
+    /// Windows that use these cursors keep them until they switch to another cursor.
+    pub fn clear_cursor_cache(&self) {
+        for (_, cursor) in self.cursors.lock().unwrap().drain() {
+            unsafe { (self.xlib.XFreeCursor)(self.display, cursor) };
+        }
+    }
+
+    /// Starts trapping the errors of the requests sent to this connection.
+    ///
+    /// Glutin's error handler is only installed for the duration of the trap, so the errors of
+    /// the requests sent before still go to the handler of the host application. Traps can be
+    /// nested and must be popped in the reverse order.
+    pub fn push_error_trap(&self) {
+        unsafe {
+            // the errors of the earlier requests don't belong to this trap
+            (self.xlib.XSync)(self.display, ffi::False);
+
+            let mut traps = ERROR_TRAPS.lock().unwrap();
+            traps.displays.entry(self.display as usize).or_insert_with(Vec::new).push(None);
+            let previous = (self.xlib.XSetErrorHandler)(Some(x_error_callback));
+            traps.previous_handlers.push(previous);
+        }
     }
+
+    /// Stops trapping errors and restores the handler that was installed when the trap was
+    /// pushed.
+    ///
+    /// Returns the last error triggered by the requests sent since `push_error_trap`.
+    pub fn pop_error_trap(&self) -> Result<(), XError> {
+        let error = unsafe {
+            // waiting for the server to process the requests, so that their errors are received
+            // by our handler
+            (self.xlib.XSync)(self.display, ffi::False);
+
+            let mut traps = ERROR_TRAPS.lock().unwrap();
+            let previous = traps.previous_handlers.pop()
+                                .expect("pop_error_trap called without push_error_trap");
+            (self.xlib.XSetErrorHandler)(previous);
+
+            let key = self.display as usize;
+            let (error, last) = {
+                let errors = traps.displays.get_mut(&key)
+                                  .expect("pop_error_trap called without push_error_trap");
+                (errors.pop().and_then(|e| e), errors.is_empty())
+            };
+            if last {
+                traps.displays.remove(&key);
+            }
+            error
+        };
+
+        match error {
+            Some(error) => Err(self.describe_error(error)),
+            None => Ok(()),
+        }
+    }
+
+    /// Builds the error reported by `pop_error_trap`.
+    fn describe_error(&self, error: TrappedError) -> XError {
+        let mut buff: Vec<u8> = vec![0; 1024];
+        let description = unsafe {
+            (self.xlib.XGetErrorText)(self.display, error.error_code as libc::c_int,
+                                      buff.as_mut_ptr() as *mut libc::c_char,
+                                      buff.len() as libc::c_int);
+            CStr::from_ptr(buff.as_ptr() as *const libc::c_char).to_string_lossy().into_owned()
+        };
+
+        XError {
+            description: description,
+            error_code: error.error_code,
+            request_code: error.request_code,
+            minor_code: error.minor_code,
+        }
+    }
+}
+
+/// The error handler installed by `push_error_trap`.
+///
+/// Records the error if its display is trapping errors, and forwards it to the handler that
+/// was installed before glutin's otherwise.
+unsafe extern "C" fn x_error_callback(display: *mut ffi::Display, event: *mut ffi::XErrorEvent)
+                                      -> libc::c_int
+{
+    ::api::panic::abort_on_panic("x_error_callback", || {
+        let handler = {
+            let mut traps = ERROR_TRAPS.lock().unwrap();
+
+            let trap = traps.displays.get_mut(&(display as usize)).and_then(|e| e.last_mut());
+            if let Some(trap) = trap {
+                *trap = Some(TrappedError {
+                    error_code: (*event).error_code,
+                    request_code: (*event).request_code,
+                    minor_code: (*event).minor_code,
+                });
+                return 0;
+            }
+
+            // nested traps replace glutin's handler by itself
+            let own = x_error_callback as usize;
+            traps.previous_handlers.iter()
+                 .cloned()
+                 .find(|h| h.map(|h| h as usize) != Some(own))
+                 .and_then(|h| h)
+        };
+
+        match handler {
+            Some(handler) => handler(display, event),
+            None => 0,
+        }
+    })
 }
 
 impl Drop for XConnection {
     #[inline]
     fn drop(&mut self) {
+        self.clear_cursor_cache();
         unsafe { (self.xlib.XCloseDisplay)(self.display) };
     }
 }
@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
+use std::sync::Arc;
+
+use Event;
+use Window;
+use platform;
+
+/// Owns the windows of an application and dispatches their events.
+///
+/// Windows are destroyed when they receive a `Destroyed` event, or when `remove_window` is
+/// called, for example in response to a `Closed` event once the user has confirmed. Once there
+/// isn't any window left, an `AllWindowsClosed` event is dispatched and, if
+/// `set_quit_on_last_window_closed` was called with `true`, `run_forever` returns.
+///
+/// The application also owns the connection to the display server: on X11, it is closed when
+/// the application is dropped, once no window uses it anymore.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut app = Application::new();
+/// app.add_window(Window::new().unwrap());
+/// app.add_window(Window::new().unwrap());
+///
+/// app.run_forever(|app, window, event| {
+///     match event {
+///         Event::Refresh => window.swap_buffers().unwrap(),
+///         Event::Closed => app.remove_window(window),
+///         _ => ()
+///     }
+/// });
+/// ```
+pub struct Application {
+    windows: Vec<Window>,
+    /// The windows to destroy once the events have been dispatched, by address
+    removed: RefCell<Vec<*const Window>>,
+    quit_on_last_window_closed: bool,
+    /// Keeps the connection to the display server alive until the application is dropped
+    #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
+    _context: Arc<platform::PlatformContext>,
+}
+
+impl Application {
+    /// Builds an application without any window.
+    #[inline]
+    pub fn new() -> Application {
+        Application {
+            windows: Vec::new(),
+            removed: RefCell::new(Vec::new()),
+            quit_on_last_window_closed: true,
+            #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
+            _context: platform::PlatformContext::get(),
+        }
+    }
+
+    /// Sets whether `run_forever` should return once the last window has been closed.
+    ///
+    /// The default is `true`.
+    #[inline]
+    pub fn set_quit_on_last_window_closed(&mut self, quit: bool) {
+        self.quit_on_last_window_closed = quit;
+    }
+
+    /// Gives the ownership of a window to the application.
+    #[inline]
+    pub fn add_window(&mut self, window: Window) {
+        // the addresses of the removed windows may change
+        let removed = self.removed.borrow_mut().drain(..).collect::<Vec<_>>();
+        self.windows.retain(|w| !removed.contains(&(w as *const Window)));
+        self.windows.push(window);
+    }
+
+    /// Destroys a window of the application once the events that are being dispatched have
+    /// been processed, or at the beginning of the next call to `poll_events` otherwise.
+    ///
+    /// Does nothing if the window doesn't belong to the application.
+    #[inline]
+    pub fn remove_window(&self, window: &Window) {
+        self.removed.borrow_mut().push(window as *const Window);
+    }
+
+    /// Returns the windows that are still open.
+    #[inline]
+    pub fn windows(&self) -> &[Window] {
+        &self.windows
+    }
+
+    /// Dispatches all the pending events of all the windows, then returns.
+    ///
+    /// The callback receives the application and the window that produced the event.
+    /// `AllWindowsClosed` is dispatched with the last window, just before it is destroyed.
+    ///
+    /// Returns `false` if the application should quit.
+    pub fn poll_events<F>(&mut self, mut callback: F) -> bool
+        where F: FnMut(&Application, &Window, Event)
+    {
+        self.destroy_removed_windows(&mut callback);
+
+        {
+            let app: &Application = self;
+            for window in app.windows.iter() {
+                for event in window.poll_events() {
+                    if let Event::Destroyed = event {
+                        app.remove_window(window);
+                    }
+                    callback(app, window, event);
+                }
+            }
+        }
+
+        self.destroy_removed_windows(&mut callback);
+        !(self.windows.is_empty() && self.quit_on_last_window_closed)
+    }
+
+    /// Dispatches the events of all the windows until the application quits.
+    ///
+    /// Also returns once there isn't any window left, since no event can be received anymore,
+    /// even if `set_quit_on_last_window_closed` was set to `false`.
+    pub fn run_forever<F>(&mut self, mut callback: F)
+        where F: FnMut(&Application, &Window, Event)
+    {
+        while self.poll_events(&mut callback) && !self.windows.is_empty() {
+            self.wait_for_events();
+        }
+    }
+
+    /// Destroys the windows passed to `remove_window`, dispatching `AllWindowsClosed` if they
+    /// were the last ones.
+    fn destroy_removed_windows<F>(&mut self, callback: &mut F)
+        where F: FnMut(&Application, &Window, Event)
+    {
+        let removed = self.removed.borrow_mut().drain(..).collect::<Vec<_>>();
+        if removed.is_empty() {
+            return;
+        }
+
+        let remaining = self.windows.iter()
+                                    .filter(|&w| !removed.contains(&(w as *const Window)))
+                                    .count();
+        if remaining == 0 && !self.windows.is_empty() {
+            let app: &Application = self;
+            callback(app, &app.windows[app.windows.len() - 1], Event::AllWindowsClosed);
+        }
+
+        self.windows.retain(|w| !removed.contains(&(w as *const Window)));
+    }
+
+    /// Blocks until one of the windows may have events, or until the cursor has been resting
+    /// long enough over one of them to be reported as hovering.
+    fn wait_for_events(&self) {
+        let deadline = self.windows.iter()
+                                   .filter_map(|w| w.hover.lock().unwrap().deadline())
+                                   .min();
+        let timeout = deadline.map(|deadline| {
+            let now = Instant::now();
+            if deadline > now { deadline - now } else { Duration::new(0, 0) }
+        });
+
+        let windows: Vec<_> = self.windows.iter().map(|w| &w.window).collect();
+        platform::Window::wait_for_events(&windows, timeout);
+    }
+}
+
+impl Default for Application {
+    #[inline]
+    fn default() -> Application {
+        Application::new()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
+impl Drop for Application {
+    fn drop(&mut self) {
+        // the windows use the connection, which is closed with the last reference to it
+        self.windows.clear();
+        platform::PlatformContext::release();
+    }
+}
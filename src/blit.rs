@@ -0,0 +1,159 @@
+//! Copying pixels between the default framebuffers of two contexts.
+
+use std::io;
+use std::mem;
+use std::os::raw::{c_int, c_uint, c_void};
+
+use ContextError;
+use GlContext;
+
+const TEXTURE_2D: c_uint = 0x0DE1;
+const TEXTURE_BINDING_2D: c_uint = 0x8069;
+const RGBA: c_uint = 0x1908;
+const UNSIGNED_BYTE: c_uint = 0x1401;
+const READ_FRAMEBUFFER: c_uint = 0x8CA8;
+const DRAW_FRAMEBUFFER: c_uint = 0x8CA9;
+const READ_FRAMEBUFFER_BINDING: c_uint = 0x8CAA;
+const DRAW_FRAMEBUFFER_BINDING: c_uint = 0x8CA6;
+const COLOR_ATTACHMENT0: c_uint = 0x8CE0;
+const COLOR_BUFFER_BIT: c_uint = 0x4000;
+const NEAREST: c_uint = 0x2600;
+const LINEAR: c_uint = 0x2601;
+
+/// A rectangle in a framebuffer, in pixels. The origin is the bottom-left corner, like in OpenGL.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlitRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Copies a rectangle of the default framebuffer of `src` into a rectangle of the default
+/// framebuffer of `dst`, scaling the pixels if the two rectangles don't have the same size.
+///
+/// This is useful to display a thumbnail of a `HeadlessContext` inside of a `Window` for
+/// example. The two contexts don't need to be shared or to belong to the same backend: the
+/// pixels are read back from `src` and uploaded to `dst`.
+///
+/// Both contexts must support framebuffer objects, and `dst` must support `glBlitFramebuffer`
+/// (OpenGL 3.0, OpenGL ES 3.0 or `GL_ARB_framebuffer_object`).
+///
+/// When this function returns, `dst` is the current context, except if `src` was current
+/// before the call, in which case `src` is made current again. The read framebuffer binding
+/// of `src`, and the framebuffer and texture bindings of `dst` are preserved.
+pub unsafe fn blit<S, D>(src: &S, src_rect: BlitRect, dst: &D, dst_rect: BlitRect)
+                         -> Result<(), ContextError>
+    where S: GlContext, D: GlContext
+{
+    if src_rect.width == 0 || src_rect.height == 0 || dst_rect.width == 0 ||
+       dst_rect.height == 0
+    {
+        return Ok(());
+    }
+
+    let src_was_current = src.is_current();
+
+    // reading the pixels of the source
+    let mut pixels = vec![0u8; src_rect.width as usize * src_rect.height as usize * 4];
+    {
+        try!(src.make_current());
+
+        let get_integerv: extern "system" fn(c_uint, *mut c_int)
+                            = try!(load(src, "glGetIntegerv"));
+        let bind_framebuffer: extern "system" fn(c_uint, c_uint)
+                                = try!(load(src, "glBindFramebuffer"));
+        let read_pixels: extern "system" fn(c_int, c_int, c_int, c_int, c_uint, c_uint,
+                                            *mut c_void)
+                            = try!(load(src, "glReadPixels"));
+
+        // the application may have bound one of its own framebuffers for reading
+        let mut old_read_framebuffer = 0;
+        get_integerv(READ_FRAMEBUFFER_BINDING, &mut old_read_framebuffer);
+        bind_framebuffer(READ_FRAMEBUFFER, 0);
+        read_pixels(src_rect.x, src_rect.y, src_rect.width as c_int, src_rect.height as c_int,
+                    RGBA, UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut c_void);
+        bind_framebuffer(READ_FRAMEBUFFER, old_read_framebuffer as c_uint);
+    }
+
+    // uploading them to a texture of the destination, then blitting this texture
+    try!(dst.make_current());
+
+    let get_integerv: extern "system" fn(c_uint, *mut c_int)
+                        = try!(load(dst, "glGetIntegerv"));
+    let gen_textures: extern "system" fn(c_int, *mut c_uint)
+                        = try!(load(dst, "glGenTextures"));
+    let delete_textures: extern "system" fn(c_int, *const c_uint)
+                           = try!(load(dst, "glDeleteTextures"));
+    let bind_texture: extern "system" fn(c_uint, c_uint)
+                        = try!(load(dst, "glBindTexture"));
+    let tex_image_2d: extern "system" fn(c_uint, c_int, c_int, c_int, c_int, c_int, c_uint,
+                                         c_uint, *const c_void)
+                        = try!(load(dst, "glTexImage2D"));
+    let gen_framebuffers: extern "system" fn(c_int, *mut c_uint)
+                            = try!(load(dst, "glGenFramebuffers"));
+    let delete_framebuffers: extern "system" fn(c_int, *const c_uint)
+                               = try!(load(dst, "glDeleteFramebuffers"));
+    let bind_framebuffer: extern "system" fn(c_uint, c_uint)
+                            = try!(load(dst, "glBindFramebuffer"));
+    let framebuffer_texture_2d: extern "system" fn(c_uint, c_uint, c_uint, c_uint, c_int)
+                                  = try!(load(dst, "glFramebufferTexture2D"));
+    let blit_framebuffer: extern "system" fn(c_int, c_int, c_int, c_int, c_int, c_int, c_int,
+                                             c_int, c_uint, c_uint)
+                            = try!(load(dst, "glBlitFramebuffer"));
+
+    let mut old_texture = 0;
+    let mut old_read_framebuffer = 0;
+    let mut old_draw_framebuffer = 0;
+    get_integerv(TEXTURE_BINDING_2D, &mut old_texture);
+    get_integerv(READ_FRAMEBUFFER_BINDING, &mut old_read_framebuffer);
+    get_integerv(DRAW_FRAMEBUFFER_BINDING, &mut old_draw_framebuffer);
+
+    let mut texture = 0;
+    gen_textures(1, &mut texture);
+    bind_texture(TEXTURE_2D, texture);
+    tex_image_2d(TEXTURE_2D, 0, RGBA as c_int, src_rect.width as c_int,
+                 src_rect.height as c_int, 0, RGBA, UNSIGNED_BYTE,
+                 pixels.as_ptr() as *const c_void);
+
+    let mut framebuffer = 0;
+    gen_framebuffers(1, &mut framebuffer);
+    bind_framebuffer(READ_FRAMEBUFFER, framebuffer);
+    framebuffer_texture_2d(READ_FRAMEBUFFER, COLOR_ATTACHMENT0, TEXTURE_2D, texture, 0);
+    bind_framebuffer(DRAW_FRAMEBUFFER, 0);
+
+    let filter = if src_rect.width == dst_rect.width && src_rect.height == dst_rect.height {
+        NEAREST
+    } else {
+        LINEAR
+    };
+
+    blit_framebuffer(0, 0, src_rect.width as c_int, src_rect.height as c_int,
+                     dst_rect.x, dst_rect.y, dst_rect.x + dst_rect.width as c_int,
+                     dst_rect.y + dst_rect.height as c_int, COLOR_BUFFER_BIT, filter);
+
+    bind_framebuffer(READ_FRAMEBUFFER, old_read_framebuffer as c_uint);
+    bind_framebuffer(DRAW_FRAMEBUFFER, old_draw_framebuffer as c_uint);
+    bind_texture(TEXTURE_2D, old_texture as c_uint);
+    delete_framebuffers(1, &framebuffer);
+    delete_textures(1, &texture);
+
+    if src_was_current {
+        try!(src.make_current());
+    }
+
+    Ok(())
+}
+
+/// Loads an OpenGL function from the current context.
+unsafe fn load<C, F>(context: &C, name: &str) -> Result<F, ContextError> where C: GlContext {
+    debug_assert_eq!(mem::size_of::<F>(), mem::size_of::<*const ()>());
+
+    let addr = context.get_proc_address(name);
+    if addr.is_null() {
+        return Err(ContextError::IoError(io::Error::new(io::ErrorKind::Other,
+                                                        format!("{} is not available", name))));
+    }
+
+    Ok(mem::transmute_copy(&addr))
+}
@@ -0,0 +1,200 @@
+//! Children processes whose lifetime is tied to the one of the program.
+//!
+//! Launchers usually spawn a game and wait for it. If the launcher is killed, the child would
+//! keep running without anything to show its output or to stop it. On Linux, the child asks the
+//! kernel to be killed when its parent dies. On Windows, the children are assigned to a job
+//! object that kills them once the last handle to the job, owned by the program, is closed.
+//! They are created suspended and only resumed once assigned, so that the processes that they
+//! start themselves are in the job too.
+
+use std::io;
+use std::process::{Child, Command};
+
+/// Spawns the command, and kills the child if the program dies.
+///
+/// On Windows, the creation flags of the command are replaced.
+pub fn spawn_attached(command: &mut Command) -> io::Result<Child> {
+    imp::spawn_attached(command)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod imp {
+    use std::io;
+    use std::os::unix::process::CommandExt;
+    use std::process::{Child, Command};
+
+    use libc;
+
+    pub fn spawn_attached(command: &mut Command) -> io::Result<Child> {
+        let parent = unsafe { libc::getpid() };
+
+        // runs in the child between `fork` and `exec`, so only async-signal-safe functions can
+        // be called
+        command.before_exec(move || unsafe {
+            if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            // the parent may have died before the signal was requested
+            if libc::getppid() != parent {
+                return Err(io::Error::new(io::ErrorKind::Other, "the parent process has exited"));
+            }
+
+            Ok(())
+        });
+
+        command.spawn()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use std::io;
+    use std::mem;
+    use std::ptr;
+    use std::os::windows::io::AsRawHandle;
+    use std::os::windows::process::CommandExt;
+    use std::process::{Child, Command};
+
+    use kernel32;
+    use winapi;
+
+    /// Creates the process with its main thread suspended.
+    const CREATE_SUSPENDED: winapi::DWORD = 0x00000004;
+    /// Creates the process outside of the job of the program.
+    const CREATE_BREAKAWAY_FROM_JOB: winapi::DWORD = 0x01000000;
+    /// Allows `CREATE_BREAKAWAY_FROM_JOB` for the processes of a job.
+    const JOB_OBJECT_LIMIT_BREAKAWAY_OK: winapi::DWORD = 0x00000800;
+
+    /// The job that contains every attached child, or `None` if it couldn't be created.
+    struct Job(Option<winapi::HANDLE>);
+
+    // the handle is never closed, the system closes it when the program exits
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    lazy_static! {
+        static ref JOB: Job = Job(unsafe { create_job() });
+    }
+
+    unsafe fn create_job() -> Option<winapi::HANDLE> {
+        let job = kernel32::CreateJobObjectW(ptr::null_mut(), ptr::null());
+        if job.is_null() {
+            return None;
+        }
+
+        let mut info: winapi::JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+        info.BasicLimitInformation.LimitFlags = winapi::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        if kernel32::SetInformationJobObject(job, winapi::JobObjectExtendedLimitInformation,
+                                             &mut info as *mut _ as winapi::LPVOID,
+                                             mem::size_of_val(&info) as winapi::DWORD) == 0
+        {
+            kernel32::CloseHandle(job);
+            return None;
+        }
+
+        Some(job)
+    }
+
+    pub fn spawn_attached(command: &mut Command) -> io::Result<Child> {
+        let job = match JOB.0 {
+            Some(job) => job,
+            None => return Err(io::Error::new(io::ErrorKind::Other,
+                                              "couldn't create the job object")),
+        };
+
+        // a program that already runs in a job, for example under a launcher, can only assign
+        // its children to another job if the system supports nested jobs or if they leave the
+        // job of the program
+        let mut flags = CREATE_SUSPENDED;
+        if unsafe { can_break_away() } {
+            flags |= CREATE_BREAKAWAY_FROM_JOB;
+        }
+        command.creation_flags(flags);
+
+        let mut child = try!(command.spawn());
+
+        let handle = child.as_raw_handle() as winapi::HANDLE;
+        if unsafe { kernel32::AssignProcessToJobObject(job, handle) } == 0 {
+            let err = io::Error::last_os_error();
+            let _ = child.kill();
+            return Err(err);
+        }
+
+        if let Err(err) = unsafe { resume_threads(child.id()) } {
+            let _ = child.kill();
+            return Err(err);
+        }
+
+        Ok(child)
+    }
+
+    /// Returns whether the program runs in a job whose processes may create children outside
+    /// of it.
+    unsafe fn can_break_away() -> bool {
+        let mut info: winapi::JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+        // a null handle designates the job of the program, and the query fails if there is none
+        if kernel32::QueryInformationJobObject(ptr::null_mut(),
+                                               winapi::JobObjectExtendedLimitInformation,
+                                               &mut info as *mut _ as winapi::LPVOID,
+                                               mem::size_of_val(&info) as winapi::DWORD,
+                                               ptr::null_mut()) == 0
+        {
+            return false;
+        }
+
+        info.BasicLimitInformation.LimitFlags & JOB_OBJECT_LIMIT_BREAKAWAY_OK != 0
+    }
+
+    /// Resumes the main thread of a process that was created suspended.
+    ///
+    /// `Child` doesn't expose the handle of the thread, so it is found in a snapshot of the
+    /// threads of the system.
+    unsafe fn resume_threads(process_id: u32) -> io::Result<()> {
+        let snapshot = kernel32::CreateToolhelp32Snapshot(winapi::TH32CS_SNAPTHREAD, 0);
+        if snapshot == winapi::INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut entry: winapi::THREADENTRY32 = mem::zeroed();
+        entry.dwSize = mem::size_of::<winapi::THREADENTRY32>() as winapi::DWORD;
+
+        let mut result = Err(io::Error::new(io::ErrorKind::Other, "the child has no thread"));
+        let mut found = kernel32::Thread32First(snapshot, &mut entry) != 0;
+        while found {
+            if entry.th32OwnerProcessID == process_id {
+                let thread = kernel32::OpenThread(winapi::THREAD_SUSPEND_RESUME, winapi::FALSE,
+                                                  entry.th32ThreadID);
+                if thread.is_null() {
+                    result = Err(io::Error::last_os_error());
+                    break;
+                }
+
+                result = if kernel32::ResumeThread(thread) == winapi::DWORD::max_value() {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(())
+                };
+                kernel32::CloseHandle(thread);
+
+                // a process that is created suspended only has its main thread
+                break;
+            }
+            found = kernel32::Thread32Next(snapshot, &mut entry) != 0;
+        }
+
+        kernel32::CloseHandle(snapshot);
+        result
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "windows")))]
+mod imp {
+    use std::io;
+    use std::process::{Child, Command};
+
+    #[inline]
+    pub fn spawn_attached(command: &mut Command) -> io::Result<Child> {
+        command.spawn()
+    }
+}
@@ -0,0 +1,263 @@
+//! Parsing of the EDID blob that monitors send to the graphics card.
+
+/// Information extracted from the EDID of a monitor.
+///
+/// This can be used to identify a monitor persistently (the manufacturer, product code and
+/// serial number don't change between reboots) and to get an approximation of its color
+/// characteristics when no ICC profile is available.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edid {
+    /// Three-letter PNP identifier of the manufacturer, for example `"DEL"` or `"SAM"`.
+    pub manufacturer: String,
+
+    /// Product code assigned by the manufacturer.
+    pub product_code: u16,
+
+    /// Numeric serial number. Many monitors leave this to `0` and use `serial_string` instead.
+    pub serial_number: u32,
+
+    /// Name of the monitor, if the EDID contains a display name descriptor.
+    pub name: Option<String>,
+
+    /// Serial number of the monitor, if the EDID contains a serial number descriptor.
+    pub serial_string: Option<String>,
+
+    /// CIE 1931 `(x, y)` coordinates of the red primary.
+    pub red_primary: (f32, f32),
+
+    /// CIE 1931 `(x, y)` coordinates of the green primary.
+    pub green_primary: (f32, f32),
+
+    /// CIE 1931 `(x, y)` coordinates of the blue primary.
+    pub blue_primary: (f32, f32),
+
+    /// CIE 1931 `(x, y)` coordinates of the white point.
+    pub white_point: (f32, f32),
+
+    /// True if the monitor supports the SMPTE ST 2084 (PQ) transfer function used by HDR10.
+    pub hdr_pq: bool,
+
+    /// True if the monitor supports the Hybrid Log-Gamma transfer function.
+    pub hdr_hlg: bool,
+}
+
+impl Edid {
+    /// Parses a raw EDID blob, including its extension blocks.
+    ///
+    /// Returns `None` if the data is not a valid EDID. Extension blocks that are truncated or
+    /// whose checksum is wrong are ignored.
+    pub fn parse(data: &[u8]) -> Option<Edid> {
+        const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+        if data.len() < 128 || data[0 .. 8] != HEADER || !has_valid_checksum(&data[.. 128]) {
+            return None;
+        }
+
+        let manufacturer = {
+            let raw = (data[8] as u16) << 8 | data[9] as u16;
+            [(raw >> 10) & 0x1f, (raw >> 5) & 0x1f, raw & 0x1f].iter()
+                .map(|&c| (b'A' - 1 + c as u8) as char)
+                .collect()
+        };
+
+        let product_code = data[10] as u16 | (data[11] as u16) << 8;
+        let serial_number = data[12] as u32 | (data[13] as u32) << 8 |
+                            (data[14] as u32) << 16 | (data[15] as u32) << 24;
+
+        // each coordinate is a 10 bits value, whose two lowest bits are packed in bytes 25 and 26
+        let coord = |high: usize, low_byte: usize, low_shift: u8| {
+            let value = (data[high] as u16) << 2 | ((data[low_byte] >> low_shift) & 0x3) as u16;
+            value as f32 / 1024.0
+        };
+
+        let mut name = None;
+        let mut serial_string = None;
+
+        for descriptor in data[54 .. 126].chunks(18) {
+            // display descriptors start with a zero pixel clock
+            if descriptor[0] != 0 || descriptor[1] != 0 {
+                continue;
+            }
+
+            let text = || {
+                let text = descriptor[5 ..].iter()
+                                           .take_while(|&&c| c != b'\n')
+                                           .map(|&c| c as char)
+                                           .collect::<String>();
+                text.trim_right().to_owned()
+            };
+
+            match descriptor[3] {
+                0xfc => name = Some(text()),
+                0xff => serial_string = Some(text()),
+                _ => ()
+            }
+        }
+
+        let (hdr_pq, hdr_hlg) = parse_hdr_static_metadata(data);
+
+        Some(Edid {
+            manufacturer: manufacturer,
+            product_code: product_code,
+            serial_number: serial_number,
+            name: name,
+            serial_string: serial_string,
+            red_primary: (coord(27, 25, 6), coord(28, 25, 4)),
+            green_primary: (coord(29, 25, 2), coord(30, 25, 0)),
+            blue_primary: (coord(31, 26, 6), coord(32, 26, 4)),
+            white_point: (coord(33, 26, 2), coord(34, 26, 0)),
+            hdr_pq: hdr_pq,
+            hdr_hlg: hdr_hlg,
+        })
+    }
+}
+
+/// Looks for the HDR static metadata data block of the CTA-861 extensions and returns whether
+/// the PQ and HLG transfer functions are supported.
+fn parse_hdr_static_metadata(data: &[u8]) -> (bool, bool) {
+    let num_extensions = data[126] as usize;
+
+    for extension in data[128 ..].chunks(128).take(num_extensions) {
+        // tag 0x02 is CTA-861, byte 2 is the offset of the first detailed timing descriptor,
+        // which marks the end of the data blocks collection
+        if extension.len() < 128 || extension[0] != 0x02 || !has_valid_checksum(extension) {
+            continue;
+        }
+
+        let end = extension[2] as usize;
+        if end < 4 || end > 127 {
+            continue;
+        }
+
+        let mut offset = 4;
+        while offset < end {
+            let tag = extension[offset] >> 5;
+            let len = (extension[offset] & 0x1f) as usize;
+
+            // extended tag 6 is the HDR static metadata data block, whose first payload byte
+            // lists the supported EOTFs
+            if tag == 7 && len >= 2 && offset + 2 < end && extension[offset + 1] == 6 {
+                let eotf = extension[offset + 2];
+                return (eotf & 0x4 != 0, eotf & 0x8 != 0);
+            }
+
+            offset += len + 1;
+        }
+    }
+
+    (false, false)
+}
+
+/// The bytes of each block of 128 bytes must add up to zero, modulo 256.
+fn has_valid_checksum(block: &[u8]) -> bool {
+    block.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Edid;
+
+    /// Sets the last byte of a block so that its checksum is valid.
+    fn fix_checksum(block: &mut [u8]) {
+        let sum = block[.. 127].iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        block[127] = 0u8.wrapping_sub(sum);
+    }
+
+    /// A base block with a name and a serial number descriptor.
+    fn base_block() -> Vec<u8> {
+        let mut data = vec![0u8; 128];
+        data[0 .. 8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+
+        // "DEL", five bits per letter
+        data[8] = 0x10;
+        data[9] = 0xac;
+        data[10 .. 16].copy_from_slice(&[0xb1, 0xa0, 0x78, 0x56, 0x34, 0x12]);
+
+        // the high bits of the coordinates, the low bits stay at zero
+        data[27 .. 35].copy_from_slice(&[0xa0, 0x54, 0x4c, 0x9a, 0x26, 0x10, 0x50, 0x54]);
+
+        data[54 .. 59].copy_from_slice(&[0, 0, 0, 0xfc, 0]);
+        data[59 .. 72].copy_from_slice(b"DELL U2720Q\n ");
+        data[72 .. 77].copy_from_slice(&[0, 0, 0, 0xff, 0]);
+        data[77 .. 90].copy_from_slice(b"ABC123\n      ");
+
+        fix_checksum(&mut data);
+        data
+    }
+
+    /// A CTA-861 extension block with an HDR static metadata block that lists PQ and HLG.
+    fn hdr_extension() -> Vec<u8> {
+        let mut block = vec![0u8; 128];
+        block[0] = 0x02;
+        block[1] = 3;
+        block[2] = 8;
+        block[4 .. 8].copy_from_slice(&[0xe3, 6, 0x0c, 0x01]);
+        fix_checksum(&mut block);
+        block
+    }
+
+    #[test]
+    fn valid_base_block() {
+        let edid = Edid::parse(&base_block()).unwrap();
+        assert_eq!(edid.manufacturer, "DEL");
+        assert_eq!(edid.product_code, 0xa0b1);
+        assert_eq!(edid.serial_number, 0x12345678);
+        assert_eq!(edid.name, Some("DELL U2720Q".to_owned()));
+        assert_eq!(edid.serial_string, Some("ABC123".to_owned()));
+        assert_eq!(edid.red_primary, (0.625, 0.328125));
+        assert_eq!(edid.green_primary, (0.296875, 0.6015625));
+        assert_eq!(edid.blue_primary, (0.1484375, 0.0625));
+        assert_eq!(edid.white_point, (0.3125, 0.328125));
+        assert!(!edid.hdr_pq);
+        assert!(!edid.hdr_hlg);
+    }
+
+    #[test]
+    fn hdr_extension_block() {
+        let mut data = base_block();
+        data[126] = 1;
+        fix_checksum(&mut data);
+        data.extend(hdr_extension());
+
+        let edid = Edid::parse(&data).unwrap();
+        assert!(edid.hdr_pq);
+        assert!(edid.hdr_hlg);
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let mut data = base_block();
+        data[127] = data[127].wrapping_add(1);
+        assert_eq!(Edid::parse(&data), None);
+
+        // a corrupted extension is ignored, but the base block is still used
+        let mut data = base_block();
+        data[126] = 1;
+        fix_checksum(&mut data);
+        let mut extension = hdr_extension();
+        extension[127] = extension[127].wrapping_add(1);
+        data.extend(extension);
+
+        let edid = Edid::parse(&data).unwrap();
+        assert_eq!(edid.manufacturer, "DEL");
+        assert!(!edid.hdr_pq);
+    }
+
+    #[test]
+    fn truncated_block() {
+        let data = base_block();
+        assert_eq!(Edid::parse(&data[.. 127]), None);
+        assert_eq!(Edid::parse(&data[.. 8]), None);
+        assert_eq!(Edid::parse(&[]), None);
+
+        // an extension that is announced but cut short is ignored
+        let mut data = base_block();
+        data[126] = 1;
+        fix_checksum(&mut data);
+        data.extend(&hdr_extension()[.. 64]);
+
+        let edid = Edid::parse(&data).unwrap();
+        assert!(!edid.hdr_pq);
+        assert!(!edid.hdr_hlg);
+    }
+}
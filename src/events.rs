@@ -1,9 +1,14 @@
 use std::path::PathBuf;
 
+use LogicalSize;
+
 #[derive(Clone, Debug)]
 pub enum Event {
-    /// The size of the window has changed.
-    Resized(u32, u32),
+    /// The size of the client area of the window has changed.
+    ///
+    /// Use `LogicalSize::to_physical` with the hidpi factor of the window to get the new size of
+    /// the framebuffer.
+    Resized(LogicalSize),
 
     /// The position of the window has changed.
     Moved(i32, i32),
@@ -11,30 +16,109 @@ pub enum Event {
     /// The window has been closed.
     Closed,
 
+    /// The native window has been destroyed. No event is received after this one.
+    ///
+    /// Contrary to `Closed`, this is also received when the window is destroyed by someone else
+    /// than the user, for example with `xkill` or when its parent is destroyed.
+    Destroyed,
+
     /// A file has been dropped into the window.
     DroppedFile(PathBuf),
 
+    /// A file is being dragged over the window.
+    ///
+    /// This is received once per file when the cursor enters the window, and is followed either
+    /// by `DroppedFile` or by `HoveredFileCancelled`.
+    HoveredFile(PathBuf),
+
+    /// The files that were being dragged over the window have left it without being dropped.
+    HoveredFileCancelled,
+
     /// The window received a unicode character.
+    ///
+    /// Each character of a `ReceivedText` event is also reported with this event.
     ReceivedCharacter(char),
 
+    /// The window received some text, either typed on the keyboard or committed by an input
+    /// method.
+    ///
+    /// Text editors should prefer this event to `ReceivedCharacter`: the text that an input
+    /// method commits at once is kept together, so that the emojis and the clusters of complex
+    /// scripts aren't split. It is reported before the `ReceivedCharacter` events of the same
+    /// text, so an application should only handle one or the other.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Not reported on Android, iOS and Emscripten.
+    ReceivedText(String),
+
     /// The window gained or lost focus.
     ///
     /// The parameter is true if the window has gained focus, and false if it has lost focus.
     Focused(bool),
 
     /// An event from the keyboard has been received.
-    KeyboardInput(ElementState, ScanCode, Option<VirtualKeyCode>),
+    ///
+    /// The last parameter is the state of the modifiers when the key was pressed or released.
+    KeyboardInput(ElementState, ScanCode, Option<VirtualKeyCode>, ModifiersState),
+
+    /// The state of the lock keys has changed, usually because one of them has been pressed.
+    ///
+    /// A change that happened while the window didn't have the focus is reported when it gains
+    /// the focus again.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only reported on X11 and Windows.
+    LockKeysChanged(LockKeys),
+
+    /// The mouse has moved, by the given number of device units on each axis.
+    ///
+    /// Contrary to `MouseMoved`, the motion is never accelerated and doesn't stop at the edges
+    /// of the screen. The acceleration that the system applies to the cursor can be queried with
+    /// `Window::get_pointer_acceleration`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only reported while the cursor is grabbed, on Windows and on X11 with XInput 2.1.
+    MouseRawMovement(f64, f64),
 
     /// The cursor has moved on the window.
     ///
     /// The parameter are the (x,y) coords in pixels relative to the top-left corner of the window.
     MouseMoved(i32, i32),
 
+    /// The cursor has entered the window.
+    MouseEntered,
+
+    /// The cursor has left the window.
+    MouseLeft,
+
+    /// The cursor has been resting over the window, at the given position in pixels, for the
+    /// delay set with `Window::set_hover_delay`.
+    ///
+    /// This is received once, until the cursor moves again. Any other mouse or keyboard input
+    /// cancels it.
+    Hovered(i32, i32),
+
     /// A mouse wheel movement or touchpad scroll occurred.
-    MouseWheel(MouseScrollDelta, TouchPhase),
+    ///
+    /// The phase tells when the fingers touch and leave the touchpad, on the platforms that
+    /// report it. It is always `Moved` for mouse wheels.
+    ///
+    /// The last parameter is true if the scroll is the inertia that continues after the fingers
+    /// have left the touchpad (momentum scrolling). In this case, the phase tells when the
+    /// inertial scroll starts and ends.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only OS X reports phases other than `Moved` and momentum scrolling.
+    MouseWheel(MouseScrollDelta, TouchPhase, bool),
 
     /// An event from the mouse has been received.
-    MouseInput(ElementState, MouseButton, Option<(i32, i32)>),
+    ///
+    /// The last parameter is the state of the modifiers when the button was pressed or released.
+    MouseInput(ElementState, MouseButton, Option<(i32, i32)>, ModifiersState),
 
     /// Touchpad pressure event.
     ///
@@ -43,6 +127,27 @@ pub enum Event {
     /// is being pressed) and stage (integer representing the click level).
     TouchpadPressure(f32, i64),
 
+    /// A pinch gesture has been performed on a touchpad or touchscreen.
+    ///
+    /// The parameter is the change of the magnification since the previous event: positive
+    /// values mean that the fingers moved apart (zoom in), and `0.1` means 10% larger.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On OS X, this is the magnify gesture of the trackpad.
+    /// - On X11, this is computed from the movements of two fingers on a touchscreen, and
+    ///   requires `with_multitouch`.
+    /// - Not reported on other platforms.
+    PinchGesture(f32),
+
+    /// A rotation gesture has been performed on a touchpad or touchscreen.
+    ///
+    /// The parameter is the angle of the rotation since the previous event, in degrees.
+    /// Positive values are counterclockwise.
+    ///
+    /// The platforms that report it are the same as for `PinchGesture`.
+    RotateGesture(f32),
+
     /// The event loop was woken up by another thread.
     Awakened,
 
@@ -56,7 +161,112 @@ pub enum Event {
 
 
     /// Touch event has been received
-    Touch(Touch)
+    Touch(Touch),
+
+    /// A termination of the program has been requested with `request_termination`, usually by
+    /// a handler of `SIGINT` or `SIGTERM`. The application should save its state and exit.
+    ///
+    /// Reported once per request by every window.
+    Terminated,
+
+    /// The ICC profile of one of the monitors has changed.
+    ///
+    /// Use `MonitorId::get_icc_profile` to retreive the new profiles.
+    IccProfileChanged,
+
+    /// The window has entered or left fullscreen, either because of the application or
+    /// because of the window manager (for example when the user presses F11).
+    ///
+    /// The parameter is true if the window is now fullscreen.
+    Fullscreen(bool),
+
+    /// The window has been maximized or unmaximized, either because of the application or
+    /// because of the window manager (for example when the user double clicks the title bar).
+    ///
+    /// The parameter is true if the window is now maximized.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only reported on X11 and Windows.
+    Maximized(bool),
+
+    /// The window has been minimized or restored, either because of the application or because
+    /// of the user.
+    ///
+    /// The parameter is true if the window is now minimized.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this follows the ICCCM `WM_STATE` of the window, which the window manager
+    ///   switches to `IconicState` when the window is minimized.
+    /// - Not reported on Wayland, Android, iOS and Emscripten.
+    Minimized(bool),
+
+    /// The window has moved to a monitor with a different refresh rate.
+    ///
+    /// The parameter is the new refresh rate, in millihertz. Applications that pace their
+    /// frames by themselves should use it, vsync already follows the monitor of the window.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this requires XRandR, and follows the CRTC under the center of the window.
+    /// - Only reported on X11 and Windows.
+    RefreshRateChanged(u32),
+
+    /// A frame has been displayed, or replaced by a later frame before it could be displayed.
+    /// Only reported if the window has been built with `with_frame_timing`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only reported on X11, with the Present extension and libXpresent, and with the drivers
+    ///   that present the frames through the extension, which is the case of Mesa.
+    FramePresented(FrameTiming),
+
+    /// The window manager has tiled or untiled the window, for example when the user drags it
+    /// to an edge of the screen.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the tiling is read from the `_GTK_EDGE_CONSTRAINTS` property when the WM sets
+    ///   it (Mutter does). Otherwise a window is considered tiled if it is only maximized
+    ///   vertically and its frame touches either the left or the right edge of the workarea.
+    /// - On Windows, this reports Aero Snap.
+    /// - Not reported on other platforms.
+    Tiled(TileState),
+
+    /// The title of the window has been changed by another program, for example by the
+    /// application that embeds the window. The parameter is the new title.
+    ///
+    /// Not reported when the title is changed with `set_title`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this follows the `_NET_WM_NAME` and `WM_NAME` properties of the window.
+    /// - Only reported on X11 and Windows.
+    TitleChanged(String),
+
+    /// An item of the menu bar has been chosen, or its accelerator has been pressed. The
+    /// parameter is the ID of the item. See `Window::set_menu_bar`.
+    ///
+    /// On Linux and on the platforms without native menus, this event follows the
+    /// `KeyboardInput` event of the accelerator.
+    MenuItem(u32),
+
+    /// The last window of an `Application` has been closed.
+    AllWindowsClosed,
+}
+
+/// How the window manager has tiled a window.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum TileState {
+    /// The window is not tiled. Maximized and fullscreen windows are not tiled.
+    Untiled,
+    /// The window fills the left half of the screen.
+    Left,
+    /// The window fills the right half of the screen.
+    Right,
+    /// The window is tiled in another way, for example in a quarter of the screen.
+    Other,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -90,6 +300,24 @@ pub struct Touch {
     pub id: u64
 }
 
+/// When a frame has been displayed, as reported by `Event::FramePresented`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameTiming {
+    /// The serial number of the swap, which increases with every swap of the window.
+    pub serial: u32,
+    /// When the frame was displayed, in microseconds. On Linux, this is the time of the
+    /// `CLOCK_MONOTONIC` clock.
+    pub timestamp: u64,
+    /// The number of vertical retraces of the monitor when the frame was displayed, counted from
+    /// an arbitrary point.
+    pub msc: u64,
+    /// True if the frame was displayed by flipping the buffers, without copying it. This
+    /// usually means that the compositor has been bypassed.
+    pub flipped: bool,
+    /// True if the frame was never displayed, because a later frame replaced it first.
+    pub skipped: bool,
+}
+
 pub type ScanCode = u8;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -98,6 +326,33 @@ pub enum ElementState {
     Released,
 }
 
+/// The state of the modifier keys.
+///
+/// Whether the `KeyboardInput` event of a modifier key already reflects the new state of this
+/// modifier depends on the platform.
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct ModifiersState {
+    /// The Shift key.
+    pub shift: bool,
+    /// The Control key.
+    pub ctrl: bool,
+    /// The Alt key, or the Option key on OS X.
+    pub alt: bool,
+    /// The Windows key on Windows, the Command key on OS X, or the Super key on Linux.
+    pub logo: bool,
+}
+
+/// The state of the lock keys, which is usually shown by the LEDs of the keyboard.
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct LockKeys {
+    /// The Caps Lock key.
+    pub caps_lock: bool,
+    /// The Num Lock key.
+    pub num_lock: bool,
+    /// The Scroll Lock key.
+    pub scroll_lock: bool,
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum MouseButton {
     Left,
@@ -0,0 +1,155 @@
+//! Limitation of the number of frames that the driver queues ahead of the GPU.
+//!
+//! Drivers usually let the application submit a few frames before the first one is displayed,
+//! which adds latency between the input and the screen. A fence sync is inserted after every
+//! swap, and the oldest fences are waited for so that at most a given number of frames are
+//! pending.
+//!
+//! For measurements, `glFinish` can also be called after every swap, so that `swap_buffers`
+//! only returns once the frame is entirely rendered.
+
+use std::collections::VecDeque;
+use std::mem;
+
+use libc::c_void;
+
+use gl_info::GlInfo;
+
+type GLsync = *const c_void;
+
+const SYNC_GPU_COMMANDS_COMPLETE: u32 = 0x9117;
+const SYNC_FLUSH_COMMANDS_BIT: u32 = 0x00000001;
+
+/// How long to wait for a frame, in nanoseconds, so that a hung GPU doesn't freeze the
+/// application.
+const WAIT_TIMEOUT_NS: u64 = 1_000_000_000;
+
+type FinishFn = unsafe extern "system" fn();
+
+/// The functions of `GL_ARB_sync`, which is core since OpenGL 3.2 and OpenGL ES 3.0.
+struct SyncFunctions {
+    fence_sync: unsafe extern "system" fn(u32, u32) -> GLsync,
+    client_wait_sync: unsafe extern "system" fn(GLsync, u32, u64) -> u32,
+    delete_sync: unsafe extern "system" fn(GLsync),
+}
+
+pub struct FrameLatency {
+    max_frames: Option<u32>,
+    finish_on_swap: bool,
+    /// `glFinish`, loaded the first time that it is needed
+    finish: Option<Option<FinishFn>>,
+    /// The functions, loaded the first time that they are needed. Contains `Some(None)` if the
+    /// context doesn't support fences.
+    functions: Option<Option<SyncFunctions>>,
+    /// The fences of the frames in flight, from the oldest to the newest
+    fences: VecDeque<GLsync>,
+}
+
+// the fences are only used while the context is current, by a single thread at a time
+unsafe impl Send for FrameLatency {}
+
+impl FrameLatency {
+    #[inline]
+    pub fn new() -> FrameLatency {
+        FrameLatency {
+            max_frames: None,
+            finish_on_swap: false,
+            finish: None,
+            functions: None,
+            fences: VecDeque::new(),
+        }
+    }
+
+    #[inline]
+    pub fn set_max_frames(&mut self, max_frames: Option<u32>) {
+        self.max_frames = max_frames;
+    }
+
+    #[inline]
+    pub fn set_finish_on_swap(&mut self, finish_on_swap: bool) {
+        self.finish_on_swap = finish_on_swap;
+    }
+
+    /// Inserts the fence of the frame that has just been swapped, and waits for the oldest
+    /// frames. Must be called while the context is current.
+    pub fn after_swap<F>(&mut self, get_proc_address: F) where F: Fn(&str) -> *const () {
+        if self.finish_on_swap {
+            if self.finish.is_none() {
+                let finish = get_proc_address("glFinish");
+                self.finish = Some(if finish.is_null() {
+                    None
+                } else {
+                    Some(unsafe { mem::transmute::<_, FinishFn>(finish) })
+                });
+            }
+            if let Some(Some(finish)) = self.finish {
+                unsafe { finish() };
+            }
+        }
+
+        if self.functions.is_none() {
+            if self.max_frames.is_none() {
+                return;
+            }
+            self.functions = Some(load_functions(get_proc_address));
+        }
+
+        let functions = match self.functions {
+            Some(Some(ref functions)) => functions,
+            _ => return,
+        };
+
+        let max_frames = match self.max_frames {
+            Some(max_frames) => max_frames as usize,
+            None => {
+                // the limit has been removed, the pending fences are no longer needed
+                for fence in self.fences.drain(..) {
+                    unsafe { (functions.delete_sync)(fence) };
+                }
+                return;
+            },
+        };
+
+        unsafe {
+            let fence = (functions.fence_sync)(SYNC_GPU_COMMANDS_COMPLETE, 0);
+            if !fence.is_null() {
+                self.fences.push_back(fence);
+            }
+
+            while self.fences.len() > max_frames {
+                let fence = self.fences.pop_front().unwrap();
+                (functions.client_wait_sync)(fence, SYNC_FLUSH_COMMANDS_BIT, WAIT_TIMEOUT_NS);
+                (functions.delete_sync)(fence);
+            }
+        }
+    }
+}
+
+fn load_functions<F>(get_proc_address: F) -> Option<SyncFunctions>
+    where F: Fn(&str) -> *const ()
+{
+    let supported = match GlInfo::query(&get_proc_address) {
+        Some(info) => info.is_gl_at_least((3, 2)) || info.is_es_at_least((3, 0)) ||
+                      (!info.es && info.has_extension("GL_ARB_sync")),
+        None => false,
+    };
+    if !supported {
+        return None;
+    }
+
+    let fence_sync = get_proc_address("glFenceSync");
+    let client_wait_sync = get_proc_address("glClientWaitSync");
+    let delete_sync = get_proc_address("glDeleteSync");
+
+    if fence_sync.is_null() || client_wait_sync.is_null() || delete_sync.is_null() {
+        return None;
+    }
+
+    unsafe {
+        Some(SyncFunctions {
+            fence_sync: mem::transmute(fence_sync),
+            client_wait_sync: mem::transmute(client_wait_sync),
+            delete_sync: mem::transmute(delete_sync),
+        })
+    }
+}
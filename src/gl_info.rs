@@ -0,0 +1,137 @@
+//! Version and extensions of the current context.
+//!
+//! `glXGetProcAddress` returns a pointer for any name, even for the functions that the driver
+//! doesn't implement, so the optional functions are only loaded if the version or the
+//! extensions of the context provide them.
+
+use std::ffi::CStr;
+use std::mem;
+
+use libc::c_char;
+
+const VERSION: u32 = 0x1F02;
+const EXTENSIONS: u32 = 0x1F03;
+const NUM_EXTENSIONS: u32 = 0x821D;
+
+type GetStringFn = unsafe extern "system" fn(u32) -> *const u8;
+type GetStringiFn = unsafe extern "system" fn(u32, u32) -> *const u8;
+type GetIntegervFn = unsafe extern "system" fn(u32, *mut i32);
+
+pub struct GlInfo {
+    /// Whether the context is an OpenGL ES one
+    pub es: bool,
+    /// The major and minor versions
+    pub version: (u32, u32),
+    extensions: Vec<String>,
+}
+
+impl GlInfo {
+    /// Reads the version and the extensions of the current context, or returns `None` if the
+    /// version can't be read.
+    pub fn query<F>(get_proc_address: F) -> Option<GlInfo> where F: Fn(&str) -> *const () {
+        let get_string = get_proc_address("glGetString");
+        if get_string.is_null() {
+            return None;
+        }
+        let get_string: GetStringFn = unsafe { mem::transmute(get_string) };
+
+        let version = match unsafe { read_string(get_string(VERSION)) } {
+            Some(version) => version,
+            None => return None,
+        };
+        let (es, version) = match parse_version(&version) {
+            Some(parsed) => parsed,
+            None => return None,
+        };
+
+        // `GL_EXTENSIONS` can't be passed to `glGetString` in the core profiles
+        let mut extensions = Vec::new();
+        let get_stringi = get_proc_address("glGetStringi");
+        let get_integerv = get_proc_address("glGetIntegerv");
+        if version >= (3, 0) && !get_stringi.is_null() && !get_integerv.is_null() {
+            unsafe {
+                let get_stringi: GetStringiFn = mem::transmute(get_stringi);
+                let get_integerv: GetIntegervFn = mem::transmute(get_integerv);
+
+                let mut count = 0;
+                get_integerv(NUM_EXTENSIONS, &mut count);
+                for i in 0 .. count {
+                    extensions.extend(read_string(get_stringi(EXTENSIONS, i as u32)));
+                }
+            }
+        } else if let Some(list) = unsafe { read_string(get_string(EXTENSIONS)) } {
+            extensions.extend(list.split_whitespace().map(|e| e.to_owned()));
+        }
+
+        Some(GlInfo {
+            es: es,
+            version: version,
+            extensions: extensions,
+        })
+    }
+
+    /// Returns true if the context is a desktop OpenGL one of at least this version.
+    #[inline]
+    pub fn is_gl_at_least(&self, version: (u32, u32)) -> bool {
+        !self.es && self.version >= version
+    }
+
+    /// Returns true if the context is an OpenGL ES one of at least this version.
+    #[inline]
+    pub fn is_es_at_least(&self, version: (u32, u32)) -> bool {
+        self.es && self.version >= version
+    }
+
+    #[inline]
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|e| e == name)
+    }
+}
+
+unsafe fn read_string(string: *const u8) -> Option<String> {
+    if string.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(string as *const c_char).to_string_lossy().into_owned())
+    }
+}
+
+/// Parses `GL_VERSION`, which is `<major>.<minor>[.<release>] [<vendor info>]` for OpenGL
+/// and is prefixed with `OpenGL ES ` (or `OpenGL ES-CM ` for 1.x) for OpenGL ES.
+fn parse_version(version: &str) -> Option<(bool, (u32, u32))> {
+    let (es, version) = if version.starts_with("OpenGL ES") {
+        (true, version.splitn(3, ' ').nth(2).unwrap_or(""))
+    } else {
+        (false, version)
+    };
+
+    let number = version.split_whitespace().next().unwrap_or("");
+    let mut parts = number.split('.').map(|part| part.parse::<u32>().ok());
+    match (parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor))) => Some((es, (major, minor))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_version;
+
+    #[test]
+    fn desktop_version() {
+        assert_eq!(parse_version("4.6.0 NVIDIA 535.54.03"), Some((false, (4, 6))));
+        assert_eq!(parse_version("3.1 Mesa 21.0.3"), Some((false, (3, 1))));
+    }
+
+    #[test]
+    fn es_version() {
+        assert_eq!(parse_version("OpenGL ES 3.2 Mesa 23.1.0"), Some((true, (3, 2))));
+        assert_eq!(parse_version("OpenGL ES-CM 1.1"), Some((true, (1, 1))));
+    }
+
+    #[test]
+    fn invalid_version() {
+        assert_eq!(parse_version(""), None);
+        assert_eq!(parse_version("OpenGL ES"), None);
+    }
+}
@@ -10,6 +10,7 @@ use PixelFormatRequirements;
 use Robustness;
 
 use platform;
+use robustness;
 
 /// Object that allows you to build headless contexts.
 #[derive(Clone)]
@@ -24,7 +25,8 @@ pub struct HeadlessRendererBuilder<'a> {
     pf_reqs: PixelFormatRequirements,
 
     /// Platform-specific configuration.
-    platform_specific: platform::PlatformSpecificHeadlessBuilderAttributes,
+    #[doc(hidden)]
+    pub platform_specific: platform::PlatformSpecificHeadlessBuilderAttributes,
 }
 
 impl<'a> HeadlessRendererBuilder<'a> {
@@ -63,6 +65,14 @@ impl<'a> HeadlessRendererBuilder<'a> {
         self
     }
 
+    /// Sets the *forward compatible* flag for the OpenGL context, which removes the
+    /// deprecated functionality. See the docs of `GlAttributes::forward_compatible`.
+    #[inline]
+    pub fn with_gl_forward_compatible(mut self, flag: bool) -> HeadlessRendererBuilder<'a> {
+        self.opengl.forward_compatible = flag;
+        self
+    }
+
     /// Sets the robustness of the OpenGL context. See the docs of `Robustness`.
     #[inline]
     pub fn with_gl_robustness(mut self, robustness: Robustness) -> HeadlessRendererBuilder<'a> {
@@ -92,6 +102,19 @@ impl<'a> HeadlessRendererBuilder<'a> {
 }
 
 /// Represents a headless OpenGL context.
+///
+/// The context renders to an off-screen buffer of the dimensions of the builder, without any
+/// window, which allows rendering on servers and in continuous integration.
+///
+/// ## Platform-specific
+///
+/// - On Linux, the context renders to a GLX pbuffer if an X server is available, to an EGL
+///   pbuffer on Mesa's surfaceless platform otherwise, and falls back to OSMesa. The backend can
+///   be chosen with `HeadlessRendererBuilderExt::with_headless_backend`.
+/// - On Windows, the context renders to an EGL pbuffer if ANGLE is available, and to a WGL
+///   pbuffer otherwise. A hidden window is only used if the driver doesn't support
+///   `WGL_ARB_pbuffer`.
+/// - On OS X, the context is a CGL context without any drawable.
 pub struct HeadlessContext {
     context: platform::HeadlessContext,
 }
@@ -126,6 +149,14 @@ impl HeadlessContext {
         self.context.get_api()
     }
 
+    /// Returns the robustness that the context has actually obtained.
+    ///
+    /// See `Window::get_robustness` for more infos.
+    #[inline]
+    pub fn get_robustness(&self) -> Robustness {
+        robustness::get_robustness(|name| self.context.get_proc_address(name))
+    }
+
     #[inline]
     pub fn set_window_resize_callback(&mut self, _: Option<fn(u32, u32)>) {
     }
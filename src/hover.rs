@@ -0,0 +1,63 @@
+//! Detection of the cursor resting over a window, which is when tooltips are usually shown.
+
+use std::time::{Duration, Instant};
+
+use Event;
+
+pub struct Hover {
+    delay: Option<Duration>,
+    /// The position where the cursor stopped and when, if it hasn't been reported yet.
+    pending: Option<((i32, i32), Instant)>,
+}
+
+impl Hover {
+    #[inline]
+    pub fn new() -> Hover {
+        Hover {
+            delay: None,
+            pending: None,
+        }
+    }
+
+    #[inline]
+    pub fn set_delay(&mut self, delay: Option<Duration>) {
+        self.delay = delay;
+        self.pending = None;
+    }
+
+    /// Updates the state according to an event that is about to be returned to the user.
+    pub fn process(&mut self, event: &Event) {
+        match *event {
+            Event::MouseMoved(x, y) if self.delay.is_some() => {
+                self.pending = Some(((x, y), Instant::now()));
+            },
+
+            // tooltips are dismissed by any other input until the cursor moves again
+            Event::MouseLeft | Event::MouseInput(..) | Event::MouseWheel(..) |
+            Event::KeyboardInput(..) | Event::Focused(false) | Event::Closed => {
+                self.pending = None;
+            },
+
+            _ => ()
+        }
+    }
+
+    /// Returns the instant when the cursor will be considered resting, if it is over the window.
+    #[inline]
+    pub fn deadline(&self) -> Option<Instant> {
+        match (self.pending, self.delay) {
+            (Some((_, since)), Some(delay)) => Some(since + delay),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Hovered` event if the delay has elapsed.
+    pub fn poll(&mut self) -> Option<Event> {
+        match self.deadline() {
+            Some(deadline) if deadline <= Instant::now() => {
+                self.pending.take().map(|((x, y), _)| Event::Hovered(x, y))
+            },
+            _ => None,
+        }
+    }
+}
@@ -62,22 +62,42 @@ extern crate wayland_client;
 #[cfg(all(feature = "image",
           any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd")))]
 extern crate image;
+#[cfg(all(feature = "futures",
+          any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd")))]
+extern crate futures;
 
+pub use accelerator::{Accelerator, AcceleratorTable};
+pub use application::Application;
+pub use blit::{blit, BlitRect};
 pub use events::*;
 pub use headless::{HeadlessRendererBuilder, HeadlessContext};
-pub use window::{WindowProxy, PollEventsIterator, WaitEventsIterator};
+pub use menu::{Menu, MenuItem};
+pub use window::{WindowProxy, PollEventsIterator, WaitEventsIterator, SharedContext};
 pub use window::{AvailableMonitorsIter, MonitorId, get_available_monitors, get_primary_monitor};
 pub use native_monitor::NativeMonitorId;
+pub use termination::request_termination;
+pub use edid::Edid;
 
 use std::io;
 #[cfg(not(target_os = "macos"))]
 use std::cmp::Ordering;
 use std::path::PathBuf;
 
+mod accelerator;
 mod api;
+mod application;
+mod blit;
+mod child;
+mod edid;
 mod platform;
 mod events;
+mod frame_latency;
+mod gl_info;
 mod headless;
+mod hover;
+mod menu;
+mod robustness;
+mod termination;
 mod window;
 
 pub mod os;
@@ -107,6 +127,22 @@ pub mod os;
 /// ```
 pub struct Window {
     window: platform::Window,
+    hidpi_factor_override: Option<f32>,
+    /// The last cursor passed to `set_cursor`, and whether the busy cursor is shown instead.
+    cursor: std::sync::Mutex<(MouseCursor, bool)>,
+    /// Detection of the cursor resting over the window.
+    hover: std::sync::Mutex<hover::Hover>,
+    /// The accelerators of the menu bar, when the platform doesn't trigger them.
+    menu_shortcuts: std::sync::Mutex<menu::MenuShortcuts>,
+    /// Limitation of the frames queued by the driver.
+    frame_latency: std::sync::Mutex<frame_latency::FrameLatency>,
+    /// Detection of the resets of the GPU.
+    reset_detection: std::sync::Mutex<robustness::ResetDetection>,
+    /// Reports the requests of termination.
+    termination: termination::Listener,
+    /// The requirements of the context, to create the contexts that share its objects.
+    pf_reqs: PixelFormatRequirements,
+    opengl: GlAttributes<()>,
 }
 
 /// Object that allows you to build windows.
@@ -163,6 +199,12 @@ pub enum CreationError {
     RobustnessNotSupported,
     OpenGlVersionNotSupported,
     NoAvailablePixelFormat,
+    /// No pixel format matches the criterias, but one would if the given requirement was
+    /// dropped. The parameter is the name of the field of `PixelFormatRequirements`.
+    PixelFormatRequirementNotSupported(&'static str),
+    /// An sRGB-capable framebuffer was requested, but none of the pixel formats that match the
+    /// other requirements is sRGB-capable. The parameter contains the formats that were found.
+    NoSrgbPixelFormat(Vec<PixelFormat>),
 }
 
 impl CreationError {
@@ -177,13 +219,33 @@ impl CreationError {
                                                          supported.",
             CreationError::NoAvailablePixelFormat => "Couldn't find any pixel format that matches \
                                                       the criterias.",
+            CreationError::PixelFormatRequirementNotSupported(_) => "Couldn't find any pixel \
+                                                                    format that matches one of \
+                                                                    the criterias.",
+            CreationError::NoSrgbPixelFormat(_) => "Couldn't find any sRGB-capable pixel format.",
         }
     }
 }
 
 impl std::fmt::Display for CreationError {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        formatter.write_str(self.to_string())
+        match *self {
+            CreationError::PixelFormatRequirementNotSupported(requirement) => {
+                write!(formatter, "Couldn't find any pixel format that matches the criterias \
+                                   because of the `{}` requirement", requirement)
+            },
+            CreationError::NoSrgbPixelFormat(ref found) => {
+                try!(write!(formatter, "Couldn't find any sRGB-capable pixel format, found {} \
+                                        other format(s)", found.len()));
+                for format in found.iter() {
+                    try!(write!(formatter, "; {} color bits, {} alpha bits, {} depth bits, \
+                                            {} stencil bits", format.color_bits,
+                                format.alpha_bits, format.depth_bits, format.stencil_bits));
+                }
+                Ok(())
+            },
+            _ => formatter.write_str(self.to_string()),
+        }
     }
 }
 
@@ -316,6 +378,8 @@ pub enum Robustness {
     /// Everything is checked to avoid any crash. If a problem occurs, the context will enter a
     /// "context lost" state. It must then be recreated. For the moment, glutin doesn't provide a
     /// way to recreate a context with the same window :-/
+    ///
+    /// Once the reset has been detected, `swap_buffers` returns `ContextError::ContextLost`.
     RobustLoseContextOnReset,
 
     /// Same as `RobustLoseContextOnReset` but the context creation doesn't fail if it's not
@@ -333,6 +397,20 @@ pub enum ReleaseBehavior {
     Flush,
 }
 
+/// What happens to the back buffer when the buffers are swapped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SwapMethod {
+    /// The front and back buffers are exchanged. The content of the new back buffer is the
+    /// previous frame.
+    Exchange,
+
+    /// The back buffer is copied to the front buffer, and its content is preserved.
+    Copy,
+
+    /// The content of the back buffer is undefined after a swap.
+    Undefined,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum MouseCursor {
     /// The platform-dependent default cursor.
@@ -403,7 +481,137 @@ pub enum CursorState {
     /// the cursor.
     ///
     /// This is useful for first-person cameras for example.
+    ///
+    /// On X11 and Windows, the grab is released and the cursor is shown again as soon as a
+    /// thread of the program panics, so that a crash never leaves the user without a cursor.
     Grab,
+
+    /// Grabs and hides the cursor, and keeps moving it back to the center of the window so that
+    /// its motion never stops at an edge of the screen.
+    ///
+    /// `MouseMoved` events are not reported in this mode: the motion of the mouse is read from
+    /// the `MouseRawMovement` events instead. Behaves like `Grab` on platforms that don't
+    /// report raw movements.
+    ///
+    /// On Windows, the cursor is only confined to the window like with `Grab`, but the raw
+    /// movements are reported.
+    GrabRelative,
+
+    /// The cursor will be invisible when over the window while the window has the focus, so
+    /// that the application can draw its own cursor at the position reported by `MouseMoved`.
+    ///
+    /// The system cursor is shown again when the window loses the focus or when the cursor
+    /// leaves the window.
+    Soft,
+}
+
+/// The settings of the system for the acceleration of the cursor, as returned by
+/// `Window::get_pointer_acceleration`.
+///
+/// They apply to the `MouseMoved` events, but never to the `MouseRawMovement` events. Games that
+/// read the raw movements can use them to match the sensitivity of the cursor.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointerAcceleration {
+    /// True if the cursor moves further when the mouse moves faster.
+    pub enabled: bool,
+    /// The multiplier of the motion of the mouse.
+    ///
+    /// On X11, this is the acceleration factor, which only applies above the threshold. On
+    /// Windows, this is the multiplier of the pointer speed slider, which always applies.
+    pub speed: f64,
+    /// The number of device units that the mouse must move at once for the acceleration to
+    /// apply, if the system uses a simple threshold rather than a curve.
+    pub threshold: Option<u32>,
+}
+
+/// A half or a quarter of a monitor, where a window can be snapped with `Window::snap_to`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SnapPosition {
+    /// The left half of the monitor.
+    Left,
+    /// The right half of the monitor.
+    Right,
+    /// The top half of the monitor.
+    Top,
+    /// The bottom half of the monitor.
+    Bottom,
+    /// The top-left quarter of the monitor.
+    TopLeft,
+    /// The top-right quarter of the monitor.
+    TopRight,
+    /// The bottom-left quarter of the monitor.
+    BottomLeft,
+    /// The bottom-right quarter of the monitor.
+    BottomRight,
+}
+
+/// What happens when a fullscreen window that has changed the video mode of the monitor loses the
+/// focus, for example because the user switches to another application.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FullscreenFocusPolicy {
+    /// The video mode of the desktop is restored and the window is minimized. The video mode of
+    /// the window is applied again when it gets the focus back.
+    Minimize,
+    /// The window stays fullscreen, and the monitor keeps the video mode of the window.
+    Stay,
+}
+
+impl Default for FullscreenFocusPolicy {
+    #[inline]
+    fn default() -> FullscreenFocusPolicy {
+        FullscreenFocusPolicy::Minimize
+    }
+}
+
+/// A size in logical units, also called points.
+///
+/// Logical units don't depend on the DPI of the monitor: a window of 800x600 points has roughly
+/// the same physical size on a regular and on a hidpi monitor. Multiply by the hidpi factor of
+/// the window to get a `PhysicalSize`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LogicalSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl LogicalSize {
+    #[inline]
+    pub fn new(width: u32, height: u32) -> LogicalSize {
+        LogicalSize { width: width, height: height }
+    }
+
+    /// Converts to physical pixels, given the hidpi factor of the window.
+    #[inline]
+    pub fn to_physical(&self, hidpi_factor: f32) -> PhysicalSize {
+        PhysicalSize {
+            width: (self.width as f32 * hidpi_factor).round() as u32,
+            height: (self.height as f32 * hidpi_factor).round() as u32,
+        }
+    }
+}
+
+/// A size in physical pixels. This is the unit of the framebuffer, and therefore the one you
+/// should use when calling `glViewport`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicalSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PhysicalSize {
+    #[inline]
+    pub fn new(width: u32, height: u32) -> PhysicalSize {
+        PhysicalSize { width: width, height: height }
+    }
+
+    /// Converts to logical units, given the hidpi factor of the window.
+    #[inline]
+    pub fn to_logical(&self, hidpi_factor: f32) -> LogicalSize {
+        LogicalSize {
+            width: (self.width as f32 / hidpi_factor).round() as u32,
+            height: (self.height as f32 / hidpi_factor).round() as u32,
+        }
+    }
 }
 
 /// Describes a possible format. Unused.
@@ -418,7 +626,66 @@ pub struct PixelFormat {
     pub stereoscopy: bool,
     pub double_buffer: bool,
     pub multisampling: Option<u16>,
+    /// Number of coverage samples per pixel, if the format uses coverage sample anti-aliasing
+    /// (CSAA). In this case `multisampling` contains the number of color samples.
+    pub coverage_samples: Option<u16>,
     pub srgb: bool,
+    pub color_space: ColorSpace,
+    /// What happens to the back buffer when the buffers are swapped. `None` if the platform
+    /// doesn't tell.
+    pub swap_method: Option<SwapMethod>,
+    /// Identifier of the format in the underlying API. Can be used to create other surfaces
+    /// that are compatible with the window.
+    pub native_id: NativePixelFormatId,
+}
+
+/// Identifier of a pixel format in the underlying API.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NativePixelFormatId {
+    /// The `GLX_FBCONFIG_ID` of the FBConfig and the id of its X visual.
+    Glx {
+        fbconfig_id: i32,
+        visual_id: u64,
+    },
+
+    /// The `EGL_CONFIG_ID` of the config and the id of its native visual.
+    Egl {
+        config_id: i32,
+        native_visual_id: i32,
+    },
+
+    /// The index of the pixel format, as passed to `SetPixelFormat`.
+    Wgl(i32),
+
+    /// The platform doesn't expose an identifier.
+    Unavailable,
+}
+
+/// Describes how the compositor or the monitor should interpret the values of the default
+/// framebuffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// The default behavior of the platform. The values are usually interpreted as sRGB.
+    Default,
+
+    /// The values are written as-is, without any conversion. This is what you get with a
+    /// framebuffer that is not sRGB-capable.
+    Linear,
+
+    /// The framebuffer is sRGB-capable: when `GL_FRAMEBUFFER_SRGB` is enabled, the linear values
+    /// written by the shaders are converted to sRGB.
+    Srgb,
+
+    /// Like `Srgb`, but with the Display P3 primaries. Used by wide-gamut monitors.
+    DisplayP3,
+
+    /// scRGB: linear values with the sRGB primaries. Values outside of the `[0.0, 1.0]` range
+    /// are used for wide gamut and high dynamic range. Requires a floating-point color buffer.
+    ScRgbLinear,
+
+    /// HDR10: values encoded with the SMPTE ST 2084 (PQ) transfer function, using the BT.2020
+    /// primaries.
+    Bt2020Pq,
 }
 
 /// Describes how the backend should choose a pixel format.
@@ -459,16 +726,44 @@ pub struct PixelFormatRequirements {
     /// A value of `Some(0)` indicates that multisampling must not be enabled.
     pub multisampling: Option<u16>,
 
+    /// Number of coverage samples per pixel, for coverage sample anti-aliasing (CSAA). Must be
+    /// greater than or equal to `multisampling`, which then designates the number of color
+    /// samples. Only NVidia hardware supports this. Default is `None`.
+    pub coverage_samples: Option<u16>,
+
     /// If true, only stereoscopic formats will be considered. If false, only non-stereoscopic
     /// formats. The default is `false`.
     pub stereoscopy: bool,
 
-    /// If true, only sRGB-capable formats will be considered. If false, don't care.
-    /// The default is `false`.
-    pub srgb: bool,
+    /// If true and no format with more than 24 bits of color is available, the backend will try
+    /// again with 24 bits of color and 8 bits of alpha. The default is `false`.
+    pub deep_color_fallback: bool,
+
+    /// The color space of the default framebuffer. `ColorSpace::Srgb` only considers
+    /// sRGB-capable formats. The default is `ColorSpace::Default`.
+    pub color_space: ColorSpace,
 
     /// The behavior when changing the current context. Default is `Flush`.
     pub release_behavior: ReleaseBehavior,
+
+    /// What must happen to the back buffer when the buffers are swapped. `None` means
+    /// "don't care". Default is `None`.
+    pub swap_method: Option<SwapMethod>,
+
+    /// If set, chooses among the formats that satisfy the other requirements, instead of the
+    /// heuristic of glutin. Default is `None`.
+    pub selector: Option<PixelFormatSelector>,
+}
+
+/// A function that receives the pixel formats that satisfy the requirements, and returns the
+/// index of the one to use.
+#[derive(Clone)]
+pub struct PixelFormatSelector(pub std::sync::Arc<Fn(&[PixelFormat]) -> usize + Send + Sync>);
+
+impl std::fmt::Debug for PixelFormatSelector {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        formatter.write_str("PixelFormatSelector")
+    }
 }
 
 impl Default for PixelFormatRequirements {
@@ -483,9 +778,13 @@ impl Default for PixelFormatRequirements {
             stencil_bits: Some(8),
             double_buffer: None,
             multisampling: None,
+            coverage_samples: None,
             stereoscopy: false,
-            srgb: false,
+            deep_color_fallback: false,
+            color_space: ColorSpace::Default,
             release_behavior: ReleaseBehavior::Flush,
+            swap_method: None,
+            selector: None,
         }
     }
 }
@@ -516,6 +815,12 @@ pub struct WindowAttributes {
     /// The default is `None`.
     pub dimensions: Option<(u32, u32)>,
 
+    /// Whether `dimensions` is the outer size of the window, including the decorations drawn by
+    /// the system, instead of the size of the client area.
+    ///
+    /// The default is `false`.
+    pub outer_dimensions: bool,
+
     /// The minimum dimensions a window can be, If this is `None`, the window will have no minimum dimensions (aside from reserved).
     ///
     /// The default is `None`.
@@ -531,6 +836,22 @@ pub struct WindowAttributes {
     /// The default is `None`.
     pub monitor: Option<platform::MonitorId>,
 
+    /// What happens when the window loses the focus while it is fullscreen with `monitor`.
+    ///
+    /// The default is `FullscreenFocusPolicy::Minimize`.
+    pub fullscreen_focus_policy: FullscreenFocusPolicy,
+
+    /// Whether to ask the compositor to stop compositing the screen while the window is
+    /// fullscreen, which removes the latency and the stutter of the compositing effects.
+    ///
+    /// The default is `false`.
+    pub bypass_compositor: bool,
+
+    /// Whether to report when each frame is displayed with `Event::FramePresented`.
+    ///
+    /// The default is `false`.
+    pub frame_timing: bool,
+
     /// The title of the window in the title bar.
     ///
     /// The default is `"glutin window"`.
@@ -566,6 +887,22 @@ pub struct WindowAttributes {
     ///
     /// The default is `None`.
     pub parent: Option<WindowID>,
+
+    /// Whether the window requests variable refresh rate.
+    ///
+    /// The default is `false`.
+    pub variable_refresh: bool,
+
+    /// If set, replaces the hidpi factor reported by the system.
+    ///
+    /// The default is `None`.
+    pub scale_factor_override: Option<f32>,
+
+    /// Whether glutin repeats the keys that are held down on the platforms where the system
+    /// doesn't do it.
+    ///
+    /// The default is `true`.
+    pub key_repeat: bool,
 }
 
 impl Default for WindowAttributes {
@@ -573,9 +910,13 @@ impl Default for WindowAttributes {
     fn default() -> WindowAttributes {
         WindowAttributes {
             dimensions: None,
+            outer_dimensions: false,
             min_dimensions: None,
             max_dimensions: None,
             monitor: None,
+            fullscreen_focus_policy: FullscreenFocusPolicy::default(),
+            bypass_compositor: false,
+            frame_timing: false,
             title: "glutin window".to_owned(),
             visible: true,
             transparent: false,
@@ -583,6 +924,9 @@ impl Default for WindowAttributes {
             multitouch: false,
             icon: None,
             parent: None,
+            variable_refresh: false,
+            scale_factor_override: None,
+            key_repeat: true,
         }
     }
 }
@@ -612,6 +956,16 @@ pub struct GlAttributes<S> {
     /// The default is `true` in debug mode and `false` in release mode.
     pub debug: bool,
 
+    /// Whether to enable the `forward compatible` flag of the context, which removes the
+    /// functionality that is deprecated in the requested version.
+    ///
+    /// This only exists for OpenGL 3.0 and above, and the creation of the context fails with
+    /// `OpenGlVersionNotSupported` if an earlier version is requested. Core contexts are
+    /// always forward-compatible on OS X, where this flag has no effect.
+    ///
+    /// The default is `false`.
+    pub forward_compatible: bool,
+
     /// How the OpenGL context should detect errors.
     ///
     /// The default is `NotRobust` because this is what is typically expected when you create an
@@ -623,6 +977,22 @@ pub struct GlAttributes<S> {
     ///
     /// The default is `false`.
     pub vsync: bool,
+
+    /// Whether the `vsync` setting must be enforced. The swap interval is then always set,
+    /// including to `0` when vsync is disabled, and the creation of the context fails if the
+    /// driver doesn't apply it.
+    ///
+    /// Drivers often enable vsync by default, which caps the frame rate of benchmarks.
+    ///
+    /// The default is `false`.
+    pub strict_vsync: bool,
+
+    /// Whether `swap_buffers` calls `glFinish` after the swap, so that it only returns once the
+    /// frame has been rendered. This gives deterministic frame boundaries for latency
+    /// measurements and profiling, at the cost of the parallelism between the CPU and the GPU.
+    ///
+    /// The default is `false`.
+    pub finish_on_swap: bool,
 }
 
 impl<S> GlAttributes<S> {
@@ -634,8 +1004,11 @@ impl<S> GlAttributes<S> {
             version: self.version,
             profile: self.profile,
             debug: self.debug,
+            forward_compatible: self.forward_compatible,
             robustness: self.robustness,
             vsync: self.vsync,
+            strict_vsync: self.strict_vsync,
+            finish_on_swap: self.finish_on_swap,
         }
     }
 }
@@ -648,8 +1021,11 @@ impl<S> Default for GlAttributes<S> {
             version: GlRequest::Latest,
             profile: None,
             debug: cfg!(debug_assertions),
+            forward_compatible: false,
             robustness: Robustness::NotRobust,
             vsync: false,
+            strict_vsync: false,
+            finish_on_swap: false,
         }
     }
 }
@@ -0,0 +1,134 @@
+//! Menu bars, which are realized with the native menus of the platform when it has some.
+//!
+//! OS X shows the menus in the global menu bar and Windows in the menu bar of the window. On
+//! X11, they are exported to the global menu of the desktop when it has one, and drawn in a
+//! simple bar at the top of the window otherwise. The other platforms have no menus that glutin
+//! can create, so the application draws them by itself, for example with an immediate mode GUI,
+//! and glutin only triggers their shortcuts.
+//!
+//! Except on OS X, the accelerators are triggered by glutin rather than by the menus.
+
+use std::collections::VecDeque;
+
+use Accelerator;
+use AcceleratorTable;
+use Event;
+
+/// A menu of the menu bar, or a submenu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Menu {
+    /// The title of the menu, which is shown in the menu bar or in the parent menu.
+    pub title: String,
+    /// The items of the menu, from the top to the bottom.
+    pub items: Vec<MenuItem>,
+}
+
+/// An item of a menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuItem {
+    /// An item that produces an `Event::MenuItem` with its ID when it is chosen, or when its
+    /// accelerator is pressed.
+    Action {
+        /// The ID reported by the event. Only the 16 lower bits are kept on Windows.
+        id: u32,
+        label: String,
+        /// The keyboard shortcut of the item, which is shown next to its label.
+        accelerator: Option<Accelerator>,
+        /// Disabled items are greyed out, and their accelerator does nothing.
+        enabled: bool,
+    },
+    /// A line between two groups of items.
+    Separator,
+    /// An item that opens another menu.
+    Submenu(Menu),
+}
+
+impl Menu {
+    #[inline]
+    pub fn new<S: Into<String>>(title: S) -> Menu {
+        Menu {
+            title: title.into(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Adds an enabled item at the bottom of the menu.
+    #[inline]
+    pub fn with_action<S: Into<String>>(mut self, id: u32, label: S,
+                                        accelerator: Option<Accelerator>) -> Menu
+    {
+        self.items.push(MenuItem::Action {
+            id: id,
+            label: label.into(),
+            accelerator: accelerator,
+            enabled: true,
+        });
+        self
+    }
+
+    /// Adds a separator at the bottom of the menu.
+    #[inline]
+    pub fn with_separator(mut self) -> Menu {
+        self.items.push(MenuItem::Separator);
+        self
+    }
+
+    /// Adds a submenu at the bottom of the menu.
+    #[inline]
+    pub fn with_submenu(mut self, menu: Menu) -> Menu {
+        self.items.push(MenuItem::Submenu(menu));
+        self
+    }
+
+    /// Registers the accelerators of the enabled items of the menu and of its submenus.
+    fn register_accelerators(&self, table: &mut AcceleratorTable<u32>) {
+        for item in self.items.iter() {
+            match *item {
+                MenuItem::Action { id, accelerator: Some(accelerator), enabled: true, .. } => {
+                    table.insert(accelerator, id);
+                },
+                MenuItem::Submenu(ref menu) => menu.register_accelerators(table),
+                _ => ()
+            }
+        }
+    }
+}
+
+/// Triggers the accelerators of the menus on the platforms where the system doesn't.
+pub struct MenuShortcuts {
+    accelerators: AcceleratorTable<u32>,
+    /// The `MenuItem` events that follow the `KeyboardInput` events already returned
+    pending: VecDeque<Event>,
+}
+
+impl MenuShortcuts {
+    #[inline]
+    pub fn new() -> MenuShortcuts {
+        MenuShortcuts {
+            accelerators: AcceleratorTable::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Replaces the accelerators with the ones of a new menu bar.
+    pub fn set_menus(&mut self, menus: &[Menu]) {
+        self.accelerators = AcceleratorTable::new();
+        for menu in menus.iter() {
+            menu.register_accelerators(&mut self.accelerators);
+        }
+    }
+
+    /// Updates the state according to an event that is about to be returned to the user.
+    #[inline]
+    pub fn process(&mut self, event: &Event) {
+        if let Some(&id) = self.accelerators.find(event) {
+            self.pending.push_back(Event::MenuItem(id));
+        }
+    }
+
+    /// Returns the next `MenuItem` event triggered by an accelerator.
+    #[inline]
+    pub fn poll(&mut self) -> Option<Event> {
+        self.pending.pop_front()
+    }
+}
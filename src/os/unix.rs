@@ -1,9 +1,32 @@
 #![cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
 
 use libc;
+use std::env;
+use std::os::unix::io::RawFd;
+use std::path::Path;
+#[cfg(feature = "futures")]
+use std::sync::{Arc, Condvar, Mutex};
+#[cfg(feature = "futures")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "futures")]
+use std::thread;
+use ContextError;
+use CreationError;
+#[cfg(feature = "futures")]
+use Event;
+use HeadlessRendererBuilder;
 use Window;
 use platform::Window as LinuxWindow;
+use platform::PlatformContext;
 use WindowBuilder;
+use api::x11;
+#[cfg(feature = "futures")]
+use termination;
+
+#[cfg(feature = "futures")]
+use futures::{Async, Poll, Stream};
+#[cfg(feature = "futures")]
+use futures::task::{self, Task};
 
 /// Additional methods on `Window` that are specific to Unix.
 pub trait WindowExt {
@@ -20,6 +43,55 @@ pub trait WindowExt {
     ///
     /// The pointer will become invalid when the glutin `Window` is destroyed.
     fn get_xlib_display(&self) -> Option<*mut libc::c_void>;
+
+    /// Returns the number of the X screen that the window is on.
+    ///
+    /// Returns `None` if the window doesn't use xlib (if it uses wayland for example).
+    fn get_xlib_screen_id(&self) -> Option<libc::c_int>;
+
+    /// Returns the file descriptor of the connection to the X server.
+    ///
+    /// The file descriptor becomes readable when new events are available, which allows
+    /// registering it with a reactor (mio, tokio, `select`/`poll`) and waking up only when
+    /// `poll_events` has something to return. Note that xlib buffers events internally: you
+    /// must always drain `poll_events` entirely before going back to waiting on the file
+    /// descriptor, otherwise events that are already buffered won't wake you up.
+    ///
+    /// Returns `None` if the window doesn't use xlib (if it uses wayland for example).
+    ///
+    /// The file descriptor must not be read from or closed, and becomes invalid when the
+    /// glutin `Window` is destroyed.
+    ///
+    /// The file descriptor doesn't become readable when a termination is requested with
+    /// `request_termination`. Use `event_stream` if you need both.
+    fn get_xlib_fd(&self) -> Option<RawFd>;
+
+    /// Returns a `futures` stream of the events of the window.
+    ///
+    /// The task that polls the stream is woken up when the X server sends events and when a
+    /// termination is requested, without blocking the thread in `wait_events`.
+    ///
+    /// Returns `NotSupported` if the window doesn't use xlib (if it uses wayland for example).
+    #[cfg(feature = "futures")]
+    fn event_stream(&self) -> Result<EventStream, CreationError>;
+
+    /// Returns the atom with the given name on the connection to the X server, creating it if
+    /// it doesn't exist.
+    ///
+    /// Atoms are cached, so only the first call for a given name makes a round trip to the
+    /// server.
+    ///
+    /// Returns `None` if the window doesn't use xlib (if it uses wayland for example).
+    fn get_atom(&self, name: &str) -> Option<libc::c_ulong>;
+
+    /// Creates a child window inside this window, at the given position and size in pixels.
+    ///
+    /// The OpenGL context of this window can render to the child view, which allows having
+    /// several independent viewports without creating separate top-level windows.
+    ///
+    /// Returns `NotSupported` if the window doesn't use xlib or if its context doesn't use GLX.
+    fn create_child_view(&self, x: i32, y: i32, width: u32, height: u32)
+                         -> Result<ChildView, CreationError>;
 }
 
 impl WindowExt for Window {
@@ -38,12 +110,727 @@ impl WindowExt for Window {
             _ => None
         }
     }
+
+    #[inline]
+    fn get_xlib_screen_id(&self) -> Option<libc::c_int> {
+        match self.window {
+            LinuxWindow::X(ref w) => Some(w.get_xlib_screen_id()),
+            _ => None
+        }
+    }
+
+    #[inline]
+    fn get_xlib_fd(&self) -> Option<RawFd> {
+        match self.window {
+            LinuxWindow::X(ref w) => Some(w.get_xlib_fd()),
+            _ => None
+        }
+    }
+
+    #[cfg(feature = "futures")]
+    fn event_stream(&self) -> Result<EventStream, CreationError> {
+        match self.window {
+            LinuxWindow::X(ref w) => EventStream::new(self, w.get_xlib_fd()),
+            _ => Err(CreationError::NotSupported)
+        }
+    }
+
+    #[inline]
+    fn get_atom(&self, name: &str) -> Option<libc::c_ulong> {
+        match self.window {
+            LinuxWindow::X(ref w) => Some(w.get_atom(name)),
+            _ => None
+        }
+    }
+
+    #[inline]
+    fn create_child_view(&self, x: i32, y: i32, width: u32, height: u32)
+                         -> Result<ChildView, CreationError>
+    {
+        match self.window {
+            LinuxWindow::X(ref w) => {
+                x11::ChildView::new(w, x, y, width, height).map(|view| ChildView { view: view })
+            },
+            _ => Err(CreationError::NotSupported)
+        }
+    }
+}
+
+/// A child window that the OpenGL context of its parent can render to.
+///
+/// The context of the parent must be made current on the view with `make_current` before
+/// rendering to it. The view is destroyed when this object is dropped.
+pub struct ChildView {
+    view: x11::ChildView,
+}
+
+impl ChildView {
+    /// Makes the OpenGL context of the parent window current, and renders to this view.
+    #[inline]
+    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
+        self.view.make_current()
+    }
+
+    /// Swaps the buffers of the view.
+    #[inline]
+    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        self.view.swap_buffers()
+    }
+
+    /// Changes the number of screen refreshes that `swap_buffers` waits for, independently of
+    /// the parent window. The context must be current on the view.
+    ///
+    /// See `Window::set_swap_interval` for more infos.
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        self.view.set_swap_interval(interval)
+    }
+
+    /// Moves and resizes the view, in pixels relative to the top-left corner of the parent.
+    #[inline]
+    pub fn set_rect(&self, x: i32, y: i32, width: u32, height: u32) {
+        self.view.set_rect(x, y, width, height)
+    }
+
+    /// Returns a pointer to the `Window` object of xlib that is used by this view.
+    #[inline]
+    pub fn get_xlib_window(&self) -> *mut libc::c_void {
+        self.view.get_xlib_window()
+    }
+}
+
+/// A stream of the events of a window, created by `WindowExt::event_stream`.
+///
+/// A background thread waits for the connection to the X server and for termination requests,
+/// and wakes up the task that polls the stream. The stream never ends, stop polling it once you
+/// have received `Closed`.
+///
+/// Xlib reads the events of all the windows of the connection at once, so if several windows
+/// share it, all of them must be polled when one of them is woken up.
+#[cfg(feature = "futures")]
+pub struct EventStream<'a> {
+    window: &'a Window,
+    shared: Arc<StreamShared>,
+    thread: Option<thread::JoinHandle<()>>,
+    /// The read and write ends of the pipe that stops the thread
+    stop: [RawFd; 2],
+}
+
+#[cfg(feature = "futures")]
+struct StreamShared {
+    /// The task to wake up, or `None` if the stream has already woken it up
+    task: Mutex<Option<Task>>,
+    /// Notified when a task is registered or when the stream is dropped
+    registered: Condvar,
+    stopped: AtomicBool,
+}
+
+#[cfg(feature = "futures")]
+impl<'a> EventStream<'a> {
+    fn new(window: &'a Window, x_fd: RawFd) -> Result<EventStream<'a>, CreationError> {
+        let mut stop = [0; 2];
+        if unsafe { libc::pipe(stop.as_mut_ptr()) } != 0 {
+            return Err(CreationError::OsError("pipe failed".to_owned()));
+        }
+
+        let shared = Arc::new(StreamShared {
+            task: Mutex::new(None),
+            registered: Condvar::new(),
+            stopped: AtomicBool::new(false),
+        });
+
+        let thread_shared = shared.clone();
+        let stop_fd = stop[0];
+        let thread = thread::spawn(move || watch(&thread_shared, x_fd, stop_fd));
+
+        Ok(EventStream {
+            window: window,
+            shared: shared,
+            thread: Some(thread),
+            stop: stop,
+        })
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<'a> Stream for EventStream<'a> {
+    type Item = Event;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Event>, ()> {
+        // registered before polling, so that the events that arrive afterwards wake the task up
+        *self.shared.task.lock().unwrap() = Some(task::current());
+        self.shared.registered.notify_one();
+
+        match self.window.poll_events().next() {
+            Some(event) => Ok(Async::Ready(Some(event))),
+            None => Ok(Async::NotReady),
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<'a> Drop for EventStream<'a> {
+    fn drop(&mut self) {
+        {
+            let _task = self.shared.task.lock().unwrap();
+            self.shared.stopped.store(true, Ordering::SeqCst);
+            self.shared.registered.notify_one();
+        }
+
+        let byte = 1u8;
+        unsafe { libc::write(self.stop[1], &byte as *const u8 as *const _, 1) };
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+
+        unsafe {
+            libc::close(self.stop[0]);
+            libc::close(self.stop[1]);
+        }
+    }
+}
+
+/// The loop of the thread of an `EventStream`, which wakes the task up once the connection is
+/// readable or a termination is requested.
+#[cfg(feature = "futures")]
+fn watch(shared: &StreamShared, x_fd: RawFd, stop_fd: RawFd) {
+    loop {
+        {
+            let mut task = shared.task.lock().unwrap();
+            while task.is_none() && !shared.stopped.load(Ordering::SeqCst) {
+                task = shared.registered.wait(task).unwrap();
+            }
+        }
+        if shared.stopped.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut fds = [
+            libc::pollfd { fd: x_fd, events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: stop_fd, events: libc::POLLIN, revents: 0 },
+            // negative descriptors are ignored
+            libc::pollfd {
+                fd: termination::wakeup_fd().unwrap_or(-1),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        if unsafe { libc::poll(fds.as_mut_ptr(), 3, -1) } <= 0 {
+            continue;
+        }
+
+        if fds[1].revents != 0 {
+            return;
+        }
+        if fds[2].revents & libc::POLLIN != 0 {
+            termination::drain();
+        }
+
+        // the connection stays readable until the task polls the stream, so the thread waits
+        // for the next registration instead of polling again
+        if let Some(task) = shared.task.lock().unwrap().take() {
+            task.notify();
+        }
+    }
+}
+
+/// The display server used by glutin.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DisplayServer {
+    X11,
+    Wayland,
+    /// No display server could be reached.
+    None,
+}
+
+/// Returns the display server used by all the windows of this process.
+///
+/// The connection is opened the first time this function or `Window::new` is called.
+pub fn get_display_server() -> DisplayServer {
+    let context = PlatformContext::get();
+
+    if context.x_connection().is_some() {
+        DisplayServer::X11
+    } else if context.is_wayland() {
+        DisplayServer::Wayland
+    } else {
+        DisplayServer::None
+    }
+}
+
+/// Returns a pointer to the `Display` object of xlib that is shared by all the windows of this
+/// process.
+///
+/// Returns `None` if glutin doesn't use xlib. The pointer stays valid until the end of the
+/// process, unless an `Application` is dropped while no window uses the connection anymore.
+pub fn get_shared_xlib_display() -> Option<*mut libc::c_void> {
+    PlatformContext::get().x_connection().map(|x| x.display as *mut libc::c_void)
+}
+
+/// What the running window manager supports.
+#[derive(Debug, Clone)]
+pub struct WmCapabilities {
+    /// The name of the window manager, if it provides one.
+    pub name: Option<String>,
+    /// Windows can be made fullscreen through the window manager. Otherwise glutin falls back
+    /// to covering the screen with an override-redirect window.
+    pub fullscreen: bool,
+    /// Windows can be maximized.
+    pub maximize: bool,
+    /// The window manager reports the size of the decorations of the windows.
+    pub frame_extents: bool,
+    /// Windows can ask to be activated.
+    pub activate: bool,
+    /// The names of all the hints listed in `_NET_SUPPORTED`.
+    pub supported: Vec<String>,
+}
+
+/// Returns the capabilities of the window manager running on the default screen.
+///
+/// Returns `None` if glutin doesn't use xlib or if no EWMH-compliant window manager is running.
+/// The window manager is queried each time this function is called.
+pub fn get_wm_capabilities() -> Option<WmCapabilities> {
+    let context = PlatformContext::get();
+    let x = match context.x_connection() {
+        Some(x) => x,
+        None => return None,
+    };
+
+    let screen_id = unsafe { (x.xlib.XDefaultScreen)(x.display) };
+    x11::WmInfo::query(x, screen_id).map(|wm| {
+        WmCapabilities {
+            name: wm.name.clone(),
+            fullscreen: wm.supports(x.get_atom("_NET_WM_STATE_FULLSCREEN")),
+            maximize: wm.supports(x.get_atom("_NET_WM_STATE_MAXIMIZED_HORZ")) &&
+                      wm.supports(x.get_atom("_NET_WM_STATE_MAXIMIZED_VERT")),
+            frame_extents: wm.supports(x.get_atom("_NET_FRAME_EXTENTS")),
+            activate: wm.supports(x.get_atom("_NET_ACTIVE_WINDOW")),
+            supported: wm.supported.iter().map(|&atom| x.get_atom_name(atom)).collect(),
+        }
+    })
+}
+
+/// Returns true if a window manager is running on the default screen.
+///
+/// Without a window manager, glutin positions, raises and focuses its windows by itself, and
+/// reports a `Closed` event when a window is destroyed by another client. Always returns true
+/// if glutin doesn't use xlib.
+pub fn is_wm_present() -> bool {
+    match PlatformContext::get().x_connection() {
+        Some(x) => {
+            let screen_id = unsafe { (x.xlib.XDefaultScreen)(x.display) };
+            x.is_wm_present(screen_id)
+        },
+        None => true,
+    }
 }
 
 /// Additional methods on `WindowBuilder` that are specific to Unix.
-pub trait WindowBuilderExt {
+pub trait WindowBuilderExt<'a> {
+    /// Creates the window on the given X screen instead of the default one.
+    ///
+    /// This is for setups with several independent X screens (`:0.0`, `:0.1`...), where each
+    /// screen has its own root window and visuals. The screens are the monitors returned by
+    /// `get_available_monitors`. A fullscreen monitor passed to `with_fullscreen` takes
+    /// precedence.
+    ///
+    /// Window creation fails if the screen doesn't exist. Ignored with Wayland.
+    fn with_x11_screen(self, screen_id: libc::c_int) -> WindowBuilder<'a>;
+
+    /// Applies the standard options of X clients that have been parsed from the command line.
+    ///
+    /// The dimensions of the geometry replace the ones of `with_dimensions`, and the position
+    /// is passed to the window manager as a position requested by the user. The display is
+    /// not applied, see `X11Options::apply_display`. Ignored with Wayland.
+    fn with_x11_options(self, options: &X11Options) -> WindowBuilder<'a>;
+
+    /// Applies the defaults of the application from the X resource database: `geometry`,
+    /// `iconic`, `vsync` and `decorations`.
+    ///
+    /// The resource name of the window becomes `app_name`, so that the window managers match
+    /// it with the same rules. The options of the command line take precedence over the
+    /// resources, so `with_x11_options` must be called after this method.
+    fn with_x11_resources(self, resources: &X11Resources, app_name: &str) -> WindowBuilder<'a>;
+}
+
+impl<'a> WindowBuilderExt<'a> for WindowBuilder<'a> {
+    #[inline]
+    fn with_x11_screen(mut self, screen_id: libc::c_int) -> WindowBuilder<'a> {
+        self.platform_specific.x11_screen = Some(screen_id);
+        self
+    }
+
+    #[inline]
+    fn with_x11_options(mut self, options: &X11Options) -> WindowBuilder<'a> {
+        if let Some(geometry) = options.geometry {
+            apply_geometry_dimensions(&mut self.window.dimensions, &geometry);
+            self.platform_specific.x11_geometry = Some(geometry);
+        }
+        if options.iconic {
+            self.platform_specific.x11_iconic = true;
+        }
+        if options.resource_name.is_some() {
+            self.platform_specific.x11_resource_name = options.resource_name.clone();
+        }
+        self
+    }
+
+    fn with_x11_resources(mut self, resources: &X11Resources, app_name: &str)
+                          -> WindowBuilder<'a>
+    {
+        let geometry = resources.get(app_name, "geometry").and_then(|g| X11Geometry::parse(&g));
+        if let Some(geometry) = geometry {
+            apply_geometry_dimensions(&mut self.window.dimensions, &geometry);
+            self.platform_specific.x11_geometry = Some(geometry);
+        }
+
+        if let Some(iconic) = resources.get_bool(app_name, "iconic") {
+            self.platform_specific.x11_iconic = iconic;
+        }
+        if let Some(vsync) = resources.get_bool(app_name, "vsync") {
+            self.opengl.vsync = vsync;
+        }
+        if let Some(decorations) = resources.get_bool(app_name, "decorations") {
+            self.window.decorations = decorations;
+        }
+
+        self.platform_specific.x11_resource_name = Some(app_name.to_owned());
+        self
+    }
+}
+
+/// Replaces the dimensions requested by the program with the ones of a geometry. A geometry
+/// may only give the width or the height, in which case the other one is kept.
+fn apply_geometry_dimensions(dimensions: &mut Option<(u32, u32)>, geometry: &X11Geometry) {
+    if geometry.width.is_none() && geometry.height.is_none() {
+        return;
+    }
+
+    // the size of the windows of the X11 backend when the program doesn't request one
+    let (width, height) = dimensions.unwrap_or((800, 600));
+    *dimensions = Some((geometry.width.unwrap_or(width), geometry.height.unwrap_or(height)));
+}
+
+/// The geometry of a window, as given to the `-geometry` option of X clients.
+///
+/// The format is `[=][<width>][{xX}<height>][{+-}<xoffset>{+-}<yoffset>]`, as parsed by
+/// `XParseGeometry`. The offsets may have a sign of their own, so that `+-10+5` places the
+/// window 10 pixels to the left of the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct X11Geometry {
+    /// The width of the window in pixels.
+    pub width: Option<u32>,
+    /// The height of the window in pixels.
+    pub height: Option<u32>,
+    /// The offsets of the window, in pixels from the edges of the screen.
+    pub offsets: Option<(i32, i32)>,
+    /// True if the horizontal offset is from the right edge of the screen (`-` sign).
+    pub from_right: bool,
+    /// True if the vertical offset is from the bottom edge of the screen (`-` sign).
+    pub from_bottom: bool,
+}
+
+impl X11Geometry {
+    /// Parses a geometry string. Returns `None` if it is malformed.
+    pub fn parse(geometry: &str) -> Option<X11Geometry> {
+        let mut rest = if geometry.starts_with('=') { &geometry[1..] } else { geometry };
+
+        let width = take_number(&mut rest);
+        let height = if rest.starts_with('x') || rest.starts_with('X') {
+            rest = &rest[1..];
+            match take_number(&mut rest) {
+                Some(height) => Some(height),
+                None => return None,
+            }
+        } else {
+            None
+        };
+
+        // the sign before an offset tells from which edge it is, so that `-0` can be told apart
+        // from `+0`, and the offset itself may be negative
+        let (offsets, from_right, from_bottom) = if rest.is_empty() {
+            (None, false, false)
+        } else {
+            match (take_offset(&mut rest), take_offset(&mut rest)) {
+                (Some((x_offset, from_right)), Some((y_offset, from_bottom))) => {
+                    (Some((x_offset, y_offset)), from_right, from_bottom)
+                },
+                _ => return None,
+            }
+        };
 
+        if !rest.is_empty() || (width.is_none() && height.is_none() && offsets.is_none()) {
+            return None;
+        }
+
+        Some(X11Geometry {
+            width: width,
+            height: height,
+            offsets: offsets,
+            from_right: from_right,
+            from_bottom: from_bottom,
+        })
+    }
+}
+
+/// Parses the unsigned number at the start of the string, and skips it.
+fn take_number(rest: &mut &str) -> Option<u32> {
+    let end = rest.find(|c: char| !c.is_digit(10)).unwrap_or(rest.len());
+    let number = rest[..end].parse().ok();
+    *rest = &rest[end..];
+    number
+}
+
+/// Parses an offset of a geometry at the start of the string, and skips it. Returns the offset
+/// and whether it is from the right or bottom edge.
+fn take_offset(rest: &mut &str) -> Option<(i32, bool)> {
+    let from_end = if rest.starts_with('-') {
+        true
+    } else if rest.starts_with('+') {
+        false
+    } else {
+        return None;
+    };
+    *rest = &rest[1..];
+
+    let negative = if rest.starts_with('-') || rest.starts_with('+') {
+        let negative = rest.starts_with('-');
+        *rest = &rest[1..];
+        negative
+    } else {
+        false
+    };
+
+    take_number(rest).map(|offset| {
+        let offset = offset as i32;
+        (if negative { -offset } else { offset }, from_end)
+    })
+}
+
+/// The standard options of the command line of X clients, which scripts and window manager
+/// configurations expect every client to understand.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct X11Options {
+    /// The X server to connect to, from `-display`.
+    pub display: Option<String>,
+    /// The geometry of the window, from `-geometry`.
+    pub geometry: Option<X11Geometry>,
+    /// True if the window must start minimized, from `-iconic`.
+    pub iconic: bool,
+    /// The resource name of the `WM_CLASS` property, which window managers use to match the
+    /// window with their rules. Taken from `-name`, then from the `RESOURCE_NAME` environment
+    /// variable, then from the name of the executable.
+    pub resource_name: Option<String>,
 }
 
-impl<'a> WindowBuilderExt for WindowBuilder<'a> {
+impl X11Options {
+    /// Extracts the options from the arguments of the program, and returns the arguments that
+    /// remain for the program itself. The first argument is the path of the executable, as with
+    /// `std::env::args()`, and is kept.
+    ///
+    /// A malformed geometry is ignored, like X clients do.
+    pub fn parse<I>(args: I) -> (X11Options, Vec<String>) where I: IntoIterator<Item = String> {
+        let mut options = X11Options::default();
+        let mut remaining = Vec::new();
+        let mut name = None;
+
+        let mut args = args.into_iter();
+        let program = args.next();
+
+        while let Some(arg) = args.next() {
+            match &*arg {
+                "-display" => options.display = args.next(),
+                "-geometry" => options.geometry = args.next().and_then(|g| X11Geometry::parse(&g)),
+                "-iconic" => options.iconic = true,
+                "-name" => name = args.next(),
+                _ => remaining.push(arg),
+            }
+        }
+
+        options.resource_name = name.or_else(|| env::var("RESOURCE_NAME").ok()).or_else(|| {
+            program.as_ref()
+                   .and_then(|p| Path::new(p).file_name())
+                   .map(|n| n.to_string_lossy().into_owned())
+        });
+
+        if let Some(program) = program {
+            remaining.insert(0, program);
+        }
+
+        (options, remaining)
+    }
+
+    /// Sets the `DISPLAY` environment variable to the display of the options, if there is one.
+    ///
+    /// The connection to the X server is shared by all the windows and is opened once, so this
+    /// must be called before the first window is created.
+    pub fn apply_display(&self) {
+        if let Some(ref display) = self.display {
+            env::set_var("DISPLAY", display);
+        }
+    }
+}
+
+/// The backend that renders headless contexts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadlessBackend {
+    /// Tries GLX, then the surfaceless platform of EGL, then OSMesa.
+    Auto,
+    /// A GLX pbuffer on the default screen of the X server.
+    Glx,
+    /// An EGL pbuffer on Mesa's surfaceless platform (`EGL_MESA_platform_surfaceless`). Works
+    /// without any display server, for example in containers and on build machines, and still
+    /// uses the GPU if the user can open a render node.
+    EglSurfaceless,
+    /// OSMesa, which renders on the CPU. Always works if libOSMesa is installed, but is slow.
+    OsMesa,
+}
+
+impl Default for HeadlessBackend {
+    #[inline]
+    fn default() -> HeadlessBackend {
+        HeadlessBackend::Auto
+    }
+}
+
+/// Additional methods on `HeadlessRendererBuilder` that are specific to Unix.
+pub trait HeadlessRendererBuilderExt<'a> {
+    /// Sets the backend that renders the context.
+    ///
+    /// Building fails if the backend isn't available, instead of falling back to another one. A context that shares its objects with another context always uses
+    /// the backend of the other context.
+    fn with_headless_backend(self, backend: HeadlessBackend) -> HeadlessRendererBuilder<'a>;
+}
+
+impl<'a> HeadlessRendererBuilderExt<'a> for HeadlessRendererBuilder<'a> {
+    #[inline]
+    fn with_headless_backend(mut self, backend: HeadlessBackend) -> HeadlessRendererBuilder<'a> {
+        self.platform_specific.backend = backend;
+        self
+    }
+}
+
+/// The defaults that the user has loaded in the X resource database with `xrdb`, which allows
+/// configuring deployed applications without rebuilding them.
+///
+/// For example, with `myapp.geometry: 1280x720+0+0` and `myapp.vsync: true` in `~/.Xresources`,
+/// `with_x11_resources(&resources, "myapp")` creates a window of 1280x720 pixels in the top left
+/// corner of the screen, with vsync.
+pub struct X11Resources {
+    database: x11::ResourceDatabase,
+}
+
+impl X11Resources {
+    /// Reads the database of the X server used by glutin.
+    ///
+    /// Returns `None` if glutin doesn't use xlib, or if the user hasn't loaded any resource.
+    pub fn load() -> Option<X11Resources> {
+        PlatformContext::get().x_connection()
+                              .and_then(|x| x11::ResourceDatabase::load(x))
+                              .map(|database| X11Resources { database: database })
+    }
+
+    /// Returns the value of a resource of the application, for example `geometry` for the
+    /// `myapp.geometry` entry.
+    ///
+    /// The class is the name with capitalized components, so that `Myapp.Geometry` and
+    /// `*Geometry` entries match as well.
+    pub fn get(&self, app_name: &str, resource: &str) -> Option<String> {
+        fn capitalize(component: &str) -> String {
+            let mut chars = component.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+
+        let name = format!("{}.{}", app_name, resource);
+        let class = format!("{}.{}", capitalize(app_name), capitalize(resource));
+        self.database.get(&name, &class)
+    }
+
+    /// Returns the value of a boolean resource. `true`, `on` and `yes` are true, and `false`,
+    /// `off` and `no` are false, whatever their case, like with the X toolkit. Returns `None`
+    /// if the resource is missing or isn't a boolean.
+    pub fn get_bool(&self, app_name: &str, resource: &str) -> Option<bool> {
+        self.get(app_name, resource).and_then(|value| {
+            match &*value.trim().to_lowercase() {
+                "true" | "on" | "yes" | "1" => Some(true),
+                "false" | "off" | "no" | "0" => Some(false),
+                _ => None,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{X11Geometry, X11Options};
+
+    fn geometry(width: Option<u32>, height: Option<u32>, offsets: Option<(i32, i32)>,
+                from_right: bool, from_bottom: bool) -> X11Geometry
+    {
+        X11Geometry {
+            width: width,
+            height: height,
+            offsets: offsets,
+            from_right: from_right,
+            from_bottom: from_bottom,
+        }
+    }
+
+    #[test]
+    fn geometry_with_dimensions_and_offsets() {
+        assert_eq!(X11Geometry::parse("=640x480+10+20"),
+                   Some(geometry(Some(640), Some(480), Some((10, 20)), false, false)));
+        assert_eq!(X11Geometry::parse("640X480-0-5"),
+                   Some(geometry(Some(640), Some(480), Some((0, 5)), true, true)));
+    }
+
+    #[test]
+    fn geometry_with_a_single_dimension() {
+        assert_eq!(X11Geometry::parse("=100"), Some(geometry(Some(100), None, None, false, false)));
+        assert_eq!(X11Geometry::parse("x200"), Some(geometry(None, Some(200), None, false, false)));
+    }
+
+    #[test]
+    fn geometry_with_negative_offsets() {
+        assert_eq!(X11Geometry::parse("+-10+5"),
+                   Some(geometry(None, None, Some((-10, 5)), false, false)));
+        assert_eq!(X11Geometry::parse("100x100--10+-5"),
+                   Some(geometry(Some(100), Some(100), Some((-10, -5)), true, false)));
+    }
+
+    #[test]
+    fn malformed_geometry() {
+        assert_eq!(X11Geometry::parse(""), None);
+        assert_eq!(X11Geometry::parse("100x"), None);
+        assert_eq!(X11Geometry::parse("+10"), None);
+        assert_eq!(X11Geometry::parse("100x100+10+20+30"), None);
+        assert_eq!(X11Geometry::parse("big"), None);
+    }
+
+    #[test]
+    fn options_are_stripped_from_the_arguments() {
+        let args = ["/usr/bin/game", "-display", ":1", "--level", "3", "-geometry", "800x600+0+0",
+                    "-iconic", "-name", "mygame", "save.dat"];
+        let (options, remaining) = X11Options::parse(args.iter().map(|&a| a.to_owned()));
+
+        assert_eq!(options.display, Some(":1".to_owned()));
+        assert_eq!(options.geometry,
+                   Some(geometry(Some(800), Some(600), Some((0, 0)), false, false)));
+        assert!(options.iconic);
+        assert_eq!(options.resource_name, Some("mygame".to_owned()));
+        assert_eq!(remaining, vec!["/usr/bin/game", "--level", "3", "save.dat"]);
+    }
+
+    #[test]
+    fn malformed_geometry_option_is_ignored() {
+        let args = ["game", "-geometry", "huge", "-name", "game"];
+        let (options, remaining) = X11Options::parse(args.iter().map(|&a| a.to_owned()));
+
+        assert_eq!(options.geometry, None);
+        assert_eq!(remaining, vec!["game"]);
+    }
 }
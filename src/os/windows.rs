@@ -12,6 +12,19 @@ pub trait WindowExt {
     ///
     /// The pointer will become invalid when the glutin `Window` is destroyed.
     fn get_hwnd(&self) -> *mut libc::c_void;
+
+    /// Shows an image instead of the content of the window in the taskbar thumbnail and in
+    /// the alt-tab live preview. Pass `None` to go back to the default behavior.
+    ///
+    /// The image is given as `(width, height, pixels)`, where `pixels` is in the RGBA format.
+    /// It is scaled down to the size requested by the system.
+    ///
+    /// Returns an error if `pixels` is too short for the dimensions, or if the dimensions
+    /// overflow.
+    ///
+    /// This is useful for OpenGL windows, whose preview is often black.
+    fn set_taskbar_thumbnail(&self, thumbnail: Option<(u32, u32, Vec<u8>)>)
+                             -> Result<(), String>;
 }
 
 impl WindowExt for Window {
@@ -19,12 +32,52 @@ impl WindowExt for Window {
     fn get_hwnd(&self) -> *mut libc::c_void {
         self.window.platform_window()
     }
+
+    #[inline]
+    fn set_taskbar_thumbnail(&self, thumbnail: Option<(u32, u32, Vec<u8>)>)
+                             -> Result<(), String>
+    {
+        self.window.set_taskbar_thumbnail(thumbnail)
+    }
 }
 
-/// Additional methods on `WindowBuilder` that are specific to Windows.
-pub trait WindowBuilderExt {
+/// What to do with the console of the process when a window is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsolePolicy {
+    /// Leaves the console as it is.
+    Unchanged,
+    /// Detaches the process from its console with `FreeConsole`. This closes the console window
+    /// that is opened for programs of the console subsystem when they are started from the
+    /// explorer, but not the command prompt that the program was started from.
+    ///
+    /// The console is opened before the program starts, so it still shows up briefly. Only
+    /// `#![windows_subsystem = "windows"]` avoids it completely.
+    Detach,
+    /// Attaches the process to the console of its parent, or opens a new console if the parent
+    /// doesn't have one. This makes the standard output of programs of the GUI subsystem
+    /// visible, for debug logging.
+    Attach,
+}
 
+impl Default for ConsolePolicy {
+    #[inline]
+    fn default() -> ConsolePolicy {
+        ConsolePolicy::Unchanged
+    }
 }
 
-impl<'a> WindowBuilderExt for WindowBuilder<'a> {
+/// Additional methods on `WindowBuilder` that are specific to Windows.
+pub trait WindowBuilderExt<'a> {
+    /// Sets what to do with the console of the process when the window is created.
+    ///
+    /// The console belongs to the whole process, so this affects every window.
+    fn with_console_policy(mut self, policy: ConsolePolicy) -> WindowBuilder<'a>;
+}
+
+impl<'a> WindowBuilderExt<'a> for WindowBuilder<'a> {
+    #[inline]
+    fn with_console_policy(mut self, policy: ConsolePolicy) -> WindowBuilder<'a> {
+        self.platform_specific.console_policy = policy;
+        self
+    }
 }
@@ -2,7 +2,10 @@
 pub use api::x11::{WaitEventsIterator, PollEventsIterator};*/
 
 use std::collections::VecDeque;
-use std::sync::Arc;
+use std::env;
+use std::ffi::CStr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 
 use ContextError;
 use CreationError;
@@ -10,20 +13,33 @@ use CursorState;
 use Event;
 use GlAttributes;
 use GlContext;
+use LockKeys;
+use PointerAcceleration;
 use MouseCursor;
 use PixelFormat;
 use PixelFormatRequirements;
+use SnapPosition;
 use WindowAttributes;
+use WindowID;
 use libc;
 
 use api::wayland;
 use api::x11;
 use api::x11::XConnection;
-use api::x11::XError;
 use api::x11::XNotSupported;
+use os::unix::X11Geometry;
 
 #[derive(Clone, Default)]
-pub struct PlatformSpecificWindowBuilderAttributes;
+pub struct PlatformSpecificWindowBuilderAttributes {
+    /// The X screen to create the window on, if not the default one
+    pub x11_screen: Option<i32>,
+    /// The geometry given with `-geometry`
+    pub x11_geometry: Option<X11Geometry>,
+    /// True if the window starts minimized, from `-iconic`
+    pub x11_iconic: bool,
+    /// The resource name of `WM_CLASS`, instead of the title
+    pub x11_resource_name: Option<String>,
+}
 
 enum Backend {
     X(Arc<XConnection>),
@@ -31,19 +47,99 @@ enum Backend {
     Error(XNotSupported),
 }
 
+/// State shared by all the windows of the process.
+///
+/// It is created the first time it is needed, and owns the connection to the display server
+/// along with everything that is tied to it (see `XConnection`). It lives until the end of the
+/// process, unless an `Application` releases it when it is dropped.
+pub struct PlatformContext {
+    backend: Backend,
+}
+
+/// The context of the process, and its connection to the X server.
+struct GlobalContext {
+    context: Option<Arc<PlatformContext>>,
+    /// The connection stays alive as long as one of its windows does, even once the context has
+    /// been released, in which case it is reused by the next context.
+    x_connection: Weak<XConnection>,
+}
+
 lazy_static!(
-    static ref BACKEND: Backend = {
-        // Wayland backend is not production-ready yet so we disable it
-        if false && wayland::is_available() {
+    static ref PLATFORM_CONTEXT: Mutex<GlobalContext> = Mutex::new(GlobalContext {
+        context: None,
+        x_connection: Weak::new(),
+    });
+);
+
+impl PlatformContext {
+    /// Returns the context of the process, creating it if necessary.
+    pub fn get() -> Arc<PlatformContext> {
+        let mut global = PLATFORM_CONTEXT.lock().unwrap();
+        if let Some(ref context) = global.context {
+            return context.clone();
+        }
+
+        let context = Arc::new(PlatformContext::new(global.x_connection.upgrade()));
+        if let Some(x) = context.x_connection() {
+            global.x_connection = Arc::downgrade(x);
+        }
+        global.context = Some(context.clone());
+        context
+    }
+
+    /// Stops keeping the context alive, so that the connection to the display server is closed
+    /// once its last user is dropped. The next call to `get` creates a new context.
+    pub fn release() {
+        PLATFORM_CONTEXT.lock().unwrap().context = None;
+    }
+
+    /// Creates the context, reusing the connection to the X server of the previous one if its
+    /// windows still use it.
+    fn new(x_connection: Option<Arc<XConnection>>) -> PlatformContext {
+        if let Some(x) = x_connection {
+            return PlatformContext {
+                backend: Backend::X(x),
+            };
+        }
+
+        // the Wayland backend is used when running inside of a Wayland session, unless the
+        // user explicitly asks for X11 (which then goes through XWayland)
+        let force_x11 = env::var("GLUTIN_BACKEND").map(|b| b == "x11").unwrap_or(false);
+
+        let backend = if !force_x11 && env::var_os("WAYLAND_DISPLAY").is_some() &&
+                         wayland::is_available()
+        {
             Backend::Wayland
         } else {
-            match XConnection::new(Some(x_error_callback)) {
+            match XConnection::new() {
                 Ok(x) => Backend::X(Arc::new(x)),
                 Err(e) => Backend::Error(e),
             }
+        };
+
+        PlatformContext {
+            backend: backend,
         }
-    };
-);
+    }
+
+    /// Returns the connection to the X server, if the X11 backend is used.
+    #[inline]
+    pub fn x_connection(&self) -> Option<&Arc<XConnection>> {
+        match self.backend {
+            Backend::X(ref x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the Wayland backend is used.
+    #[inline]
+    pub fn is_wayland(&self) -> bool {
+        match self.backend {
+            Backend::Wayland => true,
+            _ => false,
+        }
+    }
+}
 
 pub enum Window {
     #[doc(hidden)]
@@ -82,7 +178,7 @@ pub enum MonitorId {
 
 #[inline]
 pub fn get_available_monitors() -> VecDeque<MonitorId> {
-    match *BACKEND {
+    match PlatformContext::get().backend {
         Backend::Wayland => wayland::get_available_monitors()
                                 .into_iter()
                                 .map(MonitorId::Wayland)
@@ -97,7 +193,7 @@ pub fn get_available_monitors() -> VecDeque<MonitorId> {
 
 #[inline]
 pub fn get_primary_monitor() -> MonitorId {
-    match *BACKEND {
+    match PlatformContext::get().backend {
         Backend::Wayland => MonitorId::Wayland(wayland::get_primary_monitor()),
         Backend::X(ref connec) => MonitorId::X(x11::get_primary_monitor(connec)),
         Backend::Error(_) => MonitorId::None,
@@ -131,6 +227,42 @@ impl MonitorId {
             &MonitorId::None => (800, 600),     // FIXME:
         }
     }
+
+    #[inline]
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        match self {
+            &MonitorId::X(ref m) => m.get_workarea(),
+            &MonitorId::Wayland(ref m) => m.get_workarea(),
+            &MonitorId::None => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_edid_data(&self) -> Option<Vec<u8>> {
+        match self {
+            &MonitorId::X(ref m) => m.get_edid_data(),
+            &MonitorId::Wayland(ref m) => m.get_edid_data(),
+            &MonitorId::None => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        match self {
+            &MonitorId::X(ref m) => m.is_variable_refresh_capable(),
+            &MonitorId::Wayland(ref m) => m.is_variable_refresh_capable(),
+            &MonitorId::None => false,
+        }
+    }
+
+    #[inline]
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        match self {
+            &MonitorId::X(ref m) => m.get_icc_profile(),
+            &MonitorId::Wayland(ref m) => m.get_icc_profile(),
+            &MonitorId::None => None,
+        }
+    }
 }
 
 
@@ -175,10 +307,11 @@ impl<'a> Iterator for WaitEventsIterator<'a> {
 impl Window {
     #[inline]
     pub fn new(window: &WindowAttributes, pf_reqs: &PixelFormatRequirements,
-               opengl: &GlAttributes<&Window>, _: &PlatformSpecificWindowBuilderAttributes)
+               opengl: &GlAttributes<&Window>,
+               platform_specific: &PlatformSpecificWindowBuilderAttributes)
                -> Result<Window, CreationError>
     {
-        match *BACKEND {
+        match PlatformContext::get().backend {
             Backend::Wayland => {
                 let opengl = opengl.clone().map_sharing(|w| match w {
                     &Window::Wayland(ref w) => w,
@@ -194,13 +327,49 @@ impl Window {
                     _ => panic!()       // TODO: return an error
                 });
 
-                x11::Window::new(connec, window, pf_reqs, &opengl).map(Window::X)
+                x11::Window::new(connec, window, pf_reqs, &opengl, platform_specific)
+                    .map(Window::X)
             },
 
             Backend::Error(ref error) => Err(CreationError::NoBackendAvailable(Box::new(error.clone())))
         }
     }
 
+    /// Only X11 windows are supported. The display of the other library must be connected to
+    /// the same X server as glutin, which uses its own connection.
+    pub unsafe fn from_existing(native_display: *mut libc::c_void, native_window: *mut libc::c_void,
+                                window: &WindowAttributes, pf_reqs: &PixelFormatRequirements,
+                                opengl: &GlAttributes<&Window>,
+                                platform_specific: &PlatformSpecificWindowBuilderAttributes)
+                                -> Result<Window, CreationError>
+    {
+        match PlatformContext::get().backend {
+            Backend::X(ref connec) => {
+                if !native_display.is_null() {
+                    let theirs = CStr::from_ptr((connec.xlib.XDisplayString)(native_display as *mut _));
+                    let ours = CStr::from_ptr((connec.xlib.XDisplayString)(connec.display));
+                    if theirs != ours {
+                        return Err(CreationError::OsError(format!("The window belongs to the X \
+                                                                   display {:?}, but glutin is \
+                                                                   connected to {:?}",
+                                                                  theirs, ours)));
+                    }
+                }
+
+                let opengl = opengl.clone().map_sharing(|w| match w {
+                    &Window::X(ref w) => w,
+                    _ => panic!()       // TODO: return an error
+                });
+
+                x11::Window::from_existing(connec, native_window as x11::ffi::Window, window,
+                                           pf_reqs, &opengl, platform_specific)
+                    .map(Window::X)
+            },
+
+            _ => Err(CreationError::NotSupported),
+        }
+    }
+
     #[inline]
     pub fn set_title(&self, title: &str) {
         match self {
@@ -209,6 +378,14 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn get_title(&self) -> Option<String> {
+        match self {
+            &Window::X(ref w) => w.get_title(),
+            &Window::Wayland(ref w) => w.get_title()
+        }
+    }
+
     #[inline]
     pub fn show(&self) {
         match self {
@@ -225,6 +402,30 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn focus(&self) {
+        match self {
+            &Window::X(ref w) => w.focus(),
+            &Window::Wayland(ref w) => w.focus()
+        }
+    }
+
+    #[inline]
+    pub fn raise(&self) {
+        match self {
+            &Window::X(ref w) => w.raise(),
+            &Window::Wayland(ref w) => w.raise()
+        }
+    }
+
+    #[inline]
+    pub fn lower(&self) {
+        match self {
+            &Window::X(ref w) => w.lower(),
+            &Window::Wayland(ref w) => w.lower()
+        }
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<(i32, i32)> {
         match self {
@@ -241,6 +442,99 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn get_lock_keys(&self) -> LockKeys {
+        match self {
+            &Window::X(ref w) => w.get_lock_keys(),
+            &Window::Wayland(ref w) => w.get_lock_keys()
+        }
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        match self {
+            &Window::X(ref w) => w.make_not_current(),
+            &Window::Wayland(ref w) => w.make_not_current()
+        }
+    }
+
+    #[inline]
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        match self {
+            &Window::X(ref w) => w.get_pointer_acceleration(),
+            &Window::Wayland(ref w) => w.get_pointer_acceleration()
+        }
+    }
+
+    #[inline]
+    pub fn set_lock_keys(&self, lock_keys: LockKeys) -> Result<(), String> {
+        match self {
+            &Window::X(ref w) => w.set_lock_keys(lock_keys),
+            &Window::Wayland(ref w) => w.set_lock_keys(lock_keys)
+        }
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
+        match (self, monitor) {
+            (&Window::X(ref w), Some(MonitorId::X(m))) => w.set_fullscreen(Some(m)),
+            (&Window::X(ref w), None) => w.set_fullscreen(None),
+            (&Window::Wayland(ref w), Some(MonitorId::Wayland(m))) => w.set_fullscreen(Some(m)),
+            (&Window::Wayland(ref w), None) => w.set_fullscreen(None),
+            _ => ()
+        }
+    }
+
+    #[inline]
+    pub fn set_parent(&self, parent: Option<WindowID>) -> Result<(), String> {
+        match self {
+            &Window::X(ref w) => w.set_parent(parent.map(|p| p.window as x11::ffi::Window)),
+            &Window::Wayland(ref w) => w.set_parent(parent)
+        }
+    }
+
+    #[inline]
+    pub fn set_menu_bar(&self, menus: &[::Menu]) {
+        match self {
+            &Window::X(ref w) => w.set_menu_bar(menus),
+            // Wayland has no global menu, and glutin can't draw text on its surfaces
+            &Window::Wayland(_) => ()
+        }
+    }
+
+    #[inline]
+    pub fn get_menu_bar_height(&self) -> u32 {
+        match self {
+            &Window::X(ref w) => w.get_menu_bar_height(),
+            &Window::Wayland(_) => 0
+        }
+    }
+
+    #[inline]
+    pub fn set_maximized(&self, maximized: bool) {
+        match self {
+            &Window::X(ref w) => w.set_maximized(maximized),
+            &Window::Wayland(ref w) => w.set_maximized(maximized)
+        }
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) {
+        match self {
+            &Window::X(ref w) => w.set_minimized(minimized),
+            &Window::Wayland(ref w) => w.set_minimized(minimized)
+        }
+    }
+
+    #[inline]
+    pub fn snap_to(&self, position: SnapPosition, monitor: &MonitorId) {
+        match (self, monitor) {
+            (&Window::X(ref w), &MonitorId::X(ref m)) => w.snap_to(position, m),
+            (&Window::Wayland(ref w), &MonitorId::Wayland(ref m)) => w.snap_to(position, m),
+            _ => ()
+        }
+    }
+
     #[inline]
     pub fn get_inner_size(&self) -> Option<(u32, u32)> {
         match self {
@@ -289,6 +583,25 @@ impl Window {
         }
     }
 
+    /// Blocks until one of the windows may have events, or until the timeout expires.
+    pub fn wait_for_events(windows: &[&Window], timeout: Option<Duration>) {
+        // all the windows use the backend that was chosen for the process
+        let x: Vec<_> = windows.iter().filter_map(|w| match **w {
+            Window::X(ref w) => Some(w),
+            _ => None
+        }).collect();
+        let wayland: Vec<_> = windows.iter().filter_map(|w| match **w {
+            Window::Wayland(ref w) => Some(w),
+            _ => None
+        }).collect();
+
+        if !x.is_empty() {
+            x11::Window::wait_for_events(&x, timeout);
+        } else {
+            wayland::Window::wait_for_events(&wayland, timeout);
+        }
+    }
+
     #[inline]
     pub fn set_window_resize_callback(&mut self, callback: Option<fn(u32, u32)>) {
         match self {
@@ -313,6 +626,54 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_variable_refresh(&self, enabled: bool) {
+        match self {
+            &Window::X(ref w) => w.set_variable_refresh(enabled),
+            &Window::Wayland(ref w) => w.set_variable_refresh(enabled)
+        }
+    }
+
+    #[inline]
+    pub fn set_cursor_size(&self, size: u32) {
+        match self {
+            &Window::X(ref w) => w.set_cursor_size(size),
+            &Window::Wayland(ref w) => w.set_cursor_size(size)
+        }
+    }
+
+    #[inline]
+    pub fn set_clipboard_string(&self, text: &str) {
+        match self {
+            &Window::X(ref w) => w.set_clipboard_string(text),
+            &Window::Wayland(ref w) => w.set_clipboard_string(text)
+        }
+    }
+
+    #[inline]
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        match self {
+            &Window::X(ref w) => w.get_clipboard_string(),
+            &Window::Wayland(ref w) => w.get_clipboard_string()
+        }
+    }
+
+    #[inline]
+    pub fn set_min_size(&self, size: Option<(u32, u32)>) {
+        match self {
+            &Window::X(ref w) => w.set_min_size(size),
+            &Window::Wayland(ref w) => w.set_min_size(size)
+        }
+    }
+
+    #[inline]
+    pub fn set_max_size(&self, size: Option<(u32, u32)>) {
+        match self {
+            &Window::X(ref w) => w.set_max_size(size),
+            &Window::Wayland(ref w) => w.set_max_size(size)
+        }
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
        match self {
@@ -321,6 +682,30 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        match self {
+            &Window::X(ref w) => w.get_cursor_position(),
+            &Window::Wayland(ref w) => w.get_cursor_position()
+        }
+    }
+
+    #[inline]
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        match self {
+            &Window::X(ref w) => w.get_cursor_screen_position(),
+            &Window::Wayland(ref w) => w.get_cursor_screen_position()
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        match self {
+            &Window::X(ref w) => w.set_swap_interval(interval),
+            &Window::Wayland(ref w) => w.set_swap_interval(interval)
+        }
+    }
+
     #[inline]
     pub fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), ()> {
         match self {
@@ -395,26 +780,3 @@ impl GlContext for Window {
         }
     }
 }
-
-unsafe extern "C" fn x_error_callback(dpy: *mut x11::ffi::Display, event: *mut x11::ffi::XErrorEvent)
-                                      -> libc::c_int
-{
-    use std::ffi::CStr;
-
-    if let Backend::X(ref x) = *BACKEND {
-        let mut buff: Vec<u8> = Vec::with_capacity(1024);
-        (x.xlib.XGetErrorText)(dpy, (*event).error_code as i32, buff.as_mut_ptr() as *mut libc::c_char, buff.capacity() as i32);
-        let description = CStr::from_ptr(buff.as_mut_ptr() as *const libc::c_char).to_string_lossy();
-
-        let error = XError {
-            description: description.into_owned(),
-            error_code: (*event).error_code,
-            request_code: (*event).request_code,
-            minor_code: (*event).minor_code,
-        };
-
-        *x.latest_error.lock().unwrap() = Some(error);
-    }
-
-    0
-}
@@ -8,64 +8,230 @@ use GlContext;
 use PixelFormat;
 use PixelFormatRequirements;
 
+use std::ffi::CString;
+use std::sync::Arc;
+
+use api::dlopen;
+use api::egl::{self, Context as EglContext};
+use api::egl::ffi::egl::Egl;
+use api::glx::Context as GlxContext;
 use api::osmesa::{self, OsMesaContext};
+use api::x11::XConnection;
+use os::unix::HeadlessBackend;
 
 pub use self::api_dispatch::{Window, WindowProxy, MonitorId, get_available_monitors, get_primary_monitor};
 pub use self::api_dispatch::{WaitEventsIterator, PollEventsIterator};
 pub use self::api_dispatch::PlatformSpecificWindowBuilderAttributes;
+pub use self::api_dispatch::PlatformContext;
 mod api_dispatch;
 
 #[derive(Clone, Default)]
-pub struct PlatformSpecificHeadlessBuilderAttributes;
+pub struct PlatformSpecificHeadlessBuilderAttributes {
+    pub backend: HeadlessBackend,
+}
+
+struct EglWrapper(Egl);
+unsafe impl Sync for EglWrapper {}
 
-pub struct HeadlessContext(OsMesaContext);
+lazy_static! {
+    // libEGL, which is loaded separately from the connection to the X server since the
+    // surfaceless platform doesn't need any display server.
+    static ref EGL: Option<EglWrapper> = {
+        let mut libegl = unsafe { dlopen::dlopen(b"libEGL.so.1\0".as_ptr() as *const _, dlopen::RTLD_NOW) };
+        if libegl.is_null() {
+            libegl = unsafe { dlopen::dlopen(b"libEGL.so\0".as_ptr() as *const _, dlopen::RTLD_NOW) };
+        }
+
+        if libegl.is_null() {
+            None
+        } else {
+            Some(EglWrapper(Egl::load_with(|sym| {
+                let sym = CString::new(sym).unwrap();
+                unsafe { dlopen::dlsym(libegl, sym.as_ptr()) }
+            })))
+        }
+    };
+}
+
+/// An off-screen context, which renders to a GLX pbuffer when an X server is available, to an
+/// EGL pbuffer on Mesa's surfaceless platform, or with OSMesa.
+pub enum HeadlessContext {
+    Glx(Arc<XConnection>, GlxContext),
+    EglSurfaceless(EglContext),
+    OsMesa(OsMesaContext),
+}
 
 impl HeadlessContext {
     pub fn new(dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
                opengl: &GlAttributes<&HeadlessContext>,
-               _: &PlatformSpecificHeadlessBuilderAttributes)
+               pl_attribs: &PlatformSpecificHeadlessBuilderAttributes)
                -> Result<HeadlessContext, CreationError>
     {
-        let opengl = opengl.clone().map_sharing(|c| &c.0);
+        // a context can only share its objects with a context of the same backend
+        let backend = match opengl.sharing {
+            Some(&HeadlessContext::Glx(..)) => HeadlessBackend::Glx,
+            Some(&HeadlessContext::EglSurfaceless(_)) => HeadlessBackend::EglSurfaceless,
+            Some(&HeadlessContext::OsMesa(_)) => HeadlessBackend::OsMesa,
+            None => pl_attribs.backend,
+        };
 
-        match OsMesaContext::new(dimensions, pf_reqs, &opengl) {
-            Ok(c) => return Ok(HeadlessContext(c)),
-            Err(osmesa::OsMesaCreationError::NotSupported) => (),
-            Err(osmesa::OsMesaCreationError::CreationError(e)) => return Err(e),
+        if pl_attribs.backend != HeadlessBackend::Auto && pl_attribs.backend != backend {
+            return Err(CreationError::NotSupported);
+        }
+
+        match backend {
+            HeadlessBackend::Glx => return HeadlessContext::new_glx(dimensions, pf_reqs, opengl),
+            HeadlessBackend::EglSurfaceless => {
+                return HeadlessContext::new_egl_surfaceless(dimensions, pf_reqs, opengl);
+            },
+            HeadlessBackend::OsMesa => {
+                return HeadlessContext::new_osmesa(dimensions, pf_reqs, opengl);
+            },
+            HeadlessBackend::Auto => (),
+        }
+
+        // the backends are tried from the fastest to the slowest, and the error of the first
+        // one that is available is reported
+        let mut first_error = None;
+        let backends: [fn((u32, u32), &PixelFormatRequirements, &GlAttributes<&HeadlessContext>)
+                          -> Result<HeadlessContext, CreationError>; 3] =
+            [HeadlessContext::new_glx, HeadlessContext::new_egl_surfaceless,
+             HeadlessContext::new_osmesa];
+
+        for backend in backends.iter() {
+            match backend(dimensions, pf_reqs, opengl) {
+                Ok(context) => return Ok(context),
+                Err(CreationError::NotSupported) => (),
+                Err(err) => if first_error.is_none() { first_error = Some(err) },
+            }
+        }
+
+        Err(first_error.unwrap_or(CreationError::NotSupported))
+    }
+
+    /// Creates a pbuffer on the default screen of the X server.
+    fn new_glx(dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
+               opengl: &GlAttributes<&HeadlessContext>)
+               -> Result<HeadlessContext, CreationError>
+    {
+        let display = match PlatformContext::get().x_connection() {
+            Some(display) => display.clone(),
+            None => return Err(CreationError::NotSupported),
         };
 
-        Err(CreationError::NotSupported)
+        let glx = match display.glx {
+            Some(ref glx) => glx.clone(),
+            None => return Err(CreationError::NotSupported),
+        };
+
+        let opengl = opengl.clone().map_sharing(|c| match *c {
+            HeadlessContext::Glx(_, ref c) => c,
+            _ => unreachable!(),
+        });
+
+        let context = {
+            let screen_id = unsafe { (display.xlib.XDefaultScreen)(display.display) };
+            let prototype = try!(GlxContext::new_pbuffer(glx, &display.xlib, pf_reqs, &opengl,
+                                                         display.display, screen_id));
+            try!(prototype.finish_pbuffer(dimensions))
+        };
+
+        Ok(HeadlessContext::Glx(display, context))
+    }
+
+    /// Creates a pbuffer with Mesa's surfaceless platform, which works without any display
+    /// server.
+    fn new_egl_surfaceless(dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
+                           opengl: &GlAttributes<&HeadlessContext>)
+                           -> Result<HeadlessContext, CreationError>
+    {
+        let egl = match *EGL {
+            Some(ref egl) => egl.0.clone(),
+            None => return Err(CreationError::NotSupported),
+        };
+
+        // the EGL backend doesn't support sharing yet
+        if opengl.sharing.is_some() {
+            return Err(CreationError::NotSupported);
+        }
+
+        let opengl = opengl.clone().map_sharing(|_| unreachable!());
+        let context = try!(EglContext::new(egl, pf_reqs, &opengl, egl::NativeDisplay::Surfaceless)
+                               .and_then(|prototype| prototype.finish_pbuffer(dimensions)));
+
+        Ok(HeadlessContext::EglSurfaceless(context))
+    }
+
+    /// Renders on the CPU with OSMesa.
+    fn new_osmesa(dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
+                  opengl: &GlAttributes<&HeadlessContext>)
+                  -> Result<HeadlessContext, CreationError>
+    {
+        let opengl = opengl.clone().map_sharing(|c| match *c {
+            HeadlessContext::OsMesa(ref c) => c,
+            _ => unreachable!(),
+        });
+
+        match OsMesaContext::new(dimensions, pf_reqs, &opengl) {
+            Ok(c) => Ok(HeadlessContext::OsMesa(c)),
+            Err(osmesa::OsMesaCreationError::NotSupported) => Err(CreationError::NotSupported),
+            Err(osmesa::OsMesaCreationError::CreationError(e)) => Err(e),
+        }
     }
 }
 
 impl GlContext for HeadlessContext {
     #[inline]
     unsafe fn make_current(&self) -> Result<(), ContextError> {
-        self.0.make_current()
+        match *self {
+            HeadlessContext::Glx(_, ref c) => c.make_current(),
+            HeadlessContext::EglSurfaceless(ref c) => c.make_current(),
+            HeadlessContext::OsMesa(ref c) => c.make_current(),
+        }
     }
 
     #[inline]
     fn is_current(&self) -> bool {
-        self.0.is_current()
+        match *self {
+            HeadlessContext::Glx(_, ref c) => c.is_current(),
+            HeadlessContext::EglSurfaceless(ref c) => c.is_current(),
+            HeadlessContext::OsMesa(ref c) => c.is_current(),
+        }
     }
 
     #[inline]
     fn get_proc_address(&self, addr: &str) -> *const () {
-        self.0.get_proc_address(addr)
+        match *self {
+            HeadlessContext::Glx(_, ref c) => c.get_proc_address(addr),
+            HeadlessContext::EglSurfaceless(ref c) => c.get_proc_address(addr),
+            HeadlessContext::OsMesa(ref c) => c.get_proc_address(addr),
+        }
     }
 
     #[inline]
     fn swap_buffers(&self) -> Result<(), ContextError> {
-        self.0.swap_buffers()
+        match *self {
+            HeadlessContext::Glx(_, ref c) => c.swap_buffers(),
+            HeadlessContext::EglSurfaceless(ref c) => c.swap_buffers(),
+            HeadlessContext::OsMesa(ref c) => c.swap_buffers(),
+        }
     }
 
     #[inline]
     fn get_api(&self) -> Api {
-        self.0.get_api()
+        match *self {
+            HeadlessContext::Glx(_, ref c) => c.get_api(),
+            HeadlessContext::EglSurfaceless(ref c) => c.get_api(),
+            HeadlessContext::OsMesa(ref c) => c.get_api(),
+        }
     }
 
     #[inline]
     fn get_pixel_format(&self) -> PixelFormat {
-        self.0.get_pixel_format()
+        match *self {
+            HeadlessContext::Glx(_, ref c) => c.get_pixel_format(),
+            HeadlessContext::EglSurfaceless(ref c) => c.get_pixel_format(),
+            HeadlessContext::OsMesa(ref c) => c.get_pixel_format(),
+        }
     }
 }
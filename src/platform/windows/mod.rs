@@ -12,14 +12,21 @@ use PixelFormatRequirements;
 use GlAttributes;
 use GlContext;
 use WindowAttributes;
+use os::windows::ConsolePolicy;
 
 use api::egl::ffi::egl::Egl;
 use api::egl;
 use api::egl::Context as EglContext;
+use api::wgl::Context as WglContext;
 
 use std::ffi::CString;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 use kernel32;
+use winapi;
+
+/// Passed to `AttachConsole` to use the console of the parent process.
+const ATTACH_PARENT_PROCESS: winapi::DWORD = 0xFFFFFFFF;
 
 /// Stupid wrapper because `*const libc::c_void` doesn't implement `Sync`.
 struct EglWrapper(Egl);
@@ -52,7 +59,9 @@ lazy_static! {
 }
 
 #[derive(Clone, Default)]
-pub struct PlatformSpecificWindowBuilderAttributes;
+pub struct PlatformSpecificWindowBuilderAttributes {
+    pub console_policy: ConsolePolicy,
+}
 #[derive(Clone, Default)]
 pub struct PlatformSpecificHeadlessBuilderAttributes;
 
@@ -63,12 +72,32 @@ impl Window {
     /// See the docs in the crate root file.
     #[inline]
     pub fn new(window: &WindowAttributes, pf_reqs: &PixelFormatRequirements,
-               opengl: &GlAttributes<&Window>, _: &PlatformSpecificWindowBuilderAttributes)
+               opengl: &GlAttributes<&Window>, pl_attribs: &PlatformSpecificWindowBuilderAttributes)
                -> Result<Window, CreationError>
     {
+        apply_console_policy(pl_attribs.console_policy);
         win32::Window::new(window, pf_reqs, &opengl.clone().map_sharing(|w| &w.0),
                            EGL.as_ref().map(|w| &w.0)).map(|w| Window(w))
     }
+
+    /// Windows created by other libraries are not supported on this platform: the pixel format
+    /// of a window can only be set once, and glutin relies on its own window procedure to
+    /// receive the events.
+    #[inline]
+    pub unsafe fn from_existing(_: *mut ::libc::c_void, _: *mut ::libc::c_void, _: &WindowAttributes,
+                                _: &PixelFormatRequirements, _: &GlAttributes<&Window>,
+                                _: &PlatformSpecificWindowBuilderAttributes)
+                                -> Result<Window, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
+    /// See the docs of `win32::Window::wait_for_events`.
+    #[inline]
+    pub fn wait_for_events(windows: &[&Window], timeout: Option<Duration>) {
+        let windows: Vec<_> = windows.iter().map(|w| &w.0).collect();
+        win32::Window::wait_for_events(&windows, timeout)
+    }
 }
 
 impl Deref for Window {
@@ -87,8 +116,30 @@ impl DerefMut for Window {
     }
 }
 
+/// Attaches or detaches the console of the process.
+fn apply_console_policy(policy: ConsolePolicy) {
+    unsafe {
+        match policy {
+            ConsolePolicy::Unchanged => (),
+            ConsolePolicy::Detach => {
+                kernel32::FreeConsole();
+            },
+            ConsolePolicy::Attach => {
+                // attaching fails if the process already has a console
+                if kernel32::GetConsoleWindow().is_null() &&
+                   kernel32::AttachConsole(ATTACH_PARENT_PROCESS) == 0
+                {
+                    kernel32::AllocConsole();
+                }
+            },
+        }
+    }
+}
+
 ///
 pub enum HeadlessContext {
+    /// A WGL pbuffer.
+    WglPbuffer(WglContext),
     /// A regular window, but invisible.
     HiddenWindow(win32::Window),
     /// An EGL pbuffer.
@@ -101,10 +152,18 @@ impl HeadlessContext {
                _: &PlatformSpecificHeadlessBuilderAttributes)
                -> Result<HeadlessContext, CreationError>
     {
+        // only the WGL pbuffers can share their objects, with each other
+        let shared = match opengl.sharing {
+            Some(&HeadlessContext::WglPbuffer(_)) => true,
+            Some(_) => return Err(CreationError::NotSupported),
+            None => false,
+        };
+
         // if EGL is available, we try using EGL first
-        // if EGL returns an error, we try the hidden window method
-        if let &Some(ref egl) = &*EGL {
-            let context = EglContext::new(egl.0.clone(), pf_reqs, &opengl.clone().map_sharing(|_| unimplemented!()),       // TODO: 
+        // if EGL returns an error, we try a WGL pbuffer, and the hidden window method if the
+        // driver doesn't support pbuffers
+        if let (false, &Some(ref egl)) = (shared, &*EGL) {
+            let context = EglContext::new(egl.0.clone(), pf_reqs, &opengl.clone().map_sharing(|_| unreachable!()),
                                           egl::NativeDisplay::Other(None))
                                 .and_then(|prototype| prototype.finish_pbuffer(dimensions))
                                 .map(|ctxt| HeadlessContext::EglPbuffer(ctxt));
@@ -114,8 +173,18 @@ impl HeadlessContext {
             }
         }
 
+        let sharing = opengl.clone().map_sharing(|ctxt| match *ctxt {
+            HeadlessContext::WglPbuffer(ref ctxt) => ctxt.get_hglrc(),
+            _ => unreachable!(),
+        });
+        match unsafe { WglContext::new_pbuffer(pf_reqs, &sharing, dimensions) } {
+            // a hidden window can't share the objects of a pbuffer
+            Err(CreationError::NotSupported) if !shared => (),
+            context => return context.map(HeadlessContext::WglPbuffer),
+        }
+
         let window = try!(win32::Window::new(&WindowAttributes { visible: false, .. Default::default() },
-                                             pf_reqs, &opengl.clone().map_sharing(|_| unimplemented!()),            //TODO:
+                                             pf_reqs, &opengl.clone().map_sharing(|_| unreachable!()),
                                              EGL.as_ref().map(|w| &w.0)));
         Ok(HeadlessContext::HiddenWindow(window))
     }
@@ -125,6 +194,7 @@ impl GlContext for HeadlessContext {
     #[inline]
     unsafe fn make_current(&self) -> Result<(), ContextError> {
         match self {
+            &HeadlessContext::WglPbuffer(ref ctxt) => ctxt.make_current(),
             &HeadlessContext::HiddenWindow(ref ctxt) => ctxt.make_current(),
             &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.make_current(),
         }
@@ -133,6 +203,7 @@ impl GlContext for HeadlessContext {
     #[inline]
     fn is_current(&self) -> bool {
         match self {
+            &HeadlessContext::WglPbuffer(ref ctxt) => ctxt.is_current(),
             &HeadlessContext::HiddenWindow(ref ctxt) => ctxt.is_current(),
             &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.is_current(),
         }
@@ -141,6 +212,7 @@ impl GlContext for HeadlessContext {
     #[inline]
     fn get_proc_address(&self, addr: &str) -> *const () {
         match self {
+            &HeadlessContext::WglPbuffer(ref ctxt) => ctxt.get_proc_address(addr),
             &HeadlessContext::HiddenWindow(ref ctxt) => ctxt.get_proc_address(addr),
             &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_proc_address(addr),
         }
@@ -149,6 +221,7 @@ impl GlContext for HeadlessContext {
     #[inline]
     fn swap_buffers(&self) -> Result<(), ContextError> {
         match self {
+            &HeadlessContext::WglPbuffer(ref ctxt) => ctxt.swap_buffers(),
             &HeadlessContext::HiddenWindow(ref ctxt) => ctxt.swap_buffers(),
             &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.swap_buffers(),
         }
@@ -157,6 +230,7 @@ impl GlContext for HeadlessContext {
     #[inline]
     fn get_api(&self) -> Api {
         match self {
+            &HeadlessContext::WglPbuffer(ref ctxt) => ctxt.get_api(),
             &HeadlessContext::HiddenWindow(ref ctxt) => ctxt.get_api(),
             &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_api(),
         }
@@ -165,6 +239,7 @@ impl GlContext for HeadlessContext {
     #[inline]
     fn get_pixel_format(&self) -> PixelFormat {
         match self {
+            &HeadlessContext::WglPbuffer(ref ctxt) => ctxt.get_pixel_format(),
             &HeadlessContext::HiddenWindow(ref ctxt) => ctxt.get_pixel_format(),
             &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_pixel_format(),
         }
@@ -0,0 +1,141 @@
+//! Robustness that the context has actually obtained, and detection of the resets of the GPU.
+//!
+//! With the `Try*` variants of `Robustness`, the context is created even if the driver doesn't
+//! support the requested robustness, so the result is read back from the context itself.
+
+use std::mem;
+
+use ContextError;
+use Robustness;
+use gl_info::GlInfo;
+
+const NO_ERROR: u32 = 0;
+const CONTEXT_FLAGS: u32 = 0x821E;
+const CONTEXT_FLAG_ROBUST_ACCESS_BIT: i32 = 0x00000004;
+const CONTEXT_FLAG_NO_ERROR_BIT: i32 = 0x00000008;
+const CONTEXT_ROBUST_ACCESS: u32 = 0x90F3;
+const RESET_NOTIFICATION_STRATEGY: u32 = 0x8256;
+const LOSE_CONTEXT_ON_RESET: i32 = 0x8252;
+
+type GetIntegervFn = unsafe extern "system" fn(u32, *mut i32);
+type GetErrorFn = unsafe extern "system" fn() -> u32;
+type GetGraphicsResetStatusFn = unsafe extern "system" fn() -> u32;
+
+/// Reads the robustness of the current context. The `Try*` variants are never returned.
+///
+/// Must be called while the context is current. The queries that the context doesn't know
+/// raise errors, so the error flags of OpenGL are cleared.
+pub fn get_robustness<F>(get_proc_address: F) -> Robustness where F: Fn(&str) -> *const () {
+    let get_integerv = get_proc_address("glGetIntegerv");
+    let get_error = get_proc_address("glGetError");
+    if get_integerv.is_null() || get_error.is_null() {
+        return Robustness::NotRobust;
+    }
+
+    unsafe {
+        let get_integerv: GetIntegervFn = mem::transmute(get_integerv);
+        let get_error: GetErrorFn = mem::transmute(get_error);
+
+        // the values are left untouched if the query isn't supported
+        let mut flags = 0;
+        let mut robust_access = 0;
+        let mut strategy = 0;
+        get_integerv(CONTEXT_FLAGS, &mut flags);
+        get_integerv(CONTEXT_ROBUST_ACCESS, &mut robust_access);
+        get_integerv(RESET_NOTIFICATION_STRATEGY, &mut strategy);
+
+        // there is one error flag per kind of error, so this terminates quickly
+        while get_error() != NO_ERROR {}
+
+        if flags & CONTEXT_FLAG_NO_ERROR_BIT != 0 {
+            Robustness::NoError
+        } else if flags & CONTEXT_FLAG_ROBUST_ACCESS_BIT == 0 && robust_access == 0 {
+            Robustness::NotRobust
+        } else if strategy == LOSE_CONTEXT_ON_RESET {
+            Robustness::RobustLoseContextOnReset
+        } else {
+            Robustness::RobustNoResetNotification
+        }
+    }
+}
+
+/// Checks after every swap whether the context has been lost because of a reset of the GPU.
+pub struct ResetDetection {
+    enabled: bool,
+    /// `glGetGraphicsResetStatus`, loaded the first time that it is needed. Contains `Some(None)`
+    /// if the context doesn't support it.
+    function: Option<Option<GetGraphicsResetStatusFn>>,
+    /// A lost context never comes back, even once the reset is complete
+    lost: bool,
+}
+
+impl ResetDetection {
+    /// The detection is only enabled if the context may have been created with
+    /// `LOSE_CONTEXT_ON_RESET`, since the other contexts are never lost.
+    #[inline]
+    pub fn new(robustness: Robustness) -> ResetDetection {
+        ResetDetection {
+            enabled: match robustness {
+                Robustness::RobustLoseContextOnReset |
+                Robustness::TryRobustLoseContextOnReset => true,
+                _ => false,
+            },
+            function: None,
+            lost: false,
+        }
+    }
+
+    /// Returns `ContextLost` if the GPU has been reset. Must be called while the context is
+    /// current.
+    pub fn check<F>(&mut self, get_proc_address: F) -> Result<(), ContextError>
+        where F: Fn(&str) -> *const ()
+    {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        if self.lost {
+            return Err(ContextError::ContextLost);
+        }
+
+        if self.function.is_none() {
+            self.function = Some(load_get_graphics_reset_status(get_proc_address));
+        }
+
+        if let Some(Some(function)) = self.function {
+            if unsafe { function() } != NO_ERROR {
+                self.lost = true;
+                return Err(ContextError::ContextLost);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads `glGetGraphicsResetStatus`, which is core since OpenGL 4.5 and OpenGL ES 3.2, and
+/// an extension before.
+fn load_get_graphics_reset_status<F>(get_proc_address: F) -> Option<GetGraphicsResetStatusFn>
+    where F: Fn(&str) -> *const ()
+{
+    let info = match GlInfo::query(&get_proc_address) {
+        Some(info) => info,
+        None => return None,
+    };
+
+    // `GL_KHR_robustness` uses the unsuffixed name on OpenGL and the `KHR` suffix on OpenGL ES
+    let khr = info.has_extension("GL_KHR_robustness");
+    let candidates = [
+        ("glGetGraphicsResetStatus", info.is_gl_at_least((4, 5)) ||
+                                     info.is_es_at_least((3, 2)) || (khr && !info.es)),
+        ("glGetGraphicsResetStatusARB", info.has_extension("GL_ARB_robustness")),
+        ("glGetGraphicsResetStatusKHR", khr && info.es),
+        ("glGetGraphicsResetStatusEXT", info.has_extension("GL_EXT_robustness")),
+    ];
+
+    candidates.iter()
+              .filter(|&&(_, supported)| supported)
+              .map(|&(name, _)| get_proc_address(name))
+              .find(|function| !function.is_null())
+              .map(|function| unsafe { mem::transmute(function) })
+}
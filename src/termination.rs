@@ -0,0 +1,127 @@
+//! Requests of termination, which are sent from signal handlers.
+//!
+//! A signal handler can only call async-signal-safe functions, so a request only increments a
+//! counter and, on Unix, writes a byte to a pipe. The read end of the pipe is watched along with
+//! the connection to the display server, which wakes up `wait_events`. Every window compares the
+//! counter with the last request that it has reported.
+
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+
+use Event;
+
+/// The number of requests since the start of the program.
+static REQUESTS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Requests the termination of the program. Every window reports an `Event::Terminated` once,
+/// the next time that its events are polled or waited for, including the windows that are
+/// created after the request.
+///
+/// This function is async-signal-safe: it can be called from a handler of `SIGINT` or `SIGTERM`,
+/// so that the program shuts down cleanly instead of dying in the middle of a frame.
+///
+/// ## Platform-specific
+///
+/// - Only interrupts `wait_events` on X11 and Wayland. On the other platforms, the event is
+///   reported when the events are polled, or once another event wakes up `wait_events`.
+pub fn request_termination() {
+    REQUESTS.fetch_add(1, Ordering::SeqCst);
+    pipe::notify();
+}
+
+/// Reports the requests of termination to a window.
+pub struct Listener {
+    /// The number of requests when the last `Terminated` event was reported
+    seen: AtomicUsize,
+}
+
+impl Listener {
+    #[inline]
+    pub fn new() -> Listener {
+        pipe::init();
+        // a request sent before the window was created, for example while the program was
+        // loading, must still be reported
+        Listener { seen: AtomicUsize::new(0) }
+    }
+
+    /// Returns the `Terminated` event if a request hasn't been reported yet.
+    #[inline]
+    pub fn poll(&self) -> Option<Event> {
+        let requests = REQUESTS.load(Ordering::SeqCst);
+        if self.seen.swap(requests, Ordering::SeqCst) != requests {
+            Some(Event::Terminated)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use self::pipe::{wakeup_fd, drain};
+
+#[cfg(unix)]
+mod pipe {
+    use std::sync::{Once, ONCE_INIT};
+    use std::sync::atomic::{AtomicIsize, ATOMIC_ISIZE_INIT, Ordering};
+
+    use libc;
+
+    static INIT: Once = ONCE_INIT;
+
+    /// The read and write ends of the pipe plus one, or `0` if it hasn't been created.
+    static READ_FD: AtomicIsize = ATOMIC_ISIZE_INIT;
+    static WRITE_FD: AtomicIsize = ATOMIC_ISIZE_INIT;
+
+    /// Creates the pipe. Can't be done by `notify`, which runs in a signal handler.
+    pub fn init() {
+        INIT.call_once(|| unsafe {
+            let mut fds = [0; 2];
+            if libc::pipe(fds.as_mut_ptr()) != 0 {
+                return;
+            }
+
+            // a full pipe must neither block the signal handler nor the drain
+            for &fd in fds.iter() {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+
+            READ_FD.store(fds[0] as isize + 1, Ordering::SeqCst);
+            WRITE_FD.store(fds[1] as isize + 1, Ordering::SeqCst);
+        });
+    }
+
+    /// Makes the read end readable. Async-signal-safe.
+    pub fn notify() {
+        let fd = WRITE_FD.load(Ordering::SeqCst);
+        if fd > 0 {
+            let byte = 1u8;
+            unsafe { libc::write(fd as libc::c_int - 1, &byte as *const u8 as *const _, 1) };
+        }
+    }
+
+    /// Returns the file descriptor that becomes readable when a termination is requested.
+    pub fn wakeup_fd() -> Option<libc::c_int> {
+        let fd = READ_FD.load(Ordering::SeqCst);
+        if fd > 0 { Some(fd as libc::c_int - 1) } else { None }
+    }
+
+    /// Empties the pipe once the request has been noticed.
+    pub fn drain() {
+        if let Some(fd) = wakeup_fd() {
+            let mut buffer = [0u8; 64];
+            while unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut _, buffer.len()) } > 0 {}
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod pipe {
+    #[inline]
+    pub fn init() {
+    }
+
+    #[inline]
+    pub fn notify() {
+    }
+}
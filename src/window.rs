@@ -1,22 +1,44 @@
 use std::collections::vec_deque::IntoIter as VecDequeIter;
 use std::default::Default;
+use std::env;
+use std::io;
 use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use Api;
+use ColorSpace;
 use ContextError;
 use CreationError;
 use CursorState;
+use Edid;
 use Event;
+use FullscreenFocusPolicy;
 use GlContext;
 use GlProfile;
 use GlRequest;
+use LockKeys;
+use PointerAcceleration;
+use LogicalSize;
 use MouseCursor;
+use PhysicalSize;
 use PixelFormat;
+use PixelFormatRequirements;
+use PixelFormatSelector;
 use Robustness;
+use SnapPosition;
+use SwapMethod;
 use Window;
 use WindowID;
 use WindowAttributes;
 use WindowBuilder;
+use frame_latency::FrameLatency;
+use robustness::{self, ResetDetection};
+use termination;
+use child;
+use hover::Hover;
+use menu::{Menu, MenuShortcuts};
 use native_monitor::NativeMonitorId;
 
 use libc;
@@ -27,7 +49,9 @@ impl<'a> WindowBuilder<'a> {
     #[inline]
     pub fn new() -> WindowBuilder<'a> {
         WindowBuilder {
-            pf_reqs: Default::default(),
+            // the alpha is derived from the transparency of the window in `build` if the user
+            // doesn't request anything
+            pf_reqs: PixelFormatRequirements { alpha_bits: None, .. Default::default() },
             window: Default::default(),
             opengl: Default::default(),
             platform_specific: Default::default(),
@@ -42,6 +66,23 @@ impl<'a> WindowBuilder<'a> {
         self.window.dimensions = Some((width, height));
         self
     }
+
+    /// Requests the window to be of specific dimensions, including the decorations drawn by the
+    /// system around it.
+    ///
+    /// Width and height are in pixels.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this requires the window manager to support `_NET_REQUEST_FRAME_EXTENTS`.
+    ///   Otherwise the dimensions are those of the client area, as with `with_dimensions`.
+    /// - On Wayland, Android and iOS, this is the same as `with_dimensions`.
+    #[inline]
+    pub fn with_outer_dimensions(mut self, width: u32, height: u32) -> WindowBuilder<'a> {
+        self.window.dimensions = Some((width, height));
+        self.window.outer_dimensions = true;
+        self
+    }
     
     /// Sets a minimum dimension size for the window
     ///
@@ -78,6 +119,54 @@ impl<'a> WindowBuilder<'a> {
         self
     }
 
+    /// Sets what happens when the window loses the focus while it is fullscreen. This is only
+    /// relevant with `with_fullscreen`, which changes the video mode of the monitor.
+    ///
+    /// With `FullscreenFocusPolicy::Minimize`, which is the default, a `Focused(false)` event is
+    /// accompanied by the restoration of the desktop's video mode and the minimization of the
+    /// window. The video mode of the window is applied again before `Focused(true)` is received.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only has an effect on X11 and Windows. Other platforms never change the video mode.
+    #[inline]
+    pub fn with_fullscreen_focus_policy(mut self, policy: FullscreenFocusPolicy)
+                                        -> WindowBuilder<'a>
+    {
+        self.window.fullscreen_focus_policy = policy;
+        self
+    }
+
+    /// Asks the compositor to stop compositing the screen whenever the window is fullscreen,
+    /// either with `with_fullscreen` or with `Window::set_fullscreen`. Compositing effects add
+    /// latency and can make games stutter.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only has an effect on X11, with the compositors that support the hints, such as KWin
+    ///   and Mutter. Windows already bypasses the compositor for fullscreen windows that cover
+    ///   the whole monitor.
+    /// - The minimize animation is not disabled: neither KWin nor Mutter has a hint to disable
+    ///   it for a single window, so it still plays when the window is minimized, for example
+    ///   by `FullscreenFocusPolicy::Minimize`.
+    #[inline]
+    pub fn with_compositor_bypass(mut self, bypass: bool) -> WindowBuilder<'a> {
+        self.window.bypass_compositor = bypass;
+        self
+    }
+
+    /// Requests an `Event::FramePresented` for every frame, which tells when the frame has
+    /// been displayed. See the docs of `FrameTiming`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only has an effect on X11, where it requires the Present extension and libXpresent.
+    #[inline]
+    pub fn with_frame_timing(mut self, frame_timing: bool) -> WindowBuilder<'a> {
+        self.window.frame_timing = frame_timing;
+        self
+    }
+
     /// The created window will share all its OpenGL objects with the window in the parameter.
     ///
     /// There are some exceptions, like FBOs or VAOs. See the OpenGL documentation.
@@ -95,6 +184,18 @@ impl<'a> WindowBuilder<'a> {
     }
 
     /// Sets the desired OpenGL context profile.
+    ///
+    /// Profiles only exist for OpenGL 3.2 and above, so this is usually combined with a
+    /// `GlRequest::Specific` version. Without a profile, the driver chooses, and some drivers
+    /// such as Mesa only give a compatibility context with at most OpenGL 3.0.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - With GLX and WGL, window creation fails with `NotSupported` if the
+    ///   `*_ARB_create_context_profile` extension is missing.
+    /// - With EGL, this requires EGL 1.5 or `EGL_KHR_create_context`, and is ignored for
+    ///   OpenGL ES.
+    ///
     #[inline]
     pub fn with_gl_profile(mut self, profile: GlProfile) -> WindowBuilder<'a> {
         self.opengl.profile = Some(profile);
@@ -111,6 +212,14 @@ impl<'a> WindowBuilder<'a> {
         self
     }
 
+    /// Sets the *forward compatible* flag for the OpenGL context, which removes the
+    /// deprecated functionality. See the docs of `GlAttributes::forward_compatible`.
+    #[inline]
+    pub fn with_gl_forward_compatible(mut self, flag: bool) -> WindowBuilder<'a> {
+        self.opengl.forward_compatible = flag;
+        self
+    }
+
     /// Sets the robustness of the OpenGL context. See the docs of `Robustness`.
     #[inline]
     pub fn with_gl_robustness(mut self, robustness: Robustness) -> WindowBuilder<'a> {
@@ -125,6 +234,31 @@ impl<'a> WindowBuilder<'a> {
         self
     }
 
+    /// Requires the vsync setting to be applied, whether vsync is enabled with `with_vsync` or
+    /// not. Without `with_vsync`, this guarantees that `swap_buffers` never waits for the screen,
+    /// which benchmarks need for uncapped frame rates.
+    ///
+    /// Building the window fails if the driver doesn't support changing the swap interval, or
+    /// overrides it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this requires `GLX_EXT_swap_control` to disable vsync, and to check the
+    ///   interval.
+    #[inline]
+    pub fn with_strict_vsync(mut self) -> WindowBuilder<'a> {
+        self.opengl.strict_vsync = true;
+        self
+    }
+
+    /// Sets whether `swap_buffers` waits for the frame to be rendered by calling `glFinish`.
+    /// See the docs of `GlAttributes::finish_on_swap`.
+    #[inline]
+    pub fn with_finish_on_swap(mut self, finish_on_swap: bool) -> WindowBuilder<'a> {
+        self.opengl.finish_on_swap = finish_on_swap;
+        self
+    }
+
     /// Sets whether the window will be initially hidden or visible.
     #[inline]
     pub fn with_visibility(mut self, visible: bool) -> WindowBuilder<'a> {
@@ -144,7 +278,67 @@ impl<'a> WindowBuilder<'a> {
         self
     }
 
+    /// Requests coverage sample anti-aliasing (CSAA) with the given number of coverage samples.
+    /// The number of color samples is the one passed to `with_multisampling`, or the same as
+    /// the number of coverage samples if it wasn't called.
+    ///
+    /// # Panic
+    ///
+    /// Will panic if `samples` is not a power of two.
+    #[inline]
+    pub fn with_coverage_samples(mut self, samples: u16) -> WindowBuilder<'a> {
+        assert!(samples.is_power_of_two());
+        self.pf_reqs.coverage_samples = Some(samples);
+        self
+    }
+
+    /// Sets whether the pixel format must be double buffered. `None` means "don't care".
+    ///
+    /// With a single-buffered format, rendering goes directly to the front buffer and
+    /// `swap_buffers` only flushes the commands. This is only supported by GLX and WGL.
+    #[inline]
+    pub fn with_double_buffer(mut self, double_buffer: Option<bool>) -> WindowBuilder<'a> {
+        self.pf_reqs.double_buffer = double_buffer;
+        self
+    }
+
+    /// Requires a pixel format with the given behavior when swapping the buffers.
+    ///
+    /// Use `SwapMethod::Copy` if you rely on the content of the back buffer being preserved
+    /// across swaps. Window creation fails if no pixel format has the requested behavior.
+    ///
+    /// With EGL, `Copy` requests `EGL_SWAP_BEHAVIOR_PRESERVED` and `Exchange` is never available.
+    /// Check `get_pixel_format().swap_method` to know whether the content of the back buffer
+    /// actually survives `swap_buffers`.
+    #[inline]
+    pub fn with_swap_method(mut self, swap_method: SwapMethod) -> WindowBuilder<'a> {
+        self.pf_reqs.swap_method = Some(swap_method);
+        self
+    }
+
+    /// Sets a function that chooses the pixel format, for the applications that need a precise
+    /// configuration rather than the closest match to the requirements.
+    ///
+    /// The function receives every format that satisfies the other requirements, and returns the
+    /// index of the one to use. Relax the requirements to receive more formats. Window creation
+    /// fails with `NoAvailablePixelFormat` if the index is out of bounds.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only implemented with GLX, EGL and WGL with `WGL_ARB_pixel_format`. Other platforms
+    ///   don't enumerate the formats and ignore this setting.
+    ///
+    #[inline]
+    pub fn with_pixel_format_selector<F>(mut self, selector: F) -> WindowBuilder<'a>
+        where F: Fn(&[PixelFormat]) -> usize + Send + Sync + 'static
+    {
+        self.pf_reqs.selector = Some(PixelFormatSelector(Arc::new(selector)));
+        self
+    }
+
     /// Sets the number of bits in the depth buffer.
+    ///
+    /// Zero requests a pixel format without any depth buffer.
     #[inline]
     pub fn with_depth_buffer(mut self, bits: u8) -> WindowBuilder<'a> {
         self.pf_reqs.depth_bits = Some(bits);
@@ -152,6 +346,8 @@ impl<'a> WindowBuilder<'a> {
     }
 
     /// Sets the number of bits in the stencil buffer.
+    ///
+    /// Zero requests a pixel format without any stencil buffer.
     #[inline]
     pub fn with_stencil_buffer(mut self, bits: u8) -> WindowBuilder<'a> {
         self.pf_reqs.stencil_bits = Some(bits);
@@ -166,6 +362,39 @@ impl<'a> WindowBuilder<'a> {
         self
     }
 
+    /// Sets the number of bits of the alpha channel of the color buffer.
+    ///
+    /// By default, 8 bits are requested for transparent windows and the alpha is left
+    /// unspecified for opaque ones. Use `get_pixel_format` to know the actual value.
+    #[inline]
+    pub fn with_alpha_bits(mut self, alpha_bits: u8) -> WindowBuilder<'a> {
+        self.pf_reqs.alpha_bits = Some(alpha_bits);
+        self
+    }
+
+    /// Sets the number of bits in the color buffer, excluding the alpha channel, without
+    /// changing the number of alpha bits.
+    #[inline]
+    pub fn with_pixel_color_bits(mut self, color_bits: u8) -> WindowBuilder<'a> {
+        self.pf_reqs.color_bits = Some(color_bits);
+        self
+    }
+
+    /// Sets whether to fall back to 24 bits of color if the color depth requested with
+    /// `with_pixel_format` (usually 30 bits) is not available.
+    ///
+    /// Check the `color_bits` field of the `PixelFormat` to know what you obtained.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only implemented with GLX. Other platforms ignore this setting.
+    ///
+    #[inline]
+    pub fn with_deep_color_fallback(mut self, fallback: bool) -> WindowBuilder<'a> {
+        self.pf_reqs.deep_color_fallback = fallback;
+        self
+    }
+
     /// Request the backend to be stereoscopic.
     #[inline]
     pub fn with_stereoscopy(mut self) -> WindowBuilder<'a> {
@@ -174,13 +403,48 @@ impl<'a> WindowBuilder<'a> {
     }
 
     /// Sets whether sRGB should be enabled on the window. `None` means "I don't care".
+    ///
+    /// This is a shortcut for `with_color_space` with `ColorSpace::Srgb` for `Some(true)`, and
+    /// `ColorSpace::Default` otherwise: sRGB-capable formats are not excluded by `Some(false)`,
+    /// as they behave like the others while `GL_FRAMEBUFFER_SRGB` is disabled.
+    ///
+    /// `Some(true)` is a requirement: if none of the pixel formats that match the other
+    /// criterias is sRGB-capable, window creation fails with `NoSrgbPixelFormat`, which contains
+    /// the formats that were found.
+    #[inline]
+    pub fn with_srgb(self, srgb_enabled: Option<bool>) -> WindowBuilder<'a> {
+        self.with_color_space(match srgb_enabled {
+            Some(true) => ColorSpace::Srgb,
+            Some(false) | None => ColorSpace::Default,
+        })
+    }
+
+    /// Sets the color space of the default framebuffer, for example to request an HDR surface.
+    ///
+    /// Window creation fails with `NoAvailablePixelFormat` if the color space is not supported.
+    /// Check the `color_space` field of the `PixelFormat` to know which one you obtained.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - `Linear` and `Srgb` are supported everywhere except on OS X, where the framebuffer is
+    ///   always sRGB-capable.
+    /// - The other color spaces are only supported with EGL, through the
+    ///   `EGL_EXT_gl_colorspace_*` extensions.
+    ///
     #[inline]
-    pub fn with_srgb(mut self, srgb_enabled: Option<bool>) -> WindowBuilder<'a> {
-        self.pf_reqs.srgb = srgb_enabled.unwrap_or(false);
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> WindowBuilder<'a> {
+        // the floating-point buffer is only a consequence of the color space, so that a later
+        // call can switch back to another one
+        self.pf_reqs.float_color_buffer = color_space == ColorSpace::ScRgbLinear;
+        self.pf_reqs.color_space = color_space;
         self
     }
 
     /// Sets whether the background of the window should be transparent.
+    ///
+    /// The alpha channel of the default framebuffer is then used to blend the window with what
+    /// is behind it, which requires a compositing window manager on X11. On X11 with GLX, a
+    /// pixel format whose visual has an alpha channel is selected if there is one.
     #[inline]
     pub fn with_transparency(mut self, transparent: bool) -> WindowBuilder<'a> {
         self.window.transparent = transparent;
@@ -188,13 +452,26 @@ impl<'a> WindowBuilder<'a> {
     }
 
     /// Sets whether the window should have a border, a title bar, etc.
+    ///
+    /// Undecorated windows are useful for splash screens, or for applications that draw their
+    /// own title bar. On X11, this relies on the `_MOTIF_WM_HINTS` property, which is honored by
+    /// all the common window managers.
     #[inline]
     pub fn with_decorations(mut self, decorations: bool) -> WindowBuilder<'a> {
         self.window.decorations = decorations;
         self
     }
 
-    /// Enables multitouch
+    /// Enables multitouch.
+    ///
+    /// Touchscreens then produce `Touch` events, one sequence per finger, instead of the mouse
+    /// events that the system emulates from the first finger.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the touch sequences come from XInput2, and the emulated pointer events are
+    ///   dropped.
+    ///
     #[inline]
     pub fn with_multitouch(mut self) -> WindowBuilder<'a> {
         self.window.multitouch = true;
@@ -208,17 +485,95 @@ impl<'a> WindowBuilder<'a> {
         self
     }
 
-    /// Sets the parent window
+    /// Sets the parent window, which embeds the window in a window of another application or
+    /// toolkit. The embedded window has no decorations and is placed at the top-left corner of
+    /// its parent.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the parent is an X window ID, and the window follows the size of its parent.
+    /// - On Windows, the parent is a `HWND`, and the window is created as a `WS_CHILD`. It fills
+    ///   the client area of its parent unless dimensions have been requested, but the host
+    ///   application must resize it afterwards. Fullscreen windows ignore the parent.
+    ///   The window runs its own thread, and Windows attaches the input queue of that thread to
+    ///   the one of the thread of the parent. The two threads then wait for each other when
+    ///   they send messages to the windows, or change the focus or the capture. The thread of
+    ///   the parent must keep processing its messages, and mustn't wait for the glutin window,
+    ///   for example by calling its methods from a window procedure of the parent, or the two
+    ///   threads can deadlock.
+    /// - Ignored on the other platforms.
     pub fn with_parent(mut self, parent: Option<WindowID>) -> WindowBuilder<'a> {
         self.window.parent = parent;
         self
     }
 
+    /// Requests variable refresh rate (G-Sync/FreeSync) for this window.
+    ///
+    /// Variable refresh only applies when the window covers the whole monitor, and only if the
+    /// monitor supports it (see `MonitorId::is_variable_refresh_capable`). The refresh cycle is
+    /// then driven by `swap_buffers`: with vsync enabled, the frame rate is capped at the
+    /// maximum refresh rate of the monitor; with vsync disabled, frames rendered faster than
+    /// that tear, as usual.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this sets the `_VARIABLE_REFRESH` property of the window, which is honored by
+    ///   the Mesa drivers.
+    /// - Has no effect on other platforms, where variable refresh is controlled by the driver
+    ///   settings.
+    ///
+    #[inline]
+    pub fn with_variable_refresh(mut self, enabled: bool) -> WindowBuilder<'a> {
+        self.window.variable_refresh = enabled;
+        self
+    }
+
+    /// Forces the value returned by `hidpi_factor` instead of the one reported by the system.
+    ///
+    /// The `GLUTIN_SCALE_FACTOR` environment variable, if set to a positive number, takes
+    /// priority over this value.
+    #[inline]
+    pub fn with_scale_factor_override(mut self, factor: f32) -> WindowBuilder<'a> {
+        self.window.scale_factor_override = Some(factor);
+        self
+    }
+
+    /// Sets whether glutin should produce repeated `KeyboardInput`, `ReceivedText` and
+    /// `ReceivedCharacter` events while a key is held down, on the platforms where the system doesn't.
+    ///
+    /// The default is `true`, so that keys behave the same everywhere.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On Wayland, the repeats are produced by glutin with a delay of 600ms and a rate of 25
+    ///   repeats per second. Modifiers don't repeat.
+    /// - Has no effect on other platforms, where the keys are repeated by the system.
+    ///
+    #[inline]
+    pub fn with_key_repeat(mut self, enabled: bool) -> WindowBuilder<'a> {
+        self.window.key_repeat = enabled;
+        self
+    }
+
     /// Builds the window.
     ///
     /// Error should be very rare and only occur in case of permission denied, incompatible system,
     /// out of memory, etc.
-    pub fn build(mut self) -> Result<Window, CreationError> {
+    #[inline]
+    pub fn build(self) -> Result<Window, CreationError> {
+        self.build_impl(None)
+    }
+
+    /// Builds the window, or only the context and the events of an existing window.
+    fn build_impl(mut self, existing: Option<(*mut libc::c_void, *mut libc::c_void)>)
+                  -> Result<Window, CreationError>
+    {
+        // transparent windows need an alpha channel, but requiring one for opaque windows
+        // excludes some configs for no reason
+        if self.pf_reqs.alpha_bits.is_none() && self.window.transparent {
+            self.pf_reqs.alpha_bits = Some(8);
+        }
+
         // resizing the window to the dimensions of the monitor when fullscreen
         if self.window.dimensions.is_none() && self.window.monitor.is_some() {
             self.window.dimensions = Some(self.window.monitor.as_ref().unwrap().get_dimensions())
@@ -229,9 +584,40 @@ impl<'a> WindowBuilder<'a> {
             self.window.dimensions = Some((1024, 768));
         }
 
+        // the environment variable has priority over the builder, so that users can fix
+        // misreporting monitors without modifying the application
+        let hidpi_factor_override = env::var("GLUTIN_SCALE_FACTOR").ok()
+                                        .and_then(|v| v.parse::<f32>().ok())
+                                        .and_then(|f| if f > 0.0 { Some(f) } else { None })
+                                        .or(self.window.scale_factor_override);
+
+        let mut frame_latency = FrameLatency::new();
+        frame_latency.set_finish_on_swap(self.opengl.finish_on_swap);
+        let robustness = self.opengl.robustness;
+        let opengl = self.opengl.clone().map_sharing(|_| ());
+
         // building
-        platform::Window::new(&self.window, &self.pf_reqs, &self.opengl, &self.platform_specific)
-                            .map(|w| Window { window: w })
+        let pf_reqs = self.pf_reqs;
+        let window = match existing {
+            Some((display, window)) => unsafe {
+                platform::Window::from_existing(display, window, &self.window, &pf_reqs,
+                                                &self.opengl, &self.platform_specific)
+            },
+            None => platform::Window::new(&self.window, &pf_reqs, &self.opengl,
+                                          &self.platform_specific),
+        };
+        window.map(|w| Window {
+            window: w,
+            hidpi_factor_override: hidpi_factor_override,
+            cursor: Mutex::new((MouseCursor::Default, false)),
+            hover: Mutex::new(Hover::new()),
+            menu_shortcuts: Mutex::new(MenuShortcuts::new()),
+            frame_latency: Mutex::new(frame_latency),
+            reset_detection: Mutex::new(ResetDetection::new(robustness)),
+            termination: termination::Listener::new(),
+            pf_reqs: pf_reqs,
+            opengl: opengl,
+        })
     }
 
     /// Builds the window.
@@ -265,6 +651,41 @@ impl Window {
         builder.build()
     }
 
+    /// Creates an OpenGL context for a window that has been created by another library, such as
+    /// Qt, GTK or SDL, and receives the events of this window. This is the reverse of
+    /// `platform_display` and `platform_window`.
+    ///
+    /// The pixel format and the OpenGL attributes of the builder are used. The attributes that
+    /// only make sense for a new window, such as the title, the dimensions, the decorations,
+    /// the fullscreen monitor or the parent, are ignored: the window stays under the control of
+    /// the other library, and isn't destroyed when the `Window` is dropped.
+    ///
+    /// ## Safety
+    ///
+    /// The native display and window must be valid, and the window must outlive the returned
+    /// `Window`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the display is an Xlib `Display*`, which may be null, and the window is an X
+    ///   window ID. Glutin uses its own connection to the same X server. Only the pixel formats
+    ///   of the visual of the window are considered, and `CreationError::NoAvailablePixelFormat`
+    ///   is returned if none of them matches the requirements. The mouse buttons can't be
+    ///   reported if the other library already listens to them through XInput.
+    /// - On Windows, this is a known limitation: the pixel format of a window can only be set
+    ///   once and its window procedure belongs to the other library, so
+    ///   `CreationError::NotSupported` is returned.
+    /// - Returns `CreationError::NotSupported` on the other platforms.
+    pub unsafe fn from_existing(native_display: *mut libc::c_void,
+                                native_window: *mut libc::c_void,
+                                mut builder: WindowBuilder)
+                                -> Result<Window, CreationError>
+    {
+        builder.window.monitor = None;
+        builder.window.parent = None;
+        builder.build_impl(Some((native_display, native_window)))
+    }
+
     /// Modifies the title of the window.
     ///
     /// This is a no-op if the window has already been closed.
@@ -273,6 +694,19 @@ impl Window {
         self.window.set_title(title)
     }
 
+    /// Returns the title of the window, as it is known by the system.
+    ///
+    /// Returns `None` if the window has already been closed, or if the title can't be read.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the title may have been changed by another client.
+    /// - Always returns `None` on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn get_title(&self) -> Option<String> {
+        self.window.get_title()
+    }
+
     /// Shows the window if it was hidden.
     ///
     /// ## Platform-specific
@@ -295,6 +729,46 @@ impl Window {
         self.window.hide()
     }
 
+    /// Brings the window to the front and gives it the keyboard focus.
+    ///
+    /// This is typically used when a second instance of the application forwards its
+    /// arguments to the first one, or when the user clicks on a notification.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the request goes through the window manager, which may decide to flash the
+    ///   window instead if it considers that the focus is being stolen.
+    /// - On Windows, `SetForegroundWindow` only succeeds if the process is allowed to set the
+    ///   foreground window.
+    /// - Has no effect on Android, iOS and Wayland.
+    ///
+    #[inline]
+    pub fn focus(&self) {
+        self.window.focus()
+    }
+
+    /// Moves the window on top of the other windows, without giving it the focus.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Has no effect on Android, iOS and Wayland.
+    ///
+    #[inline]
+    pub fn raise(&self) {
+        self.window.raise()
+    }
+
+    /// Moves the window below the other windows.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Has no effect on Android, iOS and Wayland.
+    ///
+    #[inline]
+    pub fn lower(&self) {
+        self.window.lower()
+    }
+
     /// Returns the position of the top-left hand corner of the window relative to the
     ///  top-left hand corner of the desktop.
     ///
@@ -321,6 +795,160 @@ impl Window {
         self.window.set_position(x, y)
     }
 
+    /// Returns the state of the lock keys.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On OS X, only Caps Lock is reported.
+    /// - Always returns the default state on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn get_lock_keys(&self) -> LockKeys {
+        self.window.get_lock_keys()
+    }
+
+    /// Returns the settings of the system for the acceleration of the cursor, which apply to
+    /// `MouseMoved` but not to `MouseRawMovement`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, these are the settings of the core pointer. Drivers such as libinput may
+    ///   ignore them and use their own acceleration profile.
+    /// - Always returns `None` on OS X, Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn get_pointer_acceleration(&self) -> Option<PointerAcceleration> {
+        self.window.get_pointer_acceleration()
+    }
+
+    /// Changes the state of the lock keys, for example to mirror the state of an emulated
+    /// machine.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, Num Lock is expected to be bound to the `Mod2` modifier, which is the usual
+    ///   setup, and only the LED of Scroll Lock is changed.
+    /// - On Windows, this simulates presses of the keys whose state differs.
+    /// - Returns an error on the other platforms.
+    #[inline]
+    pub fn set_lock_keys(&self, lock_keys: LockKeys) -> Result<(), String> {
+        self.window.set_lock_keys(lock_keys)
+    }
+
+    /// Switches the window to fullscreen on the given monitor, or back to windowed mode if
+    /// `None`, without recreating the window and its OpenGL context.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this requires a window manager that supports `_NET_WM_STATE_FULLSCREEN`. The
+    ///   window stays on its X screen: nothing happens if the monitor belongs to another one.
+    /// - On OS X, the window uses the native fullscreen mode on its current screen.
+    /// - Has no effect on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
+        self.window.set_fullscreen(monitor.map(|MonitorId(m)| m))
+    }
+
+    /// Embeds the window in a window of another application or toolkit, or turns it back into
+    /// a top-level window with `None`. See `WindowBuilder::with_parent`.
+    ///
+    /// This allows docking and undocking the window at runtime, for example in the user
+    /// interface of a plugin.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the window is withdrawn from the window manager before being embedded, and
+    ///   managed again once it is turned back into a top-level window.
+    /// - Returns an error on the platforms other than X11 and Windows.
+    #[inline]
+    pub fn set_parent(&self, parent: Option<WindowID>) -> Result<(), String> {
+        self.window.set_parent(parent)
+    }
+
+    /// Replaces the menu bar of the window, or removes it if `menus` is empty.
+    ///
+    /// Choosing an enabled item, or pressing its accelerator while the window has the focus,
+    /// produces an `Event::MenuItem` with the ID of the item.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On OS X, the menus are shown in the global menu bar, after the application menu. The
+    ///   menu bar is shared by the windows of the application: the last call replaces the menus
+    ///   of the other windows, and the menus are removed when their window is destroyed.
+    /// - On Windows, the menus are shown in the menu bar of the window. Only the 16 lower bits of
+    ///   the IDs are reported.
+    /// - On X11, the menus are exported with the `com.canonical.dbusmenu` protocol if a global
+    ///   menu, such as the panel of Unity, is registered as `com.canonical.AppMenu.Registrar` on
+    ///   the session bus. Otherwise they are drawn in a simple bar that covers the top of the
+    ///   window, with Latin-1 labels. Its height is returned by `get_menu_bar_height`.
+    /// - Wayland, Android, iOS and Emscripten have no native menus, so the application draws them
+    ///   by itself from the same model. The accelerators still produce the events.
+    #[inline]
+    pub fn set_menu_bar(&self, menus: &[Menu]) {
+        self.window.set_menu_bar(menus);
+
+        // AppKit triggers the key equivalents of the menus by itself
+        if !cfg!(target_os = "macos") {
+            self.menu_shortcuts.lock().unwrap().set_menus(menus);
+        }
+    }
+
+    /// Returns the height in pixels of the menu bar that covers the top of the inner area of
+    /// the window, or `0` if the menus don't cover it.
+    ///
+    /// The application should keep its content below the menu bar, for example by reducing the
+    /// height of its viewport.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only the bar drawn on X11, when there is no global menu, covers the window. The menu
+    ///   bar of Windows is outside of the inner area, and the one of OS X is global.
+    #[inline]
+    pub fn get_menu_bar_height(&self) -> u32 {
+        self.window.get_menu_bar_height()
+    }
+
+    /// Maximizes the window, or restores it to its previous size if `false`.
+    ///
+    /// A `Maximized` event is produced once the change is done.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this requires a window manager that supports `_NET_WM_STATE_MAXIMIZED_HORZ`
+    ///   and `_NET_WM_STATE_MAXIMIZED_VERT`.
+    /// - On OS X, this zooms or unzooms the window, and no event is produced.
+    /// - Has no effect on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn set_maximized(&self, maximized: bool) {
+        self.window.set_maximized(maximized)
+    }
+
+    /// Minimizes the window, or restores it if `false`.
+    ///
+    /// A `Minimized` event is produced once the change is done. Restoring a window doesn't
+    /// necessarily give it the focus, as some systems prevent applications from stealing it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Has no effect on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) {
+        self.window.set_minimized(minimized)
+    }
+
+    /// Moves and resizes the window so that it fills a half or a quarter of a monitor.
+    ///
+    /// Only the workarea of the monitor is used, which excludes the taskbars, panels and docks.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the window stays on its X screen: nothing happens if the monitor belongs to
+    ///   another one.
+    /// - Has no effect on other platforms than X11 and Windows.
+    #[inline]
+    pub fn snap_to(&self, position: SnapPosition, monitor: &MonitorId) {
+        let &MonitorId(ref monitor) = monitor;
+        self.window.snap_to(position, monitor)
+    }
+
     /// Returns the size in points of the client area of the window.
     ///
     /// The client area is the content of the window, excluding the title bar and borders.
@@ -337,12 +965,13 @@ impl Window {
     /// Returns the size in points of the client area of the window.
     ///
     /// The client area is the content of the window, excluding the title bar and borders.
-    /// To get the dimensions of the frame buffer when calling `glViewport`, multiply with hidpi factor.
+    /// To get the dimensions of the frame buffer when calling `glViewport`, use
+    /// `get_inner_size_pixels` or convert with `LogicalSize::to_physical`.
     ///
     /// Returns `None` if the window no longer exists.
     #[inline]
-    pub fn get_inner_size_points(&self) -> Option<(u32, u32)> {
-        self.window.get_inner_size()
+    pub fn get_inner_size_points(&self) -> Option<LogicalSize> {
+        self.window.get_inner_size().map(|(w, h)| self.to_logical_size(LogicalSize::new(w, h)))
     }
 
 
@@ -354,11 +983,10 @@ impl Window {
     ///
     /// Returns `None` if the window no longer exists.
     #[inline]
-    pub fn get_inner_size_pixels(&self) -> Option<(u32, u32)> {
-        self.window.get_inner_size().map(|(x, y)| {
-            let hidpi = self.hidpi_factor();
-            ((x as f32 * hidpi) as u32, (y as f32 * hidpi) as u32)
-        })
+    pub fn get_inner_size_pixels(&self) -> Option<PhysicalSize> {
+        // the override doesn't change the size of the frame buffer
+        self.window.get_inner_size()
+            .map(|(w, h)| LogicalSize::new(w, h).to_physical(self.window.hidpi_factor()))
     }
 
     /// Returns the size in pixels of the window.
@@ -382,13 +1010,36 @@ impl Window {
         self.window.set_inner_size(x, y)
     }
 
+    /// Sets the minimum dimensions of the inner area of the window, or removes the constraint
+    /// if `None`. See `WindowBuilder::with_min_dimensions`.
+    ///
+    /// The window is resized if it is currently smaller. This has no effect on Wayland, Android,
+    /// iOS and Emscripten.
+    #[inline]
+    pub fn set_min_size(&self, size: Option<(u32, u32)>) {
+        self.window.set_min_size(size)
+    }
+
+    /// Sets the maximum dimensions of the inner area of the window, or removes the constraint
+    /// if `None`. See `WindowBuilder::with_max_dimensions`.
+    ///
+    /// The window is resized if it is currently larger. This has no effect on Wayland, Android,
+    /// iOS and Emscripten.
+    #[inline]
+    pub fn set_max_size(&self, size: Option<(u32, u32)>) {
+        self.window.set_max_size(size)
+    }
+
     /// Returns an iterator that poll for the next event in the window's events queue.
     /// Returns `None` if there is no event in the queue.
     ///
     /// Contrary to `wait_events`, this function never blocks.
     #[inline]
     pub fn poll_events(&self) -> PollEventsIterator {
-        PollEventsIterator(self.window.poll_events())
+        PollEventsIterator {
+            window: self,
+            inner: self.window.poll_events(),
+        }
     }
 
     /// Returns an iterator that returns events one by one, blocking if necessary until one is
@@ -397,15 +1048,41 @@ impl Window {
     /// The iterator never returns `None`.
     #[inline]
     pub fn wait_events(&self) -> WaitEventsIterator {
-        WaitEventsIterator(self.window.wait_events())
+        WaitEventsIterator {
+            window: self,
+            inner: self.window.wait_events(),
+        }
+    }
+
+    /// Sets how long the cursor must rest over the window before a `Hovered` event is
+    /// received, or disables these events if `None`. This is the default.
+    ///
+    /// Tooltips are usually shown after 500 milliseconds.
+    #[inline]
+    pub fn set_hover_delay(&self, delay: Option<Duration>) {
+        self.hover.lock().unwrap().set_delay(delay);
     }
 
     /// Sets the context as the current context.
+    ///
+    /// Returns an error instead of panicking if the platform refuses, for example because the
+    /// drawable has been destroyed or the context has been lost. The call can be retried later.
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         self.window.make_current()
     }
 
+    /// Unbinds the current context from the calling thread, so that another thread can make
+    /// the context current.
+    ///
+    /// A context can only be current on one thread at a time, so the thread that hands the
+    /// rendering over to another one must call this first. Whichever context is current on the
+    /// calling thread is unbound, even if it isn't the context of this window.
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        self.window.make_not_current()
+    }
+
     /// Returns true if this context is the current one in this thread.
     #[inline]
     pub fn is_current(&self) -> bool {
@@ -428,9 +1105,65 @@ impl Window {
     /// **Warning**: if you enabled vsync, this function will block until the next time the screen
     /// is refreshed. However drivers can choose to override your vsync settings, which means that
     /// you can't know in advance whether `swap_buffers` will block or not.
+    ///
+    /// Returns `ContextError::ContextLost` if the context has been lost, for example when an
+    /// Android application goes to the background or, with
+    /// `Robustness::RobustLoseContextOnReset`, once the GPU has been reset. The context and every
+    /// object that it contains must then be recreated. Returns `ContextError::IoError` if the
+    /// context isn't current or if the swap has failed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the errors of the server are asynchronous, so an error caused by a swap may only
+    ///   be reported by one of the next swaps.
+    /// - Never fails on macOS.
     #[inline]
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
-        self.window.swap_buffers()
+        try!(self.window.swap_buffers());
+        self.frame_latency.lock().unwrap().after_swap(|name| self.window.get_proc_address(name));
+        self.reset_detection.lock().unwrap().check(|name| self.window.get_proc_address(name))
+    }
+
+    /// Limits the number of frames that the driver can queue before they are displayed.
+    /// Pass `None` to go back to the behavior of the driver, which is usually two or three
+    /// frames.
+    ///
+    /// When the limit is reached, `swap_buffers` waits for the oldest frame to be rendered. A
+    /// limit of `1` reduces the latency between the input and the display, at the cost of less
+    /// parallelism between the CPU and the GPU. `0` waits for every frame to be rendered.
+    ///
+    /// This requires fence syncs, which are core since OpenGL 3.2. Has no effect otherwise.
+    #[inline]
+    pub fn set_max_frames_in_flight(&self, frames: Option<u32>) {
+        self.frame_latency.lock().unwrap().set_max_frames(frames)
+    }
+
+    /// Sets whether `swap_buffers` waits for the frame to be rendered by calling `glFinish`,
+    /// which gives deterministic frame boundaries for latency measurements and profiling.
+    #[inline]
+    pub fn set_finish_on_swap(&self, finish_on_swap: bool) {
+        self.frame_latency.lock().unwrap().set_finish_on_swap(finish_on_swap)
+    }
+
+    /// Changes the number of screen refreshes that `swap_buffers` waits for: `0` disables
+    /// vsync, `1` enables it, and higher values divide the frame rate.
+    ///
+    /// This overrides the choice made with `with_vsync`, without recreating the window. The
+    /// context must be current on this window.
+    ///
+    /// The interval belongs to the window rather than to the context, so that windows sharing
+    /// their objects can have different intervals, for example to only enable vsync on the
+    /// primary view.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, disabling vsync requires `GLX_EXT_swap_control`. Without it, the window must
+    ///   be the current drawable.
+    /// - On Windows, this requires `WGL_EXT_swap_control`.
+    /// - Returns an error on iOS and Emscripten.
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), String> {
+        self.window.set_swap_interval(interval)
     }
 
     /// DEPRECATED. Gets the native platform specific display for this window.
@@ -465,6 +1198,60 @@ impl Window {
         self.window.get_pixel_format()
     }
 
+    /// Spawns a child process that is killed when the program dies, even if it is killed.
+    ///
+    /// This is meant for launchers, which would otherwise leave the games that they have
+    /// started running without any window to stop them.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On Linux, the child is killed when the thread that spawned it exits, so the child
+    ///   should be spawned from the main thread.
+    /// - On Windows, the child and its own children are assigned to a job object.
+    /// - On the other platforms, the child is spawned normally and outlives the program.
+    #[inline]
+    pub fn spawn_attached(&self, command: &mut Command) -> io::Result<Child> {
+        child::spawn_attached(command)
+    }
+
+    /// Creates an OpenGL context that shares its objects with the context of this window, but
+    /// that doesn't have a visible window of its own.
+    ///
+    /// The context is created with the same requirements as this window, and is made current
+    /// on a hidden drawable. It can only render to framebuffer objects, and is typically used to
+    /// upload textures from a worker thread.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - The context can only be sent to another thread on the platforms where windows can.
+    pub fn create_shared_context(&self) -> Result<SharedContext, CreationError> {
+        let attributes = WindowAttributes {
+            dimensions: Some((1, 1)),
+            visible: false,
+            decorations: false,
+            .. WindowAttributes::default()
+        };
+
+        // vsync only matters for the swaps, which a hidden drawable doesn't need
+        let mut opengl = self.opengl.clone().map_sharing(|()| &self.window);
+        opengl.sharing = Some(&self.window);
+        opengl.vsync = false;
+        opengl.strict_vsync = false;
+
+        platform::Window::new(&attributes, &self.pf_reqs, &opengl, &Default::default())
+                         .map(|window| SharedContext { window: window })
+    }
+
+    /// Returns the robustness that the context has actually obtained, which tells whether the
+    /// `Try*` variants of `Robustness` have been honored. The `Try*` variants are never
+    /// returned.
+    ///
+    /// The context must be current. The error flags of OpenGL are cleared.
+    #[inline]
+    pub fn get_robustness(&self) -> Robustness {
+        robustness::get_robustness(|name| self.window.get_proc_address(name))
+    }
+
     /// Create a window proxy for this window, that can be freely
     /// passed to different threads.
     #[inline]
@@ -484,16 +1271,134 @@ impl Window {
 
     /// Modifies the mouse cursor of the window.
     /// Has no effect on Android.
+    ///
+    /// If the busy cursor is shown, the new cursor is only applied once it is removed.
     pub fn set_cursor(&self, cursor: MouseCursor) {
-        self.window.set_cursor(cursor);
+        let mut current = self.cursor.lock().unwrap();
+        current.0 = cursor;
+        if !current.1 {
+            self.window.set_cursor(cursor);
+        }
+    }
+
+    /// Shows the "wait" cursor if `busy` is true, or restores the last cursor passed to
+    /// `set_cursor` if it is false.
+    pub fn set_busy(&self, busy: bool) {
+        let mut current = self.cursor.lock().unwrap();
+        if current.1 == busy {
+            return;
+        }
+
+        current.1 = busy;
+        self.window.set_cursor(if busy { MouseCursor::Wait } else { current.0 });
+    }
+
+    /// Shows the "wait" cursor while the closure runs.
+    ///
+    /// The previous cursor is restored afterwards, even if the closure panics.
+    pub fn with_busy_cursor<F, R>(&self, f: F) -> R where F: FnOnce() -> R {
+        struct Restore<'a>(&'a Window, bool);
+        impl<'a> Drop for Restore<'a> {
+            fn drop(&mut self) {
+                self.0.set_busy(self.1);
+            }
+        }
+
+        let _restore = Restore(self, self.cursor.lock().unwrap().1);
+        self.set_busy(true);
+        f()
+    }
+
+    /// Sets the size in pixels of the cursor when it is over the window.
+    ///
+    /// Use this on hidpi monitors so that the cursor isn't tiny compared to the one of other
+    /// applications. Only has an effect on X11, other platforms apply the size chosen by the
+    /// user in the system settings.
+    #[inline]
+    pub fn set_cursor_size(&self, size: u32) {
+        self.window.set_cursor_size(size);
+    }
+
+    /// Replaces the content of the clipboard with some text.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, the window becomes the owner of the `CLIPBOARD` selection, and the text is sent
+    ///   to the other applications while the events of the window are processed. The content of
+    ///   the clipboard is lost when the window is destroyed.
+    /// - Has no effect on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn set_clipboard_string(&self, text: &str) {
+        self.window.set_clipboard_string(text);
+    }
+
+    /// Returns the content of the clipboard, if it contains text.
+    ///
+    /// On X11, this blocks until the application that owns the clipboard sends its content, for
+    /// at most one second. Always returns `None` on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn get_clipboard_string(&self) -> Option<String> {
+        self.window.get_clipboard_string()
     }
 
     /// Returns the ratio between the backing framebuffer resolution and the
     /// window size in screen pixels. This is typically one for a normal display
     /// and two for a retina display.
+    ///
+    /// If a factor was forced with `with_scale_factor_override` or the `GLUTIN_SCALE_FACTOR`
+    /// environment variable, it is returned instead, and the logical sizes reported by the window
+    /// are expressed with this factor.
     #[inline]
     pub fn hidpi_factor(&self) -> f32 {
-        self.window.hidpi_factor()
+        self.hidpi_factor_override.unwrap_or_else(|| self.window.hidpi_factor())
+    }
+
+    /// Converts a size in the native units of the platform to the logical units of the window.
+    #[inline]
+    fn to_logical_size(&self, size: LogicalSize) -> LogicalSize {
+        override_logical_size(size, self.window.hidpi_factor(), self.hidpi_factor_override)
+    }
+
+    /// Expresses the sizes of an event of the platform in the logical units of the window.
+    #[inline]
+    fn translate_event(&self, event: Event) -> Event {
+        match event {
+            Event::Resized(size) => Event::Resized(self.to_logical_size(size)),
+            event => event,
+        }
+    }
+
+    /// Sets whether the window would like the monitor to use variable refresh rate
+    /// (G-Sync/FreeSync). See `WindowBuilder::with_variable_refresh`.
+    #[inline]
+    pub fn set_variable_refresh(&self, enabled: bool) {
+        self.window.set_variable_refresh(enabled)
+    }
+
+    /// Returns the position of the cursor in window coordinates, even if it is outside of the
+    /// window.
+    ///
+    /// This is useful to know where the cursor is before the first `MouseMoved` event, for
+    /// example at startup or after the window gains the focus.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, returns `None` if the cursor is on another X screen.
+    /// - On OS X, the position is in pixels like the one of `MouseMoved`, that is multiplied by
+    ///   `hidpi_factor`.
+    /// - Always returns `None` on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn get_cursor_position(&self) -> Option<(i32, i32)> {
+        self.window.get_cursor_position()
+    }
+
+    /// Returns the position of the cursor in screen coordinates, with the same origin as
+    /// `get_position`.
+    ///
+    /// Same platform-specific behavior as `get_cursor_position`.
+    #[inline]
+    pub fn get_cursor_screen_position(&self) -> Option<(i32, i32)> {
+        self.window.get_cursor_screen_position()
     }
 
     /// Changes the position of the cursor in window coordinates.
@@ -543,6 +1448,74 @@ impl GlContext for Window {
     }
 }
 
+/// An OpenGL context that shares its objects with the context of a window, created with
+/// `Window::create_shared_context`.
+pub struct SharedContext {
+    /// The hidden window whose drawable the context is made current on
+    window: platform::Window,
+}
+
+impl SharedContext {
+    /// Sets the context as the current context.
+    #[inline]
+    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
+        self.window.make_current()
+    }
+
+    /// Returns true if this context is the current one in this thread.
+    #[inline]
+    pub fn is_current(&self) -> bool {
+        self.window.is_current()
+    }
+
+    /// Returns the address of an OpenGL function.
+    #[inline]
+    pub fn get_proc_address(&self, addr: &str) -> *const () {
+        self.window.get_proc_address(addr)
+    }
+
+    /// Returns the API that is provided by this context.
+    ///
+    /// See `Window::get_api` for more infos.
+    #[inline]
+    pub fn get_api(&self) -> Api {
+        self.window.get_api()
+    }
+}
+
+impl GlContext for SharedContext {
+    #[inline]
+    unsafe fn make_current(&self) -> Result<(), ContextError> {
+        self.make_current()
+    }
+
+    #[inline]
+    fn is_current(&self) -> bool {
+        self.is_current()
+    }
+
+    #[inline]
+    fn get_proc_address(&self, addr: &str) -> *const () {
+        self.get_proc_address(addr)
+    }
+
+    #[inline]
+    fn swap_buffers(&self) -> Result<(), ContextError> {
+        // the drawable is never displayed
+        Ok(())
+    }
+
+    #[inline]
+    fn get_api(&self) -> Api {
+        self.get_api()
+    }
+
+    #[inline]
+    fn get_pixel_format(&self) -> PixelFormat {
+        self.window.get_pixel_format()
+    }
+}
+
 /// Represents a thread safe subset of operations that can be called
 /// on a window. This structure can be safely cloned and sent between
 /// threads.
@@ -561,36 +1534,114 @@ impl WindowProxy {
     }
 }
 /// An iterator for the `poll_events` function.
-pub struct PollEventsIterator<'a>(platform::PollEventsIterator<'a>);
+pub struct PollEventsIterator<'a> {
+    window: &'a Window,
+    inner: platform::PollEventsIterator<'a>,
+}
 
 impl<'a> Iterator for PollEventsIterator<'a> {
     type Item = Event;
 
     #[inline]
     fn next(&mut self) -> Option<Event> {
-        self.0.next()
+        if let Some(event) = self.window.termination.poll() {
+            return Some(event);
+        }
+
+        if let Some(event) = self.window.menu_shortcuts.lock().unwrap().poll() {
+            return Some(event);
+        }
+
+        let event = self.inner.next();
+        if let Some(Event::Terminated) = event {
+            // the platform is only woken up by the request, which has already been reported
+            return self.next();
+        }
+
+        let mut hover = self.window.hover.lock().unwrap();
+        match event {
+            Some(event) => {
+                let event = self.window.translate_event(event);
+                hover.process(&event);
+                self.window.menu_shortcuts.lock().unwrap().process(&event);
+                Some(event)
+            },
+            None => hover.poll(),
+        }
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
+        self.inner.size_hint()
     }
 }
 
 /// An iterator for the `wait_events` function.
-pub struct WaitEventsIterator<'a>(platform::WaitEventsIterator<'a>);
+pub struct WaitEventsIterator<'a> {
+    window: &'a Window,
+    inner: platform::WaitEventsIterator<'a>,
+}
 
 impl<'a> Iterator for WaitEventsIterator<'a> {
     type Item = Event;
 
-    #[inline]
     fn next(&mut self) -> Option<Event> {
-        self.0.next()
+        if let Some(event) = self.window.termination.poll() {
+            return Some(event);
+        }
+
+        if let Some(event) = self.window.menu_shortcuts.lock().unwrap().poll() {
+            return Some(event);
+        }
+
+        // while the cursor may be resting, we can only block until it is reported as hovering
+        loop {
+            if let Some(event) = self.window.termination.poll() {
+                return Some(event);
+            }
+
+            let deadline = match self.window.hover.lock().unwrap().deadline() {
+                Some(deadline) => deadline,
+                None => break
+            };
+
+            if let Some(event) = self.window.window.poll_events().next() {
+                let event = self.window.translate_event(event);
+                self.window.hover.lock().unwrap().process(&event);
+                self.window.menu_shortcuts.lock().unwrap().process(&event);
+                return Some(event);
+            }
+
+            let now = Instant::now();
+            if deadline <= now {
+                return self.window.hover.lock().unwrap().poll();
+            }
+            platform::Window::wait_for_events(&[&self.window.window], Some(deadline - now));
+        }
+
+        loop {
+            let event = self.inner.next();
+            if let Some(Event::Terminated) = event {
+                // the platform has been woken up by a request, which may have been reported
+                // while polling
+                match self.window.termination.poll() {
+                    Some(event) => return Some(event),
+                    None => continue,
+                }
+            }
+
+            let event = event.map(|event| self.window.translate_event(event));
+            if let Some(ref event) = event {
+                self.window.hover.lock().unwrap().process(event);
+                self.window.menu_shortcuts.lock().unwrap().process(event);
+            }
+            return event;
+        }
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
+        self.inner.size_hint()
     }
 }
 
@@ -652,4 +1703,110 @@ impl MonitorId {
         let &MonitorId(ref id) = self;
         id.get_dimensions()
     }
+
+    /// Returns the part of the monitor where windows should be placed, which excludes the
+    /// taskbars, panels and docks, as `(x, y, width, height)` in desktop coordinates.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this is read from the `_NET_WORKAREA` property of the window manager, which
+    ///   covers the whole X screen. The whole screen is returned if it isn't set.
+    /// - On Wayland, this is the whole monitor.
+    /// - Returns `None` on Android, iOS and Emscripten.
+    #[inline]
+    pub fn get_workarea(&self) -> Option<(i32, i32, u32, u32)> {
+        let &MonitorId(ref id) = self;
+        id.get_workarea()
+    }
+
+    /// Returns the information contained in the EDID of the monitor, or `None` if it is not
+    /// available.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this requires the XRandR extension. Since monitors are X screens, this returns
+    ///   the EDID of the primary output of the screen.
+    /// - On OS X, this is the `IODisplayEDID` property of the display in the IOKit registry.
+    /// - On Windows, this is the `EDID` value of the registry key of the monitor.
+    /// - Returns `None` on Wayland, Android, iOS and Emscripten.
+    ///
+    #[inline]
+    pub fn get_edid(&self) -> Option<Edid> {
+        let &MonitorId(ref id) = self;
+        id.get_edid_data().and_then(|data| Edid::parse(&data))
+    }
+
+    /// Returns true if the monitor and its driver support variable refresh rate.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this reads the `vrr_capable` property of the XRandR output, which is only
+    ///   provided by some drivers.
+    /// - Always returns `false` on other platforms.
+    ///
+    #[inline]
+    pub fn is_variable_refresh_capable(&self) -> bool {
+        let &MonitorId(ref id) = self;
+        id.is_variable_refresh_capable()
+    }
+
+    /// Returns the ICC profile of the monitor, or `None` if no profile is assigned.
+    ///
+    /// When the profile changes, an `IccProfileChanged` event is sent to the windows.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On X11, this reads the `_ICC_PROFILE` property of the root window of the screen, as
+    ///   set by color management daemons.
+    /// - On OS X, this is the profile that ColorSync assigns to the display.
+    /// - On Windows, this is the profile that Windows Color System associates to the adapter.
+    /// - Always returns `None` on other platforms.
+    /// - `IccProfileChanged` is only sent on X11.
+    ///
+    #[inline]
+    pub fn get_icc_profile(&self) -> Option<Vec<u8>> {
+        let &MonitorId(ref id) = self;
+        id.get_icc_profile()
+    }
+}
+
+/// Converts a size reported by the platform to the logical units of a window whose hidpi
+/// factor may be overridden.
+///
+/// The platforms report pixels with a factor of `1`, except OS X which reports points, so the
+/// size is converted to pixels with the factor of the platform before applying the override.
+fn override_logical_size(size: LogicalSize, native_factor: f32, factor_override: Option<f32>)
+                         -> LogicalSize
+{
+    match factor_override {
+        Some(factor) => size.to_physical(native_factor).to_logical(factor),
+        None => size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use LogicalSize;
+    use PhysicalSize;
+
+    use super::override_logical_size;
+
+    #[test]
+    fn pixel_size_is_kept_under_an_override() {
+        // X11 with `GLUTIN_SCALE_FACTOR=2`
+        let size = override_logical_size(LogicalSize::new(800, 600), 1.0, Some(2.0));
+        assert_eq!(size, LogicalSize::new(400, 300));
+        assert_eq!(size.to_physical(2.0), PhysicalSize::new(800, 600));
+
+        // Retina forced to 1
+        let size = override_logical_size(LogicalSize::new(800, 600), 2.0, Some(1.0));
+        assert_eq!(size, LogicalSize::new(1600, 1200));
+        assert_eq!(size.to_physical(1.0), PhysicalSize::new(1600, 1200));
+    }
+
+    #[test]
+    fn sizes_are_unchanged_without_override() {
+        let size = override_logical_size(LogicalSize::new(800, 600), 2.0, None);
+        assert_eq!(size, LogicalSize::new(800, 600));
+    }
 }